@@ -158,6 +158,9 @@ impl AssertionState {
     }
 
     fn assert_data_files_present(&mut self, homedir: &Path) {
+        // Saved after each build so that later runs can reuse the benchmark executables.
+        self.assert_file_exists(homedir, "build-cache.json");
+
         let main_dir = homedir.join("data/main");
         if !self.does_exist(&main_dir) {
             println!("Found no data from benchmark.");
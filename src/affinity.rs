@@ -0,0 +1,89 @@
+//! Support for `--cpu-affinity`, which pins the benchmark targets to a set of CPUs so that the
+//! scheduler migrating them between cores doesn't add noise to the measurements.
+
+use anyhow::{bail, Context, Result};
+use std::process::Command;
+
+/// Can the benchmark targets be pinned on this platform?
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/// The highest CPU number that can be pinned to, plus one (the size of the kernel's CPU set).
+const MAX_CPUS: usize = 1024;
+
+/// Parses a list of CPUs such as "2", "0,2" or "0-3,6" into the CPU numbers.
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>> {
+    let parse_cpu = |cpu: &str| -> Result<usize> {
+        let cpu: usize = cpu
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid CPU number {:?}", cpu))?;
+        if cpu >= MAX_CPUS {
+            bail!("CPU number {} is too large", cpu);
+        }
+        Ok(cpu)
+    };
+
+    let mut cpus = vec![];
+    for item in list.split(',') {
+        let mut bounds = item.splitn(2, '-');
+        match (bounds.next().unwrap_or(""), bounds.next()) {
+            (first, Some(last)) => {
+                let (first, last) = (parse_cpu(first)?, parse_cpu(last)?);
+                if first > last {
+                    bail!("Invalid CPU range {:?}", item);
+                }
+                cpus.extend(first..=last);
+            }
+            (cpu, None) => cpus.push(parse_cpu(cpu)?),
+        }
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Restricts the process spawned by the command to the given CPUs.
+#[cfg(target_os = "linux")]
+pub fn pin(command: &mut Command, cpus: &[usize]) {
+    use std::os::unix::process::CommandExt;
+
+    let cpus = cpus.to_vec();
+    // This runs in the child between fork and exec, so it only calls async-signal-safe functions
+    // and doesn't allocate.
+    let set_affinity = move || {
+        // The set is plain data which is valid when zeroed.
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        for &cpu in &cpus {
+            unsafe { libc::CPU_SET(cpu, &mut set) };
+        }
+        let size = std::mem::size_of::<libc::cpu_set_t>();
+        if unsafe { libc::sched_setaffinity(0, size, &set) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    };
+    unsafe {
+        command.pre_exec(set_affinity);
+    }
+}
+
+/// Pinning isn't supported on this platform, so the process runs on any CPU. The user is warned
+/// about this when the option is parsed.
+#[cfg(not(target_os = "linux"))]
+pub fn pin(_: &mut Command, _: &[usize]) {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(vec![2], parse_cpu_list("2").unwrap());
+        assert_eq!(vec![0, 2], parse_cpu_list("2,0").unwrap());
+        assert_eq!(vec![0, 1, 2, 3, 6], parse_cpu_list("0-3, 6,2").unwrap());
+        assert!(parse_cpu_list("").is_err());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("one").is_err());
+        assert!(parse_cpu_list("4096").is_err());
+    }
+}
@@ -1,18 +1,22 @@
+use crate::config::{is_selected, SelectedStatistic};
 use crate::connection::{SamplingMethod, Throughput};
 use crate::estimate::{build_change_estimates, build_estimates, ConfidenceInterval, Estimate};
 use crate::estimate::{
     ChangeDistributions, ChangeEstimates, ChangePointEstimates, Distributions, Estimates,
-    PointEstimates,
+    LatencyPercentiles, PointEstimates, Statistic,
 };
-use crate::report::MeasurementData;
+use crate::kde;
+use crate::model::{BenchmarkGroup, Model, SavedStatistics};
+use crate::report::{compare_to_threshold, MeasurementData, StatisticChange};
 use crate::stats::bivariate::regression::Slope;
 use crate::stats::bivariate::Data;
 use crate::stats::univariate::outliers::tukey;
 use crate::stats::univariate::Sample;
 use crate::stats::{Distribution, Tails};
+use std::collections::HashMap;
 use std::time::Duration;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkConfig {
     pub confidence_level: f64,
     pub measurement_time: Duration,
@@ -23,19 +27,32 @@ pub struct BenchmarkConfig {
     pub warm_up_time: Duration,
 }
 
+/// The number of bootstrap resamples used in quick mode. This gives rougher confidence intervals
+/// in a fraction of the time.
+pub const QUICK_NRESAMPLES: usize = 5_000;
+
+// The percentiles at which the per-element latencies are reported.
+pub const LATENCY_PERCENTILES: [f64; 7] = [50.0, 75.0, 90.0, 95.0, 99.0, 99.5, 99.9];
+
 pub struct MeasuredValues<'a> {
     pub iteration_count: &'a [f64],
     pub sample_values: &'a [f64],
     pub avg_values: &'a [f64],
+    // The wall-clock time at which each sample was taken, if the benchmark recorded them.
+    pub timestamps: Option<&'a [f64]>,
 }
 
 // Common analysis procedure
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn analysis<'a>(
     config: &BenchmarkConfig,
     throughput: Option<Throughput>,
     new_sample: MeasuredValues<'a>,
     old_sample: Option<(MeasuredValues<'a>, &'a Estimates)>,
     sampling_method: SamplingMethod,
+    latencies: Option<&[f64]>,
+    overhead: Option<f64>,
+    statistics: Option<&[SelectedStatistic]>,
 ) -> MeasurementData<'a> {
     let iters = new_sample.iteration_count;
     let values = new_sample.sample_values;
@@ -44,9 +61,12 @@ pub(crate) fn analysis<'a>(
 
     let data = Data::new(iters, values);
     let labeled_sample = tukey::classify(avg_values);
+    let zero_variance = has_zero_variance(avg_values);
+    let multimodal = !zero_variance && kde::is_multimodal(avg_values);
     let (mut distributions, mut estimates) = estimates(avg_values, config);
 
-    if sampling_method.is_linear() {
+    // The slope takes a bootstrap of its own, so skip it if it isn't going to be reported.
+    if sampling_method.is_linear() && is_selected(statistics, Statistic::Slope) {
         let (distribution, slope) = regression(&data, config);
         estimates.slope = Some(slope);
         distributions.slope = Some(distribution);
@@ -55,7 +75,24 @@ pub(crate) fn analysis<'a>(
     let compare_data = if let Some((old_sample, old_estimates)) = old_sample {
         let (t_value, t_distribution, relative_estimates, relative_distributions, base_avg_times) =
             compare(avg_values, &old_sample, config);
-        let p_value = t_distribution.p_value(t_value, &Tails::Two);
+        // If neither sample varies at all, neither do their resamples, so the t distribution is
+        // empty. The means are then either certainly different or exactly the same.
+        let p_value = if t_distribution.is_empty() {
+            if t_value.is_nan() {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            t_distribution.p_value(t_value, &Tails::Two)
+        };
+        let statistic_changes = statistic_changes(
+            &relative_estimates,
+            estimates.slope.as_ref(),
+            old_estimates.slope.as_ref(),
+            config.noise_threshold,
+            statistics,
+        );
         Some(crate::report::ComparisonData {
             p_value,
             t_distribution,
@@ -67,7 +104,9 @@ pub(crate) fn analysis<'a>(
             base_iter_counts: old_sample.iteration_count.iter().copied().collect(),
             base_sample_times: old_sample.sample_values.iter().copied().collect(),
             base_avg_times,
+            base_timestamps: old_sample.timestamps.map(<[f64]>::to_vec),
             base_estimates: old_estimates.clone(),
+            statistic_changes,
         })
     } else {
         None
@@ -80,7 +119,270 @@ pub(crate) fn analysis<'a>(
         distributions,
         comparison: compare_data,
         throughput,
+        multimodal,
+        zero_variance,
+        latency_percentiles: latencies.and_then(latency_percentiles),
+        overhead,
+        timestamps: new_sample.timestamps,
+        config: config.clone(),
+        overlay_baselines: vec![],
+    }
+}
+
+/// Repeats the analysis of a stored measurement, eg. to draw its plots later. The bootstrap is
+/// repeated, so the distributions differ slightly from those of the original analysis, but the
+/// stored estimates are reported rather than the new ones. The sampling method isn't stored, so
+/// it's inferred from whether the measurement has a slope.
+pub(crate) fn reanalysis<'a>(
+    config: &BenchmarkConfig,
+    throughput: Option<Throughput>,
+    stats: &'a SavedStatistics,
+    baseline: Option<(MeasuredValues<'a>, &'a Estimates)>,
+    statistics: Option<&[SelectedStatistic]>,
+) -> MeasurementData<'a> {
+    let sampling_method = if stats.estimates.slope.is_some() {
+        SamplingMethod::Linear
+    } else {
+        SamplingMethod::Flat
+    };
+    let mut measured_data = analysis(
+        config,
+        throughput,
+        MeasuredValues {
+            iteration_count: &stats.iterations,
+            sample_values: &stats.values,
+            avg_values: &stats.avg_values,
+            timestamps: stats.timestamps.as_deref(),
+        },
+        baseline,
+        sampling_method,
+        None,
+        stats.overhead,
+        statistics,
+    );
+
+    measured_data.absolute_estimates = stats.estimates.clone();
+    measured_data.latency_percentiles = stats.latency_percentiles.clone();
+    measured_data.multimodal = stats.multimodal;
+    if let (Some(comparison), Some(changes)) = (&mut measured_data.comparison, &stats.changes) {
+        comparison.relative_estimates = changes.clone();
+    }
+    measured_data
+}
+
+/// Samples whose standard deviation is at most this fraction of their mean are treated as having
+/// no variance at all.
+const ZERO_VARIANCE_TOLERANCE: f64 = 1e-9;
+
+/// Returns true if every sample took the same time per iteration, to within rounding error (eg.
+/// because the benchmark returns a constant). Such benchmarks have no distribution to plot.
+pub(crate) fn has_zero_variance(avg_times: &Sample<f64>) -> bool {
+    avg_times.std_dev(None) <= ZERO_VARIANCE_TOLERANCE * avg_times.mean().abs()
+}
+
+/// Measurement overhead above this fraction of the typical time per iteration is warned about.
+pub(crate) const HIGH_OVERHEAD_FRACTION: f64 = 0.1;
+
+/// Returns the overhead of the measurement loop as a fraction of the typical time per iteration,
+/// if the benchmark reported it.
+pub(crate) fn overhead_fraction(measurements: &MeasurementData<'_>) -> Option<f64> {
+    let typical = measurements.absolute_estimates.typical().point_estimate;
+    measurements
+        .overhead
+        .map(|overhead| overhead / typical)
+        .filter(|fraction| fraction.is_finite())
+}
+
+// Computes the percentiles of the per-element latencies. There's no resampling here; the
+// latencies are usually numerous enough that the percentiles speak for themselves.
+fn latency_percentiles(latencies: &[f64]) -> Option<LatencyPercentiles> {
+    let latencies: Vec<f64> = latencies
+        .iter()
+        .copied()
+        .filter(|latency| latency.is_finite())
+        .collect();
+    if latencies.len() < 2 {
+        return None;
+    }
+    let percentiles = Sample::new(&latencies).percentiles();
+    Some(LatencyPercentiles {
+        percentiles: LATENCY_PERCENTILES.to_vec(),
+        latencies: LATENCY_PERCENTILES
+            .iter()
+            .map(|&percentile| percentiles.at(percentile))
+            .collect(),
+        count: latencies.len(),
+    })
+}
+
+// The relative change in each selected statistic which can be compared with the baseline,
+// classified against the noise threshold.
+fn statistic_changes(
+    relative_estimates: &ChangeEstimates,
+    new_slope: Option<&Estimate>,
+    base_slope: Option<&Estimate>,
+    noise_threshold: f64,
+    statistics: Option<&[SelectedStatistic]>,
+) -> Vec<StatisticChange> {
+    let mut changes = vec![
+        (Statistic::Mean, relative_estimates.mean.clone()),
+        (Statistic::Median, relative_estimates.median.clone()),
+    ];
+    if let (Some(new_slope), Some(base_slope)) = (new_slope, base_slope) {
+        if let Some(estimate) = slope_change(new_slope, base_slope) {
+            changes.push((Statistic::Slope, estimate));
+        }
+    }
+
+    changes
+        .into_iter()
+        .filter(|(statistic, _)| is_selected(statistics, *statistic))
+        .map(|(statistic, estimate)| StatisticChange {
+            statistic,
+            result: compare_to_threshold(&estimate, noise_threshold),
+            estimate,
+        })
+        .collect()
+}
+
+// The relative change in the slope. The bootstrap distribution of the base slope isn't stored, so
+// the confidence interval is the ratio of the two confidence intervals. This is wider than a
+// bootstrapped interval would be, which errs on the side of reporting no change.
+fn slope_change(new: &Estimate, base: &Estimate) -> Option<Estimate> {
+    let new_ci = &new.confidence_interval;
+    let base_ci = &base.confidence_interval;
+    if base.point_estimate <= 0.0 || base_ci.lower_bound <= 0.0 || new.point_estimate <= 0.0 {
+        return None;
     }
+
+    let ratio = new.point_estimate / base.point_estimate;
+    let relative_error = ((new.standard_error / new.point_estimate).powi(2)
+        + (base.standard_error / base.point_estimate).powi(2))
+    .sqrt();
+    Some(Estimate {
+        confidence_interval: ConfidenceInterval {
+            confidence_level: new_ci.confidence_level,
+            lower_bound: new_ci.lower_bound / base_ci.upper_bound - 1.0,
+            upper_bound: new_ci.upper_bound / base_ci.lower_bound - 1.0,
+        },
+        point_estimate: ratio - 1.0,
+        standard_error: ratio * relative_error,
+    })
+}
+
+/// The geometric mean of the mean times of the benchmarks in a group.
+pub(crate) struct GroupGeometricMean {
+    pub mean: f64,
+    // The relative change in the geometric mean of the benchmarks which have a baseline.
+    pub change: Option<f64>,
+    // Were any of the benchmarks given a weight other than 1?
+    pub weighted: bool,
+}
+
+// Computes the geometric mean of the mean estimates of the benchmarks in a group, with each
+// benchmark weighted by its entry in `weights` (keyed by title, defaulting to 1). Returns None if
+// the group is empty or any of the estimates isn't positive.
+pub(crate) fn group_geometric_mean(
+    group: &BenchmarkGroup,
+    weights: &HashMap<String, f64>,
+) -> Option<GroupGeometricMean> {
+    let benchmarks: Vec<(f64, f64, Option<f64>)> = group
+        .benchmarks
+        .iter()
+        .map(|(id, benchmark)| {
+            let stats = &benchmark.latest_stats;
+            let weight = weights.get(id.as_title()).copied().unwrap_or(1.0);
+            let change = stats
+                .changes
+                .as_ref()
+                .map(|changes| changes.mean.point_estimate);
+            (stats.estimates.mean.point_estimate, weight, change)
+        })
+        .collect();
+
+    let means: Vec<(f64, f64)> = benchmarks
+        .iter()
+        .map(|&(mean, weight, _)| (mean, weight))
+        .collect();
+    let mean = weighted_geometric_mean(&means)?;
+
+    // The geometric mean of the ratios to the baselines is the ratio of the geometric means.
+    let ratios: Vec<(f64, f64)> = benchmarks
+        .iter()
+        .filter_map(|&(_, weight, change)| change.map(|change| (1.0 + change, weight)))
+        .collect();
+    let change = weighted_geometric_mean(&ratios).map(|ratio| ratio - 1.0);
+
+    Some(GroupGeometricMean {
+        mean,
+        change,
+        weighted: means.iter().any(|&(_, weight)| weight != 1.0),
+    })
+}
+
+/// The combined mean time of the benchmarks measured in this run.
+pub(crate) struct SuiteTotal {
+    pub total: f64,
+    // The relative change in the combined time of the benchmarks which have a baseline, compared
+    // to the combined time of those baselines.
+    pub change: Option<f64>,
+}
+
+// Adds up the mean estimates of the benchmarks measured in this run. Returns None if no
+// benchmarks were measured.
+pub(crate) fn suite_total(model: &Model) -> Option<SuiteTotal> {
+    let benchmarks: Vec<(f64, Option<f64>)> = model
+        .groups
+        .values()
+        .flat_map(|group| group.benchmarks.values())
+        .filter(|benchmark| benchmark.measured)
+        .map(|benchmark| {
+            let stats = &benchmark.latest_stats;
+            let change = stats
+                .changes
+                .as_ref()
+                .map(|changes| changes.mean.point_estimate);
+            (stats.estimates.mean.point_estimate, change)
+        })
+        .collect();
+    combine_totals(&benchmarks)
+}
+
+// Takes the mean time of each benchmark and its relative change from its baseline, if any. The
+// baseline time is recovered from the change so that the baseline window is respected.
+fn combine_totals(benchmarks: &[(f64, Option<f64>)]) -> Option<SuiteTotal> {
+    if benchmarks.is_empty() {
+        return None;
+    }
+    let total = benchmarks.iter().map(|&(mean, _)| mean).sum();
+
+    let mut current_total = 0.0;
+    let mut baseline_total = 0.0;
+    for &(mean, change) in benchmarks {
+        if let Some(change) = change {
+            current_total += mean;
+            baseline_total += mean / (1.0 + change);
+        }
+    }
+    let change = if baseline_total > 0.0 {
+        Some(current_total / baseline_total - 1.0)
+    } else {
+        None
+    };
+    Some(SuiteTotal { total, change })
+}
+
+// Takes pairs of a value and its weight.
+fn weighted_geometric_mean(values: &[(f64, f64)]) -> Option<f64> {
+    if values.is_empty() || values.iter().any(|&(value, _)| value <= 0.0) {
+        return None;
+    }
+    let log_sum: f64 = values
+        .iter()
+        .map(|&(value, weight)| weight * value.ln())
+        .sum();
+    let total_weight: f64 = values.iter().map(|&(_, weight)| weight).sum();
+    Some((log_sum / total_weight).exp())
 }
 
 // Performs a simple linear regression on the sample
@@ -257,3 +559,82 @@ fn difference_estimates(
 
     (estimates, distributions)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_has_zero_variance() {
+        assert!(has_zero_variance(Sample::new(&[2.5, 2.5, 2.5])));
+        assert!(has_zero_variance(Sample::new(&[0.0, 0.0])));
+        assert!(has_zero_variance(Sample::new(&[1e9, 1e9 + 1e-3])));
+        assert!(!has_zero_variance(Sample::new(&[2.5, 2.6, 2.5])));
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        let unweighted = |values: &[f64]| {
+            let values: Vec<(f64, f64)> = values.iter().map(|&value| (value, 1.0)).collect();
+            weighted_geometric_mean(&values)
+        };
+        assert_eq!(None, unweighted(&[]));
+        assert_eq!(None, unweighted(&[1.0, 0.0]));
+        let mean = unweighted(&[2.0, 8.0]).unwrap();
+        assert!((mean - 4.0).abs() < 1e-9);
+        let mean = unweighted(&[1.0, 10.0, 100.0]).unwrap();
+        assert!((mean - 10.0).abs() < 1e-9);
+
+        // A weight of 3 counts the value three times.
+        let mean = weighted_geometric_mean(&[(1.0, 3.0), (16.0, 1.0)]).unwrap();
+        assert!((mean - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_slope_change() {
+        let estimate = |point, lb, ub| Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: lb,
+                upper_bound: ub,
+            },
+            point_estimate: point,
+            standard_error: 0.0,
+        };
+
+        let change = slope_change(&estimate(12.0, 11.0, 13.0), &estimate(10.0, 9.0, 11.0)).unwrap();
+        assert!((change.point_estimate - 0.2).abs() < 1e-9);
+        assert!((change.confidence_interval.lower_bound - 0.0).abs() < 1e-9);
+        assert!((change.confidence_interval.upper_bound - (13.0 / 9.0 - 1.0)).abs() < 1e-9);
+
+        assert!(slope_change(&estimate(12.0, 11.0, 13.0), &estimate(1.0, -1.0, 3.0)).is_none());
+    }
+
+    #[test]
+    fn test_combine_totals() {
+        assert!(combine_totals(&[]).is_none());
+
+        let total = combine_totals(&[(10.0, None), (20.0, None)]).unwrap();
+        assert!((total.total - 30.0).abs() < 1e-9);
+        assert!(total.change.is_none());
+
+        // 20 -> 10 and 5 -> 10; the combined baseline is 25 and the combined time is 20. The
+        // benchmark without a baseline is excluded from the change.
+        let total = combine_totals(&[(10.0, Some(-0.5)), (10.0, Some(1.0)), (7.0, None)]).unwrap();
+        assert!((total.total - 27.0).abs() < 1e-9);
+        assert!((total.change.unwrap() - -0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        assert!(latency_percentiles(&[]).is_none());
+        assert!(latency_percentiles(&[1.0, f64::NAN]).is_none());
+
+        let latencies: Vec<f64> = (0..=1000).map(|i| i as f64).collect();
+        let percentiles = latency_percentiles(&latencies).unwrap();
+        assert_eq!(1001, percentiles.count);
+        assert_eq!(&LATENCY_PERCENTILES[..], &percentiles.percentiles[..]);
+        assert!((percentiles.latencies[0] - 500.0).abs() < 1e-9);
+        assert!((percentiles.latencies[6] - 999.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,169 @@
+//! Support for plain output (`--color never`), which guarantees that nothing we print contains
+//! ANSI escape sequences. Besides our own colors, escape sequences can come from the output of the
+//! benchmark targets, which is copied to ours, and from the benchmark IDs themselves, so those are
+//! stripped out as well.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const ESC: u8 = 0x1b;
+const BEL: u8 = 0x07;
+
+static PLAIN_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether escape sequences should be stripped from the output copied from the benchmark
+/// targets and from the log messages.
+pub fn set_plain_output(plain: bool) {
+    PLAIN_OUTPUT.store(plain, Ordering::Relaxed);
+}
+
+pub fn is_plain_output() -> bool {
+    PLAIN_OUTPUT.load(Ordering::Relaxed)
+}
+
+/// Removes any ANSI escape sequences from the text.
+pub fn strip(text: &str) -> Cow<'_, str> {
+    match strip_bytes(text.as_bytes()) {
+        Cow::Borrowed(_) => Cow::Borrowed(text),
+        Cow::Owned(bytes) => Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()),
+    }
+}
+
+/// Removes any ANSI escape sequences from the bytes: control sequences such as colors and cursor
+/// movements, operating system commands such as window titles and hyperlinks, and the rest.
+pub fn strip_bytes(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if !bytes.contains(&ESC) {
+        return Cow::Borrowed(bytes);
+    }
+
+    let mut stripped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != ESC {
+            stripped.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        i = match bytes.get(i + 1) {
+            // Control sequence: parameters and intermediates, then a final byte from @ to ~.
+            Some(b'[') => bytes[i + 2..]
+                .iter()
+                .position(|b| (0x40..=0x7e).contains(b))
+                .map_or(bytes.len(), |end| i + 2 + end + 1),
+            // Strings such as operating system commands, which run until the string terminator
+            // (ESC \) or, for operating system commands, a bell.
+            Some(b']') | Some(b'P') | Some(b'X') | Some(b'^') | Some(b'_') => {
+                let mut end = i + 2;
+                loop {
+                    match bytes.get(end) {
+                        None => break end,
+                        Some(&BEL) => break end + 1,
+                        Some(&ESC) if bytes.get(end + 1) == Some(&b'\\') => break end + 2,
+                        Some(_) => end += 1,
+                    }
+                }
+            }
+            // Any other escape sequence: intermediates from space to /, then a final byte.
+            Some(_) => bytes[i + 1..]
+                .iter()
+                .position(|b| !(0x20..=0x2f).contains(b))
+                .map_or(bytes.len(), |end| i + 1 + end + 1),
+            None => bytes.len(),
+        };
+    }
+    Cow::Owned(stripped)
+}
+
+/// Removes any ANSI escape sequences from the bytes if plain output is enabled.
+pub fn plain_bytes(bytes: &[u8]) -> Cow<'_, [u8]> {
+    if is_plain_output() {
+        strip_bytes(bytes)
+    } else {
+        Cow::Borrowed(bytes)
+    }
+}
+
+/// Writer which strips the escape sequences from everything written to it if plain output is
+/// enabled. The output is buffered a line at a time, so that sequences split across writes are
+/// still found.
+pub struct PlainWriter<W: Write> {
+    inner: W,
+    line: Vec<u8>,
+}
+impl<W: Write> PlainWriter<W> {
+    pub fn new(inner: W) -> PlainWriter<W> {
+        PlainWriter {
+            inner,
+            line: vec![],
+        }
+    }
+
+    fn write_line(&mut self, end: usize) -> io::Result<()> {
+        let line: Vec<u8> = self.line.drain(..end).collect();
+        self.inner.write_all(&plain_bytes(&line))
+    }
+}
+impl<W: Write> Write for PlainWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        while let Some(newline) = self.line.iter().position(|&b| b == b'\n') {
+            self.write_line(newline + 1)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let end = self.line.len();
+        self.write_line(end)?;
+        self.inner.flush()
+    }
+}
+impl<W: Write> Drop for PlainWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_plain(text: &str) {
+        assert!(!text.contains('\x1b'), "Found an escape in {:?}", text);
+    }
+
+    #[test]
+    fn test_strip() {
+        assert_eq!("fib/20", strip("\x1b[1mfib/20\x1b[22m"));
+        assert_eq!("red", strip("\x1b[38;2;255;0;0mred\x1b[39m"));
+        assert_eq!(
+            "link",
+            strip("\x1b]8;;https://example.com\x07link\x1b]8;;\x1b\\")
+        );
+        assert_eq!("ab", strip("a\x1b(Bb"));
+        assert_eq!("a", strip("a\x1b"));
+        assert_eq!("a", strip("a\x1b[31"));
+        assert_eq!("é ✓", strip("é \x1b[32m✓\x1b[0m"));
+        assert!(matches!(strip("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn test_plain_writer() {
+        set_plain_output(true);
+        let mut output = vec![];
+        {
+            let mut writer = PlainWriter::new(&mut output);
+            // The sequences are split across writes.
+            write!(writer, "\x1b[3").unwrap();
+            writeln!(writer, "3mWARN\x1b[0m: slow").unwrap();
+            write!(writer, "done\x1b").unwrap();
+            write!(writer, "[0m").unwrap();
+        }
+        set_plain_output(false);
+
+        let output = String::from_utf8(output).unwrap();
+        assert_plain(&output);
+        assert_eq!("WARN: slow\ndone", output);
+    }
+}
@@ -0,0 +1,56 @@
+//! Support for fetching the baselines of the comparisons from an external store of results (see
+//! `--baseline-store`) rather than from the data directory, so that the history of a project can
+//! be kept in a shared database.
+//!
+//! A store is anything which implements `BaselineStore`. To back cargo-criterion with another kind
+//! of storage, implement the trait and give it a scheme in `open`.
+
+#[cfg(feature = "sqlite_baselines")]
+mod sqlite;
+
+use crate::model::SavedStatistics;
+use crate::report::BenchmarkId;
+use anyhow::{bail, Result};
+
+/// An external store of benchmark results.
+pub trait BaselineStore: std::fmt::Debug {
+    /// Loads the statistics which the given benchmark should be compared against, usually those
+    /// of its most recently stored run. Returns None if the store has no results for it.
+    fn load_baseline(&self, id: &BenchmarkId) -> Result<Option<SavedStatistics>>;
+
+    /// Stores the statistics of a benchmark measured in this run.
+    fn store_run(&self, id: &BenchmarkId, stats: &SavedStatistics) -> Result<()>;
+}
+
+/// Opens the store at the given location, eg. `sqlite:target/criterion.sqlite`.
+pub fn open(location: &str) -> Result<Box<dyn BaselineStore>> {
+    let mut parts = location.splitn(2, ':');
+    match (parts.next().unwrap_or(""), parts.next()) {
+        ("sqlite", Some(path)) => open_sqlite(path),
+        _ => bail!(
+            "Unsupported baseline store {:?}. Baseline stores are given as sqlite:PATH",
+            location
+        ),
+    }
+}
+
+#[cfg(feature = "sqlite_baselines")]
+fn open_sqlite(path: &str) -> Result<Box<dyn BaselineStore>> {
+    Ok(Box::new(sqlite::SqliteStore::open(path)?))
+}
+
+#[cfg(not(feature = "sqlite_baselines"))]
+fn open_sqlite(_: &str) -> Result<Box<dyn BaselineStore>> {
+    bail!("SQLite baseline stores require cargo-criterion to be built with the sqlite_baselines feature")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_open_unsupported() {
+        assert!(open("postgres://localhost/criterion").is_err());
+        assert!(open("target/criterion.sqlite").is_err());
+    }
+}
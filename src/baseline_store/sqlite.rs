@@ -0,0 +1,65 @@
+//! The reference implementation of `BaselineStore`, which keeps the results in a SQLite database.
+//! Every stored run is kept, so the table doubles as the history of each benchmark.
+
+use crate::baseline_store::BaselineStore;
+use crate::model::SavedStatistics;
+use crate::report::BenchmarkId;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    benchmark TEXT NOT NULL,
+    datetime TEXT NOT NULL,
+    statistics BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS runs_by_benchmark ON runs (benchmark, id);
+";
+
+#[derive(Debug)]
+pub struct SqliteStore {
+    connection: Connection,
+}
+impl SqliteStore {
+    /// Opens the database at the given path, creating it if necessary.
+    pub fn open(path: &str) -> Result<SqliteStore> {
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open baseline database {:?}", path))?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to create the tables in {:?}", path))?;
+        Ok(SqliteStore { connection })
+    }
+}
+impl BaselineStore for SqliteStore {
+    fn load_baseline(&self, id: &BenchmarkId) -> Result<Option<SavedStatistics>> {
+        let statistics: Option<Vec<u8>> = self
+            .connection
+            .query_row(
+                "SELECT statistics FROM runs WHERE benchmark = ?1 ORDER BY id DESC LIMIT 1",
+                params![id.as_title()],
+                |row| row.get(0),
+            )
+            .optional()
+            .with_context(|| format!("Failed to load the baseline of {}", id.as_title()))?;
+        statistics
+            .map(|statistics| {
+                serde_cbor::from_slice(&statistics).with_context(|| {
+                    format!("Failed to read the stored baseline of {}", id.as_title())
+                })
+            })
+            .transpose()
+    }
+
+    fn store_run(&self, id: &BenchmarkId, stats: &SavedStatistics) -> Result<()> {
+        let statistics = serde_cbor::to_vec(stats)?;
+        self.connection
+            .execute(
+                "INSERT INTO runs (benchmark, datetime, statistics) VALUES (?1, ?2, ?3)",
+                params![id.as_title(), stats.datetime.to_rfc3339(), statistics],
+            )
+            .with_context(|| format!("Failed to store the results of {}", id.as_title()))?;
+        Ok(())
+    }
+}
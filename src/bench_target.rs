@@ -1,17 +1,102 @@
+use crate::config::{RoundingMode, SelectedStatistic, TimeUnit};
 use crate::connection::{AxisScale, Connection, IncomingMessage, PlotConfiguration};
 use crate::model::Model;
 use crate::report::{BenchmarkId, Report, ReportContext};
+use crate::stats::reservoir;
 use anyhow::{anyhow, Context, Result};
+use std::collections::VecDeque;
 use std::ffi::OsString;
+use std::fmt;
+use std::io::{BufRead, BufReader, Write};
 use std::net::TcpListener;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+/// The number of lines at the end of the benchmark target's stderr which are kept to find the
+/// panic message in if the target fails.
+const STDERR_TAIL_LINES: usize = 50;
+
+/// Copies the stderr of a benchmark target to our stderr, keeping the last few lines so that the
+/// panic message can be reported if a benchmark panics. If the target's stderr wasn't captured,
+/// there's nothing to copy and no panic message is found.
+struct StderrTail {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    thread: Option<JoinHandle<()>>,
+}
+impl StderrTail {
+    fn capture(child: &mut Child) -> StderrTail {
+        let lines = Arc::new(Mutex::new(VecDeque::new()));
+        let thread = child.stderr.take().map(|stderr| {
+            let lines = lines.clone();
+            std::thread::spawn(move || {
+                let mut stderr = BufReader::new(stderr);
+                let mut line = vec![];
+                while let Ok(length) = stderr.read_until(b'\n', &mut line) {
+                    if length == 0 {
+                        break;
+                    }
+                    let _ = std::io::stderr().write_all(&crate::ansi::plain_bytes(&line));
+                    let mut lines = lines.lock().unwrap();
+                    if lines.len() == STDERR_TAIL_LINES {
+                        lines.pop_front();
+                    }
+                    lines.push_back(String::from_utf8_lossy(&line).trim_end().to_owned());
+                    line.clear();
+                }
+            })
+        });
+        StderrTail { lines, thread }
+    }
+
+    /// Waits for the target's stderr to be closed, then returns its panic message, if it printed
+    /// one.
+    fn panic_message(&mut self) -> Option<String> {
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let lines = self.lines.lock().unwrap();
+        panic_message(lines.iter().map(String::as_str))
+    }
+
+    /// Describes why the target exited in the middle of a benchmark: its panic message, or else
+    /// its exit status if the panic message wasn't captured or isn't in the standard (English)
+    /// format.
+    fn failure_message(&mut self, target: &str, exit_status: impl fmt::Display) -> String {
+        self.panic_message()
+            .unwrap_or_else(|| format!("Benchmark target {} exited with {}", target, exit_status))
+    }
+}
+
+/// Finds the last panic message in the given lines of output, including any lines of the message
+/// after the first but not the notes that follow it.
+fn panic_message<'a>(lines: impl DoubleEndedIterator<Item = &'a str>) -> Option<String> {
+    let mut message: Vec<&str> = vec![];
+    for line in lines.rev() {
+        if line.contains(" panicked at ") {
+            message.push(line);
+            message.reverse();
+            return Some(message.join("\n"));
+        }
+        if line.starts_with("note: ") || line.starts_with("error: ") || line.trim().is_empty() {
+            message.clear();
+        } else {
+            message.push(line);
+        }
+    }
+    None
+}
 
 /// Structure representing a compiled benchmark executable.
 #[derive(Debug)]
 pub struct BenchTarget {
     pub name: String,
     pub executable: PathBuf,
+    /// The Cargo.toml of the package the target belongs to, if cargo reported it.
+    pub manifest_path: Option<PathBuf>,
+    /// The rustup toolchain the target was compiled with, if one was given with `--toolchains`.
+    pub toolchain: Option<String>,
 }
 impl BenchTarget {
     /// Launches this benchmark target with the given additional arguments.
@@ -25,14 +110,23 @@ impl BenchTarget {
     /// The report will be notified about important events happening with the benchmark and the
     /// model will be updated with the new benchmark IDs and measurements as we go. This function
     /// will block until the benchmark target terminates.
+    #[allow(clippy::too_many_arguments)]
     pub fn execute(
         &self,
         criterion_home: &Path,
+        reports_directory: &Path,
         additional_args: &[OsString],
         library_paths: &[PathBuf],
         report: &dyn Report,
         model: &mut Model,
         redirect_stdout: bool,
+        time_unit: Option<TimeUnit>,
+        rounding: Option<RoundingMode>,
+        statistics: Option<&[SelectedStatistic]>,
+        quick: bool,
+        sample_size: Option<usize>,
+        max_samples: usize,
+        cpu_affinity: Option<&[usize]>,
     ) -> Result<()> {
         let listener = TcpListener::bind("localhost:0")
             .context("Unable to open socket to connect to Criterion.rs")?;
@@ -47,6 +141,12 @@ impl BenchTarget {
             .context("Unable to get local address of socket")?;
         let port = addr.port();
 
+        // Intercepting the target's stderr hides the terminal from it, which changes its own
+        // colors and progress output, so it's only done when our stderr isn't a terminal anyway or
+        // the escape sequences have to be stripped out. Otherwise the panic message can't be
+        // picked out, and a panicking benchmark is reported with the exit status instead.
+        let capture_stderr = crate::ansi::is_plain_output() || !atty::is(atty::Stream::Stderr);
+
         let mut command = Command::new(&self.executable);
         command
             .arg("--bench")
@@ -63,7 +163,15 @@ impl BenchTarget {
                 // If not, we might as well let the target see the true stdout.
                 Stdio::inherit()
             })
-            .stderr(Stdio::inherit());
+            // Intercept stderr to pick out the panic message if one of the benchmarks panics.
+            .stderr(if capture_stderr {
+                Stdio::piped()
+            } else {
+                Stdio::inherit()
+            });
+        if let Some(cpus) = cpu_affinity {
+            crate::affinity::pin(&mut command, cpus);
+        }
 
         debug!("Running '{:?}'", command);
 
@@ -73,8 +181,12 @@ impl BenchTarget {
 
         if redirect_stdout {
             let mut stdout = child.stdout.take().unwrap();
-            std::thread::spawn(move || std::io::copy(&mut stdout, &mut std::io::stderr()));
+            std::thread::spawn(move || {
+                let mut stderr = crate::ansi::PlainWriter::new(std::io::stderr());
+                std::io::copy(&mut stdout, &mut stderr)
+            });
         }
+        let mut stderr_tail = StderrTail::capture(&mut child);
 
         loop {
             match listener.accept() {
@@ -85,7 +197,20 @@ impl BenchTarget {
                     let conn = Connection::new(socket).with_context(|| {
                         format!("Unable to open connection to bench target {}", self.name)
                     })?;
-                    return self.communicate(&mut child, conn, report, criterion_home, model);
+                    return self.communicate(
+                        &mut child,
+                        &mut stderr_tail,
+                        conn,
+                        report,
+                        reports_directory,
+                        model,
+                        time_unit,
+                        rounding,
+                        statistics,
+                        quick,
+                        sample_size,
+                        max_samples,
+                    );
                 }
                 Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
                     // No connection yet, try again in a bit.
@@ -124,21 +249,38 @@ impl BenchTarget {
     /// This function is called when a benchmark connects to the socket. It interacts with the
     /// benchmark target to receive information about the measurements and inform the report and
     /// model about the benchmarks. This function returns when the benchmark target terminates.
+    ///
+    /// If the target exits in the middle of a benchmark (usually because the benchmark panicked),
+    /// the benchmark is reported as failed and this returns successfully, so that the remaining
+    /// targets are still run. The benchmarks after it in the same target are not run.
+    #[allow(clippy::too_many_arguments)]
     fn communicate(
         &self,
         child: &mut Child,
+        stderr_tail: &mut StderrTail,
         mut conn: Connection,
         report: &dyn Report,
-        criterion_home: &std::path::Path,
+        reports_directory: &Path,
         model: &mut Model,
+        time_unit: Option<TimeUnit>,
+        rounding: Option<RoundingMode>,
+        statistics: Option<&[SelectedStatistic]>,
+        quick: bool,
+        sample_size: Option<usize>,
+        max_samples: usize,
     ) -> Result<()> {
         let mut context = ReportContext {
-            output_directory: criterion_home.join("reports"),
+            output_directory: reports_directory.to_owned(),
             plot_config: PlotConfiguration {
                 summary_scale: AxisScale::Linear,
             },
+            time_unit,
+            rounding,
+            statistics: statistics.map(<[_]>::to_vec),
         };
         let mut any_from_group_executed = false;
+        // The benchmark the target disconnected in the middle of, if any.
+        let mut interrupted: Option<BenchmarkId> = None;
         loop {
             let message_opt = conn.recv().with_context(|| {
                 format!(
@@ -156,7 +298,11 @@ impl BenchTarget {
                     IncomingMessage::FinishedBenchmarkGroup { group } => {
                         let benchmark_group = model.add_benchmark_group(&self.name, &group);
                         {
-                            let formatter = crate::value_formatter::ValueFormatter::new(&mut conn);
+                            let formatter = crate::value_formatter::ValueFormatter::new(
+                                &mut conn,
+                                context.time_unit,
+                                context.rounding,
+                            );
                             report.summarize(&context, &group, benchmark_group, &formatter);
                             if any_from_group_executed {
                                 report.group_separator();
@@ -165,13 +311,26 @@ impl BenchTarget {
                     }
                     IncomingMessage::BeginningBenchmark { id } => {
                         any_from_group_executed = true;
-                        let mut id = id.into();
+                        let mut id = self.benchmark_id(id.into());
                         model.add_benchmark_id(&self.name, &mut id);
-                        self.run_benchmark(&mut conn, report, model, id, &mut context)?;
+                        let completed = self.run_benchmark(
+                            &mut conn,
+                            report,
+                            model,
+                            &id,
+                            &mut context,
+                            quick,
+                            sample_size,
+                            max_samples,
+                        )?;
+                        if !completed {
+                            interrupted = Some(id);
+                        }
                     }
                     IncomingMessage::SkippingBenchmark { id } => {
-                        let mut id = id.into();
+                        let mut id = self.benchmark_id(id.into());
                         model.add_benchmark_id(&self.name, &mut id);
+                        model.benchmark_skipped(&id);
                     }
                     other => panic!("Unexpected message {:?}", other),
                 }
@@ -187,6 +346,11 @@ impl BenchTarget {
                 Ok(Some(exit_status)) => {
                     if exit_status.success() {
                         return Ok(());
+                    } else if let Some(id) = interrupted {
+                        let error = stderr_tail.failure_message(&self.name, exit_status);
+                        model.benchmark_failed(&id);
+                        report.benchmark_failed(&id, &context, &error);
+                        return Ok(());
                     } else {
                         return Err(anyhow!(
                             "Criterion.rs benchmark target {} exited with {}",
@@ -200,16 +364,29 @@ impl BenchTarget {
         }
     }
 
+    /// Tags the ID with the toolchain this target was compiled with, if any.
+    fn benchmark_id(&self, id: BenchmarkId) -> BenchmarkId {
+        match &self.toolchain {
+            Some(toolchain) => id.with_toolchain(toolchain),
+            None => id,
+        }
+    }
+
     /// Helper function for communicating with the benchmark target about a single benchmark.
+    /// Returns false if the target disconnected before the benchmark completed.
+    #[allow(clippy::too_many_arguments)]
     fn run_benchmark(
         &self,
         conn: &mut Connection,
         report: &dyn Report,
         model: &mut Model,
-        id: BenchmarkId,
+        id: &BenchmarkId,
         context: &mut ReportContext,
-    ) -> Result<()> {
-        report.benchmark_start(&id, context);
+        quick: bool,
+        sample_size: Option<usize>,
+        max_samples: usize,
+    ) -> Result<bool> {
+        report.benchmark_start(id, context);
 
         loop {
             let message = conn.recv().with_context(|| {
@@ -220,18 +397,18 @@ impl BenchTarget {
             })?;
             let message = match message {
                 Some(message) => message,
-                None => return Ok(()),
+                None => return Ok(false),
             };
             match message {
                 IncomingMessage::Warmup { nanos } => {
-                    report.warmup(&id, context, nanos);
+                    report.warmup(id, context, nanos);
                 }
                 IncomingMessage::MeasurementStart {
                     sample_count,
                     estimate_ns,
                     iter_count,
                 } => {
-                    report.measurement_start(&id, context, sample_count, estimate_ns, iter_count);
+                    report.measurement_start(id, context, sample_count, estimate_ns, iter_count);
                 }
                 IncomingMessage::MeasurementComplete {
                     iters,
@@ -239,9 +416,51 @@ impl BenchTarget {
                     plot_config,
                     sampling_method,
                     benchmark_config,
+                    latencies,
+                    overhead,
+                    timestamps,
                 } => {
                     context.plot_config = plot_config;
-                    report.analysis(&id, context);
+                    report.analysis(id, context);
+
+                    // The sample size is only a request; the analysis uses however many samples
+                    // the benchmark actually collected.
+                    if let Some(sample_size) = sample_size {
+                        if iters.len() != sample_size {
+                            warn!(
+                                "Benchmark {} collected {} samples rather than the {} requested \
+                                by --sample-size. It may not support setting the sample size.",
+                                id.as_title(),
+                                iters.len(),
+                                sample_size
+                            );
+                        }
+                    }
+                    let (iters, times) = model.pool_samples(id, iters, times);
+                    // Pooled samples only have the timestamps of the latest run, so they fall
+                    // back to the sample indices.
+                    let timestamps =
+                        timestamps.filter(|timestamps| timestamps.len() == iters.len());
+
+                    // Enormous samples are cut down to a random subset, which bounds the memory
+                    // and time taken by the bootstrap while keeping the statistics representative.
+                    let (iters, times, timestamps) = if iters.len() > max_samples {
+                        warn!(
+                            "Benchmark {} collected {} samples, so only a random subset of {} \
+                            of them is analyzed. Raise --max-samples to analyze more.",
+                            id.as_title(),
+                            iters.len(),
+                            max_samples
+                        );
+                        let indices = reservoir::reservoir_indices(iters.len(), max_samples);
+                        (
+                            reservoir::select(&iters, &indices),
+                            reservoir::select(&times, &indices),
+                            timestamps.map(|timestamps| reservoir::select(&timestamps, &indices)),
+                        )
+                    } else {
+                        (iters, times, timestamps)
+                    };
 
                     let avg_values: Vec<f64> = iters
                         .iter()
@@ -255,35 +474,65 @@ impl BenchTarget {
                         that your routine is correctly measured.", id.as_title());
                         // Create and drop a value formatter because the benchmark will be waiting
                         // for that
-                        crate::value_formatter::ValueFormatter::new(conn);
-                        return Ok(());
+                        crate::value_formatter::ValueFormatter::new(
+                            conn,
+                            context.time_unit,
+                            context.rounding,
+                        );
+                        return Ok(true);
                     }
 
-                    let saved_stats = model.get_last_sample(&id).cloned();
-
-                    let benchmark_config: crate::analysis::BenchmarkConfig =
+                    let mut benchmark_config: crate::analysis::BenchmarkConfig =
                         benchmark_config.into();
 
-                    let measured_data = crate::analysis::analysis(
+                    // In quick mode, skip the comparison and use fewer resamples.
+                    let saved_stats = if quick {
+                        benchmark_config.nresamples = benchmark_config
+                            .nresamples
+                            .min(crate::analysis::QUICK_NRESAMPLES);
+                        None
+                    } else {
+                        model.get_baseline(id)
+                    };
+
+                    let mut measured_data = crate::analysis::analysis(
                         &benchmark_config,
                         id.throughput.clone(),
                         crate::analysis::MeasuredValues {
                             iteration_count: &iters,
                             sample_values: &times,
                             avg_values: &avg_values,
+                            timestamps: timestamps.as_deref(),
                         },
                         saved_stats.as_ref().map(|stats| {
                             let measured_values = crate::analysis::MeasuredValues {
                                 iteration_count: &stats.iterations,
                                 sample_values: &stats.values,
                                 avg_values: &stats.avg_values,
+                                timestamps: stats.timestamps.as_deref(),
                             };
                             (measured_values, &stats.estimates)
                         }),
                         sampling_method,
+                        latencies.as_deref(),
+                        overhead,
+                        context.statistics.as_deref(),
                     );
+                    measured_data.overlay_baselines = model.get_overlay_baselines(id);
 
-                    if let Err(e) = model.benchmark_complete(&id, &measured_data) {
+                    if let Some(fraction) = crate::analysis::overhead_fraction(&measured_data) {
+                        if fraction > crate::analysis::HIGH_OVERHEAD_FRACTION {
+                            warn!(
+                                "The measurement overhead of benchmark {} is {:.0}% of its time \
+                                per iteration, so the results may mostly measure the harness. \
+                                Consider doing more work per iteration.",
+                                id.as_title(),
+                                fraction * 100.0
+                            );
+                        }
+                    }
+
+                    if let Err(e) = model.benchmark_complete(&self.name, id, &measured_data) {
                         error!(
                             "Failed to save results for target {} benchmark {}: {}",
                             self.name,
@@ -293,15 +542,19 @@ impl BenchTarget {
                     }
 
                     {
-                        let formatter = crate::value_formatter::ValueFormatter::new(conn);
-                        report.measurement_complete(&id, context, &measured_data, &formatter);
+                        let formatter = crate::value_formatter::ValueFormatter::new(
+                            conn,
+                            context.time_unit,
+                            context.rounding,
+                        );
+                        report.measurement_complete(id, context, &measured_data, &formatter);
 
-                        match model.load_history(&id) {
-                            Ok(history) => report.history(context, &id, &history, &formatter),
+                        match model.load_history(id) {
+                            Ok(history) => report.history(context, id, &history, &formatter),
                             Err(e) => error!("Failed to load historical data: {:?}", e),
                         }
                     }
-                    return Ok(());
+                    return Ok(true);
                 }
                 other => panic!("Unexpected message {:?}", other),
             }
@@ -341,3 +594,61 @@ fn dylib_search_path(linked_paths: &[PathBuf]) -> Result<OsString> {
     std::env::join_paths(&dylib_path)
         .with_context(|| format!("Failed to join dynamic lib search paths together. Does {} have an unterminated quote character? Paths:\n{:?}", dylib_path_envvar(), &dylib_path))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_panic_message() {
+        let output = [
+            "Benchmarking fib/20",
+            "thread 'main' panicked at src/benches/fib.rs:12:5:",
+            "index out of bounds: the len is 3 but the index is 7",
+            "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace",
+        ];
+        assert_eq!(
+            Some(
+                "thread 'main' panicked at src/benches/fib.rs:12:5:\n\
+                index out of bounds: the len is 3 but the index is 7"
+                    .to_owned()
+            ),
+            panic_message(output.iter().copied())
+        );
+
+        let output = ["thread 'main' panicked at 'boom', src/lib.rs:1:1", ""];
+        assert_eq!(
+            Some("thread 'main' panicked at 'boom', src/lib.rs:1:1".to_owned()),
+            panic_message(output.iter().copied())
+        );
+        assert_eq!(None, panic_message(["Benchmarking fib/20"].iter().copied()));
+    }
+
+    #[test]
+    fn test_failure_message_falls_back_to_exit_status() {
+        let tail = |lines: &[&str]| StderrTail {
+            lines: Arc::new(Mutex::new(lines.iter().map(|&l| l.to_owned()).collect())),
+            thread: None,
+        };
+        let expected = "Benchmark target fib exited with exit status: 101";
+
+        // A panic message which isn't in the standard format isn't recognized.
+        let mut localized = tail(&["Le fil 'main' a paniqué à src/lib.rs:1:1", "boum"]);
+        assert_eq!(
+            expected,
+            localized.failure_message("fib", "exit status: 101")
+        );
+
+        // Nothing is kept if stderr wasn't captured.
+        assert_eq!(
+            expected,
+            tail(&[]).failure_message("fib", "exit status: 101")
+        );
+
+        let mut panicked = tail(&["thread 'main' panicked at 'boom', src/lib.rs:1:1"]);
+        assert_eq!(
+            "thread 'main' panicked at 'boom', src/lib.rs:1:1",
+            panicked.failure_message("fib", "exit status: 101")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+//! Support for checking benchmarks against the absolute time budgets set in criterion.toml (see
+//! `--fail-on-budget`).
+
+use crate::format;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{anyhow, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Returns the budget (in nanoseconds) of the given benchmark, if one is set either for it or for
+/// its group.
+fn budget_for(budgets: &HashMap<String, f64>, id: &BenchmarkId) -> Option<f64> {
+    budgets
+        .get(id.as_title())
+        .or_else(|| budgets.get(&id.group_id))
+        .copied()
+}
+
+/// Returns the fraction of the budget left unused by the given mean time. This is negative if the
+/// benchmark is over budget.
+fn headroom(mean: f64, budget: f64) -> f64 {
+    1.0 - mean / budget
+}
+
+/// Report which records the mean time of every benchmark with a budget, so that their headroom
+/// can be listed (and the run failed if any are over budget) once all benchmarks have been
+/// executed.
+pub struct BudgetGate {
+    budgets: HashMap<String, f64>,
+    fail: bool,
+    // The title, mean time and budget of each budgeted benchmark, in the order they were run.
+    measured: RefCell<Vec<(String, f64, f64)>>,
+}
+impl BudgetGate {
+    pub fn new(budgets: HashMap<String, f64>, fail: bool) -> BudgetGate {
+        BudgetGate {
+            budgets,
+            fail,
+            measured: RefCell::new(vec![]),
+        }
+    }
+
+    /// Prints the headroom of each budgeted benchmark, then lists those over budget, if there were
+    /// any. This is an error if `fail` is set and a warning otherwise.
+    pub fn check(&self) -> Result<()> {
+        let measured = self.measured.borrow();
+        if measured.is_empty() {
+            return Ok(());
+        }
+
+        eprintln!("Budgets:");
+        for (title, mean, budget) in measured.iter() {
+            eprintln!(
+                "{:<40} mean {} of {} ({} headroom)",
+                crate::ansi::strip(title),
+                format::time(*mean).trim(),
+                format::time(*budget).trim(),
+                format::change(headroom(*mean, *budget), true).trim()
+            );
+        }
+
+        let over: Vec<String> = measured
+            .iter()
+            .filter(|(_, mean, budget)| mean > budget)
+            .map(|(title, mean, budget)| {
+                format!(
+                    "{} ({} over its budget of {})",
+                    title,
+                    format::time(mean - budget).trim(),
+                    format::time(*budget).trim()
+                )
+            })
+            .collect();
+        if over.is_empty() {
+            return Ok(());
+        }
+        let message = format!(
+            "The following benchmarks exceeded their time budget:\n{}",
+            over.join("\n")
+        );
+        if self.fail {
+            Err(anyhow!(message))
+        } else {
+            warn!("{}", message);
+            Ok(())
+        }
+    }
+}
+impl Report for BudgetGate {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        if let Some(budget) = budget_for(&self.budgets, id) {
+            let mean = measurements.absolute_estimates.mean.point_estimate;
+            let mut measured = self.measured.borrow_mut();
+            // A benchmark measured again (see `--stabilize`) is judged on its latest measurement.
+            measured.retain(|(title, _, _)| title != id.as_title());
+            measured.push((id.as_title().to_owned(), mean, budget));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_budget_for() {
+        let mut budgets = HashMap::new();
+        budgets.insert("fib".to_owned(), 1000.0);
+        budgets.insert("fib/20".to_owned(), 50.0);
+        let id = |function: &str| BenchmarkId::new("fib".into(), Some(function.into()), None, None);
+
+        assert_eq!(Some(50.0), budget_for(&budgets, &id("20")));
+        assert_eq!(Some(1000.0), budget_for(&budgets, &id("30")));
+        let other = BenchmarkId::new("sort".into(), None, None, None);
+        assert_eq!(None, budget_for(&budgets, &other));
+    }
+
+    #[test]
+    fn test_headroom() {
+        assert!((headroom(4.0, 5.0) - 0.2).abs() < 1e-12);
+        assert!((headroom(6.0, 5.0) + 0.2).abs() < 1e-12);
+    }
+}
@@ -3,9 +3,17 @@
 //! emits.
 
 use crate::bench_target::BenchTarget;
-use anyhow::{Context, Result};
-use std::path::PathBuf;
+use crate::config::BuildMode;
+use anyhow::{bail, Context, Result};
+use std::ffi::OsString;
+use std::fs::File;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Stdio};
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+// The file in the Criterion home directory which records the benchmarks compiled by the last run.
+const BUILD_CACHE_FILE: &str = "build-cache.json";
 
 #[derive(Debug)]
 /// Enum representing the different ways calling Cargo might fail
@@ -48,6 +56,7 @@ enum Message {
     CompilerArtifact {
         target: Target,
         executable: Option<PathBuf>,
+        manifest_path: Option<PathBuf>,
     },
 
     // TODO: Delete these and replace with a #[serde(other)] variant
@@ -62,7 +71,7 @@ enum Message {
     BuildFinished {},
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct CompiledBenchmarks {
     pub targets: Vec<BenchTarget>,
     pub library_paths: Vec<PathBuf>,
@@ -71,15 +80,24 @@ pub struct CompiledBenchmarks {
 /// Launches `cargo bench` with the given additional arguments, with some additional arguments to
 /// list out the benchmarks and their executables and parses that information. This compiles the
 /// benchmarks but doesn't run them. Returns information on the compiled benchmarks that we can use
-/// to run them directly.
-pub fn compile(debug_build: bool, cargo_args: &[std::ffi::OsString]) -> Result<CompiledBenchmarks> {
+/// to run them directly. If a toolchain is given, cargo is invoked through rustup as
+/// `cargo +<toolchain>`.
+pub fn compile(
+    debug_build: bool,
+    toolchain: Option<&str>,
+    cargo_args: &[std::ffi::OsString],
+) -> Result<CompiledBenchmarks> {
     let subcommand: &[&'static str] = if debug_build {
         &["test", "--benches"]
     } else {
         &["bench"]
     };
 
-    let mut cargo = Command::new("cargo")
+    let mut cargo = Command::new("cargo");
+    if let Some(toolchain) = toolchain {
+        cargo.arg(format!("+{}", toolchain));
+    }
+    let mut cargo = cargo
         .args(subcommand)
         .args(cargo_args)
         .args(&["--no-run", "--message-format", "json-render-diagnostics"])
@@ -101,7 +119,11 @@ pub fn compile(debug_build: bool, cargo_args: &[std::ffi::OsString]) -> Result<C
     for message in stream {
         let message = message.context("Failed to parse message from cargo")?;
         match message {
-            Message::CompilerArtifact { target, executable } => {
+            Message::CompilerArtifact {
+                target,
+                executable,
+                manifest_path,
+            } => {
                 if target
                     .kind
                     .iter()
@@ -112,6 +134,8 @@ pub fn compile(debug_build: bool, cargo_args: &[std::ffi::OsString]) -> Result<C
                         targets.push(BenchTarget {
                             name: target.name,
                             executable,
+                            manifest_path,
+                            toolchain: toolchain.map(ToOwned::to_owned),
                         });
                     }
                 }
@@ -146,3 +170,192 @@ pub fn compile(debug_build: bool, cargo_args: &[std::ffi::OsString]) -> Result<C
         })
     }
 }
+
+/// Produces the list of benchmark targets to run, either by compiling them or, depending on the
+/// build mode, by reusing the executables compiled by a previous run. Each toolchain has its own
+/// build cache.
+pub fn compile_or_reuse(
+    build_mode: BuildMode,
+    criterion_home: &Path,
+    debug_build: bool,
+    toolchain: Option<&str>,
+    cargo_args: &[OsString],
+) -> Result<CompiledBenchmarks> {
+    let cache_path = match toolchain {
+        Some(toolchain) => criterion_home.join(format!("build-cache-{}.json", toolchain)),
+        None => criterion_home.join(BUILD_CACHE_FILE),
+    };
+    match build_mode {
+        BuildMode::Always => {}
+        BuildMode::Reuse => match load_build_cache(&cache_path, debug_build, cargo_args) {
+            Ok(cache) => match cache.check_fresh() {
+                Ok(()) => {
+                    info!("Benchmarks are unchanged since the last build; skipping cargo.");
+                    return Ok(cache.into_compiled_benchmarks(toolchain));
+                }
+                Err(e) => info!("Rebuilding benchmarks: {}", e),
+            },
+            Err(e) => info!("Not reusing the previous build: {:?}", e),
+        },
+        BuildMode::Never => {
+            let cache = load_build_cache(&cache_path, debug_build, cargo_args).context(
+                "--no-build requires the benchmarks to have been built by a previous run",
+            )?;
+            cache.check_executables()?;
+            return Ok(cache.into_compiled_benchmarks(toolchain));
+        }
+    }
+
+    let build_start = SystemTime::now();
+    let compiled = compile(debug_build, toolchain, cargo_args)?;
+    if let Err(e) = save_build_cache(&cache_path, build_start, debug_build, cargo_args, &compiled) {
+        error!("Failed to save the build cache: {:?}", e);
+    }
+    Ok(compiled)
+}
+
+// Identifies a version of a file without reading its contents.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Fingerprint {
+    len: u64,
+    modified: SystemTime,
+}
+impl Fingerprint {
+    fn of(path: &Path) -> Result<Fingerprint> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read metadata of {:?}", path))?;
+        Ok(Fingerprint {
+            len: metadata.len(),
+            modified: metadata.modified()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CachedTarget {
+    name: String,
+    executable: PathBuf,
+    fingerprint: Fingerprint,
+    #[serde(default)]
+    manifest_path: Option<PathBuf>,
+}
+
+// The benchmark executables produced by the last build, along with what is needed to tell whether
+// they are still up to date.
+#[derive(Serialize, Deserialize, Debug)]
+struct BuildCache {
+    debug_build: bool,
+    cargo_args: Vec<String>,
+    built_at: SystemTime,
+    targets: Vec<CachedTarget>,
+    library_paths: Vec<PathBuf>,
+}
+impl BuildCache {
+    // Checks that the executables haven't been rebuilt or removed since the cache was saved.
+    fn check_executables(&self) -> Result<()> {
+        for target in &self.targets {
+            match Fingerprint::of(&target.executable) {
+                Ok(fingerprint) if fingerprint == target.fingerprint => {}
+                Ok(_) => bail!("benchmark executable {:?} has changed", target.executable),
+                Err(_) => bail!("benchmark executable {:?} is missing", target.executable),
+            }
+        }
+        Ok(())
+    }
+
+    // Checks that the executables are unchanged and that none of the files in the current
+    // directory have been modified since the build started.
+    fn check_fresh(&self) -> Result<()> {
+        self.check_executables()?;
+
+        let entries = WalkDir::new(".")
+            .into_iter()
+            .filter_entry(|entry| {
+                // Skip build output and hidden directories such as .git
+                let name = entry.file_name().to_string_lossy();
+                entry.depth() == 0 || !(name == "target" || name.starts_with('.'))
+            })
+            // Ignore errors.
+            .filter_map(::std::result::Result::ok)
+            .filter(|entry| entry.file_type().is_file());
+        for entry in entries {
+            let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+            match modified {
+                Some(modified) if modified <= self.built_at => {}
+                _ => bail!("{:?} was modified since the last build", entry.path()),
+            }
+        }
+        Ok(())
+    }
+
+    fn into_compiled_benchmarks(self, toolchain: Option<&str>) -> CompiledBenchmarks {
+        CompiledBenchmarks {
+            targets: self
+                .targets
+                .into_iter()
+                .map(|target| BenchTarget {
+                    name: target.name,
+                    executable: target.executable,
+                    manifest_path: target.manifest_path,
+                    toolchain: toolchain.map(ToOwned::to_owned),
+                })
+                .collect(),
+            library_paths: self.library_paths,
+        }
+    }
+}
+
+fn args_to_strings(cargo_args: &[OsString]) -> Vec<String> {
+    cargo_args
+        .iter()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+fn load_build_cache(path: &Path, debug_build: bool, cargo_args: &[OsString]) -> Result<BuildCache> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open build cache {:?}", path))?;
+    let cache: BuildCache = serde_json::from_reader(file)
+        .with_context(|| format!("Failed to read build cache {:?}", path))?;
+    if cache.debug_build != debug_build || cache.cargo_args != args_to_strings(cargo_args) {
+        bail!("The cached benchmarks were built with different options");
+    }
+    Ok(cache)
+}
+
+fn save_build_cache(
+    path: &Path,
+    built_at: SystemTime,
+    debug_build: bool,
+    cargo_args: &[OsString],
+    compiled: &CompiledBenchmarks,
+) -> Result<()> {
+    let targets = compiled
+        .targets
+        .iter()
+        .map(|target| {
+            Ok(CachedTarget {
+                name: target.name.clone(),
+                executable: target.executable.clone(),
+                fingerprint: Fingerprint::of(&target.executable)?,
+                manifest_path: target.manifest_path.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let cache = BuildCache {
+        debug_build,
+        cargo_args: args_to_strings(cargo_args),
+        built_at,
+        targets,
+        library_paths: compiled.library_paths.clone(),
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let file = File::create(path).with_context(|| format!("Failed to create file {:?}", path))?;
+    serde_json::to_writer(file, &cache)
+        .with_context(|| format!("Failed to write build cache {:?}", path))?;
+    Ok(())
+}
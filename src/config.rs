@@ -1,10 +1,16 @@
-use anyhow::{Context, Result};
+use crate::analysis::LATENCY_PERCENTILES;
+use crate::derived::DerivedMetric;
+use crate::estimate::Statistic;
+use crate::kde::Estimator;
+use anyhow::{bail, Context, Result};
 use std::borrow::ToOwned;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsString;
 use std::fs::File;
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 
 #[derive(Deserialize, Debug, Clone, Copy)]
 pub struct Color {
@@ -21,6 +27,17 @@ const DARK_ORANGE: Color = Color { r: 5, g: 127, b: 0 };
 const DARK_RED: Color = Color { r: 7, g: 26, b: 28 };
 
 const NUM_COLORS: usize = 8;
+/// The colors of the regressions and improvements marked on the plots, unless configured.
+pub const REGRESSED_PLOT_COLOR: Color = Color {
+    r: 214,
+    g: 39,
+    b: 40,
+};
+pub const IMPROVED_PLOT_COLOR: Color = Color {
+    r: 44,
+    g: 160,
+    b: 44,
+};
 #[rustfmt::skip]
 static COMPARISON_COLORS: [Color; NUM_COLORS] = [
     Color { r: 8, g: 34, b: 34 },
@@ -48,6 +65,15 @@ pub struct Colors {
     pub severe_outlier: Color,
     /// Sequence of colors used for the line chart. Defaults to COMPARISON_COLORS
     pub comparison_colors: Vec<Color>,
+    /// The color used to highlight regressions in the reports and the command-line output.
+    /// Defaults to red
+    pub regressed: Option<Color>,
+    /// The color used to highlight improvements in the reports and the command-line output.
+    /// Defaults to green
+    pub improved: Option<Color>,
+    /// The color used for changes that aren't significant in the reports and the command-line
+    /// output. Defaults to the normal text color
+    pub no_change: Option<Color>,
 }
 impl Default for Colors {
     fn default() -> Self {
@@ -58,10 +84,279 @@ impl Default for Colors {
             mild_outlier: DARK_ORANGE,
             severe_outlier: DARK_RED,
             comparison_colors: COMPARISON_COLORS.to_vec(),
+            regressed: None,
+            improved: None,
+            no_change: None,
         }
     }
 }
 
+/// Where to draw the legend of a chart. `Auto` puts it in whichever corner has the fewest data
+/// points.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LegendPosition {
+    Auto,
+    UpperLeft,
+    UpperMiddle,
+    UpperRight,
+    MiddleLeft,
+    MiddleRight,
+    LowerLeft,
+    LowerMiddle,
+    LowerRight,
+}
+
+/// The kernel of the density estimates in the plots.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KdeKernel {
+    Gaussian,
+    Epanechnikov,
+}
+
+/// The legend position of each type of plot, from the `[plots.legend]` section of criterion.toml.
+/// Unset plot types keep their usual position.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct LegendPositions {
+    pub pdf: Option<LegendPosition>,
+    pub distributions: Option<LegendPosition>,
+    pub iteration_times: Option<LegendPosition>,
+    pub regression: Option<LegendPosition>,
+    pub summary: Option<LegendPosition>,
+    pub history: Option<LegendPosition>,
+    pub t_test: Option<LegendPosition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct PlotOptions {
+    /// Append the number of samples (eg. "(n=100)") to the captions of the distribution plots.
+    /// Only supported by the plotters backend. Defaults to false
+    pub show_sample_count: bool,
+    /// Append the throughput of the typical estimate (eg. "(1.2 GiB/s)") to the captions of the
+    /// distribution and regression plots of benchmarks which have a throughput. Only supported by
+    /// the plotters backend. Defaults to false
+    pub show_throughput: bool,
+    /// Embed tooltips showing the exact values of the data points in the SVG plots. Only
+    /// supported by the plotters backend. Defaults to false
+    pub interactive_svg: bool,
+    /// The margin around each chart, as a percentage of the chart size. Only supported by the
+    /// plotters backend. Defaults to 5
+    pub margin: f64,
+    /// The size of the axis label areas, as a percentage of the chart size. Only supported by the
+    /// plotters backend. Defaults to 5
+    pub label_area: f64,
+    /// The radius of the markers in the scatter plots, in pixels. Only supported by the plotters
+    /// backend. Defaults to 3
+    pub point_size: u32,
+    /// The opacity of the markers in the scatter plots, from 0 (transparent) to 1 (opaque). Only
+    /// supported by the plotters backend. Defaults to 1
+    pub point_alpha: f64,
+    /// Mark the median and the confidence interval of the mean of each benchmark on the violin
+    /// plots. Defaults to false
+    pub violin_estimates: bool,
+    /// The vertical space given to each benchmark in the violin plots, in pixels. Defaults to 18
+    /// for the plotters backend and 25 for the gnuplot backend
+    pub violin_spacing: Option<u32>,
+    /// The thickness of each violin, as a fraction of the spacing. Values above 1 make the violins
+    /// overlap. Defaults to 1 for the plotters backend and 0.9 for the gnuplot backend
+    pub violin_width: Option<f64>,
+    /// The number of points at which the kernel density estimates are evaluated to draw the PDF
+    /// and distribution plots. Larger values give smoother curves but take longer to plot.
+    /// Defaults to 500
+    pub kde_points: usize,
+    /// The kernel of the kernel density estimates, either "gaussian" or "epanechnikov". The
+    /// Epanechnikov kernel has bounded support, so it doesn't smear the density as far past the
+    /// samples. Defaults to gaussian
+    pub kde_kernel: KdeKernel,
+    /// Estimate the densities of the sample times and of the estimates in the log domain, so that
+    /// none of the density of these strictly positive values is put below zero. Defaults to false
+    pub kde_log_domain: bool,
+    /// The largest number of points drawn on the iteration times and regression plots. Larger
+    /// samples are downsampled to this many points for drawing only; the statistics and the
+    /// regression line still use every point. Defaults to no limit
+    pub max_plot_points: Option<usize>,
+    /// Shade the region within the noise threshold of the baseline's estimate on the absolute
+    /// distribution plots of benchmarks that have a baseline. Defaults to false
+    pub abs_noise_band: bool,
+    /// How far the y axis of the distribution plots extends above the highest point of the curve
+    /// and the point estimate, as a factor of its height. Defaults to 1.1
+    pub y_headroom: f64,
+    /// Use a logarithmic y axis on the linear regression plots, so that samples spanning several
+    /// orders of magnitude remain distinguishable. Defaults to false
+    pub regression_log_y: bool,
+    /// Performance targets, in nanoseconds, keyed by benchmark ID (eg. "fib/20") or group ID. The
+    /// summary plots draw the target of the group as a line and highlight the benchmarks which
+    /// are slower than their target. Defaults to no targets
+    pub targets: HashMap<String, f64>,
+    /// Stamp the time of the run in the bottom-right corner of each plot, so that plots copied out
+    /// of the report can still be traced back to it. Only supported by the plotters backend.
+    /// Defaults to false
+    pub timestamp: bool,
+    /// Append the abbreviated hash of the checked-out git commit to the timestamp. Defaults to
+    /// false
+    pub timestamp_git_hash: bool,
+    /// Where to draw the legend of each type of plot (eg. `pdf = "upper_left"`), or "auto" to pick
+    /// the corner with the fewest data points. Only supported by the plotters backend. Defaults to
+    /// the usual position of each plot
+    pub legend: LegendPositions,
+    /// The number of significant figures shown in the axis labels, from 1 to 15. Only supported
+    /// by the plotters backend. Defaults to the precision chosen by plotters for each label
+    pub axis_significant_figures: Option<usize>,
+    /// A factor applied to the width of the lines and the outlines of the shaded areas. The widths
+    /// are also scaled with the width of the plot, so thumbnails get thinner lines. Only supported
+    /// by the plotters backend. Defaults to 1
+    pub stroke_width: f64,
+    /// Draw the cumulative distribution of the sample times of each benchmark to cdf.svg. Only
+    /// supported by the plotters backend. Defaults to false
+    pub cdf: bool,
+    /// The percentiles marked with horizontal reference lines on the CDF plots, each between 0 and
+    /// 100. Defaults to 50, 95 and 99
+    pub cdf_percentiles: Vec<f64>,
+    /// The percentiles of the bootstrap distribution (eg. `[25, 75]` for the interquartile range)
+    /// between which the absolute distribution plots are shaded. Defaults to shading the
+    /// confidence interval
+    pub band_percentiles: Option<[f64; 2]>,
+    /// Mark the runs on the history plots whose change from the previous run was a significant
+    /// regression or improvement, in the regressed and improved colors. Defaults to false
+    pub history_changes: bool,
+    /// Shade the band on the absolute distribution plots with a gradient which is most opaque at
+    /// the point estimate and fades toward the bounds, rather than with a flat fill. Only supported
+    /// by the plotters backend. Defaults to false
+    pub gradient_bands: bool,
+    /// Draw the gradient of `gradient_bands` as a few nested shapes, each a single path, rather
+    /// than as many adjacent slices. Some SVG viewers show seams between the translucent slices,
+    /// which this avoids at the cost of a stepped rather than smooth gradient. Only supported by
+    /// the plotters backend. Defaults to false
+    pub combined_fills: bool,
+    /// Mirror the layout of the charts for reports in right-to-left languages: the y-axis is
+    /// drawn on the right and the legends on the opposite side, while the data still runs from
+    /// left to right. Only supported by the plotters backend. Defaults to false
+    pub right_to_left: bool,
+    /// Overlay the median of each window of this many consecutive samples on the iteration times
+    /// plots, which shows drift in the measurements more clearly than the scattered samples.
+    /// Defaults to no overlay
+    pub rolling_median: Option<usize>,
+    /// Plot the iteration times against the time at which each sample was taken, in seconds since
+    /// the first sample, for benchmarks which record the timestamps of their samples. Released
+    /// versions of Criterion.rs don't, so their benchmarks are plotted against the sample index as
+    /// usual. Defaults to false
+    pub sample_timestamps: bool,
+    /// Copied from the top-level `speedup_threshold` setting, for the change plots.
+    #[serde(skip)]
+    pub speedup_threshold: Option<f64>,
+}
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            show_sample_count: false,
+            show_throughput: false,
+            interactive_svg: false,
+            margin: 5.0,
+            label_area: 5.0,
+            point_size: 3,
+            point_alpha: 1.0,
+            violin_estimates: false,
+            violin_spacing: None,
+            violin_width: None,
+            kde_points: 500,
+            kde_kernel: KdeKernel::Gaussian,
+            kde_log_domain: false,
+            max_plot_points: None,
+            abs_noise_band: false,
+            y_headroom: 1.1,
+            regression_log_y: false,
+            targets: HashMap::new(),
+            timestamp: false,
+            timestamp_git_hash: false,
+            legend: LegendPositions::default(),
+            axis_significant_figures: None,
+            stroke_width: 1.0,
+            cdf: false,
+            cdf_percentiles: vec![50.0, 95.0, 99.0],
+            band_percentiles: None,
+            history_changes: false,
+            gradient_bands: false,
+            combined_fills: false,
+            right_to_left: false,
+            rolling_median: None,
+            sample_timestamps: false,
+            speedup_threshold: None,
+        }
+    }
+}
+impl PlotOptions {
+    /// The estimator for the densities of the sample times and of the estimates.
+    pub fn kde_estimator(&self) -> Estimator {
+        Estimator {
+            kernel: self.kde_kernel,
+            log_domain: self.kde_log_domain,
+        }
+    }
+
+    /// Clamp the layout and marker settings to sane bounds so that the charts don't collapse.
+    fn validated(mut self) -> Self {
+        fn clamp(name: &str, value: f64, min: f64, max: f64) -> f64 {
+            if value.is_nan() || value < min || value > max {
+                let clamped = if value > max { max } else { min };
+                warn!(
+                    "Plot setting {} = {} is outside the range {}-{}, using {} instead.",
+                    name, value, min, max, clamped
+                );
+                clamped
+            } else {
+                value
+            }
+        }
+        self.margin = clamp("margin", self.margin, 0.0, 20.0);
+        self.label_area = clamp("label_area", self.label_area, 1.0, 25.0);
+        self.point_size = clamp("point_size", self.point_size as f64, 1.0, 20.0) as u32;
+        self.point_alpha = clamp("point_alpha", self.point_alpha, 0.0, 1.0);
+        self.violin_spacing = self
+            .violin_spacing
+            .map(|spacing| clamp("violin_spacing", spacing as f64, 5.0, 200.0) as u32);
+        self.violin_width = self
+            .violin_width
+            .map(|width| clamp("violin_width", width, 0.1, 3.0));
+        self.kde_points = clamp("kde_points", self.kde_points as f64, 50.0, 10_000.0) as usize;
+        self.max_plot_points = self
+            .max_plot_points
+            .map(|points| clamp("max_plot_points", points as f64, 10.0, f64::INFINITY) as usize);
+        self.y_headroom = clamp("y_headroom", self.y_headroom, 1.0, 2.0);
+        self.rolling_median = self
+            .rolling_median
+            .map(|window| clamp("rolling_median", window as f64, 2.0, 1_000.0) as usize);
+        self.stroke_width = clamp("stroke_width", self.stroke_width, 0.25, 5.0);
+        self.axis_significant_figures = self
+            .axis_significant_figures
+            .map(|figures| clamp("axis_significant_figures", figures as f64, 1.0, 15.0) as usize);
+        self.cdf_percentiles.retain(|&percentile| {
+            let valid = (0.0..=100.0).contains(&percentile);
+            if !valid {
+                warn!(
+                    "Plot setting cdf_percentiles contains {}, which isn't between 0 and 100. \
+                     Ignoring it.",
+                    percentile
+                );
+            }
+            valid
+        });
+        if let Some([lower, upper]) = self.band_percentiles {
+            if !(0.0 <= lower && lower < upper && upper <= 100.0) {
+                warn!(
+                    "Plot setting band_percentiles = [{}, {}] isn't an increasing pair of \
+                     percentiles between 0 and 100, shading the confidence interval instead.",
+                    lower, upper
+                );
+                self.band_percentiles = None;
+            }
+        }
+        self
+    }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(default)]
 /// Struct to hold the various configuration settings that we can read from the TOML config file.
@@ -70,20 +365,95 @@ struct TomlConfig {
     pub criterion_home: Option<PathBuf>,
     /// Output format
     pub output_format: Option<String>,
+    /// Progress format ("full" or "compact")
+    pub progress: Option<String>,
     /// Plotting backend
     pub plotting_backend: Option<String>,
+    /// Benchmark ordering used in the reports
+    pub order: Option<String>,
+    /// Number of stored runs averaged together to form the comparison baseline
+    pub baseline_window: Option<usize>,
+    /// External store to load the comparison baselines from, eg. "sqlite:criterion.sqlite"
+    pub baseline_store: Option<String>,
+    /// Largest number of samples of a benchmark which are analyzed
+    pub max_samples: Option<usize>,
+    /// Unit used for all measured times in the textual output
+    pub time_unit: Option<String>,
+    /// How the estimates are rounded for display ("half-even" or "half-up")
+    pub rounding: Option<String>,
+    /// The statistics to compute, compare and report (eg. "mean,p99"), as for `--statistics`
+    pub statistics: Option<String>,
 
     /// The colors used for the charts. Users may wish to override this to accommodate
     /// colorblindness, or just to make things look prettier.
     pub colors: Colors,
+
+    /// Additional settings that control the content of the generated plots.
+    pub plots: PlotOptions,
+
+    /// Glob patterns matching the titles of benchmarks which should never cause
+    /// `--fail-on-regression` or `--fail-on-change` to fail the run.
+    pub ungated_benchmarks: Vec<String>,
+
+    /// Absolute time budgets, in nanoseconds, keyed by benchmark ID (eg. "fib/20") or group ID.
+    /// The mean time of each budgeted benchmark is checked against its budget after the run (see
+    /// `--fail-on-budget`), and the summary plots draw the budgets as targets.
+    pub budgets: HashMap<String, f64>,
+
+    /// Weights applied to the benchmarks in the geometric means of their groups (see
+    /// `--geometric-mean`), keyed by benchmark ID (eg. "fib/20"). Benchmarks without a weight have
+    /// a weight of 1.
+    pub weights: HashMap<String, f64>,
+
+    /// Changes in the history tables of the reports whose magnitude is below this fraction (eg.
+    /// 0.01 for 1%), or which aren't statistically significant, are shown as "—" rather than as a
+    /// number. Defaults to showing every change.
+    pub min_displayed_change: Option<f64>,
+
+    /// Changes in time which are at least this factor (eg. 2.0) faster or slower are shown as a
+    /// speedup or slowdown factor, eg. "3.2000x faster", rather than as a percentage, in the
+    /// command-line output, the reports and the change plots. The JSON output includes the
+    /// factor of every change regardless. Defaults to always showing percentages.
+    pub speedup_threshold: Option<f64>,
+
+    /// Metrics computed from the estimates of each benchmark and shown in the reports and JSON
+    /// messages, keyed by name. Each is an arithmetic expression over the fields `typical`,
+    /// `mean`, `median`, `median_abs_dev`, `std_dev`, `slope` (all in nanoseconds),
+    /// `throughput_bytes` and `throughput_elements`, eg. `ns_per_byte = "mean / throughput_bytes"`.
+    pub derived_metrics: BTreeMap<String, String>,
+
+    /// The paths (relative to the current directory) whose changes cause each benchmark target to
+    /// be run with `--since`, keyed by the name of the target.
+    pub benchmark_paths: HashMap<String, Vec<PathBuf>>,
+
+    /// The names of environment variables (eg. CI build numbers or branch names) whose values are
+    /// saved with each run as metadata, as for `--metadata-env`.
+    pub metadata_env: Vec<String>,
 }
 impl Default for TomlConfig {
     fn default() -> Self {
         TomlConfig {
             criterion_home: None,
             output_format: None,
+            progress: None,
             plotting_backend: None,
+            order: None,
+            baseline_window: None,
+            baseline_store: None,
+            max_samples: None,
+            time_unit: None,
+            rounding: None,
+            statistics: None,
             colors: Default::default(),
+            plots: Default::default(),
+            ungated_benchmarks: vec![],
+            budgets: HashMap::new(),
+            weights: HashMap::new(),
+            min_displayed_change: None,
+            speedup_threshold: None,
+            derived_metrics: BTreeMap::new(),
+            benchmark_paths: HashMap::new(),
+            metadata_env: vec![],
         }
     }
 }
@@ -107,6 +477,31 @@ impl OutputFormat {
     }
 }
 
+/// How the progress and results of each benchmark are printed (see `--progress`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Progress {
+    Full,
+    Compact,
+}
+impl Progress {
+    fn from_str(s: &str) -> Result<Progress> {
+        match s {
+            "full" => Ok(Progress::Full),
+            "compact" => Ok(Progress::Compact),
+            other => bail!(
+                "Unknown progress format {:?}. Expected \"full\" or \"compact\"",
+                other
+            ),
+        }
+    }
+}
+
+/// Returns true if the NO_COLOR environment variable is set to a non-empty value, which asks for
+/// output without colors unless they're explicitly requested (see https://no-color.org).
+fn no_color_requested() -> bool {
+    matches!(std::env::var_os("NO_COLOR"), Some(value) if !value.is_empty())
+}
+
 #[derive(Debug)]
 pub enum TextColor {
     Always,
@@ -143,14 +538,190 @@ impl PlottingBackend {
     }
 }
 
+/// Enum representing whether the benchmarks should be compiled before running them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildMode {
+    /// Always call cargo to build the benchmarks.
+    Always,
+    /// Reuse the executables from the last build if nothing appears to have changed since then.
+    Reuse,
+    /// Never call cargo; run the executables from the last build.
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkOrder {
+    Discovery,
+    Name,
+}
+impl BenchmarkOrder {
+    fn from_str(s: &str) -> BenchmarkOrder {
+        match s {
+            "discovery" => BenchmarkOrder::Discovery,
+            "name" => BenchmarkOrder::Name,
+            other => panic!("Unknown benchmark order: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DurationOrder {
+    Longest,
+    Shortest,
+}
+impl DurationOrder {
+    fn from_str(s: &str) -> DurationOrder {
+        match s {
+            "longest" => DurationOrder::Longest,
+            "shortest" => DurationOrder::Shortest,
+            other => panic!("Unknown duration order: {}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Nanoseconds,
+    Microseconds,
+    Milliseconds,
+    Seconds,
+}
+impl TimeUnit {
+    fn from_str(s: &str) -> TimeUnit {
+        match s {
+            "ns" => TimeUnit::Nanoseconds,
+            "us" => TimeUnit::Microseconds,
+            "ms" => TimeUnit::Milliseconds,
+            "s" => TimeUnit::Seconds,
+            other => panic!("Unknown time unit: {}", other),
+        }
+    }
+}
+
+/// How the estimates are rounded to the number of digits shown in the text output and the tables
+/// of the HTML reports. Values which are within floating-point error of halfway between two
+/// displayed values are treated as exactly halfway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Round halfway values to the nearest even digit, eg. 2.345 to 2.34.
+    HalfEven,
+    /// Round halfway values away from zero, eg. 2.345 to 2.35.
+    HalfUp,
+}
+impl RoundingMode {
+    fn from_str(s: &str) -> RoundingMode {
+        match s {
+            "half-even" => RoundingMode::HalfEven,
+            "half-up" => RoundingMode::HalfUp,
+            other => panic!("Unknown rounding mode: {}", other),
+        }
+    }
+}
+
+/// A statistic selected with `--statistics`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectedStatistic {
+    Estimate(Statistic),
+    /// A percentile of the per-element latencies, eg. 99.0 for p99.
+    Percentile(f64),
+}
+impl SelectedStatistic {
+    fn from_str(s: &str) -> Result<SelectedStatistic> {
+        let statistic = match s {
+            "mean" => Statistic::Mean,
+            "median" => Statistic::Median,
+            "mad" => Statistic::MedianAbsDev,
+            "sd" => Statistic::StdDev,
+            "slope" => Statistic::Slope,
+            "typical" => Statistic::Typical,
+            _ => {
+                let percentile: f64 = s
+                    .strip_prefix('p')
+                    .and_then(|percentile| percentile.parse().ok())
+                    .with_context(|| {
+                        format!(
+                            "Unknown statistic {:?}. Possible values are mean, median, mad, sd, \
+                            slope, typical and percentiles such as p99.",
+                            s
+                        )
+                    })?;
+                if !LATENCY_PERCENTILES.contains(&percentile) {
+                    let available: Vec<String> = LATENCY_PERCENTILES
+                        .iter()
+                        .map(|percentile| format!("p{}", percentile))
+                        .collect();
+                    bail!(
+                        "Percentile {} is not computed. The computed percentiles are {}.",
+                        s,
+                        available.join(", ")
+                    );
+                }
+                return Ok(SelectedStatistic::Percentile(percentile));
+            }
+        };
+        Ok(SelectedStatistic::Estimate(statistic))
+    }
+}
+
+/// Parses a comma-separated list of statistics, as given to `--statistics`.
+fn parse_statistics(list: &str) -> Result<Vec<SelectedStatistic>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(SelectedStatistic::from_str)
+        .collect()
+}
+
+/// Returns true if the given estimate should be computed, compared and reported, given the
+/// statistics selected with `--statistics` (all of them if there is no selection). The typical
+/// estimate is the slope if there is one and the mean otherwise, so selecting it selects both.
+pub fn is_selected(statistics: Option<&[SelectedStatistic]>, statistic: Statistic) -> bool {
+    let statistics = match statistics {
+        Some(statistics) => statistics,
+        None => return true,
+    };
+    statistics.iter().any(|selected| match *selected {
+        SelectedStatistic::Estimate(Statistic::Typical) => matches!(
+            statistic,
+            Statistic::Typical | Statistic::Slope | Statistic::Mean
+        ),
+        SelectedStatistic::Estimate(selected) => selected == statistic,
+        SelectedStatistic::Percentile(_) => false,
+    })
+}
+
+/// Operations on the stored data, which are performed instead of running the benchmarks.
+#[derive(Debug)]
+pub enum StoreCommand {
+    List,
+    Remove {
+        name: String,
+        force: bool,
+    },
+    /// Delete every baseline and the reports. Asks for confirmation unless `yes` is set.
+    Clean {
+        yes: bool,
+    },
+    /// Draw the plots which --lazy-plots left out, for the benchmark with the given ID or else
+    /// for every benchmark which is missing them.
+    Render {
+        id: Option<String>,
+    },
+    /// Check that every stored file can be loaded, and report those which can't.
+    Verify,
+}
+
 #[derive(Debug)]
 pub enum MessageFormat {
     Json,
+    /// The Bencher Metric Format, for uploading to bencher.dev.
+    Bmf,
 }
 impl MessageFormat {
     fn from_str(s: &str) -> MessageFormat {
         match s {
             "json" => MessageFormat::Json,
+            "bmf" => MessageFormat::Bmf,
             other => panic!("Unknown message format: {}", other),
         }
     }
@@ -165,22 +736,154 @@ pub struct SelfConfig {
     pub do_run: bool,
     /// Should we fail immediately if a benchmark target fails, or continue with the others?
     pub do_fail_fast: bool,
+    /// Should we exit with an error if any benchmark has regressed?
+    pub fail_on_regression: bool,
+    /// Should we exit with an error if any benchmark has regressed or improved?
+    pub fail_on_change: bool,
+    /// If set, benchmarks whose relative standard deviation exceeds this fraction are flagged.
+    pub max_rsd: Option<f64>,
+    /// Should we exit with an error if any benchmark is flagged by `max_rsd`?
+    pub fail_on_high_variance: bool,
+    /// If set, benchmarks whose relative standard deviation exceeds this fraction are measured
+    /// again, pooling the samples, until it doesn't or `max_attempts` is reached.
+    pub stabilize: Option<f64>,
+    /// The maximum number of times each benchmark is measured for `stabilize`.
+    pub max_attempts: usize,
+    /// Time budgets, in nanoseconds, keyed by benchmark title or group ID.
+    pub budgets: HashMap<String, f64>,
+    /// Should we exit with an error if any benchmark's mean time exceeds its budget?
+    pub fail_on_budget: bool,
+    /// If set, the benchmarks are run twice and those whose estimates differ between the runs by
+    /// more than this fraction are flagged as nondeterministic.
+    pub determinism_tolerance: Option<f64>,
+    /// A shell command to pipe the JSON results of each benchmark to.
+    pub post_process: Option<String>,
+    /// Should we exit with an error if the `post_process` command fails?
+    pub post_process_required: bool,
+    /// The CSV file to write the change in each benchmark since its baseline to, if any.
+    pub export_diff_csv: Option<PathBuf>,
+    /// The Rust source file to write the estimates of each benchmark to, if any.
+    pub export_rust: Option<PathBuf>,
+    /// The file to write the full results of the run to in a compact binary format, if any.
+    pub export_bin: Option<PathBuf>,
+    /// Glob patterns for benchmarks which are excluded from the regression check.
+    pub ungated_benchmarks: Vec<String>,
+    /// Should we report the geometric mean of each benchmark group?
+    pub geometric_mean: bool,
+    /// The weights of the benchmarks in the geometric means, keyed by benchmark title.
+    pub weights: HashMap<String, f64>,
+    /// Should we report the combined time of all of the benchmarks in the run?
+    pub total_time: bool,
+    /// Should we save the estimates of each benchmark to a JSON file in its report directory?
+    pub json_reports: bool,
+    /// Should we save the KDE curves of each benchmark to a JSON file in its report directory?
+    pub export_curves: bool,
+    /// Should we save the bootstrap resamples of each benchmark to a JSON file in its report
+    /// directory?
+    pub export_bootstrap: bool,
+    /// Should we save the samples of each benchmark to a CSV file in its report directory?
+    pub export_samples: bool,
+    /// Should we write the reports into a staging directory and move it into place at the end?
+    pub atomic_reports: bool,
+    /// If set, the reports are copied into this directory as a static site after the run.
+    pub export_site: Option<PathBuf>,
+    /// If set, the distributions of all of the benchmarks are drawn in a grid in this SVG image.
+    pub contact_sheet: Option<PathBuf>,
+    /// If set, the reports of the benchmarks (and groups) which finish once this much time has
+    /// been spent generating reports are skipped, and listed in the index instead.
+    pub report_time_limit: Option<Duration>,
+    /// Should we fail up front if the plotting backend can't draw every plot the reports need?
+    pub strict_plots: bool,
+    /// Should the plots of each benchmark be left out of the run, to be drawn later with
+    /// `cargo criterion render`?
+    pub lazy_plots: bool,
+    /// Should we generate the thumbnail-sized versions of the plots?
+    pub thumbnails: bool,
+    /// Should we generate an HTML page listing only the benchmarks which changed in this run?
+    pub diff_report: bool,
     /// How should the CLI output be formatted
     pub output_format: OutputFormat,
+    pub progress: Progress,
     /// Should we print the output in color?
     pub text_color: TextColor,
     /// Which plotting backend to use?
     pub plotting_backend: PlottingBackend,
+    /// In what order should benchmarks appear in the reports and summary plots?
+    pub order: BenchmarkOrder,
+    /// How many of the most recent stored runs should be averaged to form the baseline?
+    pub baseline_window: usize,
+    /// If set, the baselines are loaded from (and the results saved to) this external store.
+    pub baseline_store: Option<String>,
+    /// The named baselines whose distributions are drawn over the comparison plots.
+    pub overlay_baselines: Vec<String>,
+    /// Should the stored measurements be encrypted with the key from the environment?
+    pub encrypt_history: bool,
+    /// Should measured times be printed in a fixed unit rather than scaled per benchmark?
+    pub time_unit: Option<TimeUnit>,
+    /// If set, the estimates are rounded for display with this rounding mode rather than by the
+    /// benchmark.
+    pub rounding: Option<RoundingMode>,
+    /// If set, only these statistics are computed, compared and reported.
+    pub statistics: Option<Vec<SelectedStatistic>>,
     /// Should we compile the benchmarks in debug mode (true) or release mode (false, default)
     pub debug_build: bool,
+    /// Should we trade statistical rigor for speed? See the help text of --quick.
+    pub quick: bool,
+    /// If set, the exact number of samples the benchmarks are asked to collect.
+    pub sample_size: Option<usize>,
+    /// The largest number of samples of a benchmark which are analyzed; larger samples are
+    /// reservoir-sampled down to this many.
+    pub max_samples: usize,
+    /// Should we call cargo to compile the benchmarks, or reuse the ones from the last build?
+    pub build_mode: BuildMode,
+    /// If set, run the benchmark targets in a random order determined by this seed.
+    pub shuffle_seed: Option<u64>,
+    /// If set, the benchmark targets are pinned to these CPUs.
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// If set, the benchmark targets are run in order of how long they took in the last run.
+    pub order_by_duration: Option<DurationOrder>,
+    /// The rustup toolchains to compile and run the benchmarks with. If empty, the default toolchain
+    /// is used.
+    pub toolchains: Vec<String>,
+    /// If set, only run the benchmark targets whose sources have changed since this git revision.
+    pub since: Option<String>,
+    /// If set, the benchmark targets whose sources haven't changed since this git revision aren't
+    /// run, and their stored results are reported instead.
+    pub reuse_unchanged: Option<String>,
+    /// If set, changes in the history tables below this fraction, or which aren't significant,
+    /// are shown as "—".
+    pub min_displayed_change: Option<f64>,
+    /// If set, changes in time of at least this factor either way are shown as speedup factors.
+    pub speedup_threshold: Option<f64>,
+    /// Metrics computed from the estimates of each benchmark, in order of name.
+    pub derived_metrics: Vec<DerivedMetric>,
+
+    /// The paths whose changes cause each benchmark target to be run with `since`.
+    pub benchmark_paths: HashMap<String, Vec<PathBuf>>,
+    /// If set, analyze the samples read from stdin as the benchmark with this ID instead of
+    /// compiling and running the benchmarks.
+    pub stdin_benchmark: Option<String>,
+    /// If set, report on the results exported to this file by --export-bin instead of compiling
+    /// and running the benchmarks.
+    pub import_bin: Option<PathBuf>,
+    /// If set, listen on this address and run the benchmarks on request instead of running them
+    /// once.
+    pub serve: Option<String>,
     /// Should we print machine-readable output, and if so, in what format?
     pub message_format: Option<MessageFormat>,
     /// The colors to use for charts.
     pub colors: Colors,
+    /// Additional settings for the generated plots.
+    pub plot_options: PlotOptions,
     // An optional identifier used to identify this run in the history reports.
     pub history_id: Option<String>,
     // An optional description used to describe this run in the history reports.
     pub history_description: Option<String>,
+    // An optional free-form label which is saved with this run and shown in the reports.
+    pub label: Option<String>,
+    // The values of the environment variables named by --metadata-env, which are saved with this
+    // run and shown in the reports. Variables which aren't set are left out.
+    pub metadata: BTreeMap<String, String>,
 }
 
 /// Overall struct that represents all of the configuration data for this run.
@@ -190,8 +893,12 @@ pub struct FullConfig {
     pub self_config: SelfConfig,
     /// The arguments we pass through to cargo bench
     pub cargo_args: Vec<OsString>,
-    /// The additional arguments we pass through to the benchmark executables
+    /// The benchmark filter passed to the benchmark executables, if any
+    pub bench_filter: Option<OsString>,
+    /// The additional arguments we pass through to the benchmark executables, after the filter
     pub additional_args: Vec<OsString>,
+    /// If set, operate on the stored data rather than running the benchmarks
+    pub store_command: Option<StoreCommand>,
 }
 
 /// Call `cargo criterion` and parse the output to get the path to the target directory.
@@ -210,11 +917,29 @@ fn get_target_directory_from_metadata() -> Result<PathBuf> {
     Ok(path)
 }
 
+// The Criterion.rs options passed to the benchmarks in quick mode, in seconds.
+const QUICK_TIMING_ARGS: &[(&str, &str)] = &[("--warm-up-time", "1"), ("--measurement-time", "1")];
+
+// The smallest sample size that Criterion.rs accepts.
+const MIN_SAMPLE_SIZE: usize = 10;
+
+// The number of samples analyzed unless --max-samples says otherwise. This is far more than
+// Criterion.rs collects by default, so only benchmarks with custom sampling schemes reach it.
+const DEFAULT_MAX_SAMPLES: usize = 100_000;
+
+// The largest relative difference between the two runs of --verify-determinism that is accepted
+// unless --determinism-tolerance says otherwise.
+const DEFAULT_DETERMINISM_TOLERANCE: f64 = 0.05;
+
+// The number of times each benchmark is measured for --stabilize unless --max-attempts says
+// otherwise.
+const DEFAULT_MAX_ATTEMPTS: usize = 5;
+
 /// Parse the command-line arguments, load the criterion.toml config file, and generate a
 /// configuration object used for the rest of the run.
 #[cfg_attr(feature = "cargo-clippy", allow(clippy::or_fun_call))]
 pub fn configure() -> Result<FullConfig, anyhow::Error> {
-    use clap::{App, AppSettings, Arg};
+    use clap::{App, AppSettings, Arg, SubCommand};
 
     let matches = App::new("cargo-criterion")
         .version(env!("CARGO_PKG_VERSION"))
@@ -378,6 +1103,233 @@ pub fn configure() -> Result<FullConfig, anyhow::Error> {
                 .long("--no-fail-fast")
                 .help("Run all benchmarks regardless of failure"),
         )
+        .arg(
+            Arg::with_name("fail-on-regression")
+                .long("--fail-on-regression")
+                .help("Exit with an error if the performance of any benchmark has regressed.")
+                .long_help(
+"Exit with an error if the performance of any benchmark has regressed compared to the previous run.
+All benchmarks are still executed and reported before the run fails.
+
+Benchmarks whose titles match one of the glob patterns in the `ungated_benchmarks` list in the
+criterion.toml file are still measured and reported, but are never considered regressions.
+")
+        )
+        .arg(
+            Arg::with_name("fail-on-change")
+                .long("--fail-on-change")
+                .help("Exit with an error if the performance of any benchmark has regressed or improved.")
+                .long_help(
+"Exit with an error if the performance of any benchmark has changed significantly in either
+direction compared to the previous run. This is stricter than --fail-on-regression; an unexpected
+speedup can indicate a broken benchmark, eg. one whose work has been optimized away. The error
+lists whether each benchmark regressed or improved.
+
+Benchmarks matching the `ungated_benchmarks` patterns in criterion.toml are exempt, as with
+--fail-on-regression.
+")
+        )
+        .arg(
+            Arg::with_name("max-rsd")
+                .long("--max-rsd")
+                .takes_value(true)
+                .value_name("FRACTION")
+                .help("Flag benchmarks whose relative standard deviation exceeds FRACTION (eg. 0.05).")
+                .long_help(
+"Flag benchmarks whose relative standard deviation (the standard deviation of the sample times
+over their mean) exceeds FRACTION, eg. 0.05 for 5%. Measurements that noisy make the comparisons
+with previous runs unreliable. Once all benchmarks have been executed, the flagged benchmarks are
+listed with their relative standard deviation so that they can be stabilized.
+")
+        )
+        .arg(
+            Arg::with_name("fail-on-high-variance")
+                .long("--fail-on-high-variance")
+                .requires("max-rsd")
+                .help("Exit with an error if any benchmark is flagged by --max-rsd.")
+        )
+        .arg(
+            Arg::with_name("stabilize")
+                .long("--stabilize")
+                .takes_value(true)
+                .value_name("TARGET_RSD")
+                .help("Measure benchmarks again until their relative standard deviation is below TARGET_RSD (eg. 0.02).")
+                .long_help(
+"Measure the benchmarks whose relative standard deviation exceeds TARGET_RSD (eg. 0.02 for 2%)
+again, until it doesn't or --max-attempts is reached. Only the benchmarks which haven't stabilized
+are run again, and their samples are pooled with those of the earlier attempts, so each attempt
+narrows the estimates. The benchmarks are still compared against the previous run, and those which
+never stabilize are listed at the end of the run.
+")
+        )
+        .arg(
+            Arg::with_name("max-attempts")
+                .long("--max-attempts")
+                .takes_value(true)
+                .value_name("N")
+                .requires("stabilize")
+                .help("The maximum number of times to measure each benchmark for --stabilize. Defaults to 5.")
+        )
+        .arg(
+            Arg::with_name("fail-on-budget")
+                .long("--fail-on-budget")
+                .help("Exit with an error if the mean time of any benchmark exceeds its budget.")
+                .long_help(
+"Exit with an error if the mean time of any benchmark exceeds the absolute time budget set for it
+(or its group) in the `budgets` table of the criterion.toml file. Unlike --fail-on-regression, this
+doesn't depend on previous runs. The headroom of every budgeted benchmark is listed after the run
+whether or not this is set.
+")
+        )
+        .arg(
+            Arg::with_name("verify-determinism")
+                .long("--verify-determinism")
+                .help("Run the benchmarks a second time and flag those whose estimates differ between the two runs.")
+                .long_help(
+"Run the benchmarks a second time straight after the first run, and flag the benchmarks whose
+typical estimates differ between the two runs by more than --determinism-tolerance. Unlike the
+comparison with the previous run, this catches benchmarks which don't measure the same thing every
+time they run, such as those depending on uninitialized state or the order they run in.
+
+Only the first run is reported and stored; the second run is only used for the comparison.
+")
+        )
+        .arg(
+            Arg::with_name("determinism-tolerance")
+                .long("--determinism-tolerance")
+                .takes_value(true)
+                .value_name("FRACTION")
+                .requires("verify-determinism")
+                .help("The largest difference between the two runs of --verify-determinism which is accepted, eg. 0.05 for 5%. Defaults to 0.05.")
+        )
+        .arg(
+            Arg::with_name("post-process")
+                .long("--post-process")
+                .takes_value(true)
+                .value_name("COMMAND")
+                .help("Pipe the JSON results of each benchmark to COMMAND as it completes.")
+                .long_help(
+"Run COMMAND through the shell as each benchmark completes, writing the benchmark's results to
+its stdin as a single line of JSON. This is the same benchmark-complete message printed by
+--message-format=json. It can be used to send notifications, insert the results into a database
+and so on.
+
+If the command exits with an error, a warning is printed and the run continues. See
+--post-process-required.
+")
+        )
+        .arg(
+            Arg::with_name("post-process-required")
+                .long("--post-process-required")
+                .requires("post-process")
+                .help("Exit with an error if the --post-process command fails for any benchmark.")
+                .long_help(
+"Exit with an error if the --post-process command fails for any benchmark. All benchmarks are
+still executed and reported before the run fails.
+")
+        )
+        .arg(
+            Arg::with_name("export-diff-csv")
+                .long("--export-diff-csv")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Write a CSV table of the change in each benchmark since its baseline to PATH.")
+                .long_help(
+"Write a CSV table of the change in each benchmark since its baseline to PATH once the run is
+over, for pasting into a spreadsheet. The table has one row per benchmark, with the columns
+benchmark, baseline, current, delta, change_percent, verdict and unit. The times are the means of
+the baseline and the current run, and the verdict is one of improved, regressed, within noise,
+no change or new (for benchmarks without a baseline, which leave the baseline and change columns
+empty).
+")
+        )
+        .arg(
+            Arg::with_name("export-rust")
+                .long("--export-rust")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Write the estimates of each benchmark to PATH as Rust source, for golden tests.")
+                .long_help(
+"Write the estimates of each benchmark to PATH as Rust source once the run is over, so that they
+can be checked into a crate's own tests to assert that its benchmarks keep their shape. The file
+defines the Estimate and Estimates structs, a static ESTIMATES slice of (ID, Estimates) pairs in the
+order the benchmarks were run, and an estimates(id) function to look one up. Each estimate holds
+the point estimate and the bounds of its confidence interval for the mean, median, MAD, standard
+deviation and (if it was measured) slope, in the unit named by the benchmark's unit field.
+
+Include the file with include!() or as a module; it doesn't depend on any crates.
+")
+        )
+        .arg(
+            Arg::with_name("export-bin")
+                .long("--export-bin")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Write the full results of the run to PATH in a compact binary format.")
+                .long_help(
+"Write the full results of the run to PATH in a compact binary format (CBOR) once the run is over.
+For each benchmark this includes its samples, estimates and the settings of its analysis, and the
+comparison against its baseline (including the baseline's samples) if it had one. This is much
+smaller than the JSON output, for pipelines which keep the results of many runs.
+
+The file can be read back with --import-bin to generate the reports offline.
+")
+        )
+        .arg(
+            Arg::with_name("from-stdin")
+                .long("--from-stdin")
+                .takes_value(true)
+                .value_name("ID")
+                .help("Analyze samples read from stdin as the benchmark ID instead of running the benchmarks.")
+                .long_help(
+"Read samples measured by some other tool from stdin, and analyze and report on them as the
+benchmark with the given ID (eg. 'group/function/value'). No benchmarks are compiled or run.
+
+The samples may be given as CSV, with one 'iterations,time' pair per line, or as a JSON array of
+objects with 'iterations' and 'time' fields. Times are the total wall-clock time of all iterations
+in the sample, in nanoseconds.
+")
+        )
+        .arg(
+            Arg::with_name("import-bin")
+                .long("--import-bin")
+                .takes_value(true)
+                .value_name("PATH")
+                .conflicts_with("from-stdin")
+                .help("Report on the results written by --export-bin instead of running the benchmarks.")
+                .long_help(
+"Read the results written to PATH by --export-bin, and report on them as if their benchmarks had
+just been measured. No benchmarks are compiled or run. The results are stored like those of any
+other run, so they appear in the history and later runs are compared against them.
+
+The analysis is repeated to draw the plots, so the plotted distributions may differ slightly from
+the original ones, but the estimates and comparisons reported are those in the file. The values are
+formatted as wall-clock times.
+")
+        )
+        .arg(
+            Arg::with_name("serve")
+                .long("--serve")
+                .takes_value(true)
+                .value_name("ADDRESS")
+                .conflicts_with_all(&["from-stdin", "import-bin", "message-format"])
+                .help("Keep running and run the benchmarks on request from clients connected to ADDRESS (eg. localhost:7878).")
+                .long_help(
+"Instead of running the benchmarks once, keep running and listen for requests on the given local
+address (eg. 'localhost:7878'), for IDEs and other tools which run benchmarks often. Only loopback
+addresses are accepted, since anyone who can connect can run the benchmarks.
+
+Clients send one JSON request per line. '{\"command\": \"run\", \"filter\": \"fib\"}' compiles the
+benchmarks if necessary and runs those which match the optional filter (the filter given on the
+command line is used otherwise). The same messages as --message-format=json are sent back to the
+client as the benchmarks run, followed by a run-complete message with a success field (and an error
+field if the run failed). '{\"command\": \"shutdown\"}' stops the server. Clients are served one
+at a time.
+
+Each run is the same as a normal run with the other options given: the reports and exports are
+generated as usual, and checks such as --fail-on-regression make the run unsuccessful if they fail.
+")
+        )
         .arg(
             Arg::with_name("debug")
                 .long("--debug")
@@ -389,6 +1341,124 @@ different language (eg. external C modules).
 
 Note however that it will tend to increase the measurement overhead, as the measurement loops 
 in the benchmark will not be optimized either. This may result in less-accurate measurements.
+")
+        )
+        .arg(
+            Arg::with_name("quick")
+                .long("--quick")
+                .help("Measure and analyze the benchmarks quickly, at the cost of accuracy.")
+                .long_help(
+"Measure and analyze the benchmarks quickly, at the cost of accuracy. This is a preset for fast
+feedback during development, which:
+
+- Asks the benchmarks to warm up and measure for one second each (--warm-up-time 1
+  --measurement-time 1) unless those options are passed to the benchmarks explicitly.
+- Uses at most 5000 bootstrap resamples, which gives rougher confidence intervals.
+- Skips the comparison with the previous run.
+- Skips generating the plots and HTML reports.
+
+The results are still saved, so the next run will be compared against them. Only benchmarks
+which use Criterion.rs accept the timing options.
+")
+        )
+        .arg(
+            Arg::with_name("sample-size")
+                .long("--sample-size")
+                .takes_value(true)
+                .value_name("N")
+                .help("Ask the benchmarks to collect exactly N samples.")
+                .long_help(
+"Ask the benchmarks to collect exactly N samples, overriding the sample size set in the benchmark
+code. Must be at least 10. This is passed to the benchmarks as --sample-size N unless that option
+is passed to them explicitly.
+
+Benchmarks which don't use Criterion.rs may not support this, in which case they fall back to
+their own sampling scheme and a warning is printed showing how many samples were collected.
+")
+        )
+        .arg(
+            Arg::with_name("max-samples")
+                .long("--max-samples")
+                .takes_value(true)
+                .value_name("N")
+                .help("Analyze at most N samples of each benchmark. Defaults to 100000.")
+                .long_help(
+"Analyze at most N samples of each benchmark. Benchmarks which collect more samples than this
+(usually ones with their own sampling scheme) have a random subset of N of them analyzed instead,
+with a warning, so that the analysis and the bootstrap don't run out of memory. Must be at least
+10.
+
+Defaults to 100000. This can also be set with max_samples in the criterion.toml file.
+")
+        )
+        .arg(
+            Arg::with_name("reuse-build")
+                .long("--reuse-build")
+                .conflicts_with("no-build")
+                .help("Skip the cargo build if nothing has changed since the last build.")
+                .long_help(
+"Skip calling cargo to build the benchmarks if the executables from the last build are unchanged
+and no files in the current directory (excluding the target directory and hidden directories)
+have been modified since then. The executables are launched directly instead. If anything has
+changed, the benchmarks are built as usual.
+")
+        )
+        .arg(
+            Arg::with_name("no-build")
+                .long("--no-build")
+                .help("Never call cargo; run the benchmark executables from the last build.")
+                .long_help(
+"Never call cargo to build the benchmarks, and run the executables from the last build instead,
+even if the source code has changed since then. Fails if the benchmarks haven't been built by a
+previous run with the same options.
+")
+        )
+        .arg(
+            Arg::with_name("toolchains")
+                .long("--toolchains")
+                .takes_value(true)
+                .value_name("TOOLCHAINS")
+                .help("Compile and run the benchmarks with each of the given comma-separated rustup toolchains.")
+                .long_help(
+"Compile and run the benchmarks once with each of the given comma-separated rustup toolchains (eg.
+'stable,nightly'), by invoking 'cargo +<toolchain>'. The toolchain is appended to the function ID
+of each benchmark (eg. 'group/function (nightly)/10'), so the results of each toolchain are stored
+separately and drawn as separate lines in the summary plots of each benchmark group.
+")
+        )
+        .arg(
+            Arg::with_name("since")
+                .long("--since")
+                .takes_value(true)
+                .value_name("REVISION")
+                .help("Only run the benchmark targets whose sources have changed since the given git revision.")
+                .long_help(
+"Only run the benchmark targets whose sources differ between the given git revision (eg. 'main'
+or 'HEAD~3') and the working tree, including uncommitted and untracked files. Other targets are
+skipped.
+
+By default, a target's sources are the directory of the package it belongs to. To be more
+specific, list the paths for a target in the benchmark_paths table in criterion.toml, eg.
+'my_bench = [\"src/parser\", \"benches/my_bench.rs\"]'. The paths are relative to the
+directory cargo-criterion is run in.
+")
+        )
+        .arg(
+            Arg::with_name("reuse-unchanged")
+                .long("--reuse-unchanged")
+                .takes_value(true)
+                .value_name("REVISION")
+                .conflicts_with("since")
+                .help("Only measure the benchmark targets whose sources have changed since the given git revision, and reuse the stored results of the others.")
+                .long_help(
+"Like --since, only run the benchmark targets whose sources differ between the given git revision
+and the working tree. The other targets aren't run either, but the stored results of their
+benchmarks from the last time they were measured are still included in the reports, so that the
+report covers every benchmark. The reused benchmarks are counted separately in the summary at the
+end of the run.
+
+Only results measured by this version of cargo-criterion or later record which target they came
+from. Older results are shown in the reports as before, but aren't counted as reused.
 ")
         )
         .arg(
@@ -411,6 +1481,27 @@ eg. on a library's README.
 verbose: Like criterion, but prints additional statistics.
 
 bencher: Emulates the output format of the bencher crate and nightly-only libtest benchmarks.
+")
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .takes_value(true)
+                .possible_values(&["full", "compact"])
+                .hide_possible_values(true)
+                .help("Change how the results are printed. Possible values are full, compact.")
+                .long_help(
+"Change how the progress and results of each benchmark are printed. Possible values are [full, \
+compact].
+
+full: Prints the results of each benchmark over several lines, as set by --output-format. The \
+default.
+
+compact: Prints exactly one line per completed benchmark, with its name, time and change. When \
+stderr is a terminal, the progress of the running benchmark is shown on a status line which is \
+overwritten; otherwise it isn't printed at all. Useful for keeping CI logs short.
+
+This only applies to the criterion, quiet and verbose output formats.
 ")
         )
         .arg(
@@ -419,17 +1510,438 @@ bencher: Emulates the output format of the bencher crate and nightly-only libtes
                 .takes_value(true)
                 .possible_values(&["gnuplot", "plotters", "disabled"])
                 .help("Set the plotting backend. By default, cargo-criterion will use the gnuplot backend if gnuplot is available, or the plotters backend if it isn't. If set to 'disabled', plot generation will be disabled."))
+        .arg(
+            Arg::with_name("interactive-svg")
+                .long("--interactive-svg")
+                .help("Embed tooltips showing the exact values of the data points in the SVG plots. Only supported by the plotters backend.")
+        )
+        .arg(
+            Arg::with_name("regression-log-y")
+                .long("--regression-log-y")
+                .help("Use a logarithmic y axis on the linear regression plots.")
+        )
+        .arg(
+            Arg::with_name("kde-points")
+                .long("--kde-points")
+                .takes_value(true)
+                .value_name("N")
+                .help("The number of points at which to evaluate the kernel density estimates in the plots. Defaults to 500.")
+                .long_help(
+"The number of points at which to evaluate the kernel density estimates when drawing the PDF and
+distribution plots. Larger values give smoother curves for very wide distributions, but make
+plotting slower. Must be between 50 and 10000; defaults to 500. This can also be set with the
+kde_points setting in the [plots] section of criterion.toml.
+")
+        )
+        .arg(
+            Arg::with_name("max-plot-points")
+                .long("--max-plot-points")
+                .takes_value(true)
+                .value_name("N")
+                .help("Draw at most N points on the iteration times and regression plots.")
+                .long_help(
+"Draw at most N points on the iteration times and regression plots. Benchmarks with more samples
+than that are downsampled to an evenly spread subset of N points, which keeps the SVG files small
+and quick to render. The statistics and the regression line still use every sample. Must be at
+least 10. This can also be set with the max_plot_points setting in the [plots] section of
+criterion.toml.
+")
+        )
+        .arg(
+            Arg::with_name("time-unit")
+                .long("--time-unit")
+                .takes_value(true)
+                .possible_values(&["ns", "us", "ms", "s"])
+                .help("Print all measured times in the given unit. Possible values are ns, us, ms, s.")
+                .long_help(
+"Print all measured times in the given unit, rather than choosing a unit for each benchmark.
+Possible values are [ns, us, ms, s].
+
+This applies to the command-line output and the tables in the HTML reports, which makes it easier
+to compare benchmarks of very different speeds. The axes of the plots are still scaled
+automatically. This assumes that the benchmarks measure wall-clock time.
+")
+        )
+        .arg(
+            Arg::with_name("statistics")
+                .long("--statistics")
+                .takes_value(true)
+                .value_name("LIST")
+                .help("Compute, compare and report only these statistics, eg. mean,p99.")
+                .long_help(
+"A comma-separated list of the statistics to compute, compare and report, eg. mean,p99. Possible
+values are mean, median, mad, sd, slope, typical and the latency percentiles p50, p75, p90, p95,
+p99, p99.5 and p99.9.
+
+The command-line output lists the selected statistics, and the other statistics are left out of
+the change detection and the per-statistic plots. The slope's linear regression is skipped unless
+slope or typical is selected, which saves a bootstrap per benchmark. The percentiles are only
+available for benchmarks which record the latencies of individual elements.
+")
+        )
+        .arg(
+            Arg::with_name("rounding")
+                .long("--rounding")
+                .takes_value(true)
+                .possible_values(&["half-even", "half-up"])
+                .help("How to round the estimates for display. Possible values are half-even, half-up.")
+                .long_help(
+"How to round the estimates to the number of digits displayed in the command-line output and the
+tables in the HTML reports. Possible values are [half-even, half-up].
+
+With half-even, values halfway between two displayed values are rounded to the even one (eg.
+2.345 ns to 2.34 ns); with half-up they are rounded away from zero (eg. to 2.35 ns). Values within
+floating-point error of halfway count as halfway, so the output agrees with tools which round
+decimal numbers. By default, the benchmark formats the values itself.
+")
+        )
+        .arg(
+            Arg::with_name("geometric-mean")
+                .long("--geometric-mean")
+                .help("Report the geometric mean of the mean times of the benchmarks in each group.")
+                .long_help(
+"Report the geometric mean of the mean times of the benchmarks in each benchmark group. This is
+printed after each group completes and included in the group-complete message of the JSON output.
+This is useful when a benchmark group represents a mix of workloads.
+
+Benchmarks can be weighted so that the most representative workloads dominate the mean, by giving
+them a positive weight in the weights table of the criterion.toml file, eg.
+'weights = { \"parse/large\" = 3.0 }'. Benchmarks without a weight have a weight of 1. If the
+benchmarks have baselines, the change in the geometric mean is reported as well.
+")
+        )
+        .arg(
+            Arg::with_name("total-time")
+                .long("--total-time")
+                .help("Report the combined mean time of all of the benchmarks in the run.")
+                .long_help(
+"Report the sum of the mean times of all of the benchmarks measured in this run, along with the
+change in that sum compared to the baselines. Only benchmarks that have a baseline are included in
+the change. This is printed after all of the benchmarks complete and sent as a suite-complete
+message in the JSON output. This assumes that the benchmarks measure wall-clock time.
+")
+        )
+        .arg(
+            Arg::with_name("no-thumbnails")
+                .long("--no-thumbnails")
+                .help("Don't generate the thumbnail-sized plots.")
+                .long_help(
+"Don't generate the thumbnail-sized versions of the plots. The HTML reports display the full-size
+plots scaled down instead. This roughly halves the time spent plotting, which is useful when the
+HTML reports are not the main output, eg. on CI.
+")
+        )
+        .arg(
+            Arg::with_name("diff-report")
+                .long("--diff-report")
+                .help("Generate an HTML page listing only the benchmarks which changed significantly.")
+                .long_help(
+"Generate an HTML page (target/criterion/reports/diff.html) listing only the benchmarks which
+changed significantly since the previous run, with the regressions first. Each entry shows the
+change in the mean time and the distribution of that change. This is useful for reviewing the
+effect of a change on a large benchmark suite.
+")
+        )
+        .arg(
+            Arg::with_name("json-reports")
+                .long("--json-reports")
+                .help("Save the estimates of each benchmark to an estimates.json file in its report directory.")
+                .long_help(
+"Save the estimates of each benchmark to an estimates.json file in its report directory, eg.
+target/criterion/reports/<benchmark>/estimates.json. The file contains the same data as the
+benchmark-complete message of --message-format=json, along with the standard deviation, the
+number of samples and the p-value of the comparison with the previous run.
+")
+        )
+        .arg(
+            Arg::with_name("export-curves")
+                .long("--export-curves")
+                .help("Save the KDE curves of each benchmark to a curves.json file in its report directory.")
+                .long_help(
+"Save the kernel density estimates behind the PDF and distribution plots of each benchmark to a
+curves.json file in its report directory, eg. target/criterion/reports/<benchmark>/curves.json.
+The file contains the x/y points of the probability density of the sample times, and of the
+bootstrap distribution of each statistic along with its point estimate and confidence interval.
+This is useful for drawing the plots with other tools. The number of points in each curve is set
+by --kde-points.
+")
+        )
+        .arg(
+            Arg::with_name("export-bootstrap")
+                .long("--export-bootstrap")
+                .help("Save the bootstrap distribution of each statistic to a bootstrap.json file in its report directory.")
+                .long_help(
+"Save the raw bootstrap resamples of each statistic of each benchmark to a bootstrap.json file in
+its report directory, eg. target/criterion/reports/<benchmark>/bootstrap.json. The file contains the
+point estimate and the resampled values of the mean, median, median absolute deviation, standard
+deviation and (if available) slope, all in the same unit. If the benchmark was compared to a
+baseline, it also contains the resampled relative changes in the mean and median. This is useful
+for computing custom quantiles or statistical tests with other tools. The number of resamples is
+set by the benchmark's nresamples setting.
+")
+        )
+        .arg(
+            Arg::with_name("export-samples")
+                .long("--export-samples")
+                .help("Save the samples of each benchmark to a samples.csv file in its report directory.")
+                .long_help(
+"Save the raw samples of each benchmark to a samples.csv file in its report directory, eg.
+target/criterion/reports/<benchmark>/samples.csv. The table has one row per sample, with the
+columns sample (its index, from 1), iterations, time (the total time of the sample's iterations),
+unit and timestamp. The timestamp is the wall-clock time at which the sample was taken, in seconds
+since the Unix epoch, which is useful for correlating the samples with other events on the system.
+It's left empty unless the benchmark records the timestamps of its samples, which no released
+version of Criterion.rs does.
+")
+        )
+        .arg(
+            Arg::with_name("atomic-reports")
+                .long("--atomic-reports")
+                .help("Only replace the previous reports once the run has completed.")
+                .long_help(
+"Write the reports into a staging copy of the report directory (target/criterion/reports.staging)
+and only move it into place once the run has completed. If the run is interrupted, the previous
+reports are left untouched instead of being partially overwritten, which is useful when the
+reports are served while the benchmarks run. Falls back to copying the staged reports file by
+file if the report directory can't be renamed, eg. because it is on a different filesystem.
+")
+        )
+        .arg(
+            Arg::with_name("export-site")
+                .long("--export-site")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Copy the reports into DIR as a self-contained static site once the run is over.")
+                .long_help(
+"Once the run is over, copy the HTML reports into DIR as a self-contained static site which can be
+published as it is, eg. to GitHub Pages. The reports are copied into DIR/reports along with the
+flamegraphs of any profiled benchmarks, so all of the links between them are relative and stay
+within DIR. DIR/index.html redirects to the report index.
+
+The reports of a previous export to DIR are replaced. Nothing else in DIR is removed.
+")
+        )
+        .arg(
+            Arg::with_name("contact-sheet")
+                .long("--contact-sheet")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("Draw the distributions of all of the benchmarks in a grid in one SVG image at PATH.")
+                .long_help(
+"Once the run is over, draw the probability distribution of every benchmark in a grid in one SVG
+image at PATH, so that they can be compared at a glance. The grid is as close to square as the
+number of benchmarks allows, and each cell is titled with its benchmark's ID. Each benchmark is
+scaled on its own, so compare the shapes of the distributions rather than their positions.
+
+This requires the plotters backend, and isn't drawn when plotting is disabled.
+")
+        )
+        .arg(
+            Arg::with_name("report-time-limit")
+                .long("--report-time-limit")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .help("Stop generating the reports of further benchmarks once SECONDS have been spent on the reports.")
+                .long_help(
+"Set a soft time limit on generating the HTML reports, which can take minutes for enormous suites.
+Once SECONDS have been spent generating reports (mostly plotting), the reports of the benchmarks and
+groups which finish afterwards are skipped. The report being generated when the limit is reached is
+finished, so the limit may be exceeded by a little. The index is always generated, and lists the
+reports which were skipped.
+
+The benchmarks are still measured, analyzed, and compared as usual.
+")
+        )
+        .arg(
+            Arg::with_name("strict-plots")
+                .long("--strict-plots")
+                .help("Fail before running any benchmarks if the plotting backend can't draw every plot the reports need.")
+                .long_help(
+"Check that the plotting backend can draw every plot which the HTML reports could need before
+running any benchmarks, and fail with an error naming the missing plots if it can't. Without this,
+plots which the backend doesn't support are left out of the reports.
+
+The heatmaps and latency percentile plots are always required, since whether they're drawn depends
+on the benchmarks. The CDF plots and the contact sheet are only required when they're enabled. The
+gnuplot backend can't draw any of these, so strict mode needs the plotters backend.
+
+This has no effect when plotting is disabled.
+")
+        )
+        .arg(
+            Arg::with_name("lazy-plots")
+                .long("--lazy-plots")
+                .help("Leave the plots of each benchmark out of the run, to be drawn later with 'cargo criterion render'.")
+                .long_help(
+"Skip drawing the plots of each benchmark during the run. Their reports show placeholder images
+instead, and the benchmarks are listed in lazy_plots.json in the reports directory. The plots of a
+benchmark are drawn when they're needed by running 'cargo criterion render ID', or those of every
+listed benchmark by running 'cargo criterion render'.
+
+The plots are drawn from the measurements stored by the run, so the analysis is repeated. The
+estimates shown are those of the run, but the plotted distributions come from a fresh bootstrap
+and may differ slightly from the original ones. The values are formatted as wall-clock times.
+
+The summary plots of the benchmark groups are still drawn during the run.
+")
+        )
+        .arg(
+            Arg::with_name("order")
+                .long("order")
+                .takes_value(true)
+                .possible_values(&["discovery", "name"])
+                .help("Set the order in which benchmarks appear in the reports. Possible values are discovery, name.")
+                .long_help(
+"Set the order in which benchmarks and benchmark groups appear in the reports and summary plots.
+Possible values are [discovery, name].
+
+discovery: Benchmarks appear in the order in which they were executed. The default.
+
+name: Benchmarks are sorted by their ID, so that reports are stable across runs and builds.
+")
+        )
+        .arg(
+            Arg::with_name("shuffle")
+                .long("--shuffle")
+                .help("Run the benchmark targets in a random order.")
+                .long_help(
+"Run the benchmark targets in a random order, so that systematic effects like the machine heating
+up or the caches being warmed by the previous benchmark don't always favor the same benchmarks.
+The seed is printed so that the order can be reproduced with --shuffle-seed. Criterion.rs always
+runs the benchmarks within each target in the order they are defined in, so only the order of the
+targets is randomized.
+
+Unless --order is set, benchmarks are sorted by name in the reports so that they are stable
+regardless of the execution order.
+")
+        )
+        .arg(
+            Arg::with_name("shuffle-seed")
+                .long("--shuffle-seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("Run the benchmark targets in the random order given by SEED. Implies --shuffle.")
+        )
+        .arg(
+            Arg::with_name("cpu-affinity")
+                .long("--cpu-affinity")
+                .takes_value(true)
+                .value_name("CPUS")
+                .help("Pin the benchmark targets to the given CPUs (eg. 2 or 0-3,6).")
+                .long_help(
+"Pin the benchmark targets to the given CPUs, so that the scheduler can't migrate them between
+cores while they are measured, which adds noise to the measurements. CPUS is a comma-separated list
+of CPU numbers and ranges, eg. '2' or '0-3,6'. Pinning to a single CPU which is otherwise idle (eg.
+one isolated with the isolcpus kernel parameter) gives the most stable results.
+
+Only supported on Linux. On other platforms a warning is printed and the benchmarks run unpinned.
+")
+        )
+        .arg(
+            Arg::with_name("order-by-duration")
+                .long("--order-by-duration")
+                .takes_value(true)
+                .possible_values(&["longest", "shortest"])
+                .conflicts_with_all(&["shuffle", "shuffle-seed"])
+                .help("Run the benchmark targets in order of how long they took in the last run. Possible values are longest, shortest.")
+                .long_help(
+"Run the benchmark targets in order of how long their benchmarks took to measure in the last run.
+Possible values are [longest, shortest].
+
+longest: The slowest targets run first, eg. to get them started early.
+
+shortest: The quickest targets run first, for quick feedback.
+
+The duration of a target is the total time of the samples stored for its benchmarks. Targets
+without stored results (including results stored by versions of cargo-criterion which didn't
+record their target) run last, in the order they were discovered. As with --shuffle, Criterion.rs
+runs the benchmarks within each target in the order they are defined in, and unless --order is set,
+benchmarks are sorted by name in the reports.
+")
+        )
+        .arg(
+            Arg::with_name("baseline-window")
+                .long("--baseline-window")
+                .takes_value(true)
+                .value_name("N")
+                .help("Compare against the average of the last N stored runs instead of only the previous run.")
+                .long_help(
+"Compare each benchmark against the average of the last N stored runs instead of only the previous
+run. The samples of those runs are pooled together to form the baseline, which makes the change
+detection (and --fail-on-regression) less sensitive to a single unusually fast or slow run.
+
+Defaults to 1, which compares against the previous run only.
+")
+        )
+        .arg(
+            Arg::with_name("baseline-store")
+                .long("--baseline-store")
+                .takes_value(true)
+                .value_name("LOCATION")
+                .help("Load the baselines from, and save the results to, an external store such as sqlite:PATH.")
+                .long_help(
+"Compare the benchmarks against the results in an external store instead of those in the data
+directory, and save the results of the run to it once the run is over. This lets a team keep the
+history of their benchmarks in a shared database. The results are still saved to the data
+directory as well, which is used for the reports.
+
+The only store built in is SQLite, given as sqlite:PATH, which requires cargo-criterion to be built
+with the sqlite_baselines feature. Each benchmark is compared against its most recent run in the
+store, so --baseline-window has no effect.
+
+This can also be set with baseline_store in the criterion.toml file.
+")
+        )
+        .arg(
+            Arg::with_name("overlay-baselines")
+                .long("--overlay-baselines")
+                .takes_value(true)
+                .value_name("NAMES")
+                .help("Draw the distributions of these stored baselines (eg. v1,v2) over the comparison plots.")
+                .long_help(
+"Draw the distributions of the given comma-separated list of stored baselines (eg. v1,v2) over the
+distribution plot which compares each benchmark against its baseline, each in its own color and
+labeled with its name in the legend. This shows how the benchmark has moved across several
+historical baselines at once rather than only since the previous run.
+
+The baselines are the directories in the data directory, as listed by 'cargo criterion baselines
+list'. The latest measurements of each benchmark in each baseline are drawn, and baselines which
+have no measurements of a benchmark are left out of its plot.
+")
+        )
+        .arg(
+            Arg::with_name("encrypt-history")
+                .long("--encrypt-history")
+                .help("Encrypt the stored measurements with the key in CARGO_CRITERION_HISTORY_KEY.")
+                .long_help(
+"Encrypt the stored measurements with the key in the CARGO_CRITERION_HISTORY_KEY environment
+variable, which must be 64 hexadecimal digits (a 256-bit key). The files are sealed with
+ChaCha20-Poly1305, so a wrong key is reported as an error rather than producing garbage.
+
+Encrypted files are decrypted whenever the key is set, with or without this option. Plaintext
+files are always readable, so an existing store can be encrypted gradually as benchmarks are
+re-run. The HTML reports are not encrypted.
+")
+        )
             .arg(Arg::with_name("message-format")
                 .long("message-format")
                 .takes_value(true)
-                .possible_values(&["json"])
+                .possible_values(&["json", "bmf"])
                 .help("If set, machine-readable output of the requested format will be printed to stdout.")
                 .long_help(
-"Change the machine-readable output format. Possible values are [json].
+"Change the machine-readable output format. Possible values are [json, bmf].
 
 Machine-readable information on the benchmarks will be printed in the requested format to stdout.
 All of cargo-criterion's other output will be printed to stderr.
 
+The JSON format also includes progress messages (benchmark-started, warmup-complete and
+measurement-progress) which are sent while each benchmark is running, followed by its
+benchmark-complete message.
+
+The bmf format is the Bencher Metric Format used by bencher.dev: a single JSON object printed once
+all benchmarks have run, giving the latency of each benchmark in nanoseconds (and its throughput
+per second, if it has one) with the bounds of its confidence interval.
+
 See the documentation for details on the data printed by each format.
 ")
         )
@@ -445,6 +1957,27 @@ See the documentation for details on the data printed by each format.
                 .takes_value(true)
                 .help("An optional description string such as a commit message that will be shown in the history reports to describe this run.")
         )
+        .arg(
+            Arg::with_name("label")
+                .long("--label")
+                .takes_value(true)
+                .value_name("TEXT")
+                .help("An optional free-form note such as \"before SIMD rewrite\" that will be saved with this run and shown in the reports and JSON messages.")
+        )
+        .arg(
+            Arg::with_name("metadata-env")
+                .long("--metadata-env")
+                .takes_value(true)
+                .value_name("VARS")
+                .help("A comma-separated list of environment variables (eg. BUILD_NUMBER,BRANCH) whose values are saved with this run and shown in the reports and JSON messages.")
+                .long_help(
+"A comma-separated list of environment variables (eg. BUILD_NUMBER,BRANCH,RUNNER_NAME) whose
+values are saved with this run as metadata, for tracing the results back to the CI job that
+produced them. The metadata is shown in the header of each benchmark's report, in the history
+report and in the JSON messages. Variables which aren't set are left out. Overrides the
+metadata_env list in the configuration file.
+")
+        )
         .arg(
             Arg::with_name("verbose")
                 .long("--verbose")
@@ -457,7 +1990,16 @@ See the documentation for details on the data printed by each format.
                 .long("--color")
                 .takes_value(true)
                 .possible_values(&["auto", "always", "never"])
-                .help("Coloring: auto, always, never"),
+                .help("Coloring: auto, always, never")
+                .long_help(
+"Coloring: auto, always, never. With never, the output is guaranteed to be plain text without any
+ANSI escape sequences, for capturing into log files. Any escape sequences in the output of the
+benchmark targets, in the benchmark IDs or in the warnings are removed as well, and the progress
+messages are printed on separate lines rather than overwritten in place.
+
+Defaults to auto, which only colors the output if it's a terminal, or to never if the NO_COLOR
+environment variable is set.
+"),
         )
         .arg(
             Arg::with_name("frozen")
@@ -497,6 +2039,51 @@ See the documentation for details on the data printed by each format.
                 .multiple(true)
                 .help("Arguments for the bench binary"),
         )
+        .subcommand(
+            SubCommand::with_name("baselines")
+                .about("List or delete the stored baselines")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("Print the stored baselines with the time and label of their latest measurements"),
+                )
+                .subcommand(
+                    SubCommand::with_name("rm")
+                        .about("Delete a stored baseline")
+                        .arg(
+                            Arg::with_name("NAME")
+                                .required(true)
+                                .help("The name of the baseline to delete"),
+                        )
+                        .arg(
+                            Arg::with_name("force")
+                                .long("--force")
+                                .help("Allow deleting the 'main' baseline, which is used by every run"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clean")
+                .about("Delete all stored measurements, history and reports, so that the next run starts fresh")
+                .arg(
+                    Arg::with_name("yes")
+                        .long("--yes")
+                        .short("y")
+                        .help("Don't ask for confirmation before deleting"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("render")
+                .about("Draw the plots which --lazy-plots left out of the reports")
+                .arg(
+                    Arg::with_name("ID")
+                        .help("The ID of the benchmark to draw the plots of. If not given, the plots of every benchmark which is missing them are drawn"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Check that the stored measurements can be loaded, and list the files which are corrupt"),
+        )
         .after_help(
             "\
 The benchmark filtering argument BENCHNAME and all the arguments following the
@@ -621,6 +2208,9 @@ Compilation can be customized with the `bench` profile in the manifest.
     if let Some(value) = matches.value_of_os("color") {
         cargo_args.push("--color".into());
         cargo_args.push(value.to_owned());
+    } else if no_color_requested() {
+        cargo_args.push("--color".into());
+        cargo_args.push("never".into());
     }
     if matches.is_present("frozen") {
         cargo_args.push("--frozen".into());
@@ -655,42 +2245,415 @@ Compilation can be customized with the `bench` profile in the manifest.
         PathBuf::from("target/criterion")
     };
 
+    let shuffle_seed = match matches.value_of("shuffle-seed") {
+        Some(value) => Some(
+            value
+                .parse()
+                .with_context(|| format!("Invalid value for --shuffle-seed: {:?}", value))?,
+        ),
+        None if matches.is_present("shuffle") => Some(crate::shuffle::random_seed()),
+        None => None,
+    };
+
+    let order_by_duration = matches
+        .value_of("order-by-duration")
+        .map(DurationOrder::from_str);
+
+    let cpu_affinity = match matches.value_of("cpu-affinity") {
+        Some(list) => {
+            let cpus = crate::affinity::parse_cpu_list(list)
+                .with_context(|| format!("Invalid value for --cpu-affinity: {:?}", list))?;
+            if !crate::affinity::SUPPORTED {
+                warn!("--cpu-affinity is not supported on this platform; the benchmarks will run unpinned");
+            }
+            Some(cpus)
+        }
+        None => None,
+    };
+
+    let metadata_env: Vec<String> = match matches.value_of("metadata-env") {
+        Some(names) => names
+            .split(',')
+            .map(|name| name.trim().to_owned())
+            .collect(),
+        None => toml_config.metadata_env.clone(),
+    };
+
+    let progress = (matches.value_of("progress"))
+        .or(toml_config.progress.as_deref())
+        .map(Progress::from_str)
+        .transpose()?
+        .unwrap_or(Progress::Full);
+
+    for (id, &weight) in &toml_config.weights {
+        if !(weight > 0.0 && weight.is_finite()) {
+            bail!(
+                "The weight of benchmark {} must be a positive number, but was {}",
+                id,
+                weight
+            );
+        }
+    }
+
+    if let Some(min_displayed_change) = toml_config.min_displayed_change {
+        if !(min_displayed_change >= 0.0 && min_displayed_change.is_finite()) {
+            bail!(
+                "min_displayed_change must be a non-negative fraction, but was {}",
+                min_displayed_change
+            );
+        }
+    }
+
+    if let Some(speedup_threshold) = toml_config.speedup_threshold {
+        if !(speedup_threshold >= 1.0 && speedup_threshold.is_finite()) {
+            bail!(
+                "speedup_threshold must be a factor of at least 1, but was {}",
+                speedup_threshold
+            );
+        }
+    }
+
+    let derived_metrics = toml_config
+        .derived_metrics
+        .iter()
+        .map(|(name, expression)| {
+            DerivedMetric::parse(name, expression)
+                .with_context(|| format!("Invalid derived metric {} = {:?}", name, expression))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // The summary plots draw the budgets like targets, unless a target is set as well.
+    let mut plots = toml_config.plots;
+    for (id, budget) in &toml_config.budgets {
+        plots.targets.entry(id.clone()).or_insert(*budget);
+    }
+    plots.speedup_threshold = toml_config.speedup_threshold;
+
     let self_config = SelfConfig {
         output_format: (matches.value_of("output-format"))
             .or(toml_config.output_format.as_deref())
             .map(OutputFormat::from_str)
             .unwrap_or(OutputFormat::Criterion),
+        progress,
         criterion_home,
         do_run: !matches.is_present("no-run"),
         do_fail_fast: !matches.is_present("no-fail-fast"),
-        text_color: (matches.value_of("color"))
-            .map(TextColor::from_str)
-            .unwrap_or(TextColor::Auto),
+        fail_on_regression: matches.is_present("fail-on-regression"),
+        fail_on_change: matches.is_present("fail-on-change"),
+        max_rsd: match matches.value_of("max-rsd") {
+            Some(value) => {
+                let max_rsd: f64 = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --max-rsd: {:?}", value))?;
+                if max_rsd.is_nan() || max_rsd <= 0.0 {
+                    bail!("--max-rsd must be greater than zero, but was {}", value);
+                }
+                Some(max_rsd)
+            }
+            None => None,
+        },
+        fail_on_high_variance: matches.is_present("fail-on-high-variance"),
+        stabilize: match matches.value_of("stabilize") {
+            Some(value) => {
+                let target_rsd: f64 = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --stabilize: {:?}", value))?;
+                if target_rsd.is_nan() || target_rsd <= 0.0 {
+                    bail!("--stabilize must be greater than zero, but was {}", value);
+                }
+                Some(target_rsd)
+            }
+            None => None,
+        },
+        max_attempts: match matches.value_of("max-attempts") {
+            Some(value) => {
+                let max_attempts: usize = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --max-attempts: {:?}", value))?;
+                if max_attempts == 0 {
+                    bail!("--max-attempts must be at least 1");
+                }
+                max_attempts
+            }
+            None => DEFAULT_MAX_ATTEMPTS,
+        },
+        budgets: toml_config.budgets,
+        fail_on_budget: matches.is_present("fail-on-budget"),
+        determinism_tolerance: if matches.is_present("verify-determinism") {
+            let tolerance = match matches.value_of("determinism-tolerance") {
+                Some(value) => value.parse().with_context(|| {
+                    format!("Invalid value for --determinism-tolerance: {:?}", value)
+                })?,
+                None => DEFAULT_DETERMINISM_TOLERANCE,
+            };
+            if tolerance.is_nan() || tolerance <= 0.0 {
+                bail!(
+                    "--determinism-tolerance must be greater than zero, but was {}",
+                    tolerance
+                );
+            }
+            Some(tolerance)
+        } else {
+            None
+        },
+        post_process: matches.value_of("post-process").map(|s| s.to_owned()),
+        post_process_required: matches.is_present("post-process-required"),
+        export_diff_csv: matches.value_of_os("export-diff-csv").map(PathBuf::from),
+        export_rust: matches.value_of_os("export-rust").map(PathBuf::from),
+        export_bin: matches.value_of_os("export-bin").map(PathBuf::from),
+        ungated_benchmarks: toml_config.ungated_benchmarks,
+        geometric_mean: matches.is_present("geometric-mean"),
+        weights: toml_config.weights,
+        min_displayed_change: toml_config.min_displayed_change,
+        speedup_threshold: toml_config.speedup_threshold,
+        derived_metrics,
+        total_time: matches.is_present("total-time"),
+        json_reports: matches.is_present("json-reports"),
+        export_curves: matches.is_present("export-curves"),
+        export_bootstrap: matches.is_present("export-bootstrap"),
+        export_samples: matches.is_present("export-samples"),
+        atomic_reports: matches.is_present("atomic-reports"),
+        export_site: matches.value_of_os("export-site").map(PathBuf::from),
+        contact_sheet: matches.value_of_os("contact-sheet").map(PathBuf::from),
+        report_time_limit: match matches.value_of("report-time-limit") {
+            Some(value) => {
+                let seconds: f64 = value.parse().with_context(|| {
+                    format!("Invalid value for --report-time-limit: {:?}", value)
+                })?;
+                if !(seconds > 0.0 && seconds.is_finite()) {
+                    bail!(
+                        "--report-time-limit must be a positive number of seconds, but was {}",
+                        value
+                    );
+                }
+                Some(Duration::from_secs_f64(seconds))
+            }
+            None => None,
+        },
+        strict_plots: matches.is_present("strict-plots"),
+        lazy_plots: matches.is_present("lazy-plots"),
+        thumbnails: !matches.is_present("no-thumbnails"),
+        diff_report: matches.is_present("diff-report"),
+        text_color: match matches.value_of("color") {
+            Some(value) => TextColor::from_str(value),
+            None if no_color_requested() => TextColor::Never,
+            None => TextColor::Auto,
+        },
         plotting_backend: (matches.value_of("plotting-backend"))
             .or(toml_config.plotting_backend.as_deref())
             .map(PlottingBackend::from_str)
             .unwrap_or(PlottingBackend::Auto),
+        order: (matches.value_of("order"))
+            .or(toml_config.order.as_deref())
+            .map(BenchmarkOrder::from_str)
+            .unwrap_or(if shuffle_seed.is_some() || order_by_duration.is_some() {
+                // The execution order changes every run, so don't let it determine the reports.
+                BenchmarkOrder::Name
+            } else {
+                BenchmarkOrder::Discovery
+            }),
+        encrypt_history: matches.is_present("encrypt-history"),
+        baseline_window: match matches.value_of("baseline-window") {
+            Some(value) => value
+                .parse()
+                .with_context(|| format!("Invalid value for --baseline-window: {:?}", value))?,
+            None => toml_config.baseline_window.unwrap_or(1),
+        },
+        baseline_store: matches
+            .value_of("baseline-store")
+            .or(toml_config.baseline_store.as_deref())
+            .map(ToOwned::to_owned),
+        overlay_baselines: match matches.value_of("overlay-baselines") {
+            Some(value) => {
+                let names: Vec<String> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+                if names.is_empty() {
+                    bail!("--overlay-baselines requires at least one baseline name");
+                }
+                if let Some(name) = names
+                    .iter()
+                    .find(|name| !crate::model::is_valid_baseline_name(name))
+                {
+                    bail!("Invalid baseline name {:?} in --overlay-baselines", name);
+                }
+                names
+            }
+            None => vec![],
+        },
+        time_unit: (matches.value_of("time-unit"))
+            .or(toml_config.time_unit.as_deref())
+            .map(TimeUnit::from_str),
+        rounding: (matches.value_of("rounding"))
+            .or(toml_config.rounding.as_deref())
+            .map(RoundingMode::from_str),
+        statistics: match matches
+            .value_of("statistics")
+            .or(toml_config.statistics.as_deref())
+        {
+            Some(list) => Some(parse_statistics(list).context("Invalid value for --statistics")?),
+            None => None,
+        },
         debug_build: matches.is_present("debug"),
+        quick: matches.is_present("quick"),
+        sample_size: match matches.value_of("sample-size") {
+            Some(value) => {
+                let sample_size: usize = value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --sample-size: {:?}", value))?;
+                if sample_size < MIN_SAMPLE_SIZE {
+                    bail!(
+                        "--sample-size must be at least {}, but was {}",
+                        MIN_SAMPLE_SIZE,
+                        sample_size
+                    );
+                }
+                Some(sample_size)
+            }
+            None => None,
+        },
+        max_samples: {
+            let max_samples = match matches.value_of("max-samples") {
+                Some(value) => value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --max-samples: {:?}", value))?,
+                None => toml_config.max_samples.unwrap_or(DEFAULT_MAX_SAMPLES),
+            };
+            if max_samples < MIN_SAMPLE_SIZE {
+                bail!(
+                    "--max-samples must be at least {}, but was {}",
+                    MIN_SAMPLE_SIZE,
+                    max_samples
+                );
+            }
+            max_samples
+        },
+        build_mode: if matches.is_present("no-build") {
+            BuildMode::Never
+        } else if matches.is_present("reuse-build") {
+            BuildMode::Reuse
+        } else {
+            BuildMode::Always
+        },
+        shuffle_seed,
+        order_by_duration,
+        cpu_affinity,
+        toolchains: match matches.value_of("toolchains") {
+            Some(value) => {
+                let toolchains: Vec<String> = value
+                    .split(',')
+                    .map(|toolchain| toolchain.trim().trim_start_matches('+'))
+                    .filter(|toolchain| !toolchain.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+                if toolchains.is_empty() {
+                    bail!("--toolchains requires at least one toolchain");
+                }
+                toolchains
+            }
+            None => vec![],
+        },
+        since: matches.value_of("since").map(|s| s.to_owned()),
+        reuse_unchanged: matches.value_of("reuse-unchanged").map(|s| s.to_owned()),
+        benchmark_paths: toml_config.benchmark_paths,
+        stdin_benchmark: matches.value_of("from-stdin").map(|s| s.to_owned()),
+        import_bin: matches.value_of_os("import-bin").map(PathBuf::from),
+        serve: matches.value_of("serve").map(|s| s.to_owned()),
         message_format: (matches.value_of("message-format")).map(MessageFormat::from_str),
         colors: toml_config.colors,
+        plot_options: PlotOptions {
+            interactive_svg: plots.interactive_svg || matches.is_present("interactive-svg"),
+            regression_log_y: plots.regression_log_y || matches.is_present("regression-log-y"),
+            kde_points: match matches.value_of("kde-points") {
+                Some(value) => value
+                    .parse()
+                    .with_context(|| format!("Invalid value for --kde-points: {:?}", value))?,
+                None => plots.kde_points,
+            },
+            max_plot_points: match matches.value_of("max-plot-points") {
+                Some(value) => Some(value.parse().with_context(|| {
+                    format!("Invalid value for --max-plot-points: {:?}", value)
+                })?),
+                None => plots.max_plot_points,
+            },
+            ..plots
+        }
+        .validated(),
         history_id: matches.value_of("history_id").map(|s| s.to_owned()),
         history_description: matches
             .value_of("history_description")
             .map(|s| s.to_owned()),
+        label: matches.value_of("label").map(|s| s.to_owned()),
+        metadata: read_metadata(&metadata_env),
     };
 
     // These are the extra arguments to be passed to the benchmark targets.
+    let bench_filter = matches.value_of_os("BENCHNAME").map(ToOwned::to_owned);
     let mut additional_args: Vec<OsString> = vec![];
-    additional_args.extend(matches.value_of_os("BENCHNAME").map(ToOwned::to_owned));
 
     if let Some(args) = matches.values_of_os("args") {
         additional_args.extend(args.map(ToOwned::to_owned));
     }
 
+    // In quick mode, shorten the measurements unless the user has chosen the times themselves.
+    if self_config.quick {
+        for &(arg, value) in QUICK_TIMING_ARGS {
+            if !additional_args.iter().any(|existing| existing == arg) {
+                additional_args.push(arg.into());
+                additional_args.push(value.into());
+            }
+        }
+    }
+
+    if let Some(sample_size) = self_config.sample_size {
+        if !additional_args
+            .iter()
+            .any(|existing| existing == "--sample-size")
+        {
+            additional_args.push("--sample-size".into());
+            additional_args.push(sample_size.to_string().into());
+        }
+    }
+
+    let store_command = matches
+        .subcommand_matches("baselines")
+        .and_then(|baselines| match baselines.subcommand() {
+            ("list", _) => Some(StoreCommand::List),
+            ("rm", Some(rm)) => Some(StoreCommand::Remove {
+                name: rm.value_of("NAME").unwrap().to_owned(),
+                force: rm.is_present("force"),
+            }),
+            _ => None,
+        })
+        .or_else(|| {
+            matches
+                .subcommand_matches("clean")
+                .map(|clean| StoreCommand::Clean {
+                    yes: clean.is_present("yes"),
+                })
+        })
+        .or_else(|| {
+            matches
+                .subcommand_matches("render")
+                .map(|render| StoreCommand::Render {
+                    id: render.value_of("ID").map(ToOwned::to_owned),
+                })
+        })
+        .or_else(|| {
+            matches
+                .subcommand_matches("verify")
+                .map(|_| StoreCommand::Verify)
+        });
+
     let configuration = FullConfig {
         self_config,
         cargo_args,
+        bench_filter,
         additional_args,
+        store_command,
     };
     Ok(configuration)
 }
@@ -712,3 +2675,63 @@ fn load_toml_file(toml_path: &Path) -> Result<TomlConfig, anyhow::Error> {
         .with_context(|| format!("Failed to parse config file {:?}", toml_path))?;
     Ok(config)
 }
+
+/// Reads the values of the named environment variables. Variables which aren't set are left out.
+fn read_metadata(names: &[String]) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+    for name in names.iter().filter(|name| !name.is_empty()) {
+        match std::env::var(name) {
+            Ok(value) => {
+                metadata.insert(name.clone(), value);
+            }
+            Err(std::env::VarError::NotPresent) => {
+                debug!("Metadata variable {} isn't set, leaving it out", name)
+            }
+            Err(std::env::VarError::NotUnicode(_)) => {
+                warn!(
+                    "Metadata variable {} isn't valid Unicode, leaving it out",
+                    name
+                )
+            }
+        }
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_statistics() {
+        let statistics = parse_statistics("mean, p99").unwrap();
+        assert_eq!(
+            vec![
+                SelectedStatistic::Estimate(Statistic::Mean),
+                SelectedStatistic::Percentile(99.0)
+            ],
+            statistics
+        );
+        assert!(is_selected(Some(&statistics), Statistic::Mean));
+        assert!(!is_selected(Some(&statistics), Statistic::Slope));
+        assert!(is_selected(None, Statistic::Slope));
+
+        assert!(parse_statistics("p98").is_err());
+        assert!(parse_statistics("mode").is_err());
+    }
+
+    #[test]
+    fn test_read_metadata() {
+        let name = format!("CARGO_CRITERION_TEST_METADATA_{}", std::process::id());
+        std::env::set_var(&name, "1234");
+        let metadata = read_metadata(&[
+            name.clone(),
+            "CARGO_CRITERION_TEST_METADATA_UNSET".to_owned(),
+            String::new(),
+        ]);
+        std::env::remove_var(&name);
+
+        assert_eq!(1, metadata.len());
+        assert_eq!(Some("1234"), metadata.get(&name).map(String::as_str));
+    }
+}
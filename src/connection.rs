@@ -179,6 +179,21 @@ pub enum IncomingMessage {
         plot_config: PlotConfiguration,
         sampling_method: SamplingMethod,
         benchmark_config: BenchmarkConfig,
+        // The latencies of the individual elements processed by the benchmark, for benchmarks
+        // which measure them. This is an extension of the protocol which no released version of
+        // Criterion.rs sends, so it's always missing with those.
+        #[serde(default)]
+        latencies: Option<Vec<f64>>,
+        // The estimated overhead of the measurement loop per iteration, in nanoseconds. Criterion.rs
+        // doesn't estimate this yet, so only a benchmark harness which extends the protocol sends
+        // it.
+        #[serde(default)]
+        overhead: Option<f64>,
+        // The wall-clock time at which each sample was taken, in seconds since the Unix epoch,
+        // for benchmarks which record them. Released versions of Criterion.rs don't record them,
+        // so this depends on a harness which implements the extension.
+        #[serde(default)]
+        timestamps: Option<Vec<f64>>,
     },
     // Value formatting responses
     FormattedValue {
@@ -0,0 +1,254 @@
+//! User-defined derived metrics (the `derived_metrics` table of criterion.toml), which are simple
+//! arithmetic expressions over the estimates of each benchmark, eg. `mean / throughput_bytes` for
+//! the time per byte.
+
+use crate::connection::Throughput;
+use crate::report::MeasurementData;
+use anyhow::{anyhow, bail, Context, Result};
+
+/// The values which can be referred to in an expression. Times are the point estimates in
+/// nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Typical,
+    Mean,
+    Median,
+    MedianAbsDev,
+    StdDev,
+    Slope,
+    ThroughputBytes,
+    ThroughputElements,
+}
+const FIELDS: [(&str, Field); 8] = [
+    ("typical", Field::Typical),
+    ("mean", Field::Mean),
+    ("median", Field::Median),
+    ("median_abs_dev", Field::MedianAbsDev),
+    ("std_dev", Field::StdDev),
+    ("slope", Field::Slope),
+    ("throughput_bytes", Field::ThroughputBytes),
+    ("throughput_elements", Field::ThroughputElements),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Field(Field),
+    Negate(Box<Expr>),
+    Binary(Box<Expr>, char, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E') {
+                    break;
+                }
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .with_context(|| format!("Invalid number {:?}", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            bail!("Unexpected character {:?}", c);
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive-descent parser for the usual precedence of `+ - * /`, unary minus and parentheses.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<Expr> {
+        let mut left = self.term()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != '+' && op != '-' {
+                break;
+            }
+            self.advance();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        let mut left = self.factor()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != '*' && op != '/' {
+                break;
+            }
+            self.advance();
+            left = Expr::Binary(Box::new(left), op, Box::new(self.factor()?));
+        }
+        Ok(left)
+    }
+
+    fn factor(&mut self) -> Result<Expr> {
+        match self.advance() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) => FIELDS
+                .iter()
+                .find(|(field_name, _)| *field_name == name)
+                .map(|&(_, field)| Expr::Field(field))
+                .ok_or_else(|| {
+                    let names: Vec<_> = FIELDS.iter().map(|(name, _)| *name).collect();
+                    anyhow!(
+                        "Unknown field {:?}; expected one of {}",
+                        name,
+                        names.join(", ")
+                    )
+                }),
+            Some(Token::Op('-')) => Ok(Expr::Negate(Box::new(self.factor()?))),
+            Some(Token::Op('(')) => {
+                let inner = self.expression()?;
+                match self.advance() {
+                    Some(Token::Op(')')) => Ok(inner),
+                    _ => bail!("Expected a closing parenthesis"),
+                }
+            }
+            Some(Token::Op(op)) => bail!("Unexpected {:?}", op),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+/// A named metric computed from the estimates of each benchmark.
+#[derive(Debug, Clone)]
+pub struct DerivedMetric {
+    pub name: String,
+    expression: Expr,
+}
+impl DerivedMetric {
+    /// Parses the expression, checking that it only refers to known fields.
+    pub fn parse(name: &str, source: &str) -> Result<DerivedMetric> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+        };
+        let expression = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected {:?} after the end of the expression", token);
+        }
+        Ok(DerivedMetric {
+            name: name.to_owned(),
+            expression,
+        })
+    }
+
+    /// Computes the metric for a benchmark. Returns None if the expression refers to a value the
+    /// benchmark doesn't have (eg. its throughput) or the result isn't a finite number.
+    pub fn evaluate(&self, measurements: &MeasurementData<'_>) -> Option<f64> {
+        let value = evaluate(&self.expression, &|field| {
+            let estimates = &measurements.absolute_estimates;
+            match (field, &measurements.throughput) {
+                (Field::Typical, _) => Some(estimates.typical().point_estimate),
+                (Field::Mean, _) => Some(estimates.mean.point_estimate),
+                (Field::Median, _) => Some(estimates.median.point_estimate),
+                (Field::MedianAbsDev, _) => Some(estimates.median_abs_dev.point_estimate),
+                (Field::StdDev, _) => Some(estimates.std_dev.point_estimate),
+                (Field::Slope, _) => estimates.slope.as_ref().map(|slope| slope.point_estimate),
+                (Field::ThroughputBytes, Some(Throughput::Bytes(bytes))) => Some(*bytes as f64),
+                (Field::ThroughputElements, Some(Throughput::Elements(elements))) => {
+                    Some(*elements as f64)
+                }
+                (Field::ThroughputBytes, _) | (Field::ThroughputElements, _) => None,
+            }
+        })?;
+        Some(value).filter(|value| value.is_finite())
+    }
+}
+
+fn evaluate(expression: &Expr, field: &dyn Fn(Field) -> Option<f64>) -> Option<f64> {
+    match expression {
+        Expr::Number(value) => Some(*value),
+        Expr::Field(f) => field(*f),
+        Expr::Negate(inner) => evaluate(inner, field).map(|value| -value),
+        Expr::Binary(left, op, right) => {
+            let (left, right) = (evaluate(left, field)?, evaluate(right, field)?);
+            Some(match op {
+                '+' => left + right,
+                '-' => left - right,
+                '*' => left * right,
+                _ => left / right,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn eval(source: &str, throughput_bytes: Option<f64>) -> Option<f64> {
+        let metric = DerivedMetric::parse("test", source).unwrap();
+        evaluate(&metric.expression, &|field| match field {
+            Field::Mean => Some(100.0),
+            Field::Median => Some(80.0),
+            Field::ThroughputBytes => throughput_bytes,
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(Some(0.5), eval("mean / throughput_bytes", Some(200.0)));
+        assert_eq!(None, eval("mean / throughput_bytes", None));
+        assert_eq!(Some(260.0), eval("mean + median * 2", None));
+        assert_eq!(Some(360.0), eval("(mean + median) * 2", None));
+        assert_eq!(Some(-20.0), eval("median - mean", None));
+        assert_eq!(Some(20.0), eval("-(median - mean)", None));
+        assert_eq!(Some(0.1), eval("mean / 1e3", None));
+        assert_eq!(None, eval("slope", None));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(DerivedMetric::parse("x", "mean / bytes").is_err());
+        assert!(DerivedMetric::parse("x", "mean /").is_err());
+        assert!(DerivedMetric::parse("x", "(mean").is_err());
+        assert!(DerivedMetric::parse("x", "mean median").is_err());
+        assert!(DerivedMetric::parse("x", "mean % 2").is_err());
+        assert!(DerivedMetric::parse("x", "").is_err());
+    }
+}
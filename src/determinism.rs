@@ -0,0 +1,92 @@
+//! Support for `--verify-determinism`, which runs the benchmarks a second time straight after the
+//! first run and flags the benchmarks whose estimates disagree between the two runs.
+
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+fn percentage(fraction: f64) -> String {
+    crate::format::change(fraction, false).trim().to_owned()
+}
+
+/// Report which records the typical estimate of each benchmark in the first run and compares it
+/// to the estimate of the same benchmark in the second run.
+pub struct DeterminismCheck {
+    tolerance: f64,
+    second_run: Cell<bool>,
+    first_estimates: RefCell<HashMap<String, f64>>,
+    // The benchmarks which disagreed, with the relative difference between the two runs.
+    flagged: RefCell<Vec<(String, f64)>>,
+    compared: Cell<usize>,
+}
+impl DeterminismCheck {
+    pub fn new(tolerance: f64) -> DeterminismCheck {
+        DeterminismCheck {
+            tolerance,
+            second_run: Cell::new(false),
+            first_estimates: RefCell::new(HashMap::new()),
+            flagged: RefCell::new(vec![]),
+            compared: Cell::new(0),
+        }
+    }
+
+    /// Called before the benchmarks are run again; estimates from now on are compared to the
+    /// first run rather than recorded.
+    pub fn start_second_run(&self) {
+        self.second_run.set(true);
+    }
+
+    /// Lists the benchmarks whose estimates differed between the two runs by more than the
+    /// tolerance.
+    pub fn report(&self) {
+        let flagged = self.flagged.borrow();
+        if flagged.is_empty() {
+            eprintln!(
+                "All {} benchmarks agreed between the two runs to within {}.",
+                self.compared.get(),
+                percentage(self.tolerance)
+            );
+            return;
+        }
+
+        let list: Vec<String> = flagged
+            .iter()
+            .map(|(title, difference)| format!("{} ({})", title, percentage(*difference)))
+            .collect();
+        warn!(
+            "The following benchmarks appear to be nondeterministic; their estimates differed \
+            between two consecutive runs by more than {}:\n{}",
+            percentage(self.tolerance),
+            list.join("\n")
+        );
+    }
+}
+impl Report for DeterminismCheck {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        let estimate = measurements.absolute_estimates.typical().point_estimate;
+        let title = id.as_title().to_owned();
+        if !self.second_run.get() {
+            self.first_estimates.borrow_mut().insert(title, estimate);
+            return;
+        }
+
+        // Benchmarks which only ran once (eg. because the first run failed) can't be compared.
+        let first = match self.first_estimates.borrow().get(&title) {
+            Some(&first) => first,
+            None => return,
+        };
+        self.compared.set(self.compared.get() + 1);
+        let difference = estimate / first - 1.0;
+        // A zero estimate gives NaN or infinity, which is as good as a disagreement.
+        if !difference.is_finite() || difference.abs() > self.tolerance {
+            self.flagged.borrow_mut().push((title, difference));
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Encryption of the stored benchmark data at rest (see `--encrypt-history`).
+//!
+//! Encrypted files start with a short magic string, followed by the nonce and the CBOR data
+//! sealed with ChaCha20-Poly1305. Files without the magic string are plain CBOR, so stores written
+//! before encryption was enabled (or with it disabled) can still be read.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::TryFrom;
+use std::fmt;
+use std::path::Path;
+
+/// The environment variable holding the key, as 64 hexadecimal digits.
+pub const KEY_VARIABLE: &str = "CARGO_CRITERION_HISTORY_KEY";
+
+const MAGIC: &[u8] = b"CCAEAD1\0";
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit key for encrypting the stored benchmark data.
+#[derive(Clone)]
+struct HistoryKey(Key);
+impl HistoryKey {
+    fn parse(hex: &str) -> Result<HistoryKey> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!(
+                "{} must be 64 hexadecimal digits (a 256-bit key)",
+                KEY_VARIABLE
+            );
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).unwrap();
+        }
+        Ok(HistoryKey(Key::from(bytes)))
+    }
+}
+impl fmt::Debug for HistoryKey {
+    // Don't let the key end up in the logs.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("HistoryKey(..)")
+    }
+}
+
+/// Reads and writes the files of the benchmark store, encrypting them if `--encrypt-history` is
+/// set. The key is used to decrypt encrypted files whenever it's available, so the store can still
+/// be read after encryption is turned off.
+#[derive(Debug, Clone, Default)]
+pub struct HistoryCipher {
+    key: Option<HistoryKey>,
+    encrypt: bool,
+}
+impl HistoryCipher {
+    /// Reads the key from the environment. It's an error if `encrypt` is set but the key isn't.
+    pub fn new(encrypt: bool) -> Result<HistoryCipher> {
+        let key = match std::env::var(KEY_VARIABLE) {
+            Ok(hex) => Some(HistoryKey::parse(&hex)?),
+            Err(std::env::VarError::NotPresent) => None,
+            Err(e) => return Err(e).with_context(|| format!("Invalid value for {}", KEY_VARIABLE)),
+        };
+        if encrypt && key.is_none() {
+            bail!(
+                "--encrypt-history requires the key to be set in the {} environment variable",
+                KEY_VARIABLE
+            );
+        }
+        Ok(HistoryCipher { key, encrypt })
+    }
+
+    /// Serializes the value to the given file, encrypting it if encryption is enabled.
+    pub fn write<T: Serialize>(&self, path: &Path, value: &T) -> Result<()> {
+        let plaintext = serde_cbor::to_vec(value)?;
+        let contents = match &self.key {
+            Some(key) if self.encrypt => seal(key, &plaintext)?,
+            _ => plaintext,
+        };
+        std::fs::write(path, contents).with_context(|| format!("Failed to write file {:?}", path))
+    }
+
+    /// Deserializes the value from the given file, decrypting it first if it's encrypted.
+    pub fn read<T: DeserializeOwned>(&self, path: &Path) -> Result<T> {
+        let contents =
+            std::fs::read(path).with_context(|| format!("Failed to open file {:?}", path))?;
+        if !contents.starts_with(MAGIC) {
+            return Ok(serde_cbor::from_slice(&contents)?);
+        }
+        let key = self.key.as_ref().ok_or_else(|| {
+            anyhow!(
+                "File {:?} is encrypted, but the key isn't set in the {} environment variable",
+                path,
+                KEY_VARIABLE
+            )
+        })?;
+        let plaintext = open(key, &contents[MAGIC.len()..]).ok_or_else(|| {
+            anyhow!(
+                "Failed to decrypt file {:?}. Either the key in {} is wrong or the file is corrupt",
+                path,
+                KEY_VARIABLE
+            )
+        })?;
+        Ok(serde_cbor::from_slice(&plaintext)?)
+    }
+}
+
+fn seal(key: &HistoryKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut nonce).map_err(|e| anyhow!("Failed to generate a nonce: {}", e))?;
+    let ciphertext = ChaCha20Poly1305::new(&key.0)
+        .encrypt(&Nonce::from(nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt the benchmark data"))?;
+
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+// Returns None if the data is truncated or fails authentication.
+fn open(key: &HistoryKey, sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = <[u8; NONCE_LEN]>::try_from(nonce).ok()?;
+    ChaCha20Poly1305::new(&key.0)
+        .decrypt(&Nonce::from(nonce), ciphertext)
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cipher(hex: &str, encrypt: bool) -> HistoryCipher {
+        HistoryCipher {
+            key: Some(HistoryKey::parse(hex).unwrap()),
+            encrypt,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "cargo-criterion-encryption-{}.cbor",
+            std::process::id()
+        ));
+        let key = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+
+        cipher(key, true).write(&path, &vec![1, 2, 3]).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(MAGIC));
+        let read: Vec<i32> = cipher(key, false).read(&path).unwrap();
+        assert_eq!(vec![1, 2, 3], read);
+
+        let wrong_key = "f".repeat(64);
+        assert!(cipher(&wrong_key, false).read::<Vec<i32>>(&path).is_err());
+        assert!(HistoryCipher::default().read::<Vec<i32>>(&path).is_err());
+
+        // Plaintext files are readable with or without a key.
+        HistoryCipher::default().write(&path, &vec![4]).unwrap();
+        let read: Vec<i32> = cipher(key, true).read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vec![4], read);
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert!(HistoryKey::parse("abc").is_err());
+        assert!(HistoryKey::parse(&"g".repeat(64)).is_err());
+    }
+}
@@ -41,6 +41,30 @@ pub struct Estimate {
     /// The standard error of this estimate
     pub standard_error: f64,
 }
+impl Estimate {
+    /// Converts an estimate of the relative change in the time per iteration into the equivalent
+    /// relative change in throughput. The bounds trade places, since the throughput falls as the
+    /// time rises.
+    pub fn to_throughput_change(&self) -> Estimate {
+        let time_ratio = 1.0 + self.point_estimate;
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: self.confidence_interval.confidence_level,
+                lower_bound: throughput_change(self.confidence_interval.upper_bound),
+                upper_bound: throughput_change(self.confidence_interval.lower_bound),
+            },
+            point_estimate: throughput_change(self.point_estimate),
+            // The first-order approximation, from the derivative of 1 / time_ratio.
+            standard_error: self.standard_error / (time_ratio * time_ratio),
+        }
+    }
+}
+
+/// Converts a relative change in the time per iteration into the equivalent relative change in
+/// throughput. Reducing the time by 50% increases the throughput by 100%.
+pub fn throughput_change(time_change: f64) -> f64 {
+    1.0 / (1.0 + time_change) - 1.0
+}
 
 pub fn build_estimates(
     distributions: &Distributions,
@@ -126,6 +150,17 @@ impl Estimates {
     }
 }
 
+/// The latencies of the individual elements measured by a benchmark at a fixed set of percentiles.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LatencyPercentiles {
+    /// The percentiles, in ascending order (eg. 50.0 for the median).
+    pub percentiles: Vec<f64>,
+    /// The latency at each of the percentiles.
+    pub latencies: Vec<f64>,
+    /// The number of latencies the percentiles were computed from.
+    pub count: usize,
+}
+
 pub struct Distributions {
     pub mean: Distribution<f64>,
     pub median: Distribution<f64>,
@@ -182,3 +217,26 @@ impl ChangeDistributions {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_throughput_change() {
+        let time_change = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: -0.5,
+                upper_bound: 0.25,
+            },
+            point_estimate: 0.0,
+            standard_error: 0.1,
+        };
+        let throughput_change = time_change.to_throughput_change();
+        assert_eq!(0.0, throughput_change.point_estimate);
+        assert!((throughput_change.confidence_interval.lower_bound + 0.2).abs() < 1e-9);
+        assert_eq!(1.0, throughput_change.confidence_interval.upper_bound);
+        assert_eq!(0.1, throughput_change.standard_error);
+    }
+}
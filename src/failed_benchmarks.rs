@@ -0,0 +1,37 @@
+//! Support for listing the benchmarks which failed (eg. because they panicked) once the run is
+//! over, rather than letting a single failure abort the whole run.
+
+use crate::report::{BenchmarkId, Report, ReportContext};
+use anyhow::{anyhow, Result};
+use std::cell::RefCell;
+
+/// Report which records every benchmark that failed, along with its error.
+#[derive(Default)]
+pub struct FailedBenchmarks {
+    failed: RefCell<Vec<(String, String)>>,
+}
+impl FailedBenchmarks {
+    /// Returns an error listing the failed benchmarks and their errors, if there were any.
+    pub fn check(&self) -> Result<()> {
+        let failed = self.failed.borrow();
+        if failed.is_empty() {
+            return Ok(());
+        }
+
+        let list: Vec<String> = failed
+            .iter()
+            .map(|(title, error)| format!("{}:\n    {}", title, error.replace('\n', "\n    ")))
+            .collect();
+        Err(anyhow!(
+            "The following benchmarks failed:\n{}",
+            list.join("\n")
+        ))
+    }
+}
+impl Report for FailedBenchmarks {
+    fn benchmark_failed(&self, id: &BenchmarkId, _: &ReportContext, error: &str) {
+        self.failed
+            .borrow_mut()
+            .push((id.as_title().to_owned(), error.to_owned()));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::config::{RoundingMode, TimeUnit};
+
 pub fn change(pct: f64, signed: bool) -> String {
     if signed {
         format!("{:>+6}%", signed_short(pct * 1e2))
@@ -6,6 +8,38 @@ pub fn change(pct: f64, signed: bool) -> String {
     }
 }
 
+/// Formats a relative change in time as a speedup or slowdown factor, eg. "3.2000x faster" for
+/// -0.6875.
+pub fn speedup(change: f64) -> String {
+    let ratio = 1.0 + change;
+    if ratio < 1.0 {
+        format!("{}x faster", short(1.0 / ratio))
+    } else {
+        format!("{}x slower", short(ratio))
+    }
+}
+
+/// Returns true if the relative change in time is at least `threshold`-fold in either direction,
+/// so that it's shown as a speedup factor rather than as a percentage.
+pub fn shows_speedup(change: f64, threshold: Option<f64>) -> bool {
+    let ratio = 1.0 + change;
+    match threshold {
+        Some(threshold) => ratio.max(1.0 / ratio) >= threshold,
+        None => false,
+    }
+}
+
+/// Formats a relative change in time as a speedup factor if the point estimate it belongs to is
+/// shown as one, and as a signed percentage otherwise, so that the bounds of a confidence interval
+/// are shown the same way as its point estimate.
+pub fn change_or_speedup(change: f64, point_estimate: f64, threshold: Option<f64>) -> String {
+    if shows_speedup(point_estimate, threshold) {
+        speedup(change)
+    } else {
+        self::change(change, true)
+    }
+}
+
 pub fn time(ns: f64) -> String {
     if ns < 1.0 {
         format!("{:>6} ps", short(ns * 1e3))
@@ -20,20 +54,66 @@ pub fn time(ns: f64) -> String {
     }
 }
 
-pub fn short(n: f64) -> String {
+pub fn time_in_unit(ns: f64, unit: TimeUnit) -> String {
+    let (nanos_per_unit, suffix) = time_unit_scale(unit);
+    format!("{:>6} {}", short(ns / nanos_per_unit), suffix)
+}
+
+/// Returns the number of nanoseconds in the given unit, and its suffix.
+pub fn time_unit_scale(unit: TimeUnit) -> (f64, &'static str) {
+    match unit {
+        TimeUnit::Nanoseconds => (1.0, "ns"),
+        TimeUnit::Microseconds => (1e3, "us"),
+        TimeUnit::Milliseconds => (1e6, "ms"),
+        TimeUnit::Seconds => (1e9, "s"),
+    }
+}
+
+/// The number of decimal places `short` shows for the given value.
+fn short_precision(n: f64) -> usize {
     if n < 10.0 {
-        format!("{:.4}", n)
+        4
     } else if n < 100.0 {
-        format!("{:.3}", n)
+        3
     } else if n < 1000.0 {
-        format!("{:.2}", n)
+        2
     } else if n < 10000.0 {
-        format!("{:.1}", n)
+        1
     } else {
-        format!("{:.0}", n)
+        0
     }
 }
 
+pub fn short(n: f64) -> String {
+    format!("{:.*}", short_precision(n), n)
+}
+
+/// Like `short`, but rounds the value with the given rounding mode.
+pub fn short_rounded(n: f64, rounding: RoundingMode) -> String {
+    let precision = short_precision(n);
+    format!("{:.*}", precision, round(n, precision, rounding))
+}
+
+/// Rounds the value to the given number of decimal places. Values within floating-point error of
+/// halfway between two results (eg. 2.345, which is really 2.34499999...) count as halfway.
+fn round(n: f64, decimals: usize, rounding: RoundingMode) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = n * factor;
+    let floor = scaled.floor();
+    let tolerance = 1e-9 * scaled.abs().max(1.0);
+    let rounded = if ((scaled - floor) - 0.5).abs() > tolerance {
+        scaled.round()
+    } else {
+        match rounding {
+            RoundingMode::HalfUp if n < 0.0 => floor,
+            RoundingMode::HalfUp => floor + 1.0,
+            RoundingMode::HalfEven if (floor as i64) % 2 == 0 => floor,
+            RoundingMode::HalfEven => floor + 1.0,
+        }
+    };
+    rounded / factor
+}
+
 fn signed_short(n: f64) -> String {
     let n_abs = n.abs();
 
@@ -76,10 +156,34 @@ pub fn integer(n: f64) -> String {
     format!("{}", n as u64)
 }
 
+pub fn bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KiB", (bytes as f64) / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MiB", (bytes as f64) / (1024.0 * 1024.0))
+    } else {
+        format!("{:.1} GiB", (bytes as f64) / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn test_short_rounded() {
+        assert_eq!("2.3457", short_rounded(2.34565, RoundingMode::HalfUp));
+        assert_eq!("2.3456", short_rounded(2.34565, RoundingMode::HalfEven));
+        assert_eq!("2.3458", short_rounded(2.34575, RoundingMode::HalfEven));
+        assert_eq!("23.455", short_rounded(23.4545, RoundingMode::HalfUp));
+        assert_eq!("-2.3457", short_rounded(-2.34565, RoundingMode::HalfUp));
+        // Values which aren't halfway round to the nearest either way.
+        assert_eq!("2.3456", short_rounded(2.345649, RoundingMode::HalfUp));
+        assert_eq!("2.3457", short_rounded(2.345651, RoundingMode::HalfEven));
+    }
+
     #[test]
     fn short_max_len() {
         let mut float = 1.0;
@@ -91,6 +195,35 @@ mod test {
         }
     }
 
+    #[test]
+    fn speedup_factors() {
+        assert_eq!("3.2000x faster", speedup(-0.6875));
+        assert_eq!("2.5000x slower", speedup(1.5));
+        assert!(shows_speedup(-0.6875, Some(2.0)));
+        assert!(shows_speedup(1.0, Some(2.0)));
+        assert!(!shows_speedup(-0.4, Some(2.0)));
+        assert!(!shows_speedup(-0.6875, None));
+        // The bounds follow the point estimate, even if they're below the threshold.
+        assert_eq!("1.9048x faster", change_or_speedup(-0.475, -0.6, Some(2.0)));
+        assert_eq!("-47.500%", change_or_speedup(-0.475, -0.4, Some(2.0)));
+    }
+
+    #[test]
+    fn time_in_fixed_unit() {
+        assert_eq!("1.5000 us", time_in_unit(1500.0, TimeUnit::Microseconds));
+        assert_eq!("1500.0 ns", time_in_unit(1500.0, TimeUnit::Nanoseconds));
+        assert_eq!("0.0015 ms", time_in_unit(1500.0, TimeUnit::Milliseconds));
+        assert_eq!("25.000 s", time_in_unit(25e9, TimeUnit::Seconds));
+    }
+
+    #[test]
+    fn bytes_units() {
+        assert_eq!("512 B", bytes(512));
+        assert_eq!("1.5 KiB", bytes(1536));
+        assert_eq!("2.0 MiB", bytes(2 * 1024 * 1024));
+        assert_eq!("3.0 GiB", bytes(3 * 1024 * 1024 * 1024));
+    }
+
     #[test]
     fn signed_short_max_len() {
         let mut float = -1.0;
@@ -1,10 +1,14 @@
-use crate::estimate::Estimate;
+use crate::config::{is_selected, Color, Colors};
+use crate::derived::DerivedMetric;
+use crate::estimate::{Estimate, Statistic};
 use crate::format;
+use crate::lazy_plots;
 use crate::model::{
     Benchmark as BenchmarkModel, BenchmarkGroup as GroupModel, ChangeDirection, Model,
     SavedStatistics,
 };
-use crate::plot::{PlotContext, Plotter, Size};
+use crate::plot::{History, PlotContext, Plotter, Size};
+use crate::regression_gate::significant_change;
 use crate::report::{
     compare_to_threshold, make_filename_safe, BenchmarkId, ComparisonResult, MeasurementData,
     Report, ReportContext,
@@ -15,11 +19,13 @@ use crate::value_formatter::ValueFormatter;
 use anyhow::{Context as AnyhowContext, Result};
 use linked_hash_set::LinkedHashSet;
 use serde::Serialize;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tinytemplate::TinyTemplate;
 
 const THUMBNAIL_SIZE: Option<Size> = Some(Size(450, 300));
@@ -73,12 +79,22 @@ fn debug_context<S: Serialize + Debug>(path: &Path, context: &S) {
 #[derive(Serialize, Debug)]
 struct Context {
     common_css: &'static str,
+    highlight_css: String,
 
     title: String,
+    label: Option<String>,
+    metadata: Vec<MetadataEntry>,
+    multimodal: bool,
+    zero_variance: bool,
+    // Is the benchmark exempt from --fail-on-regression and --fail-on-change?
+    ungated: bool,
     confidence: String,
 
+    thumbnails: bool,
     thumbnail_width: usize,
     thumbnail_height: usize,
+    // If the plots were left out for --lazy-plots, the command which draws them.
+    render_command: Option<String>,
 
     slope: Option<ConfidenceInterval>,
     r2: ConfidenceInterval,
@@ -87,6 +103,14 @@ struct Context {
     median: ConfidenceInterval,
     mad: ConfidenceInterval,
     throughput: Option<ConfidenceInterval>,
+    // The coefficient of variation, as a percentage, and whether it's above `--max-rsd`.
+    cv: String,
+    high_cv: bool,
+    // The measurement overhead per iteration and its share of the typical time, if the benchmark
+    // harness reported it (released versions of Criterion.rs don't).
+    overhead: Option<String>,
+    high_overhead: bool,
+    derived_metrics: Vec<DerivedMetricEntry>,
 
     additional_plots: Vec<Plot>,
 
@@ -97,20 +121,26 @@ struct Context {
 struct IndividualBenchmark {
     name: String,
     path: PathBuf,
+    pdf_exists: bool,
     regression_exists: bool,
+    ungated: bool,
 }
 impl IndividualBenchmark {
     fn from_id(
         output_directory: &Path,
         path_prefix: &str,
         id: &BenchmarkId,
+        ungated: bool,
     ) -> IndividualBenchmark {
+        let pdf_path = path!(output_directory, id.as_directory_name(), "pdf.svg");
         let regression_path = path!(output_directory, id.as_directory_name(), "regression.svg");
 
         IndividualBenchmark {
             name: id.as_title().to_owned(),
             path: path!(path_prefix, id.as_directory_name()),
+            pdf_exists: pdf_path.exists(),
             regression_exists: regression_path.exists(),
+            ungated,
         }
     }
 }
@@ -121,11 +151,13 @@ struct SummaryContext {
 
     group_id: String,
 
+    thumbnails: bool,
     thumbnail_width: usize,
     thumbnail_height: usize,
 
     violin_plot: Option<String>,
     line_chart: Option<String>,
+    matrix_plot: Option<String>,
 
     benchmarks: Vec<IndividualBenchmark>,
 }
@@ -160,9 +192,57 @@ struct Comparison {
 
     change: ConfidenceInterval,
     thrpt_change: Option<ConfidenceInterval>,
+    change_class: &'static str,
+    statistic_changes: Vec<StatisticChange>,
     additional_plots: Vec<Plot>,
 }
 
+#[derive(Serialize, Debug)]
+struct StatisticChange {
+    name: String,
+    change: ConfidenceInterval,
+    verdict: &'static str,
+    class: &'static str,
+}
+
+/// Returns the URL of the benchmark's flamegraph relative to its report page, if it has one.
+/// Criterion.rs profilers (such as pprof's) write their output to `profile/` in the benchmark's
+/// directory under the Criterion home when the benchmarks are run with `--profile-time`.
+fn flamegraph_url(criterion_home: &Path, id: &BenchmarkId) -> Option<String> {
+    let directory = id.as_directory_name();
+    if !path!(criterion_home, directory, "profile", "flamegraph.svg").is_file() {
+        return None;
+    }
+    // The report page is in reports/<directory>/ and the flamegraph in <directory>/profile/.
+    let up = "../".repeat(directory.components().count() + 1);
+    let directory = directory.to_string_lossy().replace('\\', "/");
+    Some(format!("{}{}/profile/flamegraph.svg", up, directory))
+}
+
+/// The CSS class used to highlight a change in the reports.
+fn change_class(result: &ComparisonResult) -> &'static str {
+    match result {
+        ComparisonResult::Improved => "improved",
+        ComparisonResult::Regressed => "regressed",
+        ComparisonResult::NonSignificant => "nochange",
+    }
+}
+
+/// The CSS rules which highlight regressions, improvements and insignificant changes in the
+/// reports with the configured colors.
+fn highlight_css(colors: &Colors) -> String {
+    let css_color = |color: Option<Color>, default: &str| match color {
+        Some(Color { r, g, b }) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        None => default.to_owned(),
+    };
+    format!(
+        ".regressed {{ color: {} }}\n.improved {{ color: {} }}\n.nochange {{ color: {} }}\n",
+        css_color(colors.regressed, "red"),
+        css_color(colors.improved, "green"),
+        css_color(colors.no_change, "inherit"),
+    )
+}
+
 fn if_exists(output_directory: &Path, path: &Path) -> Option<String> {
     let report_path = path.join("index.html");
     if PathBuf::from(output_directory).join(&report_path).is_file() {
@@ -289,6 +369,32 @@ impl<'a> BenchmarkGroup<'a> {
 struct IndexContext<'a> {
     common_css: &'static str,
     groups: Vec<BenchmarkGroup<'a>>,
+    total_time: Option<String>,
+    // The reports which were skipped because of `--report-time-limit`.
+    skipped_reports: Vec<String>,
+}
+
+/// An environment variable saved with a run (see `--metadata-env`).
+#[derive(Serialize, Debug)]
+struct MetadataEntry {
+    name: String,
+    value: String,
+}
+/// A user-defined metric computed for a benchmark (see `derived_metrics` in criterion.toml).
+#[derive(Serialize, Debug)]
+struct DerivedMetricEntry {
+    name: String,
+    value: String,
+}
+
+fn metadata_entries(metadata: &BTreeMap<String, String>) -> Vec<MetadataEntry> {
+    metadata
+        .iter()
+        .map(|(name, value)| MetadataEntry {
+            name: name.clone(),
+            value: value.clone(),
+        })
+        .collect()
 }
 
 #[derive(Serialize, Debug)]
@@ -299,6 +405,8 @@ struct HistoryEntry<'a> {
     id: Option<&'a str>,
     datetime: String,
     description: Option<&'a str>,
+    label: Option<&'a str>,
+    metadata: Vec<MetadataEntry>,
 
     has_improved: bool,
     has_regressed: bool,
@@ -309,9 +417,35 @@ struct HistoryEntry<'a> {
     change_class: &'static str,
 }
 
+/// A benchmark which changed significantly since the previous run, for the diff report.
+#[derive(Serialize, Debug)]
+struct ChangedBenchmark {
+    name: String,
+    path: PathBuf,
+    direction: &'static str,
+    change: ConfidenceInterval,
+    #[serde(skip)]
+    relative_change: f64,
+}
+
+#[derive(Serialize, Debug)]
+struct DiffContext<'a> {
+    common_css: &'static str,
+    highlight_css: &'a str,
+
+    thumbnail_width: usize,
+    thumbnail_height: usize,
+
+    regressed_count: usize,
+    improved_count: usize,
+    unchanged_count: usize,
+    changes: &'a [ChangedBenchmark],
+}
+
 #[derive(Serialize, Debug)]
 struct HistoryContext<'a> {
     common_css: &'static str,
+    highlight_css: &'a str,
     title: &'a str,
     history: Vec<HistoryEntry<'a>>,
 }
@@ -319,9 +453,62 @@ struct HistoryContext<'a> {
 pub struct Html {
     templates: TinyTemplate<'static>,
     plotter: RefCell<Box<dyn Plotter>>,
+    label: Option<String>,
+    metadata: BTreeMap<String, String>,
+    // Should the thumbnail-sized plots be generated? If not, the reports display the full-size
+    // plots scaled down instead.
+    thumbnails: bool,
+    // Should we generate a page listing only the benchmarks which changed in this run?
+    diff_report: bool,
+    // Benchmarks with a coefficient of variation above this are flagged in their reports.
+    max_rsd: Option<f64>,
+    // Benchmarks matching these patterns are flagged as not gated by --fail-on-regression and
+    // --fail-on-change. This is empty unless one of those is set.
+    ungated_benchmarks: Vec<String>,
+    // Changes smaller than this, or which aren't significant, are shown as "—" in the history.
+    min_displayed_change: Option<f64>,
+    // Changes in time of at least this factor either way are shown as speedup factors.
+    speedup_threshold: Option<f64>,
+    // Metrics computed from the estimates of each benchmark, shown in its report.
+    derived_metrics: Vec<DerivedMetric>,
+    // If set, the distributions of all of the benchmarks are drawn in a grid in this image.
+    contact_sheet: Option<PathBuf>,
+    // If set, the reports which finish after this much time has been spent generating reports
+    // are skipped.
+    report_time_limit: Option<Duration>,
+    report_time: Cell<Duration>,
+    // The titles of the benchmarks and groups whose reports were skipped for the time limit.
+    skipped_reports: RefCell<Vec<String>>,
+    // Should the plots of each benchmark be left out, to be drawn by `cargo criterion render`?
+    lazy_plots: bool,
+    // The benchmarks to add to the lazy plots manifest, with their report directories, and those
+    // to remove from it (None) since their plots were drawn.
+    manifest_changes: RefCell<BTreeMap<String, Option<PathBuf>>>,
+    // Where to look for the flamegraphs of profiled benchmarks.
+    criterion_home: PathBuf,
+    highlight_css: String,
+    changed_benchmarks: RefCell<Vec<ChangedBenchmark>>,
+    unchanged_benchmarks: Cell<usize>,
 }
 impl Html {
-    pub(crate) fn new(plotter: Box<dyn Plotter>) -> Html {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        plotter: Box<dyn Plotter>,
+        label: Option<String>,
+        metadata: BTreeMap<String, String>,
+        thumbnails: bool,
+        diff_report: bool,
+        max_rsd: Option<f64>,
+        ungated_benchmarks: Vec<String>,
+        min_displayed_change: Option<f64>,
+        speedup_threshold: Option<f64>,
+        derived_metrics: Vec<DerivedMetric>,
+        contact_sheet: Option<PathBuf>,
+        report_time_limit: Option<Duration>,
+        lazy_plots: bool,
+        criterion_home: PathBuf,
+        colors: &Colors,
+    ) -> Html {
         let mut templates = TinyTemplate::new();
         templates
             .add_template("report_link", include_str!("report_link.html.tt"))
@@ -338,9 +525,34 @@ impl Html {
         templates
             .add_template("history_report", include_str!("history_report.html.tt"))
             .expect("Unable to parse history_report template");
+        templates
+            .add_template("diff_report", include_str!("diff_report.html.tt"))
+            .expect("Unable to parse diff_report template");
 
         let plotter = RefCell::new(plotter);
-        Html { templates, plotter }
+        Html {
+            templates,
+            plotter,
+            label,
+            metadata,
+            thumbnails,
+            diff_report,
+            max_rsd,
+            ungated_benchmarks,
+            min_displayed_change,
+            speedup_threshold,
+            derived_metrics,
+            contact_sheet,
+            report_time_limit,
+            report_time: Cell::new(Duration::from_secs(0)),
+            skipped_reports: RefCell::new(vec![]),
+            lazy_plots,
+            manifest_changes: RefCell::new(BTreeMap::new()),
+            criterion_home,
+            highlight_css: highlight_css(colors),
+            changed_benchmarks: RefCell::new(vec![]),
+            unchanged_benchmarks: Cell::new(0),
+        }
     }
 }
 impl Report for Html {
@@ -351,12 +563,24 @@ impl Report for Html {
         measurements: &MeasurementData<'_>,
         formatter: &ValueFormatter,
     ) {
+        if self.diff_report {
+            self.record_change(id, measurements);
+        }
+
+        if self.over_time_limit() {
+            self.skipped_reports
+                .borrow_mut()
+                .push(id.as_title().to_owned());
+            return;
+        }
+
         try_else_return!({
             let report_dir = path!(&report_context.output_directory, id.as_directory_name());
             mkdirp(&report_dir)
         });
 
         let typical_estimate = measurements.absolute_estimates.typical();
+        let cv = crate::variance_gate::relative_std_dev(measurements);
 
         let time_interval = |est: &Estimate| -> ConfidenceInterval {
             ConfidenceInterval {
@@ -368,10 +592,30 @@ impl Report for Html {
 
         let data = measurements.data;
 
-        elapsed! {
-            "Generating plots",
-            self.generate_plots(id, report_context, formatter, measurements)
-        }
+        let (latency_plot, cdf_plot) = if self.lazy_plots {
+            try_else_return!(lazy_plots::write_placeholders(
+                report_context,
+                id,
+                measurements.comparison.is_some()
+            ));
+            self.manifest_changes.borrow_mut().insert(
+                id.as_title().to_owned(),
+                Some(id.as_directory_name().to_owned()),
+            );
+            (false, false)
+        } else {
+            let start = Instant::now();
+            let plots = elapsed! {
+                "Generating plots",
+                self.generate_plots(id, report_context, formatter, measurements)
+            };
+            self.report_time
+                .set(self.report_time.get() + start.elapsed());
+            self.manifest_changes
+                .borrow_mut()
+                .insert(id.as_title().to_owned(), None);
+            plots
+        };
 
         let throughput = measurements
             .throughput
@@ -384,28 +628,57 @@ impl Report for Html {
                 point: formatter.format_throughput(thr, typical_estimate.point_estimate),
             });
 
-        let mut additional_plots = vec![
-            Plot::new("Typical", "typical.svg"),
-            Plot::new("Mean", "mean.svg"),
-            Plot::new("Std. Dev.", "SD.svg"),
-            Plot::new("Median", "median.svg"),
-            Plot::new("MAD", "MAD.svg"),
-        ];
-        if measurements.absolute_estimates.slope.is_some() {
+        // The plots of the statistics are drawn with the others, so they're left out with them.
+        // They aren't drawn for benchmarks with zero variance, since there's nothing to plot.
+        let plot_statistics = !self.lazy_plots && !measurements.zero_variance;
+        let mut additional_plots: Vec<Plot> = vec![
+            (Statistic::Typical, Plot::new("Typical", "typical.svg")),
+            (Statistic::Mean, Plot::new("Mean", "mean.svg")),
+            (Statistic::StdDev, Plot::new("Std. Dev.", "SD.svg")),
+            (Statistic::Median, Plot::new("Median", "median.svg")),
+            (Statistic::MedianAbsDev, Plot::new("MAD", "MAD.svg")),
+        ]
+        .into_iter()
+        .filter(|(statistic, _)| is_selected(report_context.statistics.as_deref(), *statistic))
+        .filter(|_| plot_statistics)
+        .map(|(_, plot)| plot)
+        .collect();
+        if measurements.absolute_estimates.slope.is_some() && plot_statistics {
             additional_plots.push(Plot::new("Slope", "slope.svg"));
         }
+        if latency_plot {
+            additional_plots.push(Plot::new("Latency percentiles", "latency.svg"));
+        }
+        if cdf_plot {
+            additional_plots.push(Plot::new("Cumulative distribution", "cdf.svg"));
+        }
+        if let Some(url) = flamegraph_url(&self.criterion_home, id) {
+            additional_plots.push(Plot::new("Flamegraph", &url));
+        }
 
         let context = Context {
             common_css: COMMON_CSS,
+            highlight_css: self.highlight_css.clone(),
 
             title: id.as_title().to_owned(),
+            label: self.label.clone(),
+            metadata: metadata_entries(&self.metadata),
+            multimodal: measurements.multimodal,
+            zero_variance: measurements.zero_variance,
+            ungated: self.is_ungated(id),
             confidence: format!(
                 "{:.2}",
                 typical_estimate.confidence_interval.confidence_level
             ),
 
+            thumbnails: self.thumbnails,
             thumbnail_width: THUMBNAIL_SIZE.unwrap().0,
             thumbnail_height: THUMBNAIL_SIZE.unwrap().1,
+            render_command: if self.lazy_plots {
+                Some(lazy_plots::render_command(id))
+            } else {
+                None
+            },
 
             slope: measurements
                 .absolute_estimates
@@ -417,6 +690,32 @@ impl Report for Html {
             mad: time_interval(&measurements.absolute_estimates.median_abs_dev),
             std_dev: time_interval(&measurements.absolute_estimates.std_dev),
             throughput,
+            overhead: measurements.overhead.map(
+                |overhead| match crate::analysis::overhead_fraction(measurements) {
+                    Some(fraction) => format!(
+                        "{} ({:.1}% of the typical time)",
+                        formatter.format_value(overhead),
+                        fraction * 100.0
+                    ),
+                    None => formatter.format_value(overhead),
+                },
+            ),
+            high_overhead: matches!(
+                crate::analysis::overhead_fraction(measurements),
+                Some(fraction) if fraction > crate::analysis::HIGH_OVERHEAD_FRACTION
+            ),
+            derived_metrics: self
+                .derived_metrics
+                .iter()
+                .map(|metric| DerivedMetricEntry {
+                    name: metric.name.clone(),
+                    value: metric
+                        .evaluate(measurements)
+                        .map_or_else(|| "—".to_owned(), format::short),
+                })
+                .collect(),
+            cv: format!("{:.2}%", cv * 100.0),
+            high_cv: matches!(self.max_rsd, Some(max_rsd) if cv > max_rsd),
 
             r2: ConfidenceInterval {
                 lower: format!(
@@ -435,7 +734,12 @@ impl Report for Html {
 
             additional_plots,
 
-            comparison: self.comparison(measurements),
+            comparison: self.comparison(measurements).map(|mut comparison| {
+                if self.lazy_plots {
+                    comparison.additional_plots.clear();
+                }
+                comparison
+            }),
         };
 
         let report_path = path!(
@@ -462,6 +766,11 @@ impl Report for Html {
         if benchmark_group.benchmarks.is_empty() {
             return;
         }
+        if self.over_time_limit() {
+            self.skipped_reports.borrow_mut().push(group_id.to_owned());
+            return;
+        }
+        let start = Instant::now();
 
         let mut function_ids = LinkedHashSet::new();
         let mut value_strs = LinkedHashSet::new();
@@ -527,6 +836,8 @@ impl Report for Html {
             true,
         );
         self.plotter.borrow_mut().wait();
+        self.report_time
+            .set(self.report_time.get() + start.elapsed());
     }
 
     fn final_summary(&self, report_context: &ReportContext, model: &Model) {
@@ -542,9 +853,33 @@ impl Report for Html {
 
         let report_path = output_directory.join("index.html");
 
+        let total_time = crate::analysis::suite_total(model).map(|total| {
+            let formatter =
+                ValueFormatter::wall_time(report_context.time_unit, report_context.rounding);
+            match total.change {
+                Some(change) => format!(
+                    "{} ({})",
+                    formatter.format_value(total.total),
+                    format::change_or_speedup(change, change, self.speedup_threshold)
+                ),
+                None => formatter.format_value(total.total),
+            }
+        });
+
+        let skipped_reports = self.skipped_reports.borrow().clone();
+        if !skipped_reports.is_empty() {
+            warn!(
+                "The time limit for generating the reports was reached, so the reports of {} \
+                benchmarks and groups were skipped. They're listed in the index.",
+                skipped_reports.len()
+            );
+        }
+
         let context = IndexContext {
             common_css: COMMON_CSS,
             groups,
+            total_time,
+            skipped_reports,
         };
 
         debug_context(&report_path, &context);
@@ -554,6 +889,22 @@ impl Report for Html {
             .render("index", &context)
             .expect("Failed to render index template");
         try_else_return!(save_string(&text, &report_path,));
+
+        if self.diff_report {
+            self.diff_summary(output_directory);
+        }
+
+        if let Some(path) = &self.contact_sheet {
+            self.contact_sheet(path, report_context, model);
+        }
+
+        let manifest_changes = self.manifest_changes.borrow();
+        if !manifest_changes.is_empty() {
+            try_else_return!(lazy_plots::update_manifest(
+                output_directory,
+                &manifest_changes
+            ));
+        }
     }
 
     fn history(
@@ -563,21 +914,31 @@ impl Report for Html {
         history: &[SavedStatistics],
         formatter: &ValueFormatter,
     ) {
+        // The history is part of the benchmark's report, so it's skipped along with the rest.
+        if self
+            .skipped_reports
+            .borrow()
+            .iter()
+            .any(|title| title == id.as_title())
+        {
+            return;
+        }
+
         let ids: Vec<_> = history
             .iter()
             .enumerate()
             .map(|(i, _)| format!("#{}", i))
             .collect();
 
-        let typicals: Vec<_> = history
+        let (typicals, changes): (Vec<_>, Vec<_>) = history
             .iter()
-            .map(|stats| stats.estimates.typical())
-            .filter(|typ| {
+            .map(|stats| (stats.estimates.typical(), stats.change_direction))
+            .filter(|(typ, _)| {
                 typ.point_estimate.is_finite()
                     && typ.confidence_interval.upper_bound.is_finite()
                     && typ.confidence_interval.lower_bound.is_finite()
             })
-            .collect();
+            .unzip();
 
         // TODO: This is really starting to strain the limits of the ValueFormatter trait.
         // In order to ensure that all values in the history are scaled to the same unit, we
@@ -639,11 +1000,14 @@ impl Report for Html {
 
         self.plotter.borrow_mut().history(
             plot_ctx,
-            &upper_bounds,
-            &point_estimates,
-            &lower_bounds,
-            &ids,
-            &unit,
+            &History {
+                upper_bounds: &upper_bounds,
+                point_estimates: &point_estimates,
+                lower_bounds: &lower_bounds,
+                changes: &changes,
+                ids: &ids,
+                unit: &unit,
+            },
         );
         self.plotter.borrow_mut().wait();
 
@@ -656,6 +1020,24 @@ impl Report for Html {
                 upper: format!("{:5.2}{}", upper, unit),
             });
 
+        // With min_displayed_change, insignificant and tiny changes are shown as "—" rather than
+        // as a noisy number.
+        let hide_change = |stats: &SavedStatistics, change: f64| match self.min_displayed_change {
+            Some(min_displayed_change) => {
+                let significant = matches!(
+                    stats.change_direction,
+                    Some(ChangeDirection::Improved) | Some(ChangeDirection::Regressed)
+                );
+                !significant || change.abs() < min_displayed_change
+            }
+            None => false,
+        };
+        let hidden_change = || ConfidenceInterval {
+            point: "\u{2014}".to_owned(),
+            lower: String::new(),
+            upper: String::new(),
+        };
+
         let mut history_entries: Vec<HistoryEntry> = history
             .iter()
             .zip(intervals)
@@ -671,26 +1053,25 @@ impl Report for Html {
                     .with_timezone(&chrono::Local)
                     .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
                 description: stats.history_description.as_deref(),
-                change_value: stats.changes.as_ref().map(|c| ConfidenceInterval {
-                    point: format::change(c.mean.point_estimate, true),
-                    lower: format::change(c.mean.confidence_interval.lower_bound, true),
-                    upper: format::change(c.mean.confidence_interval.upper_bound, true),
+                label: stats.label.as_deref(),
+                metadata: metadata_entries(&stats.metadata),
+                change_value: stats.changes.as_ref().map(|c| {
+                    if hide_change(stats, c.mean.point_estimate) {
+                        return hidden_change();
+                    }
+                    self.change_interval(&c.mean)
                 }),
                 change_throughput: match (stats.changes.as_ref(), latest_throughput) {
+                    (Some(c), Some(_)) if hide_change(stats, c.mean.point_estimate) => {
+                        Some(hidden_change())
+                    }
                     (Some(c), Some(_)) => {
-                        let to_thrpt_estimate = |ratio: f64| 1.0 / (1.0 + ratio) - 1.0;
-                        let ci = ConfidenceInterval {
-                            point: format::change(to_thrpt_estimate(c.mean.point_estimate), true),
-                            lower: format::change(
-                                to_thrpt_estimate(c.mean.confidence_interval.lower_bound),
-                                true,
-                            ),
-                            upper: format::change(
-                                to_thrpt_estimate(c.mean.confidence_interval.upper_bound),
-                                true,
-                            ),
-                        };
-                        Some(ci)
+                        let thrpt_est = c.mean.to_throughput_change();
+                        Some(ConfidenceInterval {
+                            point: format::change(thrpt_est.point_estimate, true),
+                            lower: format::change(thrpt_est.confidence_interval.lower_bound, true),
+                            upper: format::change(thrpt_est.confidence_interval.upper_bound, true),
+                        })
                     }
                     _ => None,
                 },
@@ -714,6 +1095,7 @@ impl Report for Html {
 
         let context = HistoryContext {
             common_css: COMMON_CSS,
+            highlight_css: &self.highlight_css,
             title: id.as_title(),
             history: history_entries,
         };
@@ -732,17 +1114,93 @@ impl Report for Html {
     }
 }
 impl Html {
+    /// Remember whether the benchmark changed significantly, for the diff report.
+    fn record_change(&self, id: &BenchmarkId, measurements: &MeasurementData<'_>) {
+        let comparison = match &measurements.comparison {
+            Some(comparison) => comparison,
+            None => return,
+        };
+        let direction = match significant_change(comparison) {
+            Some(ComparisonResult::Regressed) => "regressed",
+            Some(ComparisonResult::Improved) => "improved",
+            Some(ComparisonResult::NonSignificant) | None => {
+                self.unchanged_benchmarks
+                    .set(self.unchanged_benchmarks.get() + 1);
+                return;
+            }
+        };
+        let mean = &comparison.relative_estimates.mean;
+        self.changed_benchmarks.borrow_mut().push(ChangedBenchmark {
+            name: id.as_title().to_owned(),
+            path: id.as_directory_name().to_owned(),
+            direction,
+            change: self.change_interval(mean),
+            relative_change: mean.point_estimate,
+        });
+    }
+
+    /// Write the diff report, listing the regressions and then the improvements, with the largest
+    /// changes first.
+    fn diff_summary(&self, output_directory: &Path) {
+        let mut changes = self.changed_benchmarks.borrow_mut();
+        changes.sort_by(|a, b| {
+            (b.direction == "regressed")
+                .cmp(&(a.direction == "regressed"))
+                .then_with(|| {
+                    let (a, b) = (a.relative_change.abs(), b.relative_change.abs());
+                    b.partial_cmp(&a).unwrap_or(Ordering::Equal)
+                })
+        });
+        let regressed_count = changes
+            .iter()
+            .filter(|change| change.direction == "regressed")
+            .count();
+
+        let context = DiffContext {
+            common_css: COMMON_CSS,
+            highlight_css: &self.highlight_css,
+            thumbnail_width: THUMBNAIL_SIZE.unwrap().0,
+            thumbnail_height: THUMBNAIL_SIZE.unwrap().1,
+            regressed_count,
+            improved_count: changes.len() - regressed_count,
+            unchanged_count: self.unchanged_benchmarks.get(),
+            changes: &changes,
+        };
+
+        let report_path = output_directory.join("diff.html");
+        debug_context(&report_path, &context);
+
+        let text = self
+            .templates
+            .render("diff_report", &context)
+            .expect("Failed to render diff report template");
+        try_else_return!(save_string(&text, &report_path));
+    }
+
+    /// Formats a relative change in time and its confidence interval as percentages, or as speedup
+    /// factors if the point estimate reaches the speedup threshold.
+    fn change_interval(&self, estimate: &Estimate) -> ConfidenceInterval {
+        let point = estimate.point_estimate;
+        let ci = &estimate.confidence_interval;
+        ConfidenceInterval {
+            point: format::change_or_speedup(point, point, self.speedup_threshold),
+            lower: format::change_or_speedup(ci.lower_bound, point, self.speedup_threshold),
+            upper: format::change_or_speedup(ci.upper_bound, point, self.speedup_threshold),
+        }
+    }
+
     fn comparison(&self, measurements: &MeasurementData<'_>) -> Option<Comparison> {
         if let Some(ref comp) = measurements.comparison {
             let different_mean = comp.p_value < comp.significance_threshold;
             let mean_est = &comp.relative_estimates.mean;
             let explanation_str: String;
+            let mut result = ComparisonResult::NonSignificant;
 
             if !different_mean {
                 explanation_str = "No change in performance detected.".to_owned();
             } else {
-                let comparison = compare_to_threshold(mean_est, comp.noise_threshold);
-                match comparison {
+                result = compare_to_threshold(mean_est, comp.noise_threshold);
+                match result {
                     ComparisonResult::Improved => {
                         explanation_str = "Performance has improved.".to_owned();
                     }
@@ -761,32 +1219,45 @@ impl Html {
                 significance_level: format!("{:.2}", comp.significance_threshold),
                 explanation: explanation_str,
 
-                change: ConfidenceInterval {
-                    point: format::change(mean_est.point_estimate, true),
-                    lower: format::change(mean_est.confidence_interval.lower_bound, true),
-                    upper: format::change(mean_est.confidence_interval.upper_bound, true),
-                },
+                change: self.change_interval(mean_est),
 
                 thrpt_change: measurements.throughput.as_ref().map(|_| {
-                    let to_thrpt_estimate = |ratio: f64| 1.0 / (1.0 + ratio) - 1.0;
+                    let thrpt_est = mean_est.to_throughput_change();
                     ConfidenceInterval {
-                        point: format::change(to_thrpt_estimate(mean_est.point_estimate), true),
-                        lower: format::change(
-                            to_thrpt_estimate(mean_est.confidence_interval.lower_bound),
-                            true,
-                        ),
-                        upper: format::change(
-                            to_thrpt_estimate(mean_est.confidence_interval.upper_bound),
-                            true,
-                        ),
+                        point: format::change(thrpt_est.point_estimate, true),
+                        lower: format::change(thrpt_est.confidence_interval.lower_bound, true),
+                        upper: format::change(thrpt_est.confidence_interval.upper_bound, true),
                     }
                 }),
+                change_class: change_class(&result),
+
+                statistic_changes: comp
+                    .statistic_changes
+                    .iter()
+                    .map(|change| {
+                        let estimate = &change.estimate;
+                        StatisticChange {
+                            name: format!("Change in {}", change.statistic),
+                            change: self.change_interval(estimate),
+                            verdict: match change.result {
+                                ComparisonResult::Improved => "Improved",
+                                ComparisonResult::Regressed => "Regressed",
+                                ComparisonResult::NonSignificant => "Within noise",
+                            },
+                            class: change_class(&change.result),
+                        }
+                    })
+                    .collect(),
 
                 additional_plots: vec![
                     Plot::new("Change in mean", "change/mean.svg"),
                     Plot::new("Change in median", "change/median.svg"),
                     Plot::new("T-Test", "change/t-test.svg"),
-                ],
+                ]
+                .into_iter()
+                // The t-test isn't plotted if neither sample varies.
+                .filter(|plot| plot.name != "T-Test" || comp.t_distribution.len() >= 2)
+                .collect(),
             };
             Some(comp)
         } else {
@@ -794,13 +1265,15 @@ impl Html {
         }
     }
 
+    /// Generates the plots for a single benchmark. Returns whether the latency percentile plot and
+    /// the CDF plot were drawn.
     fn generate_plots(
         &self,
         id: &BenchmarkId,
         context: &ReportContext,
         formatter: &ValueFormatter,
         measurements: &MeasurementData,
-    ) {
+    ) -> (bool, bool) {
         let plot_ctx = PlotContext {
             id,
             context,
@@ -814,54 +1287,95 @@ impl Html {
             ..plot_ctx
         };
 
-        self.plotter
-            .borrow_mut()
-            .pdf(plot_ctx, measurements, formatter);
-        self.plotter
-            .borrow_mut()
-            .pdf_thumbnail(plot_ctx_small, measurements, formatter);
-        if measurements.absolute_estimates.slope.is_some() {
+        if measurements.zero_variance {
+            // There's no distribution to plot, so remove any left over from an earlier run.
+            for name in &["pdf.svg", "pdf_small.svg"] {
+                let path = context.report_path(id, name);
+                if path.exists() {
+                    try_else_return!(
+                        std::fs::remove_file(&path)
+                            .with_context(|| format!("Failed to remove file {:?}", path)),
+                        || (false, false)
+                    );
+                }
+            }
+        } else {
             self.plotter
                 .borrow_mut()
-                .regression(plot_ctx, measurements, formatter);
+                .pdf(plot_ctx, measurements, formatter);
+            if self.thumbnails {
+                self.plotter
+                    .borrow_mut()
+                    .pdf_thumbnail(plot_ctx_small, measurements, formatter);
+            }
+        }
+        if measurements.absolute_estimates.slope.is_some() {
             self.plotter
                 .borrow_mut()
-                .regression_thumbnail(plot_ctx_small, measurements, formatter);
+                .regression(plot_ctx, measurements, formatter);
+            if self.thumbnails {
+                self.plotter.borrow_mut().regression_thumbnail(
+                    plot_ctx_small,
+                    measurements,
+                    formatter,
+                );
+            }
         } else {
             self.plotter
                 .borrow_mut()
                 .iteration_times(plot_ctx, measurements, formatter);
-            self.plotter.borrow_mut().iteration_times_thumbnail(
-                plot_ctx_small,
-                measurements,
-                formatter,
-            );
+            if self.thumbnails {
+                self.plotter.borrow_mut().iteration_times_thumbnail(
+                    plot_ctx_small,
+                    measurements,
+                    formatter,
+                );
+            }
         }
 
-        self.plotter
+        if !measurements.zero_variance {
+            self.plotter
+                .borrow_mut()
+                .abs_distributions(plot_ctx, measurements, formatter);
+        }
+        let latency_plot =
+            self.plotter
+                .borrow_mut()
+                .latency_percentiles(plot_ctx, measurements, formatter);
+        let cdf_plot = self
+            .plotter
             .borrow_mut()
-            .abs_distributions(plot_ctx, measurements, formatter);
+            .cdf(plot_ctx, measurements, formatter);
 
         if let Some(ref comparison) = measurements.comparison {
-            try_else_return!({
-                let change_dir = path!(&context.output_directory, id.as_directory_name(), "change");
-                mkdirp(&change_dir)
-            });
+            try_else_return!(
+                {
+                    let change_dir =
+                        path!(&context.output_directory, id.as_directory_name(), "change");
+                    mkdirp(&change_dir)
+                },
+                || (latency_plot, cdf_plot)
+            );
 
-            try_else_return!({
-                let both_dir = path!(&context.output_directory, id.as_directory_name(), "both");
-                mkdirp(&both_dir)
-            });
+            try_else_return!(
+                {
+                    let both_dir = path!(&context.output_directory, id.as_directory_name(), "both");
+                    mkdirp(&both_dir)
+                },
+                || (latency_plot, cdf_plot)
+            );
 
             self.plotter
                 .borrow_mut()
                 .pdf_comparison(plot_ctx, measurements, formatter, comparison);
-            self.plotter.borrow_mut().pdf_comparison_thumbnail(
-                plot_ctx_small,
-                measurements,
-                formatter,
-                comparison,
-            );
+            if self.thumbnails {
+                self.plotter.borrow_mut().pdf_comparison_thumbnail(
+                    plot_ctx_small,
+                    measurements,
+                    formatter,
+                    comparison,
+                );
+            }
             if measurements.absolute_estimates.slope.is_some()
                 && comparison.base_estimates.slope.is_some()
             {
@@ -871,12 +1385,14 @@ impl Html {
                     formatter,
                     comparison,
                 );
-                self.plotter.borrow_mut().regression_comparison_thumbnail(
-                    plot_ctx_small,
-                    measurements,
-                    formatter,
-                    comparison,
-                );
+                if self.thumbnails {
+                    self.plotter.borrow_mut().regression_comparison_thumbnail(
+                        plot_ctx_small,
+                        measurements,
+                        formatter,
+                        comparison,
+                    );
+                }
             } else {
                 self.plotter.borrow_mut().iteration_times_comparison(
                     plot_ctx,
@@ -884,14 +1400,16 @@ impl Html {
                     formatter,
                     comparison,
                 );
-                self.plotter
-                    .borrow_mut()
-                    .iteration_times_comparison_thumbnail(
-                        plot_ctx_small,
-                        measurements,
-                        formatter,
-                        comparison,
-                    );
+                if self.thumbnails {
+                    self.plotter
+                        .borrow_mut()
+                        .iteration_times_comparison_thumbnail(
+                            plot_ctx_small,
+                            measurements,
+                            formatter,
+                            comparison,
+                        );
+                }
             }
             self.plotter.borrow_mut().t_test(plot_ctx, comparison);
             self.plotter
@@ -900,6 +1418,44 @@ impl Html {
         }
 
         self.plotter.borrow_mut().wait();
+        (latency_plot, cdf_plot)
+    }
+
+    // Is the benchmark exempt from --fail-on-regression and --fail-on-change?
+    fn is_ungated(&self, id: &BenchmarkId) -> bool {
+        crate::regression_gate::is_ungated(&self.ungated_benchmarks, id.as_title())
+    }
+
+    // Returns true if the reports have used up their time limit, if any.
+    fn over_time_limit(&self) -> bool {
+        matches!(self.report_time_limit, Some(limit) if self.report_time.get() >= limit)
+    }
+
+    // Draws the distributions of every benchmark in the model into one image.
+    fn contact_sheet(&self, path: &Path, report_context: &ReportContext, model: &Model) {
+        let benchmarks: Vec<_> = model
+            .groups
+            .values()
+            .flat_map(|group| group.benchmarks.iter())
+            .collect();
+        if benchmarks.is_empty() {
+            return;
+        }
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            try_else_return!(mkdirp(&parent));
+        }
+
+        let formatter =
+            ValueFormatter::wall_time(report_context.time_unit, report_context.rounding);
+        let mut plotter = self.plotter.borrow_mut();
+        if !plotter.contact_sheet(path.to_owned(), &formatter, &benchmarks) {
+            warn!(
+                "Unable to draw the contact sheet; it requires the plotters backend \
+                (--plotting-backend=plotters)."
+            );
+            return;
+        }
+        plotter.wait();
     }
 
     fn generate_summary(
@@ -942,11 +1498,26 @@ impl Html {
             }
         }
 
+        // The heatmap only makes sense when comparing the parameters of a single function.
+        let mut matrix_path = None;
+        if data
+            .iter()
+            .all(|(id, _)| id.function_id == data[0].0.function_id)
+            && self.plotter.borrow_mut().matrix(plot_ctx, formatter, data)
+        {
+            matrix_path = Some(plot_ctx.matrix_path());
+        }
+
         let path_prefix = if full_summary { ".." } else { "../.." };
         let benchmarks = data
             .iter()
             .map(|(id, _)| {
-                IndividualBenchmark::from_id(&report_context.output_directory, path_prefix, id)
+                IndividualBenchmark::from_id(
+                    &report_context.output_directory,
+                    path_prefix,
+                    id,
+                    self.is_ungated(id),
+                )
             })
             .collect();
 
@@ -954,11 +1525,13 @@ impl Html {
             common_css: COMMON_CSS,
             group_id: id.as_title().to_owned(),
 
+            thumbnails: self.thumbnails,
             thumbnail_width: THUMBNAIL_SIZE.unwrap().0,
             thumbnail_height: THUMBNAIL_SIZE.unwrap().1,
 
             violin_plot: Some(plot_ctx.violin_path().to_string_lossy().into_owned()),
             line_chart: line_path.map(|p| p.to_string_lossy().into_owned()),
+            matrix_plot: matrix_path.map(|p| p.to_string_lossy().into_owned()),
 
             benchmarks,
         };
@@ -977,3 +1550,26 @@ impl Html {
         try_else_return!(save_string(&text, &report_path,), || {});
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flamegraph_url() {
+        let home =
+            std::env::temp_dir().join(format!("cargo-criterion-html-{}", std::process::id()));
+        let id = BenchmarkId::new("fib".into(), Some("20".into()), None, None);
+        assert_eq!(None, flamegraph_url(&home, &id));
+
+        let profile = home.join("fib").join("20").join("profile");
+        std::fs::create_dir_all(&profile).unwrap();
+        std::fs::write(profile.join("flamegraph.svg"), "<svg/>").unwrap();
+        let url = flamegraph_url(&home, &id);
+        std::fs::remove_dir_all(&home).unwrap();
+        assert_eq!(
+            Some("../../../fib/20/profile/flamegraph.svg".to_owned()),
+            url
+        );
+    }
+}
@@ -1,41 +1,240 @@
-use crate::stats::univariate::kde::kernel::Gaussian;
+use crate::config::KdeKernel;
+use crate::stats::univariate::kde::kernel::{Epanechnikov, Gaussian, Kernel};
 use crate::stats::univariate::kde::{Bandwidth, Kde};
 use crate::stats::univariate::Sample;
 
+/// How the densities are estimated: the kernel, and whether the estimate is made on the logarithms
+/// of the values. Estimating in the log domain keeps the density of strictly positive values such
+/// as times from spilling below zero. Samples which aren't strictly positive are always estimated
+/// in the linear domain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Estimator {
+    pub kernel: KdeKernel,
+    pub log_domain: bool,
+}
+impl Default for Estimator {
+    fn default() -> Self {
+        Estimator {
+            kernel: KdeKernel::Gaussian,
+            log_domain: false,
+        }
+    }
+}
+impl Estimator {
+    /// The same estimator in the linear domain, for values which are usually centered on zero
+    /// (eg. changes and t-statistics).
+    pub fn linear(self) -> Estimator {
+        Estimator {
+            log_domain: false,
+            ..self
+        }
+    }
+}
+
 pub fn sweep(
+    estimator: Estimator,
     sample: &Sample<f64>,
     npoints: usize,
     range: Option<(f64, f64)>,
 ) -> (Box<[f64]>, Box<[f64]>) {
-    let (xs, ys, _) = sweep_and_estimate(sample, npoints, range, sample[0]);
+    let (xs, ys, _) = sweep_and_estimate(estimator, sample, npoints, range, sample[0]);
     (xs, ys)
 }
 
+/// Sweeps the KDE of a bootstrap distribution over its confidence interval, padded on either side
+/// by a ninth of the width of the interval. This is the curve drawn by the distribution plots. An
+/// interval with no width (eg. of a benchmark with zero variance) is swept over the whole KDE
+/// instead, so that the curve isn't squeezed into a single point.
+pub fn sweep_confidence_interval(
+    estimator: Estimator,
+    distribution: &Sample<f64>,
+    npoints: usize,
+    lower_bound: f64,
+    upper_bound: f64,
+) -> (Box<[f64]>, Box<[f64]>) {
+    let padding = (upper_bound - lower_bound) / 9.;
+    let range = if padding > 0.0 {
+        Some((lower_bound - padding, upper_bound + padding))
+    } else {
+        None
+    };
+    sweep(estimator, distribution, npoints, range)
+}
+
 pub fn sweep_and_estimate(
+    estimator: Estimator,
     sample: &Sample<f64>,
     npoints: usize,
     range: Option<(f64, f64)>,
     point_to_estimate: f64,
 ) -> (Box<[f64]>, Box<[f64]>, f64) {
-    let x_min = sample.min();
-    let x_max = sample.max();
+    let log_domain = estimator.log_domain && sample.min() > 0.0;
+    match estimator.kernel {
+        KdeKernel::Gaussian => sweep_with_kernel(
+            sample,
+            Gaussian,
+            log_domain,
+            npoints,
+            range,
+            point_to_estimate,
+        ),
+        KdeKernel::Epanechnikov => sweep_with_kernel(
+            sample,
+            Epanechnikov,
+            log_domain,
+            npoints,
+            range,
+            point_to_estimate,
+        ),
+    }
+}
 
-    let kde = Kde::new(sample, Gaussian, Bandwidth::Silverman);
+fn sweep_with_kernel<K: Kernel<f64>>(
+    sample: &Sample<f64>,
+    kernel: K,
+    log_domain: bool,
+    npoints: usize,
+    range: Option<(f64, f64)>,
+    point_to_estimate: f64,
+) -> (Box<[f64]>, Box<[f64]>, f64) {
+    if !log_domain {
+        let kde = Kde::new(sample, kernel, Bandwidth::Silverman);
+        let h = kde.bandwidth();
+        let (start, end) = range.unwrap_or((sample.min() - 3. * h, sample.max() + 3. * h));
+        let xs = linspace(start, end, npoints);
+        let ys = kde.map(&xs);
+        let point_estimate = kde.estimate(point_to_estimate);
+        return (xs.into_boxed_slice(), ys, point_estimate);
+    }
+
+    let logs: Vec<f64> = sample.iter().map(|x| x.ln()).collect();
+    let logs = Sample::new(&logs);
+    let kde = Kde::new(logs, kernel, Bandwidth::Silverman);
     let h = kde.bandwidth();
+    let (start, end) = range.unwrap_or(((logs.min() - 3. * h).exp(), (logs.max() + 3. * h).exp()));
+    let xs = linspace(start, end, npoints);
 
-    let (start, end) = match range {
-        Some((start, end)) => (start, end),
-        None => (x_min - 3. * h, x_max + 3. * h),
+    // The density of x is the density of ln(x) times the derivative of ln(x). Zero and negative
+    // values map to -inf, where the kernels are zero.
+    let log_xs: Vec<f64> = xs.iter().map(|&x| x.max(0.0).ln()).collect();
+    let mut ys = kde.map(&log_xs);
+    for (y, &x) in ys.iter_mut().zip(&xs) {
+        *y = if x > 0.0 { *y / x } else { 0.0 };
+    }
+    let point_estimate = if point_to_estimate > 0.0 {
+        kde.estimate(point_to_estimate.ln()) / point_to_estimate
+    } else {
+        0.0
     };
 
-    let mut xs: Vec<f64> = Vec::with_capacity(npoints);
+    (xs.into_boxed_slice(), ys, point_estimate)
+}
+
+fn linspace(start: f64, end: f64, npoints: usize) -> Vec<f64> {
     let step_size = (end - start) / (npoints - 1) as f64;
-    for n in 0..npoints {
-        xs.push(start + (step_size * n as f64));
+    (0..npoints)
+        .map(|n| start + (step_size * n as f64))
+        .collect()
+}
+
+// The number of points at which the KDE is evaluated when searching for modes.
+const MODE_SEARCH_POINTS: usize = 500;
+// A peak only counts as a separate mode if it rises above the valleys separating it from any
+// higher peak by at least this fraction of the height of the highest peak.
+const MODE_PROMINENCE: f64 = 0.25;
+// Smaller samples are too sparse to tell a second mode apart from noise.
+const MIN_MODE_SAMPLE_SIZE: usize = 20;
+
+/// Returns true if the KDE of the sample has more than one significant peak. This is deliberately
+/// conservative; small bumps in the tails of the distribution are not counted as modes.
+pub fn is_multimodal(sample: &Sample<f64>) -> bool {
+    if sample.len() < MIN_MODE_SAMPLE_SIZE {
+        return false;
     }
+    let (_, ys) = sweep(Estimator::default(), sample, MODE_SEARCH_POINTS, None);
+    count_prominent_peaks(&ys, MODE_PROMINENCE) > 1
+}
 
-    let ys = kde.map(&xs);
-    let point_estimate = kde.estimate(point_to_estimate);
+// Counts the local maxima of the curve whose prominence is at least the given fraction of the
+// height of the curve.
+fn count_prominent_peaks(ys: &[f64], prominence: f64) -> usize {
+    let max = ys.iter().cloned().fold(0.0, f64::max);
+    if max <= 0.0 || ys.len() < 3 {
+        return 0;
+    }
+    let threshold = max * prominence;
 
-    (xs.into_boxed_slice(), ys, point_estimate)
+    (1..ys.len() - 1)
+        .filter(|&i| ys[i] > ys[i - 1] && ys[i] >= ys[i + 1])
+        .filter(|&i| peak_prominence(ys, i) >= threshold)
+        .count()
+}
+
+// The height of a peak above the higher of the two lowest points between it and the nearest
+// higher point (or the edge of the curve) on either side.
+fn peak_prominence(ys: &[f64], i: usize) -> f64 {
+    let peak = ys[i];
+    let left_min = ys[..i]
+        .iter()
+        .rev()
+        .take_while(|&&y| y <= peak)
+        .cloned()
+        .fold(peak, f64::min);
+    let right_min = ys[i + 1..]
+        .iter()
+        .take_while(|&&y| y <= peak)
+        .cloned()
+        .fold(peak, f64::min);
+    peak - left_min.max(right_min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_count_prominent_peaks() {
+        assert_eq!(1, count_prominent_peaks(&[0.0, 1.0, 2.0, 1.0, 0.0], 0.25));
+        assert_eq!(2, count_prominent_peaks(&[0.0, 2.0, 0.5, 1.5, 0.0], 0.25));
+        // The second peak barely rises above the valley next to it.
+        assert_eq!(1, count_prominent_peaks(&[0.0, 2.0, 1.0, 1.2, 0.0], 0.25));
+    }
+
+    // Integrates the curve with the trapezoidal rule.
+    fn area(xs: &[f64], ys: &[f64]) -> f64 {
+        (1..xs.len())
+            .map(|i| (xs[i] - xs[i - 1]) * (ys[i] + ys[i - 1]) / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn test_sweep_estimators() {
+        let times: Vec<f64> = (1..=50).map(|i| 1.0 + (i % 10) as f64 * 0.5).collect();
+        let times = Sample::new(&times);
+        for &kernel in &[KdeKernel::Gaussian, KdeKernel::Epanechnikov] {
+            for &log_domain in &[false, true] {
+                let estimator = Estimator { kernel, log_domain };
+                let (xs, ys) = sweep(estimator, times, 2000, Some((-5.0, 20.0)));
+                assert!((area(&xs, &ys) - 1.0).abs() < 0.01);
+                assert!(ys.iter().all(|&y| y >= 0.0));
+                if log_domain {
+                    // None of the density is below zero time.
+                    assert!(xs.iter().zip(ys.iter()).all(|(&x, &y)| x > 0.0 || y == 0.0));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_multimodal() {
+        let unimodal: Vec<f64> = (0..50).map(|i| 10.0 + (i % 10) as f64 * 0.1).collect();
+        assert!(!is_multimodal(Sample::new(&unimodal)));
+
+        let bimodal: Vec<f64> = (0..50)
+            .map(|i| (i % 10) as f64 * 0.1 + if i % 2 == 0 { 10.0 } else { 20.0 })
+            .collect();
+        assert!(is_multimodal(Sample::new(&bimodal)));
+
+        assert!(!is_multimodal(Sample::new(&bimodal[..10])));
+    }
 }
@@ -0,0 +1,249 @@
+//! Support for `--lazy-plots`, which leaves the plots of each benchmark out of the run so that
+//! they can be drawn later with `cargo criterion render`. The reports of those benchmarks show
+//! placeholder images, and the benchmarks are listed in a manifest in the reports directory until
+//! their plots are drawn.
+
+use crate::analysis::MeasuredValues;
+use crate::model::Model;
+use crate::report::{BenchmarkId, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+/// The name of the manifest, in the reports directory.
+const MANIFEST_NAME: &str = "lazy_plots.json";
+
+/// The plots which are embedded in the report pages, and so get placeholders until they're drawn.
+/// A benchmark has either the regression or the iteration times plots, so both get placeholders.
+const PLACEHOLDER_PLOTS: &[&str] = &[
+    "pdf.svg",
+    "pdf_small.svg",
+    "regression.svg",
+    "regression_small.svg",
+    "iteration_times.svg",
+    "iteration_times_small.svg",
+];
+
+/// The embedded plots of the comparison against the baseline, if there is one.
+const PLACEHOLDER_COMPARISON_PLOTS: &[&str] = &[
+    "both/pdf.svg",
+    "relative_pdf_small.svg",
+    "both/regression.svg",
+    "relative_regression_small.svg",
+    "both/iteration_times.svg",
+    "relative_iteration_times_small.svg",
+];
+
+/// The benchmarks whose plots haven't been drawn, by title, with their report directories
+/// relative to the reports directory.
+pub type Manifest = BTreeMap<String, PathBuf>;
+
+/// Loads the manifest from the reports directory. Returns an empty manifest if there isn't one.
+pub fn load_manifest(reports_directory: &Path) -> Result<Manifest> {
+    let path = reports_directory.join(MANIFEST_NAME);
+    if !path.is_file() {
+        return Ok(Manifest::new());
+    }
+    let file = File::open(&path).with_context(|| format!("Failed to open file {:?}", path))?;
+    serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to parse file {:?}", path))
+}
+
+/// Saves the manifest to the reports directory, or removes it if no benchmarks are listed.
+pub fn save_manifest(reports_directory: &Path, manifest: &Manifest) -> Result<()> {
+    let path = reports_directory.join(MANIFEST_NAME);
+    if manifest.is_empty() {
+        if path.is_file() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove file {:?}", path))?;
+        }
+        return Ok(());
+    }
+    let file = File::create(&path).with_context(|| format!("Failed to create file {:?}", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), manifest)
+        .with_context(|| format!("Failed to write file {:?}", path))
+}
+
+/// Applies the changes of a run to the manifest. Benchmarks which map to a directory are added to
+/// it, and those which map to None had their plots drawn, so they're removed.
+pub fn update_manifest(
+    reports_directory: &Path,
+    changes: &BTreeMap<String, Option<PathBuf>>,
+) -> Result<()> {
+    let mut manifest = load_manifest(reports_directory)?;
+    for (title, directory) in changes {
+        match directory {
+            Some(directory) => manifest.insert(title.clone(), directory.clone()),
+            None => manifest.remove(title),
+        };
+    }
+    save_manifest(reports_directory, &manifest)
+}
+
+/// Returns the command which draws the plots of the given benchmark.
+pub fn render_command(id: &BenchmarkId) -> String {
+    format!("cargo criterion render {}", shell_quote(id.as_title()))
+}
+
+// Quotes the argument for a POSIX shell, unless it doesn't need quoting.
+fn shell_quote(arg: &str) -> String {
+    let is_plain = |c: char| c.is_ascii_alphanumeric() || "/_-.,:=+".contains(c);
+    if !arg.is_empty() && arg.chars().all(is_plain) {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Writes the placeholder images in place of the embedded plots of the given benchmark.
+pub fn write_placeholders(
+    context: &ReportContext,
+    id: &BenchmarkId,
+    has_comparison: bool,
+) -> Result<()> {
+    let svg = placeholder_svg(&render_command(id));
+    let comparison_plots = if has_comparison {
+        PLACEHOLDER_COMPARISON_PLOTS
+    } else {
+        &[]
+    };
+    for plot in PLACEHOLDER_PLOTS.iter().chain(comparison_plots) {
+        let path = context.report_path(id, plot);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        std::fs::write(&path, &svg).with_context(|| format!("Failed to write file {:?}", path))?;
+    }
+    Ok(())
+}
+
+// An image the size of a thumbnail which shows the command that draws the real plot.
+fn placeholder_svg(command: &str) -> String {
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="450" height="300" viewBox="0 0 450 300">
+<rect width="450" height="300" fill="#f4f4f4" stroke="#cccccc"/>
+<text x="225" y="140" text-anchor="middle" font-family="sans-serif" font-size="14">This plot hasn't been drawn yet. To draw it, run</text>
+<text x="225" y="165" text-anchor="middle" font-family="monospace" font-size="12">{}</text>
+</svg>
+"##,
+        xml_escape(command)
+    )
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Draws the plots of the benchmark with the given title, or else of every benchmark in the
+/// manifest, by repeating the analysis of its latest stored measurement and passing the results
+/// to the report.
+pub fn render(
+    title: Option<&str>,
+    report: &dyn Report,
+    model: &Model,
+    context: &ReportContext,
+) -> Result<()> {
+    let mut manifest = load_manifest(&context.output_directory)?;
+    let titles: Vec<String> = match title {
+        Some(title) => vec![title.to_owned()],
+        None => manifest.keys().cloned().collect(),
+    };
+    if titles.is_empty() {
+        println!("No benchmarks are missing their plots");
+        return Ok(());
+    }
+
+    // Benchmarks which were drawn before a failure are still taken out of the manifest.
+    let result = titles.iter().try_for_each(|title| {
+        render_benchmark(title, report, model, context)?;
+        manifest.remove(title);
+        println!("Drew the plots of {}", title);
+        Ok(())
+    });
+    save_manifest(&context.output_directory, &manifest)?;
+    result
+}
+
+fn render_benchmark(
+    title: &str,
+    report: &dyn Report,
+    model: &Model,
+    context: &ReportContext,
+) -> Result<()> {
+    let (id, stats) = model
+        .groups
+        .values()
+        .flat_map(|group| group.benchmarks.iter())
+        .find(|(id, _)| id.as_title() == title)
+        .map(|(id, benchmark)| (id, &benchmark.latest_stats))
+        .with_context(|| format!("No stored measurements found for benchmark {:?}", title))?;
+    let config = stats.config.as_ref().with_context(|| {
+        format!(
+            "The stored measurements of benchmark {} were made by an older version of \
+            cargo-criterion, so its plots can't be drawn from them. Run the benchmark again.",
+            title
+        )
+    })?;
+    let baseline = model.get_latest_baseline(id);
+    let mut measured_data = crate::analysis::reanalysis(
+        config,
+        id.throughput.clone(),
+        stats,
+        baseline.as_ref().map(|baseline| {
+            let measured_values = MeasuredValues {
+                iteration_count: &baseline.iterations,
+                sample_values: &baseline.values,
+                avg_values: &baseline.avg_values,
+                timestamps: baseline.timestamps.as_deref(),
+            };
+            (measured_values, &baseline.estimates)
+        }),
+        context.statistics.as_deref(),
+    );
+    measured_data.overlay_baselines = model.get_overlay_baselines(id);
+
+    let formatter = ValueFormatter::wall_time(context.time_unit, context.rounding);
+    report.measurement_complete(id, context, &measured_data, &formatter);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!("fib/20", shell_quote("fib/20"));
+        assert_eq!("'fib 20'", shell_quote("fib 20"));
+        assert_eq!(r"'it'\''s'", shell_quote("it's"));
+        assert_eq!("''", shell_quote(""));
+    }
+
+    #[test]
+    fn test_update_manifest() {
+        let directory =
+            std::env::temp_dir().join(format!("cargo-criterion-lazy-plots-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let mut changes = BTreeMap::new();
+        changes.insert("a/1".to_owned(), Some(PathBuf::from("a/1")));
+        changes.insert("a/2".to_owned(), Some(PathBuf::from("a/2")));
+        update_manifest(&directory, &changes).unwrap();
+        assert_eq!(2, load_manifest(&directory).unwrap().len());
+
+        changes.clear();
+        changes.insert("a/1".to_owned(), None);
+        changes.insert("a/2".to_owned(), None);
+        update_manifest(&directory, &changes).unwrap();
+        let removed = !directory.join(MANIFEST_NAME).exists();
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(removed);
+    }
+}
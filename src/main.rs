@@ -24,27 +24,46 @@ mod macros_private;
 #[macro_use]
 mod plot;
 
+mod affinity;
 mod analysis;
+mod ansi;
+mod baseline_store;
 mod bench_target;
+mod budget_gate;
 mod compile;
 mod config;
 mod connection;
+mod derived;
+mod determinism;
+mod encryption;
 mod estimate;
+mod failed_benchmarks;
 mod format;
 mod html;
 mod kde;
+mod lazy_plots;
 mod message_formats;
 mod model;
+mod offline;
+mod regression_gate;
 mod report;
+mod report_dir;
+mod server;
+mod shuffle;
+mod since;
+mod stability;
 mod stats;
 mod value_formatter;
+mod variance_gate;
 
-use crate::config::{OutputFormat, PlottingBackend, SelfConfig, TextColor};
+use crate::config::{OutputFormat, PlottingBackend, Progress, SelfConfig, StoreCommand, TextColor};
 use crate::connection::{AxisScale, PlotConfiguration};
-use crate::plot::Plotter;
+use crate::plot::{PlotFeature, Plotter};
 use crate::report::{Report, ReportContext};
-use anyhow::Error;
+use anyhow::{Context, Error};
 use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
 
 lazy_static! {
     static ref DEBUG_ENABLED: bool = std::env::var_os("CRITERION_DEBUG").is_some();
@@ -62,11 +81,11 @@ fn configure_log() {
     } else {
         LevelFilter::Warn
     };
-    TermLogger::init(
+    // The messages can include benchmark IDs, which might contain escape sequences.
+    WriteLogger::init(
         filter,
         Default::default(),
-        TerminalMode::Stderr,
-        ColorChoice::Never,
+        ansi::PlainWriter::new(std::io::stderr()),
     )
     .unwrap();
 }
@@ -80,26 +99,168 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // First, load the config file and parse the command-line args.
     let configuration = config::configure()?;
     let self_config = &configuration.self_config;
+    ansi::set_plain_output(matches!(self_config.text_color, TextColor::Never));
 
-    // Launch cargo to compile the crate and produce a list of the benchmark targets to run.
+    if let Some(command) = &configuration.store_command {
+        return run_store_command(self_config, command).map_err(Into::into);
+    }
+
+    if let Some(address) = &self_config.serve {
+        return server::serve(&configuration, address).map_err(Into::into);
+    }
+
+    run_benchmarks(&configuration, configuration.bench_filter.as_deref(), None).map_err(Into::into)
+}
+
+/// Compile and run the benchmarks which match the filter and generate the reports, as configured.
+/// This is the whole of a normal run, and of each run requested from `--serve`. If `message_output`
+/// is given, the JSON messages are written to it whatever the message format.
+fn run_benchmarks(
+    configuration: &config::FullConfig,
+    filter: Option<&OsStr>,
+    message_output: Option<Box<dyn std::io::Write>>,
+) -> Result<(), Error> {
+    let self_config = &configuration.self_config;
+
+    // Launch cargo to compile the crate and produce a list of the benchmark targets to run. There's
+    // nothing to compile if the samples are read from stdin or imported instead.
     let compile::CompiledBenchmarks {
         targets,
         library_paths,
-    } = compile::compile(self_config.debug_build, &configuration.cargo_args)?;
+    } = if self_config.stdin_benchmark.is_some() || self_config.import_bin.is_some() {
+        Default::default()
+    } else if self_config.toolchains.is_empty() {
+        compile::compile_or_reuse(
+            self_config.build_mode,
+            &self_config.criterion_home,
+            self_config.debug_build,
+            None,
+            &configuration.cargo_args,
+        )?
+    } else {
+        // Compile everything up front so that a build failure doesn't waste a partial run.
+        let mut compiled = compile::CompiledBenchmarks::default();
+        for toolchain in &self_config.toolchains {
+            let mut toolchain_compiled = compile::compile_or_reuse(
+                self_config.build_mode,
+                &self_config.criterion_home,
+                self_config.debug_build,
+                Some(toolchain),
+                &configuration.cargo_args,
+            )
+            .with_context(|| format!("Failed to compile the benchmarks with {}", toolchain))?;
+            compiled.targets.append(&mut toolchain_compiled.targets);
+            for path in toolchain_compiled.library_paths {
+                if !compiled.library_paths.contains(&path) {
+                    compiled.library_paths.push(path);
+                }
+            }
+        }
+        compiled
+    };
+
+    // With --since, skip the targets that aren't affected by the changes.
+    let mut targets = match &self_config.since {
+        Some(revision) => {
+            let changed = since::changed_files(revision)?;
+            since::select_targets(targets, &changed, &self_config.benchmark_paths)
+        }
+        None => targets,
+    };
+
+    // With --reuse-unchanged, skip them as well, but report their stored results.
+    let mut reused_targets: Vec<String> = vec![];
+    if let Some(revision) = &self_config.reuse_unchanged {
+        let changed = since::changed_files(revision)?;
+        let (changed_targets, unchanged_targets) =
+            since::partition_targets(targets, &changed, &self_config.benchmark_paths);
+        for target in &unchanged_targets {
+            info!(
+                "Reusing the stored results of {}: none of its sources have changed",
+                target.name
+            );
+        }
+        reused_targets = unchanged_targets.into_iter().map(|t| t.name).collect();
+        targets = changed_targets;
+    }
+
+    if let Some(seed) = self_config.shuffle_seed {
+        eprintln!(
+            "Running the benchmark targets in a random order (--shuffle-seed {})",
+            seed
+        );
+        shuffle::shuffle(&mut targets, seed);
+    }
 
     // Load the saved measurements from the last run.
     let mut run_model = model::Model::load(
         self_config.criterion_home.clone(),
-        "main".into(),
+        model::DEFAULT_BASELINE.into(),
         self_config.history_id.clone(),
         self_config.history_description.clone(),
+        self_config.label.clone(),
+        self_config.metadata.clone(),
+        self_config.order,
+        self_config.baseline_window,
+        encryption::HistoryCipher::new(self_config.encrypt_history)?,
     );
+    if let Some(location) = &self_config.baseline_store {
+        run_model.set_baseline_store(baseline_store::open(location)?);
+    }
+    run_model.set_overlay_baselines(&self_config.overlay_baselines);
+    run_model.reuse_results(&reused_targets);
+
+    if let Some(order) = self_config.order_by_duration {
+        let durations = run_model.target_durations();
+        shuffle::order_by_duration(&mut targets, order, |target| {
+            durations.get(&target.name).copied()
+        });
+    }
 
     // Set up the reports. These receive notifications as the benchmarks proceed and generate output for the user.
     let cli_report = configure_cli_output(self_config);
     let bencher_report = crate::report::BencherReport;
-    let html_report = get_plotter(self_config)?.map(|plotter| crate::html::Html::new(plotter));
-    let machine_report = message_formats::create_machine_report(self_config);
+    let html_report = configure_html_output(self_config, self_config.lazy_plots)?;
+    let machine_report = match message_output {
+        Some(output) => Some(message_formats::create_stream_report(self_config, output)),
+        None => message_formats::create_machine_report(self_config),
+    };
+    let bmf_report = message_formats::create_bmf_report(self_config);
+    let file_report = message_formats::create_file_report(self_config);
+    let curves_report = message_formats::create_curves_report(self_config);
+    let bootstrap_report = message_formats::create_bootstrap_report(self_config);
+    let samples_csv_report = message_formats::create_samples_csv_report(self_config);
+    let post_process_report = message_formats::create_post_process_report(self_config);
+    let diff_csv_report = message_formats::create_diff_csv_report(self_config);
+    let rust_source_report = message_formats::create_rust_source_report(self_config);
+    let binary_report = message_formats::create_binary_report(self_config);
+    let regression_gate = if self_config.fail_on_regression || self_config.fail_on_change {
+        Some(regression_gate::RegressionGate::new(
+            self_config.ungated_benchmarks.clone(),
+            self_config.fail_on_change,
+        ))
+    } else {
+        None
+    };
+    let variance_gate = self_config.max_rsd.map(|max_rsd| {
+        variance_gate::VarianceGate::new(max_rsd, self_config.fail_on_high_variance)
+    });
+    let failed_benchmarks = failed_benchmarks::FailedBenchmarks::default();
+    let budget_gate = if self_config.budgets.is_empty() {
+        None
+    } else {
+        Some(budget_gate::BudgetGate::new(
+            self_config.budgets.clone(),
+            self_config.fail_on_budget,
+        ))
+    };
+    let determinism_check = self_config
+        .determinism_tolerance
+        .map(determinism::DeterminismCheck::new);
+    let stability_check = self_config.stabilize.map(|target_rsd| {
+        run_model.keep_samples();
+        stability::StabilityCheck::new(target_rsd)
+    });
 
     let mut reports: Vec<&dyn crate::report::Report> = Vec::new();
     match self_config.output_format {
@@ -114,46 +275,419 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some(machine_report) = &machine_report {
         reports.push(machine_report);
     }
+    if let Some(bmf_report) = &bmf_report {
+        reports.push(bmf_report);
+    }
+    if let Some(file_report) = &file_report {
+        reports.push(file_report);
+    }
+    if let Some(curves_report) = &curves_report {
+        reports.push(curves_report);
+    }
+    if let Some(bootstrap_report) = &bootstrap_report {
+        reports.push(bootstrap_report);
+    }
+    if let Some(samples_csv_report) = &samples_csv_report {
+        reports.push(samples_csv_report);
+    }
+    if let Some(post_process_report) = &post_process_report {
+        reports.push(post_process_report);
+    }
+    if let Some(diff_csv_report) = &diff_csv_report {
+        reports.push(diff_csv_report);
+    }
+    if let Some(rust_source_report) = &rust_source_report {
+        reports.push(rust_source_report);
+    }
+    if let Some(binary_report) = &binary_report {
+        reports.push(binary_report);
+    }
+    if let Some(regression_gate) = &regression_gate {
+        reports.push(regression_gate);
+    }
+    if let Some(variance_gate) = &variance_gate {
+        reports.push(variance_gate);
+    }
+    if let Some(budget_gate) = &budget_gate {
+        reports.push(budget_gate);
+    }
+    reports.push(&failed_benchmarks);
+    if let Some(determinism_check) = &determinism_check {
+        reports.push(determinism_check);
+    }
+    if let Some(stability_check) = &stability_check {
+        reports.push(stability_check);
+    }
     let reports = crate::report::Reports::new(reports);
 
     if self_config.do_run {
+        let reports_directory = self_config.criterion_home.join("reports");
+        let reports_directory = if self_config.atomic_reports {
+            report_dir::ReportDirectory::staged(reports_directory)?
+        } else {
+            report_dir::ReportDirectory::direct(reports_directory)
+        };
+
+        if let Some(id) = &self_config.stdin_benchmark {
+            offline::analyze_stdin(
+                id,
+                &reports,
+                &mut run_model,
+                reports_directory.path(),
+                self_config.time_unit,
+                self_config.rounding,
+                self_config.statistics.as_deref(),
+            )?;
+        }
+        if let Some(path) = &self_config.import_bin {
+            offline::import_results(
+                path,
+                &reports,
+                &mut run_model,
+                reports_directory.path(),
+                self_config.time_unit,
+                self_config.rounding,
+                self_config.statistics.as_deref(),
+            )?;
+        }
+
         // Execute each benchmark target, updating the model as we go.
-        for bench in targets {
-            info!("Executing {} - {:?}", bench.name, bench.executable);
-            let err = bench.execute(
-                &self_config.criterion_home,
-                &configuration.additional_args,
+        run_targets(
+            configuration,
+            &targets,
+            &library_paths,
+            &reports,
+            &mut run_model,
+            reports_directory.path(),
+            filter,
+        )?;
+
+        if let Some(stability_check) = &stability_check {
+            stabilize(
+                configuration,
+                &targets,
                 &library_paths,
                 &reports,
+                stability_check,
                 &mut run_model,
-                self_config.message_format.is_some(),
-            );
+                reports_directory.path(),
+            )?;
+        }
 
-            if let Err(err) = err {
-                if self_config.do_fail_fast {
-                    return Err(err.into());
-                } else {
-                    error!(
-                        "Failed to execute benchmark target {}:\n{}",
-                        bench.name, err
-                    );
-                }
-            }
+        if let Some(determinism_check) = &determinism_check {
+            verify_determinism(
+                configuration,
+                &targets,
+                &library_paths,
+                determinism_check,
+                reports_directory.path(),
+                filter,
+            )?;
         }
 
         // Generate the overall summary report using all of the records in the model.
         let final_context = ReportContext {
-            output_directory: self_config.criterion_home.join("reports"),
+            output_directory: reports_directory.path().to_owned(),
             plot_config: PlotConfiguration {
                 summary_scale: AxisScale::Linear,
             },
+            time_unit: self_config.time_unit,
+            rounding: self_config.rounding,
+            statistics: self_config.statistics.clone(),
         };
 
         reports.final_summary(&final_context, &run_model);
+        reports_directory.commit()?;
+        run_model
+            .save_to_baseline_store()
+            .context("Failed to save the results to the baseline store")?;
+
+        if let Some(site) = &self_config.export_site {
+            report_dir::export_site(&self_config.criterion_home, site)
+                .with_context(|| format!("Failed to export the reports to {:?}", site))?;
+        }
+
+        if let Some(post_process_report) = &post_process_report {
+            post_process_report.check()?;
+        }
+        if let Some(variance_gate) = &variance_gate {
+            variance_gate.check()?;
+        }
+        if let Some(budget_gate) = &budget_gate {
+            budget_gate.check()?;
+        }
+        if let Some(regression_gate) = &regression_gate {
+            regression_gate.check()?;
+        }
+        failed_benchmarks.check()?;
+    }
+    Ok(())
+}
+
+/// Execute each benchmark target in turn, notifying the reports and updating the model as we go.
+fn run_targets(
+    configuration: &config::FullConfig,
+    targets: &[bench_target::BenchTarget],
+    library_paths: &[std::path::PathBuf],
+    reports: &dyn Report,
+    model: &mut model::Model,
+    reports_directory: &std::path::Path,
+    filter: Option<&OsStr>,
+) -> Result<(), Error> {
+    let self_config = &configuration.self_config;
+    let args: Vec<OsString> = filter
+        .map(OsStr::to_owned)
+        .into_iter()
+        .chain(configuration.additional_args.iter().cloned())
+        .collect();
+    for bench in targets {
+        info!("Executing {} - {:?}", bench.name, bench.executable);
+        let err = bench.execute(
+            &self_config.criterion_home,
+            reports_directory,
+            &args,
+            library_paths,
+            reports,
+            model,
+            self_config.message_format.is_some(),
+            self_config.time_unit,
+            self_config.rounding,
+            self_config.statistics.as_deref(),
+            self_config.quick,
+            self_config.sample_size,
+            self_config.max_samples,
+            self_config.cpu_affinity.as_deref(),
+        );
+
+        if let Err(err) = err {
+            if self_config.do_fail_fast {
+                return Err(err);
+            } else {
+                error!(
+                    "Failed to execute benchmark target {}:\n{}",
+                    bench.name, err
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Measure the benchmarks which haven't stabilized again for --stabilize, until they all have or
+/// the maximum number of attempts is reached. The targets are run with a filter which matches
+/// just those benchmarks.
+fn stabilize(
+    configuration: &config::FullConfig,
+    targets: &[bench_target::BenchTarget],
+    library_paths: &[std::path::PathBuf],
+    reports: &dyn Report,
+    stability_check: &stability::StabilityCheck,
+    model: &mut model::Model,
+    reports_directory: &std::path::Path,
+) -> Result<(), Error> {
+    let max_attempts = configuration.self_config.max_attempts;
+    for attempt in 2..=max_attempts {
+        let unstable = stability_check.unstable();
+        if unstable.is_empty() {
+            return Ok(());
+        }
+        eprintln!(
+            "Measuring {} benchmarks again to stabilize them (attempt {} of {})",
+            unstable.len(),
+            attempt,
+            max_attempts
+        );
+
+        model.start_retry();
+        let filter = OsString::from(stability::exact_filter(&unstable));
+        run_targets(
+            configuration,
+            targets,
+            library_paths,
+            reports,
+            model,
+            reports_directory,
+            Some(&filter),
+        )?;
+    }
+    stability_check.report(max_attempts);
+    Ok(())
+}
+
+/// Run the benchmark targets a second time for --verify-determinism and list the benchmarks whose
+/// estimates differ from the first run. Only the determinism check sees the second run, and its
+/// measurements are kept in a scratch directory so that they don't become part of the history.
+fn verify_determinism(
+    configuration: &config::FullConfig,
+    targets: &[bench_target::BenchTarget],
+    library_paths: &[std::path::PathBuf],
+    determinism_check: &determinism::DeterminismCheck,
+    reports_directory: &std::path::Path,
+    filter: Option<&OsStr>,
+) -> Result<(), Error> {
+    let self_config = &configuration.self_config;
+    let scratch_home = std::env::temp_dir().join(format!("cargo-criterion-{}", std::process::id()));
+    let mut scratch_model = model::Model::load(
+        scratch_home.clone(),
+        model::DEFAULT_BASELINE.into(),
+        None,
+        None,
+        None,
+        BTreeMap::new(),
+        self_config.order,
+        1,
+        encryption::HistoryCipher::default(),
+    );
+
+    eprintln!("Running the benchmarks again to verify that they are deterministic");
+    determinism_check.start_second_run();
+    let result = run_targets(
+        configuration,
+        targets,
+        library_paths,
+        determinism_check,
+        &mut scratch_model,
+        reports_directory,
+        filter,
+    );
+    if let Err(e) = std::fs::remove_dir_all(&scratch_home) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            warn!("Failed to remove {:?}: {}", scratch_home, e);
+        }
     }
+    result?;
+
+    determinism_check.report();
     Ok(())
 }
 
+/// Perform one of the subcommands which operate on the stored data instead of running the
+/// benchmarks.
+fn run_store_command(self_config: &SelfConfig, command: &StoreCommand) -> Result<(), Error> {
+    match command {
+        StoreCommand::List => {
+            let cipher = encryption::HistoryCipher::new(self_config.encrypt_history)?;
+            let baselines = model::list_baselines(&self_config.criterion_home, &cipher)?;
+            if baselines.is_empty() {
+                println!(
+                    "No baselines found in {}",
+                    self_config.criterion_home.display()
+                );
+            }
+            for baseline in baselines {
+                let latest = baseline
+                    .latest
+                    .map(|datetime| {
+                        datetime
+                            .with_timezone(&chrono::Local)
+                            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+                    })
+                    .unwrap_or_else(|| "-".to_owned());
+                println!(
+                    "{:<20} {:>5} benchmarks  {:<25}  {}",
+                    baseline.name,
+                    baseline.benchmark_count,
+                    latest,
+                    baseline.label.as_deref().unwrap_or("")
+                );
+            }
+        }
+        StoreCommand::Remove { name, force } => {
+            model::remove_baseline(&self_config.criterion_home, name, *force)?;
+            println!("Deleted baseline {}", name);
+        }
+        StoreCommand::Clean { yes } => {
+            let criterion_home = &self_config.criterion_home;
+            let size = model::stored_data_size(criterion_home);
+            if size.files == 0 {
+                println!("No stored data found in {}", criterion_home.display());
+                return Ok(());
+            }
+            if !yes
+                && !confirm(&format!(
+                    "Delete all stored measurements, history and reports in {} ({} files, {})?",
+                    criterion_home.display(),
+                    size.files,
+                    crate::format::bytes(size.bytes)
+                ))?
+            {
+                println!("Nothing was deleted");
+                return Ok(());
+            }
+            model::clean(criterion_home)?;
+            println!(
+                "Deleted {} files ({})",
+                size.files,
+                crate::format::bytes(size.bytes)
+            );
+        }
+        StoreCommand::Render { id } => {
+            let html_report = match configure_html_output(self_config, false)? {
+                Some(html_report) => html_report,
+                None => anyhow::bail!("Plotting is disabled, so there are no plots to draw"),
+            };
+            let mut run_model = model::Model::load(
+                self_config.criterion_home.clone(),
+                model::DEFAULT_BASELINE.into(),
+                None,
+                None,
+                None,
+                BTreeMap::new(),
+                self_config.order,
+                self_config.baseline_window,
+                encryption::HistoryCipher::new(self_config.encrypt_history)?,
+            );
+            run_model.set_overlay_baselines(&self_config.overlay_baselines);
+            let context = ReportContext {
+                output_directory: self_config.criterion_home.join("reports"),
+                plot_config: PlotConfiguration {
+                    summary_scale: AxisScale::Linear,
+                },
+                time_unit: self_config.time_unit,
+                rounding: self_config.rounding,
+                statistics: self_config.statistics.clone(),
+            };
+            lazy_plots::render(id.as_deref(), &html_report, &run_model, &context)?;
+        }
+        StoreCommand::Verify => {
+            let cipher = encryption::HistoryCipher::new(self_config.encrypt_history)?;
+            let results = model::verify(&self_config.criterion_home, &cipher);
+            for file in &results.corrupt {
+                println!("{}: {:#}", file.path.display(), file.error);
+            }
+            if !results.corrupt.is_empty() {
+                anyhow::bail!(
+                    "{} of the {} stored files are corrupt. Delete them (or their baseline, with \
+                    'cargo criterion baselines rm') to stop them being loaded",
+                    results.corrupt.len(),
+                    results.checked
+                );
+            }
+            println!(
+                "Checked {} stored files in {}; none are corrupt",
+                results.checked,
+                self_config.criterion_home.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ask the user a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> Result<bool, Error> {
+    use std::io::{BufRead, Write};
+    if !atty::is(atty::Stream::Stdin) {
+        anyhow::bail!(
+            "Unable to ask for confirmation since stdin is not a terminal; pass --yes to proceed"
+        );
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
 /// Configure and return a Report object that prints benchmark information to the command-line.
 fn configure_cli_output(self_config: &crate::config::SelfConfig) -> crate::report::CliReport {
     let stderr_isatty = atty::is(atty::Stream::Stderr);
@@ -181,16 +715,93 @@ fn configure_cli_output(self_config: &crate::config::SelfConfig) -> crate::repor
         enable_text_coloring,
         show_differences,
         verbose,
+        self_config.progress == Progress::Compact,
+        if self_config.fail_on_regression || self_config.fail_on_change {
+            self_config.ungated_benchmarks.clone()
+        } else {
+            vec![]
+        },
+        self_config.geometric_mean,
+        self_config.weights.clone(),
+        self_config.total_time,
+        self_config.speedup_threshold,
+        &self_config.colors,
     )
 }
 
+/// Configure and return the HTML report, unless plotting is disabled.
+fn configure_html_output(
+    self_config: &crate::config::SelfConfig,
+    lazy_plots: bool,
+) -> Result<Option<crate::html::Html>, Error> {
+    // Quick mode skips the plots entirely, since they take longer to generate than the analysis.
+    if self_config.quick {
+        return Ok(None);
+    }
+    let plotter = get_plotter(self_config)?;
+    if let (true, Some(plotter)) = (self_config.strict_plots, &plotter) {
+        check_plot_support(self_config, plotter.as_ref())?;
+    }
+    Ok(plotter.map(|plotter| {
+        crate::html::Html::new(
+            plotter,
+            self_config.label.clone(),
+            self_config.metadata.clone(),
+            self_config.thumbnails,
+            self_config.diff_report,
+            self_config.max_rsd,
+            if self_config.fail_on_regression || self_config.fail_on_change {
+                self_config.ungated_benchmarks.clone()
+            } else {
+                vec![]
+            },
+            self_config.min_displayed_change,
+            self_config.speedup_threshold,
+            self_config.derived_metrics.clone(),
+            self_config.contact_sheet.clone(),
+            self_config.report_time_limit,
+            lazy_plots,
+            self_config.criterion_home.clone(),
+            &self_config.colors,
+        )
+    }))
+}
+
+/// Fails if the plotting backend can't draw any of the plots which the reports could need, rather
+/// than leaving them out of the reports later. Heatmaps and latency percentiles depend on the
+/// benchmarks, so they're always required.
+fn check_plot_support(self_config: &SelfConfig, plotter: &dyn Plotter) -> Result<(), Error> {
+    let mut required = vec![PlotFeature::Heatmap, PlotFeature::LatencyPercentiles];
+    if self_config.plot_options.cdf {
+        required.push(PlotFeature::Cdf);
+    }
+    if self_config.contact_sheet.is_some() {
+        required.push(PlotFeature::ContactSheet);
+    }
+
+    let missing: Vec<_> = required
+        .into_iter()
+        .filter(|feature| !plotter.supports(*feature))
+        .map(|feature| feature.description())
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The plotting backend can't draw {}, which --strict-plots requires. To continue, use \
+            the plotters backend (--plotting-backend=plotters) or drop --strict-plots.",
+            missing.join(", ")
+        );
+    }
+    Ok(())
+}
+
 /// Configure and return a Gnuplot plotting backend, if available.
 #[cfg(feature = "gnuplot_backend")]
 fn gnuplot_plotter(config: &SelfConfig) -> Result<Box<dyn Plotter>, Error> {
     match criterion_plot::version() {
         Ok(_) => {
             let generator = crate::plot::PlotGenerator {
-                backend: crate::plot::Gnuplot::new(&config.colors),
+                backend: crate::plot::Gnuplot::new(&config.colors, &config.plot_options),
+                options: config.plot_options.clone(),
             };
             Ok(Box::new(generator))
         },
@@ -208,7 +819,8 @@ fn gnuplot_plotter(_: &SelfConfig) -> Result<Box<dyn Plotter>, Error> {
 #[cfg(feature = "plotters_backend")]
 fn plotters_plotter(config: &SelfConfig) -> Result<Box<dyn Plotter>, Error> {
     let generator = crate::plot::PlotGenerator {
-        backend: crate::plot::PlottersBackend::new(&config.colors),
+        backend: crate::plot::PlottersBackend::new(&config.colors, &config.plot_options),
+        options: config.plot_options.clone(),
     };
     Ok(Box::new(generator))
 }
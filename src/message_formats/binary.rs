@@ -0,0 +1,166 @@
+//! The full results of a run in a compact binary format (CBOR, see `--export-bin`), for pipelines
+//! which keep the results of many runs and care about the size of their artifacts. The file can be
+//! read back with `--import-bin` to generate the reports offline.
+
+use crate::estimate::{ChangeEstimates, Estimates};
+use crate::model::{Model, SavedBenchmarkId, SavedStatistics};
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{bail, Context, Result};
+use linked_hash_map::LinkedHashMap;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The version of the format, which is increased whenever the old files can no longer be read.
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedResults {
+    pub version: u32,
+    pub benchmarks: Vec<ExportedBenchmark>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedBenchmark {
+    pub id: SavedBenchmarkId,
+    // The samples and estimates of the benchmark, along with the settings of its analysis.
+    pub stats: SavedStatistics,
+    pub comparison: Option<ExportedComparison>,
+}
+
+/// The comparison of a benchmark against its baseline, including the samples of the baseline so
+/// that the comparison can be plotted again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedComparison {
+    pub p_value: f64,
+    pub t_value: f64,
+    pub significance_threshold: f64,
+    pub noise_threshold: f64,
+    pub relative_estimates: ChangeEstimates,
+    pub base_iterations: Vec<f64>,
+    pub base_values: Vec<f64>,
+    pub base_avg_values: Vec<f64>,
+    pub base_timestamps: Option<Vec<f64>>,
+    pub base_estimates: Estimates,
+}
+
+/// Report which collects the results of each benchmark and writes them all to a binary file once
+/// the run is over.
+pub struct BinaryReport {
+    path: PathBuf,
+    benchmarks: RefCell<LinkedHashMap<String, ExportedBenchmark>>,
+}
+impl BinaryReport {
+    pub fn new(path: PathBuf) -> BinaryReport {
+        BinaryReport {
+            path,
+            benchmarks: RefCell::new(LinkedHashMap::new()),
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        let results = ExportedResults {
+            version: FORMAT_VERSION,
+            benchmarks: std::mem::replace(&mut *self.benchmarks.borrow_mut(), LinkedHashMap::new())
+                .into_iter()
+                .map(|(_, benchmark)| benchmark)
+                .collect(),
+        };
+        let file = File::create(&self.path)
+            .with_context(|| format!("Failed to create file {:?}", self.path))?;
+        let mut writer = BufWriter::new(file);
+        serde_cbor::to_writer(&mut writer, &results)
+            .with_context(|| format!("Failed to write file {:?}", self.path))?;
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write file {:?}", self.path))
+    }
+}
+impl Report for BinaryReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        let comparison = measurements
+            .comparison
+            .as_ref()
+            .map(|comparison| ExportedComparison {
+                p_value: comparison.p_value,
+                t_value: comparison.t_value,
+                significance_threshold: comparison.significance_threshold,
+                noise_threshold: comparison.noise_threshold,
+                relative_estimates: comparison.relative_estimates.clone(),
+                base_iterations: comparison.base_iter_counts.clone(),
+                base_values: comparison.base_sample_times.clone(),
+                base_avg_values: comparison.base_avg_times.clone(),
+                base_timestamps: comparison.base_timestamps.clone(),
+                base_estimates: comparison.base_estimates.clone(),
+            });
+        let benchmark = ExportedBenchmark {
+            id: id.into(),
+            stats: SavedStatistics::new(measurements),
+            comparison,
+        };
+
+        // A benchmark measured again (see --stabilize) keeps its place with its latest result.
+        self.benchmarks
+            .borrow_mut()
+            .insert(id.as_title().to_owned(), benchmark);
+    }
+
+    fn final_summary(&self, _: &ReportContext, _: &Model) {
+        if let Err(e) = self.write() {
+            error!("Failed to export the results: {:?}", e);
+        }
+    }
+}
+
+/// Reads the results written by `--export-bin`.
+pub fn load_results(path: &Path) -> Result<ExportedResults> {
+    let file = File::open(path).with_context(|| format!("Failed to open file {:?}", path))?;
+    let results: ExportedResults = serde_cbor::from_reader(BufReader::new(file))
+        .with_context(|| format!("Failed to read results from {:?}", path))?;
+    if results.version != FORMAT_VERSION {
+        bail!(
+            "The results in {:?} have format version {}, but only version {} can be read",
+            path,
+            results.version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_load_results_version() {
+        let path = std::env::temp_dir().join(format!(
+            "cargo-criterion-results-{}.cbor",
+            std::process::id()
+        ));
+        let save = |version| {
+            let results = ExportedResults {
+                version,
+                benchmarks: vec![],
+            };
+            std::fs::write(&path, serde_cbor::to_vec(&results).unwrap()).unwrap();
+        };
+
+        save(FORMAT_VERSION);
+        let current = load_results(&path);
+        save(FORMAT_VERSION + 1);
+        let newer = load_results(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(current.unwrap().benchmarks.is_empty());
+        assert!(newer.is_err());
+    }
+}
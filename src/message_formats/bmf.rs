@@ -0,0 +1,102 @@
+//! The Bencher Metric Format (BMF), a single JSON object mapping the name of each benchmark to its
+//! measures, which can be uploaded to bencher.dev as-is.
+
+use crate::connection::Throughput;
+use crate::model::Model;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::Result;
+use serde_derive::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::io::{stdout, Write};
+
+#[derive(Serialize)]
+struct Metric {
+    value: f64,
+    lower_value: f64,
+    upper_value: f64,
+}
+
+/// The measures of a benchmark. The names of the fields are the slugs of Bencher's built-in
+/// measures.
+#[derive(Serialize)]
+struct Measures {
+    /// The typical time per iteration, in nanoseconds.
+    latency: Metric,
+    /// The number of bytes or elements processed per second, if the benchmark has a throughput.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    throughput: Option<Metric>,
+}
+
+/// Report which collects the typical estimate of each benchmark and prints them all to stdout in
+/// the Bencher Metric Format once the run is over.
+#[derive(Default)]
+pub struct BmfReport {
+    benchmarks: RefCell<BTreeMap<String, Measures>>,
+}
+impl Report for BmfReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let typical = measurements.absolute_estimates.typical();
+        let mut values = [
+            typical.point_estimate,
+            typical.confidence_interval.lower_bound,
+            typical.confidence_interval.upper_bound,
+        ];
+        let unit = formatter.scale_for_machines(&mut values);
+        if unit != "ns" {
+            warn!(
+                "Benchmark {} measures {} rather than time, so it is left out of the Bencher \
+                Metric Format output.",
+                id.as_title(),
+                unit
+            );
+            return;
+        }
+
+        let [value, lower_value, upper_value] = values;
+        // The faster end of the time interval is the higher end of the throughput interval.
+        let throughput = measurements.throughput.as_ref().map(|throughput| {
+            let count = match *throughput {
+                Throughput::Bytes(count) | Throughput::Elements(count) => count as f64,
+            };
+            let per_second = |nanos: f64| count * 1e9 / nanos;
+            Metric {
+                value: per_second(value),
+                lower_value: per_second(upper_value),
+                upper_value: per_second(lower_value),
+            }
+        });
+
+        self.benchmarks.borrow_mut().insert(
+            id.as_title().to_owned(),
+            Measures {
+                latency: Metric {
+                    value,
+                    lower_value,
+                    upper_value,
+                },
+                throughput,
+            },
+        );
+    }
+
+    fn final_summary(&self, _: &ReportContext, _: &Model) {
+        fn do_print(benchmarks: &BTreeMap<String, Measures>) -> Result<()> {
+            writeln!(stdout(), "{}", serde_json::to_string(benchmarks)?)?;
+            Ok(())
+        }
+        if let Err(e) = do_print(&self.benchmarks.borrow()) {
+            error!(
+                "Unexpected error writing Bencher Metric Format output: {:?}",
+                e
+            )
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! A CSV table of the change in each benchmark since its baseline, for pasting into spreadsheets
+//! (see `--export-diff-csv`).
+
+use crate::model::Model;
+use crate::report::{
+    compare_to_threshold, BenchmarkId, ComparisonResult, MeasurementData, Report, ReportContext,
+};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{Context, Result};
+use linked_hash_map::LinkedHashMap;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+const HEADER: &str = "benchmark,baseline,current,delta,change_percent,verdict,unit";
+
+// One row of the table. The times are the means, in `unit`.
+struct DiffRow {
+    baseline: Option<f64>,
+    current: f64,
+    change: Option<f64>,
+    verdict: &'static str,
+    unit: String,
+}
+
+/// Report which collects the change in the mean of each benchmark and writes them all to a CSV
+/// file once the run is over.
+pub struct DiffCsvReport {
+    path: PathBuf,
+    rows: RefCell<LinkedHashMap<String, DiffRow>>,
+}
+impl DiffCsvReport {
+    pub fn new(path: PathBuf) -> DiffCsvReport {
+        DiffCsvReport {
+            path,
+            rows: RefCell::new(LinkedHashMap::new()),
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        let file = File::create(&self.path)
+            .with_context(|| format!("Failed to create file {:?}", self.path))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", HEADER)?;
+        let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        for (title, row) in self.rows.borrow().iter() {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                csv_field(title),
+                number(row.baseline),
+                row.current,
+                number(row.baseline.map(|baseline| row.current - baseline)),
+                number(row.change.map(|change| change * 100.0)),
+                row.verdict,
+                csv_field(&row.unit)
+            )?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write file {:?}", self.path))
+    }
+}
+impl Report for DiffCsvReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let current = measurements.absolute_estimates.mean.point_estimate;
+        let comparison = measurements.comparison.as_ref();
+        // Scale both means together so that they're in the same unit.
+        let mut values = [
+            current,
+            comparison.map_or(current, |c| c.base_estimates.mean.point_estimate),
+        ];
+        let unit = formatter.scale_for_machines(&mut values);
+
+        let row = match comparison {
+            Some(comparison) => {
+                let change = &comparison.relative_estimates.mean;
+                let verdict = if comparison.p_value >= comparison.significance_threshold {
+                    "no change"
+                } else {
+                    match compare_to_threshold(change, comparison.noise_threshold) {
+                        ComparisonResult::Improved => "improved",
+                        ComparisonResult::Regressed => "regressed",
+                        ComparisonResult::NonSignificant => "within noise",
+                    }
+                };
+                DiffRow {
+                    baseline: Some(values[1]),
+                    current: values[0],
+                    change: Some(change.point_estimate),
+                    verdict,
+                    unit,
+                }
+            }
+            None => DiffRow {
+                baseline: None,
+                current: values[0],
+                change: None,
+                verdict: "new",
+                unit,
+            },
+        };
+        // A benchmark measured again (see --stabilize) keeps its place with its latest result.
+        self.rows.borrow_mut().insert(id.as_title().to_owned(), row);
+    }
+
+    fn final_summary(&self, _: &ReportContext, _: &Model) {
+        if let Err(e) = self.write() {
+            error!("Failed to export the changes to CSV: {:?}", e);
+        }
+    }
+}
+
+// Quotes a field if it contains a separator, quote or line break, doubling any quotes within it.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("fib/20", csv_field("fib/20"));
+        assert_eq!("\"sort, 10\"", csv_field("sort, 10"));
+        assert_eq!("\"say \"\"hi\"\"\"", csv_field("say \"hi\""));
+    }
+}
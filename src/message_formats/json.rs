@@ -1,14 +1,24 @@
 use crate::connection::Throughput as ThroughputEnum;
-use crate::estimate::Estimate;
-use crate::model::BenchmarkGroup;
+use crate::derived::DerivedMetric;
+use crate::estimate::{Estimate, Statistic};
+use crate::kde;
+use crate::model::{BenchmarkGroup, Model};
 use crate::report::{
     compare_to_threshold, BenchmarkId, ComparisonResult, MeasurementData, Report, ReportContext,
 };
+use crate::stats::bivariate::regression::Slope;
+use crate::stats::univariate::Sample;
+use crate::stats::Distribution;
 use crate::value_formatter::ValueFormatter;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use serde_derive::Serialize;
 use serde_json::json;
-use std::io::{stdout, Write};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
 
 trait Message: serde::ser::Serialize {
     fn reason() -> &'static str;
@@ -45,6 +55,25 @@ impl ConfidenceInterval {
             unit: "%".to_owned(),
         }
     }
+    /// The factor by which the time fell, given its relative change, eg. 2 when it halved and 0.5
+    /// when it doubled.
+    fn from_speedup(estimate: &Estimate) -> ConfidenceInterval {
+        let throughput_change = estimate.to_throughput_change();
+        ConfidenceInterval {
+            estimate: 1.0 + throughput_change.point_estimate,
+            lower_bound: 1.0 + throughput_change.confidence_interval.lower_bound,
+            upper_bound: 1.0 + throughput_change.confidence_interval.upper_bound,
+            unit: "x".to_owned(),
+        }
+    }
+}
+
+// A unitless statistic, such as the goodness of fit of the regression.
+#[derive(Serialize)]
+struct Interval {
+    estimate: f64,
+    lower_bound: f64,
+    upper_bound: f64,
 }
 
 #[derive(Serialize)]
@@ -74,12 +103,89 @@ enum ChangeType {
     Regressed,
 }
 
+impl From<&ComparisonResult> for ChangeType {
+    fn from(result: &ComparisonResult) -> ChangeType {
+        match result {
+            ComparisonResult::Improved => ChangeType::Improved,
+            ComparisonResult::Regressed => ChangeType::Regressed,
+            ComparisonResult::NonSignificant => ChangeType::NoChange,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StatisticChangeDetails {
+    statistic: String,
+    change: ConfidenceInterval,
+    classification: ChangeType,
+}
+
+// The change in throughput which is equivalent to the change in time, for benchmarks which
+// report their throughput.
+#[derive(Serialize)]
+struct ThroughputChange {
+    mean: ConfidenceInterval,
+    median: ConfidenceInterval,
+}
+
 #[derive(Serialize)]
 struct ChangeDetails {
     mean: ConfidenceInterval,
     median: ConfidenceInterval,
+    throughput: Option<ThroughputChange>,
+    speedup: ConfidenceInterval,
 
     change: ChangeType,
+
+    statistics: Vec<StatisticChangeDetails>,
+}
+
+// Progress messages. These are sent as the benchmark target reports its progress, so that
+// frontends can show which benchmark is running. The final results are in BenchmarkComplete.
+
+#[derive(Serialize)]
+struct BenchmarkStarted {
+    id: String,
+}
+impl Message for BenchmarkStarted {
+    fn reason() -> &'static str {
+        "benchmark-started"
+    }
+}
+
+#[derive(Serialize)]
+struct WarmupComplete {
+    id: String,
+    warmup_time_ns: f64,
+}
+impl Message for WarmupComplete {
+    fn reason() -> &'static str {
+        "warmup-complete"
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum MeasurementPhase {
+    Measuring,
+    Analyzing,
+}
+
+#[derive(Serialize)]
+struct MeasurementProgress {
+    id: String,
+    phase: MeasurementPhase,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iteration_count: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    estimated_time_ns: Option<f64>,
+}
+impl Message for MeasurementProgress {
+    fn reason() -> &'static str {
+        "measurement-progress"
+    }
 }
 
 #[derive(Serialize)]
@@ -96,9 +202,27 @@ struct BenchmarkComplete {
     mean: ConfidenceInterval,
     median: ConfidenceInterval,
     median_abs_dev: ConfidenceInterval,
+    // The standard deviation over the mean, as a fraction.
+    coefficient_of_variation: f64,
     slope: Option<ConfidenceInterval>,
+    r_squared: Option<Interval>,
 
     change: Option<ChangeDetails>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+
+    multimodal: bool,
+    zero_variance: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overhead: Option<Value>,
+
+    // The user-defined derived metrics which could be computed for this benchmark.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    derived_metrics: BTreeMap<String, f64>,
 }
 impl Message for BenchmarkComplete {
     fn reason() -> &'static str {
@@ -106,45 +230,16 @@ impl Message for BenchmarkComplete {
     }
 }
 
-#[derive(Serialize)]
-struct BenchmarkGroupComplete {
-    group_name: String,
-    benchmarks: Vec<String>,
-    report_directory: String,
-}
-impl Message for BenchmarkGroupComplete {
-    fn reason() -> &'static str {
-        "group-complete"
-    }
-}
-
-pub struct JsonMessageReport;
-impl JsonMessageReport {
-    fn send_message<M: Message>(&self, message: M) {
-        fn do_send<M: Message>(message: M) -> Result<()> {
-            // Format the message to string
-            let message_text = serde_json::to_string(&message)?;
-            assert!(message_text.starts_with('{'));
-
-            let reason = json!(M::reason());
-
-            // Concatenate that into the message
-            writeln!(stdout(), "{{\"reason\":{},{}", reason, &message_text[1..])?;
-            Ok(())
-        }
-        if let Err(e) = do_send(message) {
-            error!("Unexpected error writing JSON message: {:?}", e)
-        }
-    }
-}
-impl Report for JsonMessageReport {
-    fn measurement_complete(
-        &self,
+impl BenchmarkComplete {
+    fn new(
         id: &BenchmarkId,
         context: &ReportContext,
         measurements: &MeasurementData<'_>,
         formatter: &ValueFormatter,
-    ) {
+        label: Option<String>,
+        metadata: BTreeMap<String, String>,
+        derived_metrics: &[DerivedMetric],
+    ) -> BenchmarkComplete {
         let mut measured_values = measurements.sample_times().to_vec();
         let unit = formatter.scale_for_machines(&mut measured_values);
 
@@ -154,7 +249,7 @@ impl Report for JsonMessageReport {
             .map(|count| *count as u64)
             .collect();
 
-        let message = BenchmarkComplete {
+        BenchmarkComplete {
             id: id.as_title().to_owned(),
             report_directory: path!(&context.output_directory, id.as_directory_name())
                 .display()
@@ -185,11 +280,20 @@ impl Report for JsonMessageReport {
                 &measurements.absolute_estimates.median_abs_dev,
                 formatter,
             ),
+            coefficient_of_variation: crate::variance_gate::relative_std_dev(measurements),
             slope: measurements
                 .absolute_estimates
                 .slope
                 .as_ref()
                 .map(|slope| ConfidenceInterval::from_estimate(slope, formatter)),
+            r_squared: measurements.absolute_estimates.slope.as_ref().map(|slope| {
+                let r_squared = |slope| Slope(slope).r_squared(&measurements.data);
+                Interval {
+                    estimate: r_squared(slope.point_estimate),
+                    lower_bound: r_squared(slope.confidence_interval.lower_bound),
+                    upper_bound: r_squared(slope.confidence_interval.upper_bound),
+                }
+            }),
             change: measurements.comparison.as_ref().map(|comparison| {
                 let different_mean = comparison.p_value < comparison.significance_threshold;
                 let mean_est = &comparison.relative_estimates.mean;
@@ -197,32 +301,246 @@ impl Report for JsonMessageReport {
                 let change = if !different_mean {
                     ChangeType::NoChange
                 } else {
-                    let comparison = compare_to_threshold(mean_est, comparison.noise_threshold);
-                    match comparison {
-                        ComparisonResult::Improved => ChangeType::Improved,
-                        ComparisonResult::Regressed => ChangeType::Regressed,
-                        ComparisonResult::NonSignificant => ChangeType::NoChange,
-                    }
+                    ChangeType::from(&compare_to_threshold(mean_est, comparison.noise_threshold))
                 };
 
                 ChangeDetails {
                     mean: ConfidenceInterval::from_percent(&comparison.relative_estimates.mean),
                     median: ConfidenceInterval::from_percent(&comparison.relative_estimates.median),
+                    throughput: measurements.throughput.as_ref().map(|_| ThroughputChange {
+                        mean: ConfidenceInterval::from_percent(
+                            &comparison.relative_estimates.mean.to_throughput_change(),
+                        ),
+                        median: ConfidenceInterval::from_percent(
+                            &comparison.relative_estimates.median.to_throughput_change(),
+                        ),
+                    }),
+                    speedup: ConfidenceInterval::from_speedup(&comparison.relative_estimates.mean),
                     change,
+                    statistics: comparison
+                        .statistic_changes
+                        .iter()
+                        .map(|change| StatisticChangeDetails {
+                            statistic: change.statistic.to_string(),
+                            change: ConfidenceInterval::from_percent(&change.estimate),
+                            classification: ChangeType::from(&change.result),
+                        })
+                        .collect(),
+                }
+            }),
+
+            label,
+            metadata,
+
+            multimodal: measurements.multimodal,
+            zero_variance: measurements.zero_variance,
+
+            overhead: measurements.overhead.map(|overhead| {
+                let mut values = [overhead];
+                let unit = formatter.scale_for_machines(&mut values);
+                Value {
+                    estimate: values[0],
+                    unit,
                 }
             }),
+
+            derived_metrics: derived_metrics
+                .iter()
+                .filter_map(|metric| Some((metric.name.clone(), metric.evaluate(measurements)?)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Value {
+    estimate: f64,
+    unit: String,
+}
+
+#[derive(Serialize)]
+struct BenchmarkFailed {
+    id: String,
+    error: String,
+}
+impl Message for BenchmarkFailed {
+    fn reason() -> &'static str {
+        "benchmark-failed"
+    }
+}
+
+#[derive(Serialize)]
+struct BenchmarkGroupComplete {
+    group_name: String,
+    benchmarks: Vec<String>,
+    report_directory: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geometric_mean: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geometric_mean_change: Option<f64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    geometric_mean_weighted: bool,
+}
+impl Message for BenchmarkGroupComplete {
+    fn reason() -> &'static str {
+        "group-complete"
+    }
+}
+
+#[derive(Serialize)]
+struct SuiteComplete {
+    total_time: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    change: Option<f64>,
+}
+impl Message for SuiteComplete {
+    fn reason() -> &'static str {
+        "suite-complete"
+    }
+}
+
+#[derive(Serialize)]
+struct RunSummary {
+    run: Vec<String>,
+    skipped: Vec<String>,
+    failed: Vec<String>,
+    reused: Vec<String>,
+}
+impl Message for RunSummary {
+    fn reason() -> &'static str {
+        "run-summary"
+    }
+}
+
+/// Format the message as a single line of JSON, with its reason as the first field.
+fn format_message<M: Message>(message: &M) -> Result<String> {
+    // Format the message to string
+    let message_text = serde_json::to_string(message)?;
+    assert!(message_text.starts_with('{'));
+
+    let reason = json!(M::reason());
+
+    // Concatenate that into the message
+    Ok(format!("{{\"reason\":{},{}", reason, &message_text[1..]))
+}
+
+pub struct JsonMessageReport {
+    pub geometric_mean: bool,
+    pub weights: HashMap<String, f64>,
+    pub total_time: bool,
+    pub label: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub derived_metrics: Vec<DerivedMetric>,
+    // Where to write the messages; stdout, unless they're sent to a client of `--serve`.
+    pub output: RefCell<Box<dyn Write>>,
+    // The warmup time of the running benchmark. The warmup is only complete once the measurement
+    // starts, so the warmup-complete message is held back until then.
+    pub warmup_ns: Cell<Option<f64>>,
+}
+impl JsonMessageReport {
+    fn send_message<M: Message>(&self, message: M) {
+        let do_send = || -> Result<()> {
+            writeln!(self.output.borrow_mut(), "{}", format_message(&message)?)?;
+            Ok(())
         };
+        if let Err(e) = do_send() {
+            error!("Unexpected error writing JSON message: {:?}", e)
+        }
+    }
+}
+impl Report for JsonMessageReport {
+    fn benchmark_start(&self, id: &BenchmarkId, _: &ReportContext) {
+        self.send_message(BenchmarkStarted {
+            id: id.as_title().to_owned(),
+        });
+    }
+
+    fn warmup(&self, _: &BenchmarkId, _: &ReportContext, warmup_ns: f64) {
+        self.warmup_ns.set(Some(warmup_ns));
+    }
+
+    fn measurement_start(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        sample_count: u64,
+        estimate_ns: f64,
+        iter_count: u64,
+    ) {
+        if let Some(warmup_ns) = self.warmup_ns.take() {
+            self.send_message(WarmupComplete {
+                id: id.as_title().to_owned(),
+                warmup_time_ns: warmup_ns,
+            });
+        }
+        self.send_message(MeasurementProgress {
+            id: id.as_title().to_owned(),
+            phase: MeasurementPhase::Measuring,
+            sample_count: Some(sample_count),
+            iteration_count: Some(iter_count),
+            estimated_time_ns: Some(estimate_ns),
+        });
+    }
+
+    fn analysis(&self, id: &BenchmarkId, _: &ReportContext) {
+        self.send_message(MeasurementProgress {
+            id: id.as_title().to_owned(),
+            phase: MeasurementPhase::Analyzing,
+            sample_count: None,
+            iteration_count: None,
+            estimated_time_ns: None,
+        });
+    }
 
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let message = BenchmarkComplete::new(
+            id,
+            context,
+            measurements,
+            formatter,
+            self.label.clone(),
+            self.metadata.clone(),
+            &self.derived_metrics,
+        );
         self.send_message(message);
     }
 
+    fn benchmark_failed(&self, id: &BenchmarkId, _: &ReportContext, error: &str) {
+        self.send_message(BenchmarkFailed {
+            id: id.as_title().to_owned(),
+            error: error.to_owned(),
+        });
+    }
+
     fn summarize(
         &self,
         context: &ReportContext,
         group_id: &str,
         benchmark_group: &BenchmarkGroup,
-        _formatter: &ValueFormatter,
+        formatter: &ValueFormatter,
     ) {
+        let geometric_mean = if self.geometric_mean {
+            crate::analysis::group_geometric_mean(benchmark_group, &self.weights)
+        } else {
+            None
+        };
+        let geometric_mean_change = geometric_mean.as_ref().and_then(|mean| mean.change);
+        let geometric_mean_weighted = matches!(&geometric_mean, Some(mean) if mean.weighted);
+        let geometric_mean = geometric_mean.map(|geometric_mean| {
+            let mut values = [geometric_mean.mean];
+            let unit = formatter.scale_for_machines(&mut values);
+            Value {
+                estimate: values[0],
+                unit,
+            }
+        });
+
         let message = BenchmarkGroupComplete {
             group_name: group_id.to_owned(),
             benchmarks: benchmark_group
@@ -236,8 +554,456 @@ impl Report for JsonMessageReport {
             )
             .display()
             .to_string(),
+            geometric_mean,
+            geometric_mean_change,
+            geometric_mean_weighted,
         };
 
         self.send_message(message);
     }
+
+    fn final_summary(&self, context: &ReportContext, model: &Model) {
+        let summary = model.run_summary();
+        self.send_message(RunSummary {
+            run: summary.run,
+            skipped: summary.skipped,
+            failed: summary.failed,
+            reused: summary.reused,
+        });
+
+        if !self.total_time {
+            return;
+        }
+        if let Some(total) = crate::analysis::suite_total(model) {
+            let formatter = ValueFormatter::wall_time(context.time_unit, context.rounding);
+            let mut values = [total.total];
+            let unit = formatter.scale_for_machines(&mut values);
+            self.send_message(SuiteComplete {
+                total_time: Value {
+                    estimate: values[0],
+                    unit,
+                },
+                change: total.change,
+            });
+        }
+    }
+}
+
+// Writes the value as pretty-printed JSON, creating the parent directory if necessary.
+fn save_json<T: serde::ser::Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {:?}", parent))?;
+    }
+    let file = File::create(path).with_context(|| format!("Failed to create file {:?}", path))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), value)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(())
+}
+
+// The contents of the estimates.json file written to each benchmark's report directory. This is
+// the benchmark-complete message plus the statistics which aren't included in the message.
+#[derive(Serialize)]
+struct BenchmarkEstimates {
+    #[serde(flatten)]
+    benchmark: BenchmarkComplete,
+    std_dev: ConfidenceInterval,
+    sample_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    p_value: Option<f64>,
+}
+
+/// Report which saves the estimates of each benchmark to an estimates.json file next to its HTML
+/// report.
+pub struct JsonFileReport {
+    pub label: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub derived_metrics: Vec<DerivedMetric>,
+}
+
+impl Report for JsonFileReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let estimates = BenchmarkEstimates {
+            benchmark: BenchmarkComplete::new(
+                id,
+                context,
+                measurements,
+                formatter,
+                self.label.clone(),
+                self.metadata.clone(),
+                &self.derived_metrics,
+            ),
+            std_dev: ConfidenceInterval::from_estimate(
+                &measurements.absolute_estimates.std_dev,
+                formatter,
+            ),
+            sample_count: measurements.sample_times().len(),
+            p_value: measurements
+                .comparison
+                .as_ref()
+                .map(|comparison| comparison.p_value),
+        };
+
+        let path = context.report_path(id, "estimates.json");
+        if let Err(e) = save_json(&path, &estimates) {
+            error!(
+                "Failed to save estimates for benchmark {}: {:?}",
+                id.as_title(),
+                e
+            );
+        }
+    }
+}
+
+// The KDE of a sample, evaluated at evenly-spaced points.
+#[derive(Serialize)]
+struct Curve {
+    xs: Vec<f64>,
+    ys: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct DistributionCurve {
+    statistic: String,
+    #[serde(flatten)]
+    curve: Curve,
+    confidence_interval: ConfidenceInterval,
+}
+impl DistributionCurve {
+    // The KDE of the bootstrap distribution of the statistic, swept over the same range as the
+    // distribution plots.
+    fn new(
+        statistic: Statistic,
+        distribution: &Distribution<f64>,
+        estimate: &Estimate,
+        formatter: &ValueFormatter,
+        estimator: kde::Estimator,
+        kde_points: usize,
+    ) -> DistributionCurve {
+        // Scale the estimate and the distribution together so that they're in the same unit.
+        let ci = &estimate.confidence_interval;
+        let mut values = vec![estimate.point_estimate, ci.lower_bound, ci.upper_bound];
+        values.extend(distribution.iter());
+        let unit = formatter.scale_for_machines(&mut values);
+        let (estimate, lower_bound, upper_bound) = (values[0], values[1], values[2]);
+
+        let (xs, ys) = kde::sweep_confidence_interval(
+            estimator,
+            Sample::new(&values[3..]),
+            kde_points,
+            lower_bound,
+            upper_bound,
+        );
+        DistributionCurve {
+            statistic: statistic.to_string(),
+            curve: Curve {
+                xs: xs.into_vec(),
+                ys: ys.into_vec(),
+            },
+            confidence_interval: ConfidenceInterval {
+                estimate,
+                lower_bound,
+                upper_bound,
+                unit,
+            },
+        }
+    }
+}
+
+// The contents of the curves.json file written to each benchmark's report directory.
+#[derive(Serialize)]
+struct BenchmarkCurves {
+    id: String,
+    // The probability density of the sample times, in `unit`.
+    pdf: Curve,
+    unit: String,
+    distributions: Vec<DistributionCurve>,
+}
+
+/// Report which saves the KDE curves behind the PDF and distribution plots of each benchmark to a
+/// curves.json file next to its HTML report, so that they can be plotted with other tools.
+pub struct CurvesFileReport {
+    pub estimator: kde::Estimator,
+    pub kde_points: usize,
+}
+impl Report for CurvesFileReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let mut avg_times: Vec<f64> = measurements.avg_times.to_vec();
+        let unit = formatter.scale_for_machines(&mut avg_times);
+        let (xs, ys) = kde::sweep(
+            self.estimator,
+            Sample::new(&avg_times),
+            self.kde_points,
+            None,
+        );
+
+        let distributions = CURVE_STATISTICS
+            .iter()
+            .filter_map(|&statistic| {
+                let distribution = measurements.distributions.get(statistic)?;
+                let estimate = measurements.absolute_estimates.get(statistic)?;
+                Some(DistributionCurve::new(
+                    statistic,
+                    distribution,
+                    estimate,
+                    formatter,
+                    self.estimator,
+                    self.kde_points,
+                ))
+            })
+            .collect();
+
+        let curves = BenchmarkCurves {
+            id: id.as_title().to_owned(),
+            pdf: Curve {
+                xs: xs.into_vec(),
+                ys: ys.into_vec(),
+            },
+            unit,
+            distributions,
+        };
+
+        let path = context.report_path(id, "curves.json");
+        if let Err(e) = save_json(&path, &curves) {
+            error!(
+                "Failed to save the curves for benchmark {}: {:?}",
+                id.as_title(),
+                e
+            );
+        }
+    }
+}
+
+// The statistics whose distributions are saved to curves.json. The typical value is left out
+// because it's always the same as either the slope or the mean.
+const CURVE_STATISTICS: [Statistic; 5] = [
+    Statistic::Mean,
+    Statistic::Median,
+    Statistic::MedianAbsDev,
+    Statistic::Slope,
+    Statistic::StdDev,
+];
+
+// The bootstrap resamples of one statistic.
+#[derive(Serialize)]
+struct BootstrapDistribution {
+    statistic: String,
+    estimate: f64,
+    samples: Vec<f64>,
+}
+
+// The contents of the bootstrap.json file written to each benchmark's report directory.
+#[derive(Serialize)]
+struct BenchmarkBootstrap {
+    id: String,
+    // The unit of the absolute statistics. The changes are relative, eg. 0.05 for 5% slower.
+    unit: String,
+    distributions: Vec<BootstrapDistribution>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changes: Vec<BootstrapDistribution>,
+}
+
+/// Report which saves the raw bootstrap resamples of each statistic to a bootstrap.json file next
+/// to its HTML report, so that users can compute their own quantiles or tests from them.
+pub struct BootstrapFileReport;
+impl Report for BootstrapFileReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let statistics: Vec<Statistic> = CURVE_STATISTICS
+            .iter()
+            .cloned()
+            .filter(|&statistic| measurements.distributions.get(statistic).is_some())
+            .collect();
+
+        // Scale all of the absolute statistics together so that they share a single unit.
+        let mut values = vec![];
+        for &statistic in &statistics {
+            values.push(
+                measurements
+                    .absolute_estimates
+                    .get(statistic)
+                    .unwrap()
+                    .point_estimate,
+            );
+            values.extend(measurements.distributions.get(statistic).unwrap().iter());
+        }
+        let unit = formatter.scale_for_machines(&mut values);
+
+        let mut remaining = &values[..];
+        let distributions = statistics
+            .iter()
+            .map(|&statistic| {
+                let len = measurements.distributions.get(statistic).unwrap().len();
+                let (scaled, rest) = remaining.split_at(len + 1);
+                remaining = rest;
+                BootstrapDistribution {
+                    statistic: statistic.to_string(),
+                    estimate: scaled[0],
+                    samples: scaled[1..].to_vec(),
+                }
+            })
+            .collect();
+
+        let changes = match &measurements.comparison {
+            Some(comparison) => [Statistic::Mean, Statistic::Median]
+                .iter()
+                .map(|&statistic| BootstrapDistribution {
+                    statistic: statistic.to_string(),
+                    estimate: comparison.relative_estimates.get(statistic).point_estimate,
+                    samples: comparison.relative_distributions.get(statistic).to_vec(),
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        let bootstrap = BenchmarkBootstrap {
+            id: id.as_title().to_owned(),
+            unit,
+            distributions,
+            changes,
+        };
+
+        let path = context.report_path(id, "bootstrap.json");
+        if let Err(e) = save_json(&path, &bootstrap) {
+            error!(
+                "Failed to save the bootstrap distributions for benchmark {}: {:?}",
+                id.as_title(),
+                e
+            );
+        }
+    }
+}
+
+/// Report which pipes the benchmark-complete message of each benchmark to an external command
+/// (see `--post-process`), so that users can act on the results as they complete.
+pub struct PostProcessReport {
+    command: String,
+    required: bool,
+    label: Option<String>,
+    metadata: BTreeMap<String, String>,
+    derived_metrics: Vec<DerivedMetric>,
+    failed: RefCell<Vec<String>>,
+}
+impl PostProcessReport {
+    pub fn new(
+        command: String,
+        required: bool,
+        label: Option<String>,
+        metadata: BTreeMap<String, String>,
+        derived_metrics: Vec<DerivedMetric>,
+    ) -> PostProcessReport {
+        PostProcessReport {
+            command,
+            required,
+            label,
+            metadata,
+            derived_metrics,
+            failed: RefCell::new(vec![]),
+        }
+    }
+
+    fn run(&self, input: &str) -> Result<()> {
+        let mut child = shell_command(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start {:?}", self.command))?;
+
+        // The command might not read its input at all, which isn't an error in itself.
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(e) = writeln!(stdin, "{}", input) {
+                if e.kind() != std::io::ErrorKind::BrokenPipe {
+                    return Err(e).context("Failed to write to the post-processing command");
+                }
+            }
+        }
+
+        let output = child
+            .wait_with_output()
+            .with_context(|| format!("Failed to wait for {:?}", self.command))?;
+        if !output.status.success() {
+            bail!(
+                "{:?} failed with {}: {}",
+                self.command,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns an error listing the benchmarks that the command failed for, if it's required to
+    /// succeed.
+    pub fn check(&self) -> Result<()> {
+        let failed = self.failed.borrow();
+        if !self.required || failed.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "The post-processing command failed for the following benchmarks:\n{}",
+                failed.join("\n")
+            ))
+        }
+    }
+}
+impl Report for PostProcessReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let message = BenchmarkComplete::new(
+            id,
+            context,
+            measurements,
+            formatter,
+            self.label.clone(),
+            self.metadata.clone(),
+            &self.derived_metrics,
+        );
+        let result = format_message(&message).and_then(|input| self.run(&input));
+        if let Err(e) = result {
+            warn!(
+                "Post-processing failed for benchmark {}: {:#}",
+                id.as_title(),
+                e
+            );
+            self.failed.borrow_mut().push(id.as_title().to_owned());
+        }
+    }
+}
+
+/// Run the command through the shell, so that it can contain arguments, pipes and so on.
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
 }
@@ -1,12 +1,131 @@
+mod binary;
+mod bmf;
+mod diff_csv;
 mod json;
+mod rust_source;
+mod samples_csv;
 use crate::config::{MessageFormat, SelfConfig};
 
-use self::json::JsonMessageReport;
+pub use self::binary::load_results;
+use self::binary::BinaryReport;
+use self::bmf::BmfReport;
+use self::diff_csv::DiffCsvReport;
+use self::json::{
+    BootstrapFileReport, CurvesFileReport, JsonFileReport, JsonMessageReport, PostProcessReport,
+};
+use self::rust_source::RustSourceReport;
+use self::samples_csv::SamplesCsvReport;
+use std::cell::{Cell, RefCell};
+use std::io::{stdout, Write};
 
 pub fn create_machine_report(self_config: &SelfConfig) -> Option<JsonMessageReport> {
     if let Some(MessageFormat::Json) = self_config.message_format {
-        Some(JsonMessageReport)
+        Some(JsonMessageReport {
+            geometric_mean: self_config.geometric_mean,
+            weights: self_config.weights.clone(),
+            total_time: self_config.total_time,
+            label: self_config.label.clone(),
+            metadata: self_config.metadata.clone(),
+            derived_metrics: self_config.derived_metrics.clone(),
+            output: RefCell::new(Box::new(stdout())),
+            warmup_ns: Cell::new(None),
+        })
     } else {
         None
     }
 }
+
+/// Creates a report which writes the JSON messages to the given stream rather than stdout, for
+/// the clients of `--serve`.
+pub fn create_stream_report(self_config: &SelfConfig, output: Box<dyn Write>) -> JsonMessageReport {
+    JsonMessageReport {
+        geometric_mean: self_config.geometric_mean,
+        weights: self_config.weights.clone(),
+        total_time: self_config.total_time,
+        label: self_config.label.clone(),
+        metadata: self_config.metadata.clone(),
+        derived_metrics: self_config.derived_metrics.clone(),
+        output: RefCell::new(output),
+        warmup_ns: Cell::new(None),
+    }
+}
+
+pub fn create_bmf_report(self_config: &SelfConfig) -> Option<BmfReport> {
+    if let Some(MessageFormat::Bmf) = self_config.message_format {
+        Some(BmfReport::default())
+    } else {
+        None
+    }
+}
+
+pub fn create_file_report(self_config: &SelfConfig) -> Option<JsonFileReport> {
+    if self_config.json_reports {
+        Some(JsonFileReport {
+            label: self_config.label.clone(),
+            metadata: self_config.metadata.clone(),
+            derived_metrics: self_config.derived_metrics.clone(),
+        })
+    } else {
+        None
+    }
+}
+
+pub fn create_curves_report(self_config: &SelfConfig) -> Option<CurvesFileReport> {
+    if self_config.export_curves {
+        Some(CurvesFileReport {
+            estimator: self_config.plot_options.kde_estimator(),
+            kde_points: self_config.plot_options.kde_points,
+        })
+    } else {
+        None
+    }
+}
+
+pub fn create_bootstrap_report(self_config: &SelfConfig) -> Option<BootstrapFileReport> {
+    if self_config.export_bootstrap {
+        Some(BootstrapFileReport)
+    } else {
+        None
+    }
+}
+
+pub fn create_samples_csv_report(self_config: &SelfConfig) -> Option<SamplesCsvReport> {
+    if self_config.export_samples {
+        Some(SamplesCsvReport)
+    } else {
+        None
+    }
+}
+
+pub fn create_diff_csv_report(self_config: &SelfConfig) -> Option<DiffCsvReport> {
+    self_config
+        .export_diff_csv
+        .as_ref()
+        .map(|path| DiffCsvReport::new(path.clone()))
+}
+
+pub fn create_binary_report(self_config: &SelfConfig) -> Option<BinaryReport> {
+    self_config
+        .export_bin
+        .as_ref()
+        .map(|path| BinaryReport::new(path.clone()))
+}
+
+pub fn create_rust_source_report(self_config: &SelfConfig) -> Option<RustSourceReport> {
+    self_config
+        .export_rust
+        .as_ref()
+        .map(|path| RustSourceReport::new(path.clone()))
+}
+
+pub fn create_post_process_report(self_config: &SelfConfig) -> Option<PostProcessReport> {
+    self_config.post_process.as_ref().map(|command| {
+        PostProcessReport::new(
+            command.clone(),
+            self_config.post_process_required,
+            self_config.label.clone(),
+            self_config.metadata.clone(),
+            self_config.derived_metrics.clone(),
+        )
+    })
+}
@@ -0,0 +1,184 @@
+//! The estimates of each benchmark as Rust source (see `--export-rust`), for crates which check
+//! them into their own tests to assert that their benchmarks keep their shape over time.
+
+use crate::estimate::Estimate;
+use crate::model::Model;
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{Context, Result};
+use linked_hash_map::LinkedHashMap;
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+const PRELUDE: &str = "\
+// Generated by cargo-criterion. Each estimate is a point estimate and the bounds of its confidence
+// interval, in the unit of its benchmark.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub point: f64,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimates {
+    pub unit: &'static str,
+    pub mean: Estimate,
+    pub median: Estimate,
+    pub median_abs_dev: Estimate,
+    pub std_dev: Estimate,
+    pub slope: Option<Estimate>,
+}
+
+/// Returns the estimates of the benchmark with the given ID.
+pub fn estimates(id: &str) -> Option<&'static Estimates> {
+    ESTIMATES
+        .iter()
+        .find(|(benchmark, _)| *benchmark == id)
+        .map(|(_, estimates)| estimates)
+}
+
+";
+
+/// Report which collects the estimates of each benchmark and writes them all to a Rust source
+/// file once the run is over.
+pub struct RustSourceReport {
+    path: PathBuf,
+    // The `ESTIMATES` entry of each benchmark, by ID.
+    entries: RefCell<LinkedHashMap<String, String>>,
+}
+impl RustSourceReport {
+    pub fn new(path: PathBuf) -> RustSourceReport {
+        RustSourceReport {
+            path,
+            entries: RefCell::new(LinkedHashMap::new()),
+        }
+    }
+
+    fn write(&self) -> Result<()> {
+        let file = File::create(&self.path)
+            .with_context(|| format!("Failed to create file {:?}", self.path))?;
+        let mut writer = BufWriter::new(file);
+        write!(writer, "{}", PRELUDE)?;
+        writeln!(writer, "pub static ESTIMATES: &[(&str, Estimates)] = &[")?;
+        for entry in self.entries.borrow().values() {
+            write!(writer, "{}", entry)?;
+        }
+        writeln!(writer, "];")?;
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write file {:?}", self.path))
+    }
+}
+impl Report for RustSourceReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let estimates = &measurements.absolute_estimates;
+        let all_estimates = [
+            Some(&estimates.mean),
+            Some(&estimates.median),
+            Some(&estimates.median_abs_dev),
+            Some(&estimates.std_dev),
+            estimates.slope.as_ref(),
+        ];
+
+        // Scale all of the values together so that they're in the same unit.
+        let mut values: Vec<f64> = all_estimates
+            .iter()
+            .flatten()
+            .flat_map(|estimate| estimate_values(estimate).to_vec())
+            .collect();
+        let unit = formatter.scale_for_machines(&mut values);
+        let literals: Vec<String> = values.chunks(3).map(estimate_literal).collect();
+
+        let mut entry = format!(
+            "    (\n        {:?},\n        Estimates {{\n            unit: {:?},\n",
+            id.as_title(),
+            unit
+        );
+        let names = ["mean", "median", "median_abs_dev", "std_dev"];
+        for (name, literal) in names.iter().zip(&literals) {
+            entry += &format!("            {}: {},\n", name, literal);
+        }
+        match literals.get(names.len()) {
+            Some(slope) => entry += &format!("            slope: Some({}),\n", slope),
+            None => entry += "            slope: None,\n",
+        }
+        entry += "        },\n    ),\n";
+
+        // A benchmark measured again (see --stabilize) keeps its place with its latest result.
+        self.entries
+            .borrow_mut()
+            .insert(id.as_title().to_owned(), entry);
+    }
+
+    fn final_summary(&self, _: &ReportContext, _: &Model) {
+        if let Err(e) = self.write() {
+            error!("Failed to export the estimates as Rust source: {:?}", e);
+        }
+    }
+}
+
+fn estimate_values(estimate: &Estimate) -> [f64; 3] {
+    [
+        estimate.point_estimate,
+        estimate.confidence_interval.lower_bound,
+        estimate.confidence_interval.upper_bound,
+    ]
+}
+
+// Formats the point estimate and bounds as an `Estimate` struct literal.
+fn estimate_literal(values: &[f64]) -> String {
+    format!(
+        "Estimate {{ point: {}, lower_bound: {}, upper_bound: {} }}",
+        float_literal(values[0]),
+        float_literal(values[1]),
+        float_literal(values[2])
+    )
+}
+
+// Formats the value as an f64 literal, which (unlike Display) always has a decimal point or an
+// exponent.
+fn float_literal(value: f64) -> String {
+    if value.is_nan() {
+        "f64::NAN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "f64::INFINITY"
+        } else {
+            "f64::NEG_INFINITY"
+        }
+        .to_owned()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_float_literal() {
+        assert_eq!("100.0", float_literal(100.0));
+        assert_eq!("-0.25", float_literal(-0.25));
+        assert_eq!("f64::NAN", float_literal(f64::NAN));
+        assert_eq!("f64::NEG_INFINITY", float_literal(f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn test_estimate_literal() {
+        assert_eq!(
+            "Estimate { point: 2.0, lower_bound: 1.5, upper_bound: 2.5 }",
+            estimate_literal(&[2.0, 1.5, 2.5])
+        );
+    }
+}
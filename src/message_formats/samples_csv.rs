@@ -0,0 +1,94 @@
+//! The raw samples of each benchmark as a CSV table in its report directory (see
+//! `--export-samples`), for correlating them with other records of the system, eg. by their
+//! timestamps.
+
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const HEADER: &str = "sample,iterations,time,unit,timestamp";
+
+/// Report which writes the samples of each benchmark to a samples.csv file next to its HTML
+/// report.
+pub struct SamplesCsvReport;
+impl SamplesCsvReport {
+    fn write(
+        path: &Path,
+        iterations: &[f64],
+        times: &[f64],
+        unit: &str,
+        timestamps: Option<&[f64]>,
+    ) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {:?}", parent))?;
+        }
+        let file =
+            File::create(path).with_context(|| format!("Failed to create file {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", HEADER)?;
+        for (i, (iterations, time)) in iterations.iter().zip(times).enumerate() {
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                i + 1,
+                iterations,
+                time,
+                unit,
+                timestamp_field(timestamps, i)
+            )?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write file {:?}", path))
+    }
+}
+impl Report for SamplesCsvReport {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let mut times = measurements.sample_times().to_vec();
+        let unit = formatter.scale_for_machines(&mut times);
+        let path = context.report_path(id, "samples.csv");
+        if let Err(e) = Self::write(
+            &path,
+            measurements.iter_counts(),
+            &times,
+            &unit,
+            measurements.timestamps,
+        ) {
+            error!(
+                "Failed to save the samples of benchmark {}: {:?}",
+                id.as_title(),
+                e
+            );
+        }
+    }
+}
+
+// The timestamp of the i'th sample, or nothing if the benchmark didn't record them.
+fn timestamp_field(timestamps: Option<&[f64]>, i: usize) -> String {
+    timestamps
+        .and_then(|timestamps| timestamps.get(i))
+        .map(|timestamp| timestamp.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_field() {
+        assert_eq!("", timestamp_field(None, 0));
+        assert_eq!("1700000000.5", timestamp_field(Some(&[1700000000.5]), 0));
+        assert_eq!("", timestamp_field(Some(&[1700000000.5]), 1));
+    }
+}
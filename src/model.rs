@@ -1,20 +1,30 @@
+use crate::analysis::BenchmarkConfig;
+use crate::baseline_store::BaselineStore;
+use crate::config::BenchmarkOrder;
 use crate::connection::Throughput;
-use crate::estimate::{ChangeEstimates, Estimates};
-use crate::report::{BenchmarkId, ComparisonData, MeasurementData};
-use anyhow::{Context, Result};
+use crate::encryption::HistoryCipher;
+use crate::estimate::{
+    ChangeEstimates, ConfidenceInterval, Estimate, Estimates, LatencyPercentiles,
+};
+use crate::report::{BenchmarkId, ComparisonData, MeasurementData, OverlayBaseline};
+use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
 use linked_hash_map::LinkedHashMap;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::ffi::OsStr;
-use std::fs::File;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+/// The name of the baseline (timeline) that the measurements of every run are stored in.
+pub const DEFAULT_BASELINE: &str = "main";
+
 #[derive(Debug)]
 pub struct Benchmark {
     pub latest_stats: SavedStatistics,
     pub previous_stats: Option<SavedStatistics>,
     pub target: Option<String>,
+    // Was this benchmark measured in this run, rather than only loaded from disk?
+    pub measured: bool,
 }
 impl Benchmark {
     fn new(stats: SavedStatistics) -> Self {
@@ -22,12 +32,20 @@ impl Benchmark {
             latest_stats: stats,
             previous_stats: None,
             target: None,
+            measured: false,
         }
     }
 
     fn add_stats(&mut self, stats: SavedStatistics) {
+        // If the benchmark was already measured in this run (see `--stabilize`), the new
+        // measurement supersedes that one and the previous run stays the previous run.
+        if self.measured {
+            self.latest_stats = stats;
+            return;
+        }
         let previous_stats = std::mem::replace(&mut self.latest_stats, stats);
         self.previous_stats = Some(previous_stats);
+        self.measured = true;
     }
 }
 
@@ -44,6 +62,16 @@ impl Default for BenchmarkGroup {
         }
     }
 }
+impl BenchmarkGroup {
+    /// Re-order the benchmarks in this group so that they're sorted by title.
+    fn sort_by_title(&mut self) {
+        let mut benchmarks: Vec<_> = std::mem::replace(&mut self.benchmarks, LinkedHashMap::new())
+            .into_iter()
+            .collect();
+        benchmarks.sort_by(|(id1, _), (id2, _)| id1.as_title().cmp(id2.as_title()));
+        self.benchmarks.extend(benchmarks);
+    }
+}
 
 /// The Model struct stores everything that we keep in-memory about the benchmarks and their
 /// performance. It's loaded from disk at the beginning of a run and updated as benchmarks
@@ -60,16 +88,50 @@ pub struct Model {
 
     history_id: Option<String>,
     history_description: Option<String>,
+    label: Option<String>,
+    metadata: BTreeMap<String, String>,
+
+    // The order in which benchmarks and groups should be reported.
+    order: BenchmarkOrder,
+    // The number of stored runs which are averaged to form the baseline for comparisons.
+    baseline_window: usize,
+    // Reads and writes the stored files, encrypting them if requested.
+    cipher: HistoryCipher,
+    // The titles of the benchmarks which were skipped by the filter or failed in this run.
+    skipped: Vec<String>,
+    failed: Vec<String>,
+    // The titles of the benchmarks whose stored results are reused instead of measuring them.
+    reused: Vec<String>,
+    // Are the benchmarks being measured again to stabilize them?
+    retrying: bool,
+    // If set, the samples of each benchmark are kept and pooled across the attempts.
+    keep_samples: bool,
+    pooled_samples: HashMap<BenchmarkId, (Vec<f64>, Vec<f64>)>,
+    // The measurement file written for each benchmark in this run, so that it can be replaced if
+    // the benchmark is measured again.
+    measurement_files: HashMap<BenchmarkId, PathBuf>,
+    // If set, the baselines are loaded from this store instead of the data directory.
+    baseline_store: Option<Box<dyn BaselineStore>>,
+    // The results of this run which haven't been saved to the baseline store yet.
+    unsaved_runs: LinkedHashMap<BenchmarkId, SavedStatistics>,
+    // The named baselines which are drawn over the comparison plots.
+    overlay_baselines: Vec<String>,
 }
 impl Model {
     /// Load the model from disk. The output directory is scanned for benchmark files. Any files
     /// found are loaded into the model so that we can include them in the reports even if this
     /// run doesn't execute that particular benchmark.
+    #[allow(clippy::too_many_arguments)]
     pub fn load(
         criterion_home: PathBuf,
         timeline: PathBuf,
         history_id: Option<String>,
         history_description: Option<String>,
+        label: Option<String>,
+        metadata: BTreeMap<String, String>,
+        order: BenchmarkOrder,
+        baseline_window: usize,
+        cipher: HistoryCipher,
     ) -> Model {
         let mut model = Model {
             data_directory: path!(criterion_home, "data", timeline),
@@ -78,6 +140,21 @@ impl Model {
             groups: LinkedHashMap::new(),
             history_id,
             history_description,
+            label,
+            metadata,
+            order,
+            baseline_window,
+            cipher,
+            skipped: vec![],
+            failed: vec![],
+            reused: vec![],
+            retrying: false,
+            keep_samples: false,
+            pooled_samples: HashMap::new(),
+            measurement_files: HashMap::new(),
+            baseline_store: None,
+            unsaved_runs: LinkedHashMap::new(),
+            overlay_baselines: vec![],
         };
 
         for entry in WalkDir::new(&model.data_directory)
@@ -91,26 +168,31 @@ impl Model {
             }
         }
 
+        if model.order == BenchmarkOrder::Name {
+            model.sort_groups_by_name();
+            for (_, group) in model.groups.iter_mut() {
+                group.sort_by_title();
+            }
+        }
+
         model
     }
 
-    fn load_stored_benchmark(&mut self, benchmark_path: &Path) -> Result<()> {
-        if !benchmark_path.is_file() {
-            return Ok(());
-        }
-        let mut benchmark_file = File::open(&benchmark_path)
-            .with_context(|| format!("Failed to open benchmark file {:?}", benchmark_path))?;
-        let benchmark_record: BenchmarkRecord = serde_cbor::from_reader(&mut benchmark_file)
-            .with_context(|| format!("Failed to read benchmark file {:?}", benchmark_path))?;
+    /// Re-order the benchmark groups so that they're sorted by group ID.
+    fn sort_groups_by_name(&mut self) {
+        let mut groups: Vec<_> = std::mem::replace(&mut self.groups, LinkedHashMap::new())
+            .into_iter()
+            .collect();
+        groups.sort_by(|(id1, _), (id2, _)| id1.cmp(id2));
+        self.groups.extend(groups);
+    }
 
-        let measurement_path = benchmark_path.with_file_name(benchmark_record.latest_record);
-        if !measurement_path.is_file() {
-            return Ok(());
-        }
-        let mut measurement_file = File::open(&measurement_path)
-            .with_context(|| format!("Failed to open measurement file {:?}", measurement_path))?;
-        let saved_stats: SavedStatistics = serde_cbor::from_reader(&mut measurement_file)
-            .with_context(|| format!("Failed to read measurement file {:?}", measurement_path))?;
+    fn load_stored_benchmark(&mut self, benchmark_path: &Path) -> Result<()> {
+        let (benchmark_record, saved_stats) =
+            match load_latest_record(benchmark_path, &self.cipher)? {
+                Some(loaded) => loaded,
+                None => return Ok(()),
+            };
 
         self.groups
             .entry(benchmark_record.id.group_id.clone())
@@ -147,6 +229,7 @@ impl Model {
 
     pub fn benchmark_complete(
         &mut self,
+        target: &str,
         id: &BenchmarkId,
         analysis_results: &MeasurementData,
     ) -> Result<()> {
@@ -160,30 +243,35 @@ impl Model {
             .to_string();
 
         let saved_stats = SavedStatistics {
-            datetime: chrono::Utc::now(),
-            iterations: analysis_results.iter_counts().to_vec(),
-            values: analysis_results.sample_times().to_vec(),
-            avg_values: analysis_results.avg_times.to_vec(),
-            estimates: analysis_results.absolute_estimates.clone(),
-            throughput: analysis_results.throughput.clone(),
-            changes: analysis_results
-                .comparison
-                .as_ref()
-                .map(|c| c.relative_estimates.clone()),
-            change_direction: analysis_results
-                .comparison
-                .as_ref()
-                .map(get_change_direction),
             history_id: self.history_id.clone(),
             history_description: self.history_description.clone(),
+            label: self.label.clone(),
+            metadata: self.metadata.clone(),
+            target: Some(target.to_owned()),
+            ..SavedStatistics::new(analysis_results)
         };
 
         let measurement_path = dir.join(&measurement_name);
-        let mut measurement_file = File::create(&measurement_path)
-            .with_context(|| format!("Failed to create measurement file {:?}", measurement_path))?;
-        serde_cbor::to_writer(&mut measurement_file, &saved_stats).with_context(|| {
-            format!("Failed to save measurements to file {:?}", measurement_path)
-        })?;
+        if let Some(superseded) = self.measurement_files.get(id) {
+            if let Err(e) = std::fs::remove_file(superseded) {
+                warn!(
+                    "Failed to remove superseded measurement file {:?}: {}",
+                    superseded, e
+                );
+            }
+        }
+        self.cipher
+            .write(&measurement_path, &saved_stats)
+            .with_context(|| {
+                format!("Failed to save measurements to file {:?}", measurement_path)
+            })?;
+
+        self.measurement_files
+            .insert(id.clone(), measurement_path.clone());
+        if self.baseline_store.is_some() {
+            // A benchmark measured again replaces its earlier results here too.
+            self.unsaved_runs.insert(id.clone(), saved_stats.clone());
+        }
 
         let record = BenchmarkRecord {
             id: id.into(),
@@ -191,9 +279,8 @@ impl Model {
         };
 
         let benchmark_path = dir.join("benchmark.cbor");
-        let mut benchmark_file = File::create(&benchmark_path)
-            .with_context(|| format!("Failed to create benchmark file {:?}", benchmark_path))?;
-        serde_cbor::to_writer(&mut benchmark_file, &record)
+        self.cipher
+            .write(&benchmark_path, &record)
             .with_context(|| format!("Failed to save benchmark file {:?}", benchmark_path))?;
 
         let benchmark_entry = self
@@ -205,7 +292,7 @@ impl Model {
 
         match benchmark_entry {
             vacant @ linked_hash_map::Entry::Vacant(_) => {
-                vacant.or_insert(Benchmark::new(saved_stats));
+                vacant.or_insert(Benchmark::new(saved_stats)).measured = true;
             }
             linked_hash_map::Entry::Occupied(mut occupied) => {
                 occupied.get_mut().add_stats(saved_stats)
@@ -221,6 +308,70 @@ impl Model {
             .map(|b| &b.latest_stats)
     }
 
+    /// Returns the statistics that the next measurement of the given benchmark should be compared
+    /// against. Normally this is just the last sample, but if the baseline window is larger than
+    /// one, the samples of the last N stored runs are loaded and pooled together.
+    pub fn get_baseline(&self, id: &BenchmarkId) -> Option<SavedStatistics> {
+        if let Some(store) = &self.baseline_store {
+            return match store.load_baseline(id) {
+                Ok(baseline) => baseline,
+                Err(e) => {
+                    error!(
+                        "Failed to load the baseline from the baseline store: {:?}",
+                        e
+                    );
+                    None
+                }
+            };
+        }
+
+        // A benchmark measured again to stabilize it is still compared against the previous run.
+        let benchmark = self
+            .groups
+            .get(&id.group_id)
+            .and_then(|g| g.benchmarks.get(id));
+        let measured = matches!(benchmark, Some(b) if b.measured);
+        let last_sample = || match benchmark {
+            Some(b) if b.measured => b.previous_stats.clone(),
+            _ => self.get_last_sample(id).cloned(),
+        };
+        if self.baseline_window <= 1 {
+            return last_sample();
+        }
+
+        let mut history = match self.load_history(id) {
+            Ok(history) => history,
+            Err(e) => {
+                error!("Failed to load historical data: {:?}", e);
+                return last_sample();
+            }
+        };
+        // The stored history includes the measurement from earlier in this run.
+        if measured {
+            history.pop();
+        }
+        let start = history.len().saturating_sub(self.baseline_window);
+        aggregate_statistics(&history[start..])
+    }
+
+    /// Returns the statistics that the latest stored measurement of the given benchmark was
+    /// compared against, or None if it wasn't compared against anything. This is used to repeat
+    /// the comparison when the plots of the measurement are drawn later (see --lazy-plots).
+    pub fn get_latest_baseline(&self, id: &BenchmarkId) -> Option<SavedStatistics> {
+        self.get_last_sample(id)?.changes.as_ref()?;
+
+        let mut history = match self.load_history(id) {
+            Ok(history) => history,
+            Err(e) => {
+                error!("Failed to load historical data: {:?}", e);
+                return None;
+            }
+        };
+        history.pop();
+        let start = history.len().saturating_sub(self.baseline_window);
+        aggregate_statistics(&history[start..])
+    }
+
     pub fn check_benchmark_group(&self, current_target: &str, group: &str) {
         if let Some(benchmark_group) = self.groups.get(group) {
             if let Some(target) = &benchmark_group.target {
@@ -236,19 +387,189 @@ impl Model {
         let mut group = self.groups.remove(group_name).unwrap_or_default();
         group.target = Some(target.to_owned());
         self.groups.insert(group_name.to_owned(), group);
+
+        if self.order == BenchmarkOrder::Name {
+            self.sort_groups_by_name();
+            self.groups.get_mut(group_name).unwrap().sort_by_title();
+        }
         self.groups.get(group_name).unwrap()
     }
 
+    /// Records that the benchmark target skipped the given benchmark because it didn't match the
+    /// filter.
+    pub fn benchmark_skipped(&mut self, id: &BenchmarkId) {
+        // When retrying, everything but the benchmarks being stabilized is skipped.
+        if !self.retrying {
+            self.skipped.push(id.as_title().to_owned());
+        }
+    }
+
+    /// Prepares to measure some of the benchmarks again to stabilize them. Their IDs are allowed
+    /// to be seen again without being treated as duplicates, and their new samples are pooled with
+    /// those from the earlier attempts.
+    pub fn start_retry(&mut self) {
+        self.retrying = true;
+        self.all_titles.clear();
+        self.all_directories.clear();
+        for (_, group) in self.groups.iter_mut() {
+            for (_, benchmark) in group.benchmarks.iter_mut() {
+                benchmark.target = None;
+            }
+        }
+    }
+
+    /// Loads the baselines from the given store instead of the data directory. The results of the
+    /// run are saved to it by `save_to_baseline_store`.
+    pub fn set_baseline_store(&mut self, store: Box<dyn BaselineStore>) {
+        self.baseline_store = Some(store);
+    }
+
+    /// Sets the named baselines which are drawn over the comparison plots (see
+    /// `get_overlay_baselines`). Baselines which don't exist are warned about and left out.
+    pub fn set_overlay_baselines(&mut self, names: &[String]) {
+        self.overlay_baselines = names
+            .iter()
+            .filter(|name| {
+                let exists = self.data_directory.with_file_name(name).is_dir();
+                if !exists {
+                    warn!(
+                        "No baseline named '{}' was found, so it won't be drawn",
+                        name
+                    );
+                }
+                exists
+            })
+            .cloned()
+            .collect();
+    }
+
+    /// Loads the latest measurements of the given benchmark in each of the overlaid baselines.
+    /// Baselines which have no measurements of the benchmark are left out.
+    pub fn get_overlay_baselines(&self, id: &BenchmarkId) -> Vec<OverlayBaseline> {
+        self.overlay_baselines
+            .iter()
+            .filter_map(|name| {
+                let benchmark_path = path!(
+                    self.data_directory.with_file_name(name),
+                    id.as_directory_name(),
+                    "benchmark.cbor"
+                );
+                match load_latest_record(&benchmark_path, &self.cipher) {
+                    Ok(loaded) => loaded.map(|(_, stats)| OverlayBaseline {
+                        name: name.clone(),
+                        avg_times: stats.avg_values,
+                    }),
+                    Err(e) => {
+                        error!("Failed to load baseline '{}': {:?}", name, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Saves the results of this run to the baseline store, if there is one. They're only saved
+    /// once the run is over, so that benchmarks measured again (see `--stabilize`) are still
+    /// compared against the previous run rather than an earlier attempt.
+    pub fn save_to_baseline_store(&mut self) -> Result<()> {
+        let store = match &self.baseline_store {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+        let unsaved_runs = std::mem::replace(&mut self.unsaved_runs, LinkedHashMap::new());
+        for (id, stats) in unsaved_runs {
+            store.store_run(&id, &stats)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the samples of every benchmark, so that they can be pooled with those of later
+    /// attempts.
+    pub fn keep_samples(&mut self) {
+        self.keep_samples = true;
+    }
+
+    /// Adds the samples to those collected for the benchmark in earlier attempts and returns all
+    /// of them. Unless `keep_samples` was called, the samples are returned unchanged.
+    pub fn pool_samples(
+        &mut self,
+        id: &BenchmarkId,
+        iters: Vec<f64>,
+        times: Vec<f64>,
+    ) -> (Vec<f64>, Vec<f64>) {
+        if !self.keep_samples {
+            return (iters, times);
+        }
+        let pooled = self
+            .pooled_samples
+            .entry(id.clone())
+            .or_insert_with(|| (vec![], vec![]));
+        pooled.0.extend(iters);
+        pooled.1.extend(times);
+        pooled.clone()
+    }
+
+    /// Reuses the stored results of the benchmarks last measured by the given targets, which
+    /// aren't run (see `--reuse-unchanged`). Their results are reported as they are.
+    pub fn reuse_results(&mut self, targets: &[String]) {
+        self.reused = self
+            .groups
+            .values()
+            .flat_map(|group| group.benchmarks.iter())
+            .filter(|(_, benchmark)| match &benchmark.latest_stats.target {
+                Some(target) => targets.contains(target),
+                None => false,
+            })
+            .map(|(id, _)| id.as_title().to_owned())
+            .collect();
+    }
+
+    /// Returns the total time of the stored samples of the benchmarks last measured by each
+    /// target, for `--order-by-duration`. Results which don't record their target are left out.
+    pub fn target_durations(&self) -> HashMap<String, f64> {
+        let mut durations = HashMap::new();
+        let benchmarks = self
+            .groups
+            .values()
+            .flat_map(|group| group.benchmarks.values());
+        for benchmark in benchmarks {
+            let stats = &benchmark.latest_stats;
+            if let Some(target) = &stats.target {
+                *durations.entry(target.clone()).or_insert(0.0) += stats.values.iter().sum::<f64>();
+            }
+        }
+        durations
+    }
+
+    /// Records that the given benchmark failed, eg. because it panicked.
+    pub fn benchmark_failed(&mut self, id: &BenchmarkId) {
+        self.failed.push(id.as_title().to_owned());
+    }
+
+    /// Lists the benchmarks which were run, skipped and failed in this run.
+    pub fn run_summary(&self) -> RunSummary {
+        RunSummary {
+            run: self
+                .groups
+                .values()
+                .flat_map(|group| group.benchmarks.iter())
+                .filter(|(_, benchmark)| benchmark.measured)
+                .map(|(id, _)| id.as_title().to_owned())
+                .collect(),
+            skipped: self.skipped.clone(),
+            failed: self.failed.clone(),
+            reused: self.reused.clone(),
+        }
+    }
+
     pub fn load_history(&self, id: &BenchmarkId) -> Result<Vec<SavedStatistics>> {
         let dir = path!(&self.data_directory, id.as_directory_name());
 
-        fn load_from(measurement_path: &Path) -> Result<SavedStatistics> {
-            let mut measurement_file = File::open(&measurement_path).with_context(|| {
-                format!("Failed to open measurement file {:?}", measurement_path)
-            })?;
-            serde_cbor::from_reader(&mut measurement_file)
+        let load_from = |measurement_path: &Path| -> Result<SavedStatistics> {
+            self.cipher
+                .read(measurement_path)
                 .with_context(|| format!("Failed to read measurement file {:?}", measurement_path))
-        }
+        };
 
         let mut stats = Vec::new();
         for entry in WalkDir::new(dir)
@@ -276,6 +597,240 @@ impl Model {
     }
 }
 
+/// Load the benchmark file at the given path along with the latest measurements it refers to.
+/// Returns None if either file doesn't exist.
+fn load_latest_record(
+    benchmark_path: &Path,
+    cipher: &HistoryCipher,
+) -> Result<Option<(BenchmarkRecord, SavedStatistics)>> {
+    if !benchmark_path.is_file() {
+        return Ok(None);
+    }
+    let benchmark_record: BenchmarkRecord = cipher
+        .read(benchmark_path)
+        .with_context(|| format!("Failed to read benchmark file {:?}", benchmark_path))?;
+
+    let measurement_path = benchmark_path.with_file_name(&benchmark_record.latest_record);
+    if !measurement_path.is_file() {
+        return Ok(None);
+    }
+    let saved_stats: SavedStatistics = cipher
+        .read(&measurement_path)
+        .with_context(|| format!("Failed to read measurement file {:?}", measurement_path))?;
+
+    Ok(Some((benchmark_record, saved_stats)))
+}
+
+/// The titles of the benchmarks which were run, skipped by the filter and failed in this run.
+#[derive(Debug)]
+pub struct RunSummary {
+    pub run: Vec<String>,
+    pub skipped: Vec<String>,
+    pub failed: Vec<String>,
+    pub reused: Vec<String>,
+}
+
+/// Summary of one of the baselines in the data directory.
+#[derive(Debug)]
+pub struct BaselineInfo {
+    pub name: String,
+    // The number of benchmarks with stored measurements.
+    pub benchmark_count: usize,
+    // The time and label of the most recent measurements in this baseline.
+    pub latest: Option<DateTime<Utc>>,
+    pub label: Option<String>,
+}
+
+/// Scan the data directory for the stored baselines, sorted by name.
+pub fn list_baselines(criterion_home: &Path, cipher: &HistoryCipher) -> Result<Vec<BaselineInfo>> {
+    let data_directory = path!(criterion_home, "data");
+    if !data_directory.is_dir() {
+        return Ok(vec![]);
+    }
+
+    let mut baselines = vec![];
+    let entries = std::fs::read_dir(&data_directory)
+        .with_context(|| format!("Failed to read data directory {:?}", data_directory))?;
+    for entry in entries
+        // Ignore errors.
+        .filter_map(::std::result::Result::ok)
+        .filter(|entry| entry.path().is_dir())
+    {
+        let mut info = BaselineInfo {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            benchmark_count: 0,
+            latest: None,
+            label: None,
+        };
+
+        for benchmark_entry in WalkDir::new(entry.path())
+            .into_iter()
+            // Ignore errors.
+            .filter_map(::std::result::Result::ok)
+            .filter(|entry| entry.file_name() == OsStr::new("benchmark.cbor"))
+        {
+            match load_latest_record(benchmark_entry.path(), cipher) {
+                Ok(Some((_, stats))) => {
+                    info.benchmark_count += 1;
+                    if Some(stats.datetime) > info.latest {
+                        info.latest = Some(stats.datetime);
+                        info.label = stats.label;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!("Encountered error while loading stored data: {}", e),
+            }
+        }
+        baselines.push(info);
+    }
+
+    baselines.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(baselines)
+}
+
+/// Returns true if the given name can be a baseline, ie. it's the name of a single directory in the
+/// data directory.
+pub fn is_valid_baseline_name(name: &str) -> bool {
+    !(name.is_empty() || name == "." || name == ".." || name.contains(&['/', '\\'][..]))
+}
+
+/// Delete the stored measurements of the given baseline. The default baseline is only deleted if
+/// `force` is set, since it holds the history of every regular run.
+pub fn remove_baseline(criterion_home: &Path, name: &str, force: bool) -> Result<()> {
+    if !is_valid_baseline_name(name) {
+        bail!("Invalid baseline name {:?}", name);
+    }
+    if name == DEFAULT_BASELINE && !force {
+        bail!(
+            "Refusing to delete the '{}' baseline, which is used by every run. Use --force to delete it anyway.",
+            name
+        );
+    }
+
+    let directory = path!(criterion_home, "data", name);
+    if !directory.is_dir() {
+        bail!(
+            "No baseline named '{}' was found in {:?}",
+            name,
+            criterion_home
+        );
+    }
+    std::fs::remove_dir_all(&directory)
+        .with_context(|| format!("Failed to delete baseline directory {:?}", directory))
+}
+
+/// The amount of stored data found (or deleted) by `clean`.
+pub struct StoredDataSize {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// The directories under the criterion home which hold the stored measurements and the reports
+/// generated from them.
+fn stored_data_directories(criterion_home: &Path) -> Vec<PathBuf> {
+    vec![
+        path!(criterion_home, "data"),
+        path!(criterion_home, "reports"),
+    ]
+}
+
+/// Returns the amount of stored measurements and reports which `clean` would delete.
+pub fn stored_data_size(criterion_home: &Path) -> StoredDataSize {
+    let mut size = StoredDataSize { files: 0, bytes: 0 };
+    for directory in stored_data_directories(criterion_home) {
+        for entry in WalkDir::new(directory)
+            .into_iter()
+            // Ignore errors.
+            .filter_map(::std::result::Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+        {
+            size.files += 1;
+            size.bytes += entry.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        }
+    }
+    size
+}
+
+/// Delete all of the stored measurements (every baseline and its history) and the reports, so
+/// that the next run starts from scratch.
+pub fn clean(criterion_home: &Path) -> Result<()> {
+    for directory in stored_data_directories(criterion_home) {
+        if directory.is_dir() {
+            std::fs::remove_dir_all(&directory)
+                .with_context(|| format!("Failed to delete directory {:?}", directory))?;
+        }
+    }
+    Ok(())
+}
+
+/// A stored file which couldn't be loaded, and why.
+#[derive(Debug)]
+pub struct CorruptFile {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// The results of `verify`.
+#[derive(Debug)]
+pub struct VerifyResults {
+    // The number of stored files which were checked.
+    pub checked: usize,
+    pub corrupt: Vec<CorruptFile>,
+}
+
+/// Attempts to load every benchmark and measurement file in the data directory (in every
+/// baseline), and returns those which can't be loaded or whose samples couldn't be analyzed. No
+/// analysis is done and nothing is modified.
+pub fn verify(criterion_home: &Path, cipher: &HistoryCipher) -> VerifyResults {
+    let mut results = VerifyResults {
+        checked: 0,
+        corrupt: vec![],
+    };
+    let mut entries: Vec<_> = WalkDir::new(path!(criterion_home, "data"))
+        .into_iter()
+        // Ignore errors.
+        .filter_map(::std::result::Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .collect();
+    entries.sort_by(|a, b| a.path().cmp(b.path()));
+
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy();
+        let result = if name == "benchmark.cbor" {
+            verify_benchmark_record(path, cipher)
+        } else if name.starts_with("measurement_") && name.ends_with(".cbor") {
+            cipher
+                .read::<SavedStatistics>(path)
+                .and_then(|stats| stats.check_samples())
+        } else {
+            continue;
+        };
+        results.checked += 1;
+        if let Err(error) = result {
+            results.corrupt.push(CorruptFile {
+                path: path.to_owned(),
+                error,
+            });
+        }
+    }
+    results
+}
+
+// Checks that the benchmark file can be loaded and that the latest measurement file it refers to
+// exists. That file is checked separately.
+fn verify_benchmark_record(benchmark_path: &Path, cipher: &HistoryCipher) -> Result<()> {
+    let record: BenchmarkRecord = cipher.read(benchmark_path)?;
+    let measurement_path = benchmark_path.with_file_name(&record.latest_record);
+    if !measurement_path.is_file() {
+        bail!(
+            "The latest measurement file {:?} doesn't exist",
+            record.latest_record
+        );
+    }
+    Ok(())
+}
+
 // These structs are saved to disk and may be read by future versions of cargo-criterion, so
 // backwards compatibility is important.
 
@@ -323,7 +878,7 @@ struct BenchmarkRecord {
     latest_record: PathBuf,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
 pub enum ChangeDirection {
     NoChange,
     NotSignificant,
@@ -332,7 +887,7 @@ pub enum ChangeDirection {
 }
 
 fn get_change_direction(comp: &ComparisonData) -> ChangeDirection {
-    if comp.p_value < comp.significance_threshold {
+    if comp.p_value >= comp.significance_threshold {
         return ChangeDirection::NoChange;
     }
 
@@ -350,6 +905,79 @@ fn get_change_direction(comp: &ComparisonData) -> ChangeDirection {
     }
 }
 
+// Combines several stored runs into a single baseline. The samples are concatenated, so the
+// comparison sees all of them, and the estimates are averaged. Returns None if there are no runs.
+fn aggregate_statistics(runs: &[SavedStatistics]) -> Option<SavedStatistics> {
+    let latest = runs.last()?;
+    if runs.len() == 1 {
+        return Some(latest.clone());
+    }
+
+    fn average(estimates: &[&Estimate]) -> Estimate {
+        let n = estimates.len() as f64;
+        let avg = |f: fn(&Estimate) -> f64| estimates.iter().map(|e| f(e)).sum::<f64>() / n;
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: estimates[0].confidence_interval.confidence_level,
+                lower_bound: avg(|e| e.confidence_interval.lower_bound),
+                upper_bound: avg(|e| e.confidence_interval.upper_bound),
+            },
+            point_estimate: avg(|e| e.point_estimate),
+            standard_error: avg(|e| e.standard_error),
+        }
+    }
+    let average_of = |f: fn(&Estimates) -> &Estimate| {
+        average(&runs.iter().map(|run| f(&run.estimates)).collect::<Vec<_>>())
+    };
+
+    // Only average the slopes if every run has one; otherwise the average would be misleading.
+    let slopes: Option<Vec<&Estimate>> = runs
+        .iter()
+        .map(|run| run.estimates.slope.as_ref())
+        .collect();
+
+    Some(SavedStatistics {
+        datetime: latest.datetime,
+        iterations: runs
+            .iter()
+            .flat_map(|run| run.iterations.iter().copied())
+            .collect(),
+        values: runs
+            .iter()
+            .flat_map(|run| run.values.iter().copied())
+            .collect(),
+        avg_values: runs
+            .iter()
+            .flat_map(|run| run.avg_values.iter().copied())
+            .collect(),
+        estimates: Estimates {
+            mean: average_of(|e| &e.mean),
+            median: average_of(|e| &e.median),
+            median_abs_dev: average_of(|e| &e.median_abs_dev),
+            slope: slopes.map(|slopes| average(&slopes)),
+            std_dev: average_of(|e| &e.std_dev),
+        },
+        throughput: latest.throughput.clone(),
+        changes: latest.changes.clone(),
+        change_direction: latest.change_direction,
+        history_id: latest.history_id.clone(),
+        history_description: latest.history_description.clone(),
+        label: latest.label.clone(),
+        metadata: latest.metadata.clone(),
+        target: latest.target.clone(),
+        multimodal: latest.multimodal,
+        latency_percentiles: latest.latency_percentiles.clone(),
+        overhead: latest.overhead,
+        // The timestamps are only kept if every run recorded them, like the samples they go with.
+        timestamps: runs
+            .iter()
+            .map(|run| run.timestamps.as_ref())
+            .collect::<Option<Vec<_>>>()
+            .map(|timestamps| timestamps.into_iter().flatten().copied().collect()),
+        config: latest.config.clone(),
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SavedStatistics {
     // The timestamp of when these measurements were saved.
@@ -376,4 +1004,285 @@ pub struct SavedStatistics {
     // An optional user-provided description. This might be a version control commit message or
     // something custom.
     pub history_description: Option<String>,
+    // An optional user-provided free-form label, eg. "before SIMD rewrite".
+    #[serde(default)]
+    pub label: Option<String>,
+    // The values of the environment variables named by --metadata-env when this run was made.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    // The name of the benchmark target which measured these values. Older measurements don't
+    // record it.
+    #[serde(default)]
+    pub target: Option<String>,
+    // Did the distribution of the average values appear to have more than one mode?
+    #[serde(default)]
+    pub multimodal: bool,
+    // The percentiles of the per-element latencies, if the benchmark reported them.
+    #[serde(default)]
+    pub latency_percentiles: Option<LatencyPercentiles>,
+    // The estimated overhead of the measurement loop per iteration, if the benchmark reported it.
+    #[serde(default)]
+    pub overhead: Option<f64>,
+    // The wall-clock time at which each sample was taken, in seconds since the Unix epoch, if the
+    // benchmark recorded them.
+    #[serde(default)]
+    pub timestamps: Option<Vec<f64>>,
+    // The settings of the analysis which produced these estimates, so that it can be repeated to
+    // draw the plots later (see --lazy-plots). Older measurements don't record them.
+    #[serde(default)]
+    pub config: Option<BenchmarkConfig>,
+}
+impl SavedStatistics {
+    /// The statistics of the given measurement, without any of the details of the run it was made
+    /// in (its history ID, label, metadata and target).
+    pub fn new(analysis_results: &MeasurementData) -> SavedStatistics {
+        SavedStatistics {
+            datetime: chrono::Utc::now(),
+            iterations: analysis_results.iter_counts().to_vec(),
+            values: analysis_results.sample_times().to_vec(),
+            avg_values: analysis_results.avg_times.to_vec(),
+            estimates: analysis_results.absolute_estimates.clone(),
+            throughput: analysis_results.throughput.clone(),
+            changes: analysis_results
+                .comparison
+                .as_ref()
+                .map(|c| c.relative_estimates.clone()),
+            change_direction: analysis_results
+                .comparison
+                .as_ref()
+                .map(get_change_direction),
+            history_id: None,
+            history_description: None,
+            label: None,
+            metadata: BTreeMap::new(),
+            target: None,
+            multimodal: analysis_results.multimodal,
+            latency_percentiles: analysis_results.latency_percentiles.clone(),
+            overhead: analysis_results.overhead,
+            timestamps: analysis_results.timestamps.map(<[f64]>::to_vec),
+            config: Some(analysis_results.config.clone()),
+        }
+    }
+
+    /// Checks that the samples can be analyzed again, ie. that there are at least two of them,
+    /// they're all numbers and the per-sample values all have the same length.
+    fn check_samples(&self) -> Result<()> {
+        let count = self.iterations.len();
+        if self.values.len() != count || self.avg_values.len() != count {
+            bail!(
+                "The sample values are inconsistent: {} iteration counts, {} values and {} average values",
+                count,
+                self.values.len(),
+                self.avg_values.len()
+            );
+        }
+        if count < 2 {
+            bail!(
+                "At least two samples are required, but {} were stored",
+                count
+            );
+        }
+        let mut all_values = (self.iterations.iter())
+            .chain(&self.values)
+            .chain(&self.avg_values);
+        if all_values.any(|value| value.is_nan()) {
+            bail!("The sample values include NaN");
+        }
+        match &self.timestamps {
+            Some(timestamps) if timestamps.len() != count => bail!(
+                "There are {} timestamps for {} samples",
+                timestamps.len(),
+                count
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::estimate::ChangeDistributions;
+    use crate::stats::Distribution;
+
+    #[test]
+    fn test_verify() {
+        let criterion_home =
+            std::env::temp_dir().join(format!("cargo-criterion-verify-{}", std::process::id()));
+        let directory = path!(&criterion_home, "data", DEFAULT_BASELINE, "fib");
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("benchmark.cbor"), b"not cbor").unwrap();
+        std::fs::write(directory.join("measurement_200101000000.cbor"), b"").unwrap();
+        std::fs::write(directory.join("notes.txt"), b"not a stored file").unwrap();
+
+        let results = verify(&criterion_home, &HistoryCipher::default());
+        std::fs::remove_dir_all(&criterion_home).unwrap();
+
+        assert_eq!(2, results.checked);
+        assert_eq!(2, results.corrupt.len());
+    }
+
+    fn estimate(lower_bound: f64, upper_bound: f64) -> Estimate {
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound,
+                upper_bound,
+            },
+            point_estimate: (lower_bound + upper_bound) / 2.0,
+            standard_error: 0.0,
+        }
+    }
+
+    fn comparison(p_value: f64, lower_bound: f64, upper_bound: f64) -> ComparisonData {
+        let distribution = || Distribution::from(vec![0.0].into_boxed_slice());
+        ComparisonData {
+            p_value,
+            t_distribution: distribution(),
+            t_value: 0.0,
+            relative_estimates: ChangeEstimates {
+                mean: estimate(lower_bound, upper_bound),
+                median: estimate(lower_bound, upper_bound),
+            },
+            relative_distributions: ChangeDistributions {
+                mean: distribution(),
+                median: distribution(),
+            },
+            significance_threshold: 0.05,
+            noise_threshold: 0.01,
+            base_iter_counts: vec![],
+            base_sample_times: vec![],
+            base_avg_times: vec![],
+            base_timestamps: None,
+            base_estimates: Estimates {
+                mean: estimate(1.0, 1.0),
+                median: estimate(1.0, 1.0),
+                median_abs_dev: estimate(0.0, 0.0),
+                slope: None,
+                std_dev: estimate(0.0, 0.0),
+            },
+            statistic_changes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_change_direction() {
+        assert_eq!(
+            ChangeDirection::Regressed,
+            get_change_direction(&comparison(0.001, 0.10, 0.20))
+        );
+        assert_eq!(
+            ChangeDirection::Improved,
+            get_change_direction(&comparison(0.001, -0.20, -0.10))
+        );
+        assert_eq!(
+            ChangeDirection::NotSignificant,
+            get_change_direction(&comparison(0.001, -0.005, 0.005))
+        );
+        // Not statistically significant, however large the change looks.
+        assert_eq!(
+            ChangeDirection::NoChange,
+            get_change_direction(&comparison(0.5, 0.10, 0.20))
+        );
+    }
+
+    fn saved_run(seconds: i64, values: &[f64], mean: f64) -> SavedStatistics {
+        SavedStatistics {
+            datetime: Utc::now() + chrono::Duration::seconds(seconds),
+            iterations: vec![1.0; values.len()],
+            values: values.to_vec(),
+            avg_values: values.to_vec(),
+            estimates: Estimates {
+                mean: estimate(mean - 1.0, mean + 1.0),
+                median: estimate(mean - 1.0, mean + 1.0),
+                median_abs_dev: estimate(0.0, 0.0),
+                slope: None,
+                std_dev: estimate(0.0, 0.0),
+            },
+            throughput: None,
+            changes: None,
+            change_direction: None,
+            history_id: None,
+            history_description: None,
+            label: None,
+            metadata: BTreeMap::new(),
+            target: None,
+            multimodal: false,
+            latency_percentiles: None,
+            overhead: None,
+            timestamps: None,
+            config: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_statistics() {
+        assert!(aggregate_statistics(&[]).is_none());
+
+        let runs = [
+            saved_run(0, &[1.0, 2.0], 10.0),
+            saved_run(1, &[3.0, 4.0, 5.0], 20.0),
+        ];
+        let baseline = aggregate_statistics(&runs).unwrap();
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0, 5.0], baseline.values);
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0, 5.0], baseline.avg_values);
+        assert_eq!(vec![1.0; 5], baseline.iterations);
+        assert_eq!(15.0, baseline.estimates.mean.point_estimate);
+        assert_eq!(
+            14.0,
+            baseline.estimates.mean.confidence_interval.lower_bound
+        );
+        assert_eq!(
+            16.0,
+            baseline.estimates.mean.confidence_interval.upper_bound
+        );
+        assert_eq!(15.0, baseline.estimates.median.point_estimate);
+        assert_eq!(runs[1].datetime, baseline.datetime);
+    }
+
+    #[test]
+    fn test_get_baseline() {
+        let criterion_home =
+            std::env::temp_dir().join(format!("cargo-criterion-baseline-{}", std::process::id()));
+        let id = BenchmarkId::new("fib".to_owned(), None, None, None);
+        let directory = path!(
+            &criterion_home,
+            "data",
+            DEFAULT_BASELINE,
+            id.as_directory_name()
+        );
+        std::fs::create_dir_all(&directory).unwrap();
+        let cipher = HistoryCipher::default();
+        // Only the last two runs are in the baseline window.
+        let runs = [
+            saved_run(0, &[100.0], 100.0),
+            saved_run(1, &[1.0, 2.0], 10.0),
+            saved_run(2, &[3.0, 4.0, 5.0], 20.0),
+        ];
+        for (i, run) in runs.iter().enumerate() {
+            let path = directory.join(format!("measurement_{}.cbor", i));
+            cipher.write(&path, run).unwrap();
+        }
+
+        let model = Model::load(
+            criterion_home.clone(),
+            PathBuf::from(DEFAULT_BASELINE),
+            None,
+            None,
+            None,
+            BTreeMap::new(),
+            BenchmarkOrder::Discovery,
+            2,
+            cipher,
+        );
+        let baseline = model.get_baseline(&id);
+        let missing = model.get_baseline(&BenchmarkId::new("other".to_owned(), None, None, None));
+        std::fs::remove_dir_all(&criterion_home).unwrap();
+
+        let baseline = baseline.unwrap();
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0, 5.0], baseline.values);
+        assert_eq!(15.0, baseline.estimates.mean.point_estimate);
+        // There are no stored runs of the other benchmark.
+        assert!(missing.is_none());
+    }
 }
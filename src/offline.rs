@@ -0,0 +1,313 @@
+//! Analysis of samples which were measured by some external tool and piped in on stdin, or
+//! imported from the results of an earlier run, rather than measured by a Criterion.rs benchmark.
+
+use crate::analysis::{BenchmarkConfig, MeasuredValues};
+use crate::config::{RoundingMode, SelectedStatistic, TimeUnit};
+use crate::connection::{AxisScale, PlotConfiguration, SamplingMethod};
+use crate::model::Model;
+use crate::report::{BenchmarkId, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{bail, Context, Result};
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+// The name used in place of a benchmark target for the samples read from stdin.
+const TARGET_NAME: &str = "stdin";
+// The name used in place of a benchmark target for the imported results.
+const IMPORT_TARGET_NAME: &str = "import";
+
+/// The raw measurements of a single benchmark: the number of iterations in each sample and the
+/// total time (in nanoseconds) that those iterations took.
+#[derive(Debug, Default)]
+pub struct Samples {
+    pub iters: Vec<f64>,
+    pub times: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct JsonSample {
+    iterations: f64,
+    time: f64,
+}
+
+/// Parse samples in either of the supported formats. JSON input is an array of objects with
+/// `iterations` and `time` fields. Anything else is parsed as CSV with one `iterations,time` pair
+/// per line; an optional header line, blank lines and lines starting with `#` are ignored.
+pub fn read_samples<R: Read>(mut reader: R) -> Result<Samples> {
+    let mut input = String::new();
+    reader
+        .read_to_string(&mut input)
+        .context("Failed to read samples from stdin")?;
+
+    let mut samples = Samples::default();
+    if input.trim_start().starts_with('[') {
+        let json_samples: Vec<JsonSample> =
+            serde_json::from_str(&input).context("Failed to parse JSON samples")?;
+        for sample in json_samples {
+            samples.iters.push(sample.iterations);
+            samples.times.push(sample.time);
+        }
+    } else {
+        for (i, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split(',').map(str::trim);
+            let parsed = match (fields.next(), fields.next(), fields.next()) {
+                (Some(iters), Some(time), None) => iters
+                    .parse::<f64>()
+                    .and_then(|iters| time.parse::<f64>().map(|time| (iters, time))),
+                _ => bail!("Expected 'iterations,time' on line {}: {:?}", i + 1, line),
+            };
+            match parsed {
+                Ok((iters, time)) => {
+                    samples.iters.push(iters);
+                    samples.times.push(time);
+                }
+                // Allow a header line before the first sample.
+                Err(_) if samples.iters.is_empty() => continue,
+                Err(e) => bail!("Invalid sample on line {}: {:?} ({})", i + 1, line, e),
+            }
+        }
+    }
+
+    if samples.iters.len() < 2 {
+        bail!(
+            "At least two samples are required, but {} were read",
+            samples.iters.len()
+        );
+    }
+    if samples.iters.iter().any(|&iters| iters < 1.0) {
+        bail!("Every sample must contain at least one iteration");
+    }
+    if samples.times.iter().any(|&time| time <= 0.0) {
+        bail!("Every sample must take a positive amount of time");
+    }
+    Ok(samples)
+}
+
+/// Convert an ID such as `group/function/value` into a benchmark ID.
+fn parse_id(id: &str) -> BenchmarkId {
+    let mut parts = id.splitn(3, '/').map(ToOwned::to_owned);
+    let group_id = parts.next().unwrap_or_default();
+    BenchmarkId::new(group_id, parts.next(), parts.next(), None)
+}
+
+/// Read samples from stdin and analyze and report on them as if they had been measured by a
+/// benchmark with the given ID. The results are stored in the model like any other benchmark, so
+/// they can be compared against in later runs.
+pub fn analyze_stdin(
+    id: &str,
+    report: &dyn Report,
+    model: &mut Model,
+    reports_directory: &Path,
+    time_unit: Option<TimeUnit>,
+    rounding: Option<RoundingMode>,
+    statistics: Option<&[SelectedStatistic]>,
+) -> Result<()> {
+    let stdin = std::io::stdin();
+    let Samples { iters, times } = read_samples(stdin.lock())?;
+
+    let mut id = parse_id(id);
+    let context = ReportContext {
+        output_directory: reports_directory.to_owned(),
+        plot_config: PlotConfiguration {
+            summary_scale: AxisScale::Linear,
+        },
+        time_unit,
+        rounding,
+        statistics: statistics.map(<[_]>::to_vec),
+    };
+
+    model.check_benchmark_group(TARGET_NAME, &id.group_id);
+    model.add_benchmark_id(TARGET_NAME, &mut id);
+    report.benchmark_start(&id, &context);
+    report.analysis(&id, &context);
+
+    let avg_values: Vec<f64> = iters
+        .iter()
+        .zip(times.iter())
+        .map(|(iter, time)| time / iter)
+        .collect();
+
+    // The defaults used by Criterion.rs.
+    let config = BenchmarkConfig {
+        confidence_level: 0.95,
+        measurement_time: Duration::from_secs(5),
+        noise_threshold: 0.01,
+        nresamples: 100_000,
+        sample_size: iters.len(),
+        significance_level: 0.05,
+        warm_up_time: Duration::from_secs(3),
+    };
+    // A linear regression is only possible if the iteration counts vary.
+    let sampling_method = if iters.iter().all(|&count| count == iters[0]) {
+        SamplingMethod::Flat
+    } else {
+        SamplingMethod::Linear
+    };
+
+    let saved_stats = model.get_baseline(&id);
+    let mut measured_data = crate::analysis::analysis(
+        &config,
+        id.throughput.clone(),
+        MeasuredValues {
+            iteration_count: &iters,
+            sample_values: &times,
+            avg_values: &avg_values,
+            timestamps: None,
+        },
+        saved_stats.as_ref().map(|stats| {
+            let measured_values = MeasuredValues {
+                iteration_count: &stats.iterations,
+                sample_values: &stats.values,
+                avg_values: &stats.avg_values,
+                timestamps: stats.timestamps.as_deref(),
+            };
+            (measured_values, &stats.estimates)
+        }),
+        sampling_method,
+        None,
+        None,
+        statistics,
+    );
+    measured_data.overlay_baselines = model.get_overlay_baselines(&id);
+
+    if let Err(e) = model.benchmark_complete(TARGET_NAME, &id, &measured_data) {
+        error!(
+            "Failed to save results for benchmark {}: {}",
+            id.as_title(),
+            e
+        );
+    }
+
+    let formatter = ValueFormatter::wall_time(time_unit, rounding);
+    report.measurement_complete(&id, &context, &measured_data, &formatter);
+    match model.load_history(&id) {
+        Ok(history) => report.history(&context, &id, &history, &formatter),
+        Err(e) => error!("Failed to load historical data: {:?}", e),
+    }
+
+    let benchmark_group = model.add_benchmark_group(TARGET_NAME, &id.group_id);
+    report.summarize(&context, &id.group_id, benchmark_group, &formatter);
+    Ok(())
+}
+
+/// Read the results written by `--export-bin` and report on them as if their benchmarks had just
+/// been measured. The stored estimates and comparisons are reported, and the analysis is only
+/// repeated to draw the plots.
+pub fn import_results(
+    path: &Path,
+    report: &dyn Report,
+    model: &mut Model,
+    reports_directory: &Path,
+    time_unit: Option<TimeUnit>,
+    rounding: Option<RoundingMode>,
+    statistics: Option<&[SelectedStatistic]>,
+) -> Result<()> {
+    let results = crate::message_formats::load_results(path)?;
+    let context = ReportContext {
+        output_directory: reports_directory.to_owned(),
+        plot_config: PlotConfiguration {
+            summary_scale: AxisScale::Linear,
+        },
+        time_unit,
+        rounding,
+        statistics: statistics.map(<[_]>::to_vec),
+    };
+    let formatter = ValueFormatter::wall_time(time_unit, rounding);
+
+    let mut group_ids: Vec<String> = vec![];
+    for benchmark in results.benchmarks {
+        let mut id: BenchmarkId = benchmark.id.into();
+        let config = benchmark.stats.config.as_ref().with_context(|| {
+            format!(
+                "The results of benchmark {} in {:?} don't include the settings of its analysis",
+                id.as_title(),
+                path
+            )
+        })?;
+
+        model.check_benchmark_group(IMPORT_TARGET_NAME, &id.group_id);
+        model.add_benchmark_id(IMPORT_TARGET_NAME, &mut id);
+        report.benchmark_start(&id, &context);
+        report.analysis(&id, &context);
+
+        let comparison = benchmark.comparison.as_ref();
+        let mut measured_data = crate::analysis::reanalysis(
+            config,
+            id.throughput.clone(),
+            &benchmark.stats,
+            comparison.map(|comparison| {
+                let measured_values = MeasuredValues {
+                    iteration_count: &comparison.base_iterations,
+                    sample_values: &comparison.base_values,
+                    avg_values: &comparison.base_avg_values,
+                    timestamps: comparison.base_timestamps.as_deref(),
+                };
+                (measured_values, &comparison.base_estimates)
+            }),
+            statistics,
+        );
+        if let (Some(data), Some(comparison)) = (&mut measured_data.comparison, comparison) {
+            data.p_value = comparison.p_value;
+            data.t_value = comparison.t_value;
+            data.significance_threshold = comparison.significance_threshold;
+            data.noise_threshold = comparison.noise_threshold;
+            data.relative_estimates = comparison.relative_estimates.clone();
+        }
+        measured_data.overlay_baselines = model.get_overlay_baselines(&id);
+
+        if let Err(e) = model.benchmark_complete(IMPORT_TARGET_NAME, &id, &measured_data) {
+            error!(
+                "Failed to save results for benchmark {}: {}",
+                id.as_title(),
+                e
+            );
+        }
+
+        report.measurement_complete(&id, &context, &measured_data, &formatter);
+        match model.load_history(&id) {
+            Ok(history) => report.history(&context, &id, &history, &formatter),
+            Err(e) => error!("Failed to load historical data: {:?}", e),
+        }
+
+        if !group_ids.contains(&id.group_id) {
+            group_ids.push(id.group_id.clone());
+        }
+    }
+
+    for group_id in group_ids {
+        let benchmark_group = model.add_benchmark_group(IMPORT_TARGET_NAME, &group_id);
+        report.summarize(&context, &group_id, benchmark_group, &formatter);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_samples() {
+        let input = "iterations,time\n1, 100\n\n# comment\n2,210.5\n";
+        let samples = read_samples(input.as_bytes()).unwrap();
+        assert_eq!(vec![1.0, 2.0], samples.iters);
+        assert_eq!(vec![100.0, 210.5], samples.times);
+
+        assert!(read_samples("1,100\n2,x\n".as_bytes()).is_err());
+        assert!(read_samples("1,100,3\n2,200\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_read_json_samples() {
+        let input = r#"[{"iterations": 1, "time": 100}, {"iterations": 2, "time": 210.5}]"#;
+        let samples = read_samples(input.as_bytes()).unwrap();
+        assert_eq!(vec![1.0, 2.0], samples.iters);
+        assert_eq!(vec![100.0, 210.5], samples.times);
+
+        assert!(read_samples(r#"[{"iterations": 1, "time": 100}]"#.as_bytes()).is_err());
+    }
+}
@@ -1,38 +1,47 @@
 use crate::estimate::Statistic;
 use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, SIZE};
-use crate::plot::Size;
-use crate::plot::{FilledCurve as FilledArea, Line, LineCurve, Rectangle};
-use crate::report::BenchmarkId;
+use crate::plot::{BootstrapDistribution, PlotSpec, Rectangle};
 use crate::stats::univariate::Sample;
 use criterion_plot::prelude::*;
 
 pub fn abs_distribution(
     colors: &Colors,
-    id: &BenchmarkId,
+    y_headroom: f64,
+    spec: &PlotSpec,
     statistic: Statistic,
-    size: Option<Size>,
-
-    x_unit: &str,
-    distribution_curve: LineCurve,
-    bootstrap_area: FilledArea,
-    point_estimate: Line,
+    distribution: BootstrapDistribution,
+    noise_band: Option<Rectangle>,
 ) -> Figure {
+    let BootstrapDistribution {
+        curve: distribution_curve,
+        interval: bootstrap_area,
+        interval_label: bootstrap_label,
+        point_estimate,
+        point_estimate_label,
+    } = distribution;
     let xs_sample = Sample::new(distribution_curve.xs);
+    // Make sure the point estimate line fits as well as the curve.
+    let y_max = Sample::new(distribution_curve.ys)
+        .max()
+        .max(point_estimate.end.y);
 
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .set(Title(format!(
             "{}: {}",
-            gnuplot_escape(id.as_title()),
+            gnuplot_escape(spec.id.as_title()),
             statistic
         )))
         .configure(Axis::BottomX, |a| {
-            a.set(Label(format!("Average time ({})", x_unit)))
+            a.set(Label(format!("Average time ({})", spec.unit)))
                 .set(Range::Limits(xs_sample.min(), xs_sample.max()))
         })
-        .configure(Axis::LeftY, |a| a.set(Label("Density (a.u.)")))
+        .configure(Axis::LeftY, |a| {
+            a.set(Label("Density (a.u.)"))
+                .set(Range::Limits(0., y_max * y_headroom))
+        })
         .configure(Key, |k| {
             k.set(Justification::Left)
                 .set(Order::SampleText)
@@ -58,7 +67,7 @@ pub fn abs_distribution(
             },
             |c| {
                 c.set(colors.current_sample)
-                    .set(Label("Confidence interval"))
+                    .set(Label(bootstrap_label.to_owned()))
                     .set(Opacity(0.25))
             },
         )
@@ -70,24 +79,42 @@ pub fn abs_distribution(
             |c| {
                 c.set(colors.current_sample)
                     .set(LINEWIDTH)
-                    .set(Label("Point estimate"))
+                    .set(Label(point_estimate_label.to_owned()))
                     .set(LineType::Dash)
             },
         );
+    if let Some(noise_band) = noise_band {
+        figure.plot(
+            FilledCurve {
+                x: &[noise_band.left, noise_band.right],
+                y1: &[noise_band.bottom, noise_band.bottom],
+                y2: &[noise_band.top, noise_band.top],
+            },
+            |c| {
+                c.set(Axes::BottomXRightY)
+                    .set(colors.severe_outlier)
+                    .set(Label("Noise threshold"))
+                    .set(Opacity(0.1))
+            },
+        );
+    }
     figure
 }
 
 pub fn rel_distribution(
     colors: &Colors,
-    id: &BenchmarkId,
+    spec: &PlotSpec,
     statistic: Statistic,
-    size: Option<Size>,
-
-    distribution_curve: LineCurve,
-    confidence_interval: FilledArea,
-    point_estimate: Line,
+    distribution: BootstrapDistribution,
     noise_threshold: Rectangle,
 ) -> Figure {
+    let BootstrapDistribution {
+        curve: distribution_curve,
+        interval: confidence_interval,
+        interval_label: confidence_interval_label,
+        point_estimate,
+        point_estimate_label,
+    } = distribution;
     let xs_ = Sample::new(distribution_curve.xs);
     let x_min = xs_.min();
     let x_max = xs_.max();
@@ -96,7 +123,7 @@ pub fn rel_distribution(
 
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::LeftY, |a| a.set(Label("Density (a.u.)")))
         .configure(Key, |k| {
             k.set(Justification::Left)
@@ -105,7 +132,7 @@ pub fn rel_distribution(
         })
         .set(Title(format!(
             "{}: {}",
-            gnuplot_escape(id.as_title()),
+            gnuplot_escape(spec.id.as_title()),
             statistic
         )))
         .configure(Axis::BottomX, |a| {
@@ -133,14 +160,14 @@ pub fn rel_distribution(
             },
             |c| {
                 c.set(colors.current_sample)
-                    .set(Label("Confidence interval"))
+                    .set(Label(confidence_interval_label.to_owned()))
                     .set(Opacity(0.25))
             },
         )
         .plot(to_lines!(point_estimate), |c| {
             c.set(colors.current_sample)
                 .set(LINEWIDTH)
-                .set(Label("Point estimate"))
+                .set(Label(point_estimate_label.to_owned()))
                 .set(LineType::Dash)
         })
         .plot(
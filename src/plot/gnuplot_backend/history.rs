@@ -1,27 +1,30 @@
-use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH};
-use crate::plot::Size;
-use crate::plot::{FilledCurve as FilledArea, LineCurve};
+use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, SIZE};
+use crate::plot::{FilledCurve as FilledArea, LineCurve, PlotSpec, Points as PointPlot};
 use criterion_plot::prelude::*;
 
 pub fn history_plot(
     colors: &Colors,
-    title: &str,
-    size: Size,
-    point_estimate: LineCurve,
-    confidence_interval: FilledArea,
+    spec: &PlotSpec,
+    point_estimate: (LineCurve, FilledArea),
+    changes: (PointPlot, PointPlot),
     ids: &[String],
-    unit: &str,
 ) -> Figure {
+    let (point_estimate, confidence_interval) = point_estimate;
+    let (improved, regressed) = changes;
+
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Key, |k| {
             k.set(Justification::Left)
                 .set(Order::SampleText)
                 .set(Position::Outside(Vertical::Top, Horizontal::Right))
         })
-        .set(Title(format!("{}: History", gnuplot_escape(title))))
+        .set(Title(format!(
+            "{}: History",
+            gnuplot_escape(spec.id.as_title())
+        )))
         .configure(Axis::BottomX, |a| {
             a.set(Label("Benchmark")).set(TicLabels {
                 labels: ids,
@@ -29,7 +32,7 @@ pub fn history_plot(
             })
         })
         .configure(Axis::LeftY, |a| {
-            a.set(Label(format!("Average time ({})", unit)))
+            a.set(Label(format!("Average time ({})", spec.unit)))
         });
 
     figure.plot(
@@ -55,5 +58,25 @@ pub fn history_plot(
                 .set(Label("Confidence Interval"))
         },
     );
+    for (points, color, label) in [
+        (improved, colors.improved, "Improved"),
+        (regressed, colors.regressed, "Regressed"),
+    ] {
+        if points.xs.is_empty() {
+            continue;
+        }
+        figure.plot(
+            Points {
+                x: points.xs,
+                y: points.ys,
+            },
+            |c| {
+                c.set(color)
+                    .set(Label(label))
+                    .set(PointSize(1.5))
+                    .set(PointType::FilledCircle)
+            },
+        );
+    }
     figure
 }
@@ -1,29 +1,27 @@
-use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, SIZE};
+use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, SIZE};
 use crate::plot::Points as PointPlot;
-use crate::plot::Size;
-use crate::report::BenchmarkId;
+use crate::plot::{LineCurve, PlotSpec};
 use criterion_plot::prelude::*;
 
 pub fn iteration_times(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-
-    unit: &str,
-    is_thumbnail: bool,
+    spec: &PlotSpec,
+    x_label: &str,
     current_times: PointPlot,
     base_times: Option<PointPlot>,
+    rolling_median: Option<LineCurve>,
 ) -> Figure {
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
-            a.configure(Grid::Major, |g| g.show()).set(Label("Sample"))
+            a.configure(Grid::Major, |g| g.show())
+                .set(Label(x_label.to_owned()))
         })
         .configure(Axis::LeftY, |a| {
             a.configure(Grid::Major, |g| g.show())
-                .set(Label(format!("Average Iteration Time ({})", unit)))
+                .set(Label(format!("Average Iteration Time ({})", spec.unit)))
         })
         .plot(
             Points {
@@ -53,8 +51,23 @@ pub fn iteration_times(
         );
     }
 
-    if !is_thumbnail {
-        figure.set(Title(gnuplot_escape(id.as_title())));
+    if let Some(rolling_median) = rolling_median {
+        figure.plot(
+            Lines {
+                x: rolling_median.xs,
+                y: rolling_median.ys,
+            },
+            |c| {
+                c.set(colors.current_sample)
+                    .set(LINEWIDTH)
+                    .set(Label("Rolling median"))
+                    .set(LineType::Solid)
+            },
+        );
+    }
+
+    if !spec.is_thumbnail {
+        figure.set(Title(gnuplot_escape(spec.id.as_title())));
         figure.configure(Key, |k| {
             k.set(Justification::Left)
                 .set(Order::SampleText)
@@ -1,12 +1,14 @@
+use crate::config::PlotOptions;
 use crate::connection::AxisScale;
 use crate::estimate::Statistic;
 use crate::format;
 use crate::plot::Size;
 use crate::plot::{
-    FilledCurve as FilledArea, Line, LineCurve, PlottingBackend, Points as PointPlot, Rectangle,
-    VerticalLine,
+    BootstrapDistribution, ContactSheetCell, FilledCurve as FilledArea, HeatmapGrid,
+    IterationsAxis, Line, LineCurve, LineTargets, PlotFeature, PlotSpec, PlottingBackend,
+    Points as PointPlot, Rectangle, Target, VerticalLine, ViolinEstimates,
 };
-use crate::report::{BenchmarkId, ValueType};
+use crate::report::ValueType;
 use criterion_plot::prelude::*;
 use std::path::{Path, PathBuf};
 use std::process::Child;
@@ -51,6 +53,8 @@ pub struct Colors {
     pub mild_outlier: Color,
     pub severe_outlier: Color,
     pub comparison_colors: Vec<Color>,
+    pub regressed: Color,
+    pub improved: Color,
 }
 impl From<crate::config::Color> for Color {
     fn from(other: crate::config::Color) -> Self {
@@ -71,6 +75,14 @@ impl From<&crate::config::Colors> for Colors {
                 .copied()
                 .map(Color::from)
                 .collect(),
+            regressed: other
+                .regressed
+                .unwrap_or(crate::config::REGRESSED_PLOT_COLOR)
+                .into(),
+            improved: other
+                .improved
+                .unwrap_or(crate::config::IMPROVED_PLOT_COLOR)
+                .into(),
         }
     }
 }
@@ -102,306 +114,253 @@ impl From<Size> for criterion_plot::Size {
     }
 }
 
+/// The vertical spacing (in pixels) and thickness (as a fraction of the spacing) of the violins
+/// in the violin plots.
+#[derive(Clone, Copy)]
+pub struct ViolinStyle {
+    spacing: usize,
+    width: f64,
+}
+impl From<&PlotOptions> for ViolinStyle {
+    fn from(other: &PlotOptions) -> Self {
+        ViolinStyle {
+            spacing: other.violin_spacing.map_or(25, |spacing| spacing as usize),
+            width: other.violin_width.unwrap_or(0.9),
+        }
+    }
+}
+
 pub struct Gnuplot {
     process_list: Vec<Child>,
     colors: Colors,
+    violin_style: ViolinStyle,
+    y_headroom: f64,
 }
 impl Gnuplot {
-    pub fn new(colors: &crate::config::Colors) -> Gnuplot {
+    pub fn new(colors: &crate::config::Colors, options: &PlotOptions) -> Gnuplot {
         Gnuplot {
             process_list: vec![],
             colors: colors.into(),
+            violin_style: options.into(),
+            y_headroom: options.y_headroom,
         }
     }
 }
 impl PlottingBackend for Gnuplot {
     fn abs_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        x_unit: &str,
-        distribution_curve: LineCurve,
-        bootstrap_area: FilledArea,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
+        noise_band: Option<Rectangle>,
     ) {
         let mut figure = distributions::abs_distribution(
             &self.colors,
-            id,
+            self.y_headroom,
+            spec,
             statistic,
-            size,
-            x_unit,
-            distribution_curve,
-            bootstrap_area,
-            point_estimate,
+            distribution,
+            noise_band,
         );
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap());
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap());
     }
 
     fn rel_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        distribution_curve: LineCurve,
-        confidence_interval: FilledArea,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
         noise_threshold: Rectangle,
     ) {
         let mut figure = distributions::rel_distribution(
             &self.colors,
-            id,
+            spec,
             statistic,
-            size,
-            distribution_curve,
-            confidence_interval,
-            point_estimate,
+            distribution,
             noise_threshold,
         );
 
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn iteration_times(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        file_path: PathBuf,
-
-        unit: &str,
-        is_thumbnail: bool,
+        spec: &PlotSpec,
+        x_label: &str,
         current_times: PointPlot,
         base_times: Option<PointPlot>,
+        rolling_median: Option<LineCurve>,
     ) {
         let mut figure = iteration_times::iteration_times(
             &self.colors,
-            id,
-            size,
-            unit,
-            is_thumbnail,
+            spec,
+            x_label,
             current_times,
             base_times,
+            rolling_median,
         );
 
-        debug_script(&file_path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(file_path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn regression(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        file_path: PathBuf,
-        is_thumbnail: bool,
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        y_scale: AxisScale,
         sample: PointPlot,
-        regression: Line,
-        confidence_interval: FilledArea,
+        regression: (LineCurve, FilledArea),
     ) {
-        let mut figure = regression::regression(
-            &self.colors,
-            id,
-            size,
-            is_thumbnail,
-            x_label,
-            x_scale,
-            unit,
-            sample,
-            regression,
-            confidence_interval,
-        );
+        let mut figure =
+            regression::regression(&self.colors, spec, x_axis, y_scale, sample, regression);
 
-        debug_script(&file_path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(file_path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn regression_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
-        current_regression: Line,
-        current_confidence_interval: FilledArea,
-        base_regression: Line,
-        base_confidence_interval: FilledArea,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        current_regression: (Line, FilledArea),
+        base_regression: (Line, FilledArea),
     ) {
         let mut figure = regression::regression_comparison(
             &self.colors,
-            id,
-            size,
-            is_thumbnail,
-            x_label,
-            x_scale,
-            unit,
+            spec,
+            x_axis,
             current_regression,
-            current_confidence_interval,
             base_regression,
-            base_confidence_interval,
         );
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn pdf_full(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        unit: &str,
-        y_label: &str,
-        y_scale: f64,
-        max_iters: f64,
-        pdf: FilledArea,
-        mean: VerticalLine,
+        spec: &PlotSpec,
+        y_axis: IterationsAxis,
+        pdf: (VerticalLine, FilledArea),
         fences: (VerticalLine, VerticalLine, VerticalLine, VerticalLine),
         points: (PointPlot, PointPlot, PointPlot),
     ) {
-        let mut figure = pdf::pdf_full(
-            &self.colors,
-            id,
-            size,
-            unit,
-            y_label,
-            y_scale,
-            max_iters,
-            pdf,
-            mean,
-            fences,
-            points,
-        );
+        let mut figure = pdf::pdf_full(&self.colors, spec, y_axis, pdf, fences, points);
 
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
-    fn pdf_thumbnail(
-        &mut self,
-        size: Option<Size>,
-        path: PathBuf,
-        unit: &str,
-        mean: Line,
-        pdf: FilledArea,
-    ) {
-        let mut figure = pdf::pdf_thumbnail(&self.colors, size, unit, mean, pdf);
-        debug_script(&path, &figure);
+    fn pdf_thumbnail(&mut self, spec: &PlotSpec, mean: Line, pdf: FilledArea) {
+        let mut figure = pdf::pdf_thumbnail(&self.colors, spec, mean, pdf);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn pdf_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-        unit: &str,
-        current_mean: Line,
-        current_pdf: FilledArea,
-        base_mean: Line,
-        base_pdf: FilledArea,
+        spec: &PlotSpec,
+        current_pdf: (Line, FilledArea),
+        base_pdf: (Line, FilledArea),
+        overlays: &[(&str, LineCurve)],
     ) {
-        let mut figure = pdf::pdf_comparison(
-            &self.colors,
-            id,
-            size,
-            is_thumbnail,
-            unit,
-            current_mean,
-            current_pdf,
-            base_mean,
-            base_pdf,
-        );
-        debug_script(&path, &figure);
+        let mut figure = pdf::pdf_comparison(&self.colors, spec, current_pdf, base_pdf, overlays);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
-    fn t_test(
-        &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        t: VerticalLine,
-        t_distribution: FilledArea,
-    ) {
-        let mut figure = t_test::t_test(&self.colors, id, size, t, t_distribution);
+    fn t_test(&mut self, spec: &PlotSpec, t: VerticalLine, t_distribution: FilledArea) {
+        let mut figure = t_test::t_test(&self.colors, spec, t, t_distribution);
 
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn line_comparison(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         value_type: ValueType,
         axis_scale: AxisScale,
         lines: &[(Option<&String>, LineCurve)],
+        targets: LineTargets,
     ) {
         let mut figure =
-            summary::line_comparison(&self.colors, title, unit, value_type, axis_scale, lines);
+            summary::line_comparison(&self.colors, spec, value_type, axis_scale, lines, targets);
 
-        debug_script(&path, &figure);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
     }
 
     fn violin(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         axis_scale: AxisScale,
         lines: &[(&str, LineCurve)],
+        estimates: Option<&[ViolinEstimates]>,
+        targets: &[Option<Target>],
     ) {
-        let mut figure = summary::violin(&self.colors, title, unit, axis_scale, lines);
-        debug_script(&path, &figure);
+        let mut figure = summary::violin(
+            &self.colors,
+            &self.violin_style,
+            spec,
+            axis_scale,
+            lines,
+            estimates,
+            targets,
+        );
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
+    }
+
+    fn heatmap(&mut self, _spec: &PlotSpec, _grid: HeatmapGrid) -> bool {
+        // The gnuplot backend doesn't support heatmaps.
+        false
+    }
+
+    fn latency_percentiles(&mut self, _spec: &PlotSpec, _latencies: LineCurve) -> bool {
+        // The gnuplot backend doesn't support latency percentile plots.
+        false
+    }
+
+    fn cdf(&mut self, _spec: &PlotSpec, _cdf: LineCurve, _percentiles: &[(f64, f64)]) -> bool {
+        // The gnuplot backend doesn't support CDF plots.
+        false
     }
 
     fn history_plot(
         &mut self,
-        id: &BenchmarkId,
-        size: Size,
-        path: PathBuf,
-        point_estimate: LineCurve,
-        confidence_interval: FilledArea,
+        spec: &PlotSpec,
+        point_estimate: (LineCurve, FilledArea),
+        changes: (PointPlot, PointPlot),
         ids: &[String],
-        unit: &str,
     ) {
-        let mut figure = history::history_plot(
-            &self.colors,
-            id.as_title(),
-            size,
-            point_estimate,
-            confidence_interval,
-            ids,
-            unit,
-        );
-        debug_script(&path, &figure);
+        let mut figure = history::history_plot(&self.colors, spec, point_estimate, changes, ids);
+        debug_script(&spec.path, &figure);
         self.process_list
-            .push(figure.set(Output(path)).draw().unwrap())
+            .push(figure.set(Output(spec.path.clone())).draw().unwrap())
+    }
+
+    fn contact_sheet(&mut self, _path: PathBuf, _cells: &[ContactSheetCell]) -> bool {
+        // The gnuplot backend can only draw one plot per figure.
+        false
+    }
+
+    fn supports(&self, _feature: PlotFeature) -> bool {
+        // None of the optional plots are implemented for gnuplot.
+        false
     }
 
     fn wait(&mut self) {
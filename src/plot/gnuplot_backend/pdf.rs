@@ -1,35 +1,37 @@
 use crate::plot::gnuplot_backend::{
     gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, POINT_SIZE, SIZE,
 };
-use crate::plot::Size;
-use crate::plot::{FilledCurve as FilledArea, Line, Points as PointPlot, VerticalLine};
-use crate::report::BenchmarkId;
+use crate::plot::{
+    FilledCurve as FilledArea, IterationsAxis, Line, LineCurve, PlotSpec, Points as PointPlot,
+    VerticalLine,
+};
 use crate::stats::univariate::Sample;
 use criterion_plot::prelude::*;
 
 pub fn pdf_full(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    unit: &str,
-    y_label: &str,
-    y_scale: f64,
-    max_iters: f64,
-    pdf: FilledArea,
-    mean: VerticalLine,
+    spec: &PlotSpec,
+    y_axis: IterationsAxis,
+    pdf: (VerticalLine, FilledArea),
     fences: (VerticalLine, VerticalLine, VerticalLine, VerticalLine),
     points: (PointPlot, PointPlot, PointPlot),
 ) -> Figure {
+    let IterationsAxis {
+        label: y_label,
+        scale: y_scale,
+        max: max_iters,
+    } = y_axis;
+    let (mean, pdf) = pdf;
     let (low_severe, low_mild, high_mild, high_severe) = fences;
     let (not_outlier, mild, severe) = points;
 
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
             let xs_ = Sample::new(pdf.xs);
-            a.set(Label(format!("Average time ({})", unit)))
+            a.set(Label(format!("Average time ({})", spec.unit)))
                 .set(Range::Limits(xs_.min(), xs_.max()))
         })
         .configure(Axis::LeftY, |a| {
@@ -118,17 +120,11 @@ pub fn pdf_full(
                 .set(LINEWIDTH)
                 .set(LineType::Dash)
         });
-    figure.set(Title(gnuplot_escape(id.as_title())));
+    figure.set(Title(gnuplot_escape(spec.id.as_title())));
     figure
 }
 
-pub fn pdf_thumbnail(
-    colors: &Colors,
-    size: Option<Size>,
-    unit: &str,
-    mean: Line,
-    pdf: FilledArea,
-) -> Figure {
+pub fn pdf_thumbnail(colors: &Colors, spec: &PlotSpec, mean: Line, pdf: FilledArea) -> Figure {
     let xs_ = Sample::new(pdf.xs);
     let ys_ = Sample::new(pdf.ys_1);
     let y_limit = ys_.max() * 1.1;
@@ -136,9 +132,9 @@ pub fn pdf_thumbnail(
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
-            a.set(Label(format!("Average time ({})", unit)))
+            a.set(Label(format!("Average time ({})", spec.unit)))
                 .set(Range::Limits(xs_.min(), xs_.max()))
         })
         .configure(Axis::LeftY, |a| {
@@ -171,21 +167,20 @@ pub fn pdf_thumbnail(
 
 pub fn pdf_comparison(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    is_thumbnail: bool,
-    unit: &str,
-    current_mean: Line,
-    current_pdf: FilledArea,
-    base_mean: Line,
-    base_pdf: FilledArea,
+    spec: &PlotSpec,
+    current_pdf: (Line, FilledArea),
+    base_pdf: (Line, FilledArea),
+    overlays: &[(&str, LineCurve)],
 ) -> Figure {
+    let (current_mean, current_pdf) = current_pdf;
+    let (base_mean, base_pdf) = base_pdf;
+
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
-            a.set(Label(format!("Average time ({})", unit)))
+            a.set(Label(format!("Average time ({})", spec.unit)))
         })
         .configure(Axis::LeftY, |a| a.set(Label("Density (a.u.)")))
         .configure(Axis::RightY, |a| a.hide())
@@ -229,10 +224,24 @@ pub fn pdf_comparison(
                 .set(LINEWIDTH)
         });
 
-    if is_thumbnail {
+    for (i, (name, pdf)) in overlays.iter().enumerate() {
+        figure.plot(
+            Lines {
+                x: pdf.xs,
+                y: pdf.ys,
+            },
+            |c| {
+                c.set(colors.comparison_colors[i % colors.comparison_colors.len()])
+                    .set(Label(gnuplot_escape(name)))
+                    .set(LINEWIDTH)
+            },
+        );
+    }
+
+    if spec.is_thumbnail {
         figure.configure(Key, |k| k.hide());
     } else {
-        figure.set(Title(gnuplot_escape(id.as_title())));
+        figure.set(Title(gnuplot_escape(spec.id.as_title())));
     }
     figure
 }
@@ -1,34 +1,31 @@
+use crate::connection::AxisScale;
 use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, SIZE};
 use crate::plot::Points as PointPlot;
-use crate::plot::Size;
-use crate::plot::{FilledCurve as FilledArea, Line};
-use crate::report::BenchmarkId;
+use crate::plot::{FilledCurve as FilledArea, IterationsAxis, Line, LineCurve, PlotSpec};
 use criterion_plot::prelude::*;
 
 pub fn regression(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    is_thumbnail: bool,
-    x_label: &str,
-    x_scale: f64,
-    unit: &str,
+    spec: &PlotSpec,
+    x_axis: IterationsAxis,
+    y_scale: AxisScale,
     sample: PointPlot,
-    regression: Line,
-    confidence_interval: FilledArea,
+    regression: (LineCurve, FilledArea),
 ) -> Figure {
+    let (regression, confidence_interval) = regression;
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
-                .set(Label(x_label.to_owned()))
-                .set(ScaleFactor(x_scale))
+                .set(Label(x_axis.label.to_owned()))
+                .set(ScaleFactor(x_axis.scale))
         })
         .configure(Axis::LeftY, |a| {
             a.configure(Grid::Major, |g| g.show())
-                .set(Label(format!("Total sample time ({})", unit)))
+                .set(Label(format!("Total sample time ({})", spec.unit)))
+                .set(y_scale.to_gnuplot())
         })
         .plot(
             Points {
@@ -42,12 +39,18 @@ pub fn regression(
                     .set(PointType::FilledCircle)
             },
         )
-        .plot(to_lines!(regression), |c| {
-            c.set(colors.current_sample)
-                .set(LINEWIDTH)
-                .set(Label("Linear regression"))
-                .set(LineType::Solid)
-        })
+        .plot(
+            Lines {
+                x: regression.xs,
+                y: regression.ys,
+            },
+            |c| {
+                c.set(colors.current_sample)
+                    .set(LINEWIDTH)
+                    .set(Label("Linear regression"))
+                    .set(LineType::Solid)
+            },
+        )
         .plot(
             FilledCurve {
                 x: confidence_interval.xs,
@@ -61,8 +64,8 @@ pub fn regression(
             },
         );
 
-    if !is_thumbnail {
-        figure.set(Title(gnuplot_escape(id.as_title())));
+    if !spec.is_thumbnail {
+        figure.set(Title(gnuplot_escape(spec.id.as_title())));
         figure.configure(Key, |k| {
             k.set(Justification::Left)
                 .set(Order::SampleText)
@@ -77,29 +80,25 @@ pub fn regression(
 
 pub fn regression_comparison(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    is_thumbnail: bool,
-    x_label: &str,
-    x_scale: f64,
-    unit: &str,
-    current_regression: Line,
-    current_confidence_interval: FilledArea,
-    base_regression: Line,
-    base_confidence_interval: FilledArea,
+    spec: &PlotSpec,
+    x_axis: IterationsAxis,
+    current_regression: (Line, FilledArea),
+    base_regression: (Line, FilledArea),
 ) -> Figure {
+    let (current_regression, current_confidence_interval) = current_regression;
+    let (base_regression, base_confidence_interval) = base_regression;
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
-                .set(Label(x_label.to_owned()))
-                .set(ScaleFactor(x_scale))
+                .set(Label(x_axis.label.to_owned()))
+                .set(ScaleFactor(x_axis.scale))
         })
         .configure(Axis::LeftY, |a| {
             a.configure(Grid::Major, |g| g.show())
-                .set(Label(format!("Total sample time ({})", unit)))
+                .set(Label(format!("Total sample time ({})", spec.unit)))
         })
         .configure(Key, |k| {
             k.set(Justification::Left)
@@ -135,10 +134,10 @@ pub fn regression_comparison(
                 .set(LineType::Solid)
         });
 
-    if is_thumbnail {
+    if spec.is_thumbnail {
         figure.configure(Key, |k| k.hide());
     } else {
-        figure.set(Title(gnuplot_escape(id.as_title())));
+        figure.set(Title(gnuplot_escape(spec.id.as_title())));
     }
 
     figure
@@ -1,19 +1,19 @@
 use crate::connection::AxisScale;
 use crate::plot::gnuplot_backend::{
-    gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, POINT_SIZE, SIZE,
+    gnuplot_escape, Colors, ViolinStyle, DEFAULT_FONT, LINEWIDTH, POINT_SIZE, SIZE,
 };
-use crate::plot::LineCurve;
 use crate::plot::Size;
+use crate::plot::{LineCurve, LineTargets, PlotSpec, Target, ViolinEstimates};
 use crate::report::ValueType;
 use criterion_plot::prelude::*;
 
 pub fn line_comparison(
     colors: &Colors,
-    title: &str,
-    unit: &str,
+    spec: &PlotSpec,
     value_type: ValueType,
     axis_scale: AxisScale,
     lines: &[(Option<&String>, LineCurve)],
+    targets: LineTargets,
 ) -> Figure {
     let mut figure = Figure::new();
 
@@ -31,7 +31,10 @@ pub fn line_comparison(
                 .set(Order::SampleText)
                 .set(Position::Outside(Vertical::Top, Horizontal::Right))
         })
-        .set(Title(format!("{}: Comparison", gnuplot_escape(title))))
+        .set(Title(format!(
+            "{}: Comparison",
+            gnuplot_escape(spec.id.as_title())
+        )))
         .configure(Axis::BottomX, |a| {
             a.set(Label(format!("Input{}", input_suffix)))
                 .set(axis_scale.to_gnuplot())
@@ -40,7 +43,7 @@ pub fn line_comparison(
     figure.configure(Axis::LeftY, |a| {
         a.configure(Grid::Major, |g| g.show())
             .configure(Grid::Minor, |g| g.hide())
-            .set(Label(format!("Average time ({})", unit)))
+            .set(Label(format!("Average time ({})", spec.unit)))
             .set(axis_scale.to_gnuplot())
     });
 
@@ -75,27 +78,66 @@ pub fn line_comparison(
             );
     }
 
+    if let Some(target_time) = targets.time {
+        // The target line spans the whole x axis.
+        let xs = lines.iter().flat_map(|(_, curve)| curve.xs.iter().copied());
+        let x_min = xs.clone().fold(f64::INFINITY, f64::min);
+        let x_max = xs.fold(f64::NEG_INFINITY, f64::max);
+        figure.plot(
+            Lines {
+                x: &[x_min, x_max],
+                y: &[target_time, target_time],
+            },
+            |c| {
+                c.set(Color::Black)
+                    .set(LINEWIDTH)
+                    .set(LineType::Dash)
+                    .set(Label("Target"))
+            },
+        );
+    }
+
+    if !targets.exceeded.xs.is_empty() {
+        figure.plot(
+            Points {
+                x: targets.exceeded.xs,
+                y: targets.exceeded.ys,
+            },
+            |p| {
+                p.set(PointType::Circle)
+                    .set(PointSize(1.5))
+                    .set(colors.severe_outlier)
+                    .set(Label("Above target"))
+            },
+        );
+    }
+
     figure
 }
 
 pub fn violin(
     colors: &Colors,
-    title: &str,
-    unit: &str,
+    style: &ViolinStyle,
+    spec: &PlotSpec,
     axis_scale: AxisScale,
     lines: &[(&str, LineCurve)],
+    estimates: Option<&[ViolinEstimates]>,
+    targets: &[Option<Target>],
 ) -> Figure {
     let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
-    let size: criterion_plot::Size = Size(1280, 200 + (25 * lines.len())).into();
+    let size: criterion_plot::Size = Size(1280, 200 + (style.spacing * lines.len())).into();
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
         .set(size)
-        .set(Title(format!("{}: Violin plot", gnuplot_escape(title))))
+        .set(Title(format!(
+            "{}: Violin plot",
+            gnuplot_escape(spec.id.as_title())
+        )))
         .configure(Axis::BottomX, |a| {
             a.configure(Grid::Major, |g| g.show())
                 .configure(Grid::Minor, |g| g.hide())
-                .set(Label(format!("Average time ({})", unit)))
+                .set(Label(format!("Average time ({})", spec.unit)))
                 .set(axis_scale.to_gnuplot())
         })
         .configure(Axis::LeftY, |a| {
@@ -108,10 +150,10 @@ pub fn violin(
         });
 
     let mut is_first = true;
-    for (i, (_, line)) in lines.iter().enumerate() {
-        let i = i as f64 + 0.5;
-        let y1: Vec<_> = line.ys.iter().map(|&y| i + y * 0.45).collect();
-        let y2: Vec<_> = line.ys.iter().map(|&y| i - y * 0.45).collect();
+    for (index, (_, line)) in lines.iter().enumerate() {
+        let i = index as f64 + 0.5;
+        let y1: Vec<_> = line.ys.iter().map(|&y| i + y * style.width / 2.0).collect();
+        let y2: Vec<_> = line.ys.iter().map(|&y| i - y * style.width / 2.0).collect();
 
         figure.plot(FilledCurve { x: line.xs, y1, y2 }, |c| {
             if is_first {
@@ -122,6 +164,45 @@ pub fn violin(
                 c.set(colors.current_sample)
             }
         });
+
+        if let Some(estimates) = estimates {
+            // Draw a line spanning the confidence interval of the mean, and a dot at the median.
+            let estimate = &estimates[index];
+            figure.plot(
+                Lines {
+                    x: &[estimate.mean_lower_bound, estimate.mean_upper_bound],
+                    y: &[i, i],
+                },
+                |c| c.set(Color::Black).set(LINEWIDTH),
+            );
+            figure.plot(
+                Points {
+                    x: &[estimate.median],
+                    y: &[i],
+                },
+                |c| {
+                    c.set(Color::Black)
+                        .set(POINT_SIZE)
+                        .set(PointType::FilledCircle)
+                },
+            );
+        }
+
+        if let Some(target) = &targets[index] {
+            // Mark the target across the violin, in red if the benchmark is slower than it.
+            let color = if target.exceeded {
+                colors.severe_outlier
+            } else {
+                Color::Black
+            };
+            figure.plot(
+                Lines {
+                    x: &[target.value, target.value],
+                    y: &[i - 0.4, i + 0.4],
+                },
+                |c| c.set(color).set(LINEWIDTH),
+            );
+        }
     }
     figure
 }
@@ -1,23 +1,20 @@
 use crate::plot::gnuplot_backend::{gnuplot_escape, Colors, DEFAULT_FONT, LINEWIDTH, SIZE};
-use crate::plot::Size;
-use crate::plot::{FilledCurve as FilledArea, VerticalLine};
-use crate::report::BenchmarkId;
+use crate::plot::{FilledCurve as FilledArea, PlotSpec, VerticalLine};
 use criterion_plot::prelude::*;
 
 pub fn t_test(
     colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
+    spec: &PlotSpec,
     t: VerticalLine,
     t_distribution: FilledArea,
 ) -> Figure {
     let mut figure = Figure::new();
     figure
         .set(Font(DEFAULT_FONT))
-        .set(criterion_plot::Size::from(size.unwrap_or(SIZE)))
+        .set(criterion_plot::Size::from(spec.size.unwrap_or(SIZE)))
         .set(Title(format!(
             "{}: Welch t test",
-            gnuplot_escape(id.as_title())
+            gnuplot_escape(spec.id.as_title())
         )))
         .configure(Axis::BottomX, |a| a.set(Label("t score")))
         .configure(Axis::LeftY, |a| a.set(Label("Density")))
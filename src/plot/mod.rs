@@ -8,11 +8,13 @@ pub use gnuplot_backend::Gnuplot;
 #[cfg(feature = "plotters_backend")]
 pub use plotters_backend::PlottersBackend;
 
+use crate::config::{is_selected, PlotOptions};
 use crate::connection::AxisScale;
 use crate::estimate::Statistic;
 use crate::estimate::{ConfidenceInterval, Estimate};
+use crate::format;
 use crate::kde;
-use crate::model::Benchmark;
+use crate::model::{Benchmark, ChangeDirection};
 use crate::report::{BenchmarkId, ComparisonData, MeasurementData, ReportContext, ValueType};
 use crate::stats::bivariate::regression::Slope;
 use crate::stats::bivariate::Data;
@@ -41,8 +43,6 @@ pub struct PlotContext<'a> {
     pub is_thumbnail: bool,
 }
 
-const KDE_POINTS: usize = 500;
-
 #[derive(Debug, Clone, Copy)]
 pub struct Size(pub usize, pub usize);
 
@@ -62,6 +62,33 @@ impl<'a> PlotContext<'a> {
             "violin.svg"
         )
     }
+
+    pub fn matrix_path(&self) -> PathBuf {
+        path!(
+            &self.context.output_directory,
+            self.id.as_directory_name(),
+            "matrix.svg"
+        )
+    }
+}
+
+/// The plots which not every backend can draw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotFeature {
+    Heatmap,
+    LatencyPercentiles,
+    Cdf,
+    ContactSheet,
+}
+impl PlotFeature {
+    pub fn description(self) -> &'static str {
+        match self {
+            PlotFeature::Heatmap => "heatmaps of two-dimensional benchmark groups",
+            PlotFeature::LatencyPercentiles => "latency percentile plots",
+            PlotFeature::Cdf => "CDF plots (the cdf setting)",
+            PlotFeature::ContactSheet => "contact sheets (--contact-sheet)",
+        }
+    }
 }
 
 pub trait Plotter {
@@ -170,21 +197,94 @@ pub trait Plotter {
         all_curves: &[(&BenchmarkId, &Benchmark)],
     );
 
-    fn t_test(&mut self, ctx: PlotContext<'_>, comparison: &ComparisonData);
+    /// Draws a heatmap of the mean estimates of benchmarks parameterized over two dimensions.
+    /// Returns false if no plot was generated.
+    fn matrix(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &ValueFormatter,
+        all_curves: &[(&BenchmarkId, &Benchmark)],
+    ) -> bool;
 
-    fn history(
+    /// Draws the percentiles of the per-element latencies of benchmarks which report them.
+    /// Returns false if no plot was generated.
+    fn latency_percentiles(
         &mut self,
         ctx: PlotContext<'_>,
-        upper_bound: &[f64],
-        point_estimate: &[f64],
-        lower_bound: &[f64],
-        ids: &[String],
-        unit: &str,
-    );
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter<'_>,
+    ) -> bool;
+
+    /// Draws the cumulative distribution of the sample times, if that has been enabled. Returns
+    /// false if no plot was generated.
+    fn cdf(
+        &mut self,
+        ctx: PlotContext<'_>,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter<'_>,
+    ) -> bool;
+
+    fn t_test(&mut self, ctx: PlotContext<'_>, comparison: &ComparisonData);
+
+    fn history(&mut self, ctx: PlotContext<'_>, history: &History<'_>);
+
+    /// Draws the distributions of all of the benchmarks in a grid in one image, so that they can
+    /// be compared at a glance. Returns false if no image was generated.
+    fn contact_sheet(
+        &mut self,
+        path: PathBuf,
+        formatter: &ValueFormatter,
+        all_benchmarks: &[(&BenchmarkId, &Benchmark)],
+    ) -> bool;
+
+    /// Returns false if the backend can't draw the given kind of plot, in which case it's left
+    /// out of the reports.
+    fn supports(&self, feature: PlotFeature) -> bool;
 
     fn wait(&mut self);
 }
 
+/// The estimates of a benchmark over its saved runs, oldest first, scaled to the given unit, and
+/// whether each run changed significantly from the one before it.
+pub struct History<'a> {
+    pub upper_bounds: &'a [f64],
+    pub point_estimates: &'a [f64],
+    pub lower_bounds: &'a [f64],
+    pub changes: &'a [Option<ChangeDirection>],
+    pub ids: &'a [String],
+    pub unit: &'a str,
+}
+
+/// The details of a single plot which the backends need whatever is drawn on it: the benchmark
+/// (or group) it's of, where it goes, how big it is, the unit of its values and the details to add
+/// to its caption, if those have been enabled.
+// The gnuplot backend doesn't add the details to its captions, so nothing reads them without
+// plotters.
+#[allow(dead_code)]
+pub struct PlotSpec<'a> {
+    id: &'a BenchmarkId,
+    size: Option<Size>,
+    path: PathBuf,
+    is_thumbnail: bool,
+    unit: &'a str,
+    sample_count: Option<usize>,
+    throughput: Option<&'a str>,
+}
+impl<'a> PlotSpec<'a> {
+    /// A full-size plot with no details in its caption.
+    fn new(ctx: &PlotContext<'a>, path: PathBuf, unit: &'a str) -> Self {
+        PlotSpec {
+            id: ctx.id,
+            size: ctx.size,
+            path,
+            is_thumbnail: false,
+            unit,
+            sample_count: None,
+            throughput: None,
+        }
+    }
+}
+
 // Some types representing things we might want to draw
 
 pub struct Point {
@@ -216,6 +316,76 @@ pub struct FilledCurve<'a> {
     ys_2: &'a [f64],
 }
 
+/// The bootstrap distribution of a statistic, with the interval shaded under it and the point
+/// estimate, and the labels to give those in the legend.
+pub struct BootstrapDistribution<'a> {
+    curve: LineCurve<'a>,
+    interval: FilledCurve<'a>,
+    interval_label: &'a str,
+    point_estimate: Line,
+    point_estimate_label: &'a str,
+}
+
+/// An axis of iteration counts, which are multiplied by `scale` (a power of ten, given in the
+/// label) to keep the tick labels short. `max` is the largest unscaled count.
+pub struct IterationsAxis<'a> {
+    label: &'a str,
+    scale: f64,
+    max: f64,
+}
+
+/// A performance target drawn on a summary plot, and whether the benchmark exceeds it.
+pub struct Target {
+    value: f64,
+    exceeded: bool,
+}
+
+/// The target time drawn across a line chart, if there is one, and the benchmarks which exceed
+/// their targets.
+pub struct LineTargets<'a> {
+    time: Option<f64>,
+    exceeded: Points<'a>,
+}
+
+/// One benchmark's cell in the contact sheet: the distribution of its average times, in a unit
+/// chosen for that benchmark alone.
+// If the plotting backends aren't enabled, nothing reads some of the fields here.
+#[allow(dead_code)]
+pub struct ContactSheetCell<'a> {
+    title: String,
+    unit: String,
+    mean: f64,
+    pdf: LineCurve<'a>,
+}
+
+/// Returns the number of rows and columns of a contact sheet with the given number of cells. The
+/// grid is as close to square as possible, with any spare cells at the end of the last row.
+#[cfg(feature = "plotters_backend")]
+pub fn contact_sheet_grid(count: usize) -> (usize, usize) {
+    if count == 0 {
+        return (0, 0);
+    }
+    let columns = (count as f64).sqrt().ceil() as usize;
+    let rows = (count as f64 / columns as f64).ceil() as usize;
+    (rows, columns)
+}
+
+/// The estimates which are drawn over each violin in the violin plot.
+pub struct ViolinEstimates {
+    median: f64,
+    mean_lower_bound: f64,
+    mean_upper_bound: f64,
+}
+
+/// A grid of values indexed by `values[y][x]`. Cells with no benchmark are None.
+// The gnuplot backend doesn't draw heatmaps, so nothing reads these fields without plotters.
+#[allow(dead_code)]
+pub struct HeatmapGrid<'a> {
+    x_labels: &'a [String],
+    y_labels: &'a [String],
+    values: &'a [Vec<Option<f64>>],
+}
+
 // If the plotting backends aren't enabled, nothing reads some of the fields here.
 #[allow(dead_code)]
 pub struct Rectangle {
@@ -225,173 +395,227 @@ pub struct Rectangle {
     bottom: f64,
 }
 
+// Turns sorted sample values into the corners of the step function of their empirical CDF.
+fn cdf_steps(sorted: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let n = sorted.len() as f64;
+    let mut xs = Vec::with_capacity(sorted.len() * 2);
+    let mut ys = Vec::with_capacity(sorted.len() * 2);
+    for (i, &x) in sorted.iter().enumerate() {
+        xs.push(x);
+        ys.push(i as f64 / n);
+        xs.push(x);
+        ys.push((i + 1) as f64 / n);
+    }
+    (xs, ys)
+}
+
+/// Picks at most `max_points` of the points to draw, so that the scatter plots of very large
+/// samples stay quick to render. The points are split into `max_points` runs of consecutive points
+/// and the middle point of each run is kept, so that the subset still covers the whole sample.
+fn downsample(xs: &[f64], ys: &[f64], max_points: Option<usize>) -> (Vec<f64>, Vec<f64>) {
+    let len = xs.len();
+    match max_points {
+        Some(max_points) if len > max_points => (0..max_points)
+            .map(|i| {
+                let start = i * len / max_points;
+                let end = (i + 1) * len / max_points;
+                let middle = (start + end) / 2;
+                (xs[middle], ys[middle])
+            })
+            .unzip(),
+        _ => (xs.to_vec(), ys.to_vec()),
+    }
+}
+
+/// The label of the x axis of the iteration times plots when the samples are plotted against
+/// their timestamps.
+const ELAPSED_TIME_LABEL: &str = "Elapsed Time (s)";
+
+/// Returns the x coordinates of the samples on the iteration times plots: the seconds since the
+/// first sample if they have timestamps, or else their indices.
+fn sample_xs(count: usize, timestamps: Option<&[f64]>) -> Vec<f64> {
+    match timestamps {
+        Some(timestamps) if timestamps.len() == count => {
+            timestamps.iter().map(|t| t - timestamps[0]).collect()
+        }
+        _ => (1..=count).map(|i| i as f64).collect(),
+    }
+}
+
+/// Returns the median of the window of `window` consecutive values centered on each value, which
+/// smooths out the noise in a series of samples to show any drift. The windows are cut short at
+/// the ends of the series, so there's one median per value.
+fn rolling_median(values: &[f64], window: usize) -> Vec<f64> {
+    let half = window / 2;
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(half);
+            let end = (i + window - half).min(values.len());
+            let mut sorted = values[start..end].to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            // The two middle values are the same one if there's an odd number of them.
+            let len = sorted.len();
+            (sorted[(len - 1) / 2] + sorted[len / 2]) / 2.0
+        })
+        .collect()
+}
+
 pub trait PlottingBackend {
     fn abs_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        x_unit: &str,
-        distribution_curve: LineCurve,
-        bootstrap_area: FilledCurve,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
+        noise_band: Option<Rectangle>,
     );
 
     fn rel_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        distribution_curve: LineCurve,
-        confidence_interval: FilledCurve,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
         noise_threshold: Rectangle,
     );
 
     fn iteration_times(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-
-        unit: &str,
-        is_thumbnail: bool,
+        spec: &PlotSpec,
+        x_label: &str,
         current_times: Points,
         base_times: Option<Points>,
+        rolling_median: Option<LineCurve>,
     );
 
     fn regression(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        y_scale: AxisScale,
         sample: Points,
-        regression: Line,
-        confidence_interval: FilledCurve,
+        regression: (LineCurve, FilledCurve),
     );
 
     fn regression_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
-        current_regression: Line,
-        current_confidence_interval: FilledCurve,
-        base_regression: Line,
-        base_confidence_interval: FilledCurve,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        current_regression: (Line, FilledCurve),
+        base_regression: (Line, FilledCurve),
     );
 
     fn pdf_full(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-
-        unit: &str,
-        y_label: &str,
-        y_scale: f64,
-        max_iters: f64,
-
-        pdf: FilledCurve,
-        mean: VerticalLine,
+        spec: &PlotSpec,
+        y_axis: IterationsAxis,
+        pdf: (VerticalLine, FilledCurve),
         fences: (VerticalLine, VerticalLine, VerticalLine, VerticalLine),
         points: (Points, Points, Points),
     );
-    fn pdf_thumbnail(
-        &mut self,
-        size: Option<Size>,
-        path: PathBuf,
-
-        unit: &str,
-
-        mean: Line,
-        pdf: FilledCurve,
-    );
+    fn pdf_thumbnail(&mut self, spec: &PlotSpec, mean: Line, pdf: FilledCurve);
     fn pdf_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-
-        unit: &str,
-
-        current_mean: Line,
-        current_pdf: FilledCurve,
-        base_mean: Line,
-        base_pdf: FilledCurve,
-    );
-    fn t_test(
-        &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-
-        t: VerticalLine,
-        t_distribution: FilledCurve,
+        spec: &PlotSpec,
+        current_pdf: (Line, FilledCurve),
+        base_pdf: (Line, FilledCurve),
+        overlays: &[(&str, LineCurve)],
     );
+    fn t_test(&mut self, spec: &PlotSpec, t: VerticalLine, t_distribution: FilledCurve);
 
     fn line_comparison(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         value_type: ValueType,
         axis_scale: AxisScale,
         lines: &[(Option<&String>, LineCurve)],
+        targets: LineTargets,
     );
 
     fn violin(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         axis_scale: AxisScale,
         lines: &[(&str, LineCurve)],
+        estimates: Option<&[ViolinEstimates]>,
+        targets: &[Option<Target>],
     );
 
+    /// Returns false if the backend doesn't support heatmaps.
+    fn heatmap(&mut self, spec: &PlotSpec, grid: HeatmapGrid) -> bool;
+
+    /// Returns false if the backend doesn't support latency percentile plots.
+    fn latency_percentiles(&mut self, spec: &PlotSpec, latencies: LineCurve) -> bool;
+
+    /// Returns false if the backend doesn't support CDF plots. The percentiles are pairs of the
+    /// percentile and the time at it.
+    fn cdf(&mut self, spec: &PlotSpec, cdf: LineCurve, percentiles: &[(f64, f64)]) -> bool;
+
     fn history_plot(
         &mut self,
-        id: &BenchmarkId,
-        size: Size,
-        path: PathBuf,
-
-        point_estimate: LineCurve,
-        confidence_interval: FilledCurve,
+        spec: &PlotSpec,
+        point_estimate: (LineCurve, FilledCurve),
+        changes: (Points, Points),
         ids: &[String],
-        unit: &str,
     );
 
+    /// Returns false if the backend doesn't support contact sheets.
+    fn contact_sheet(&mut self, path: PathBuf, cells: &[ContactSheetCell]) -> bool;
+
+    fn supports(&self, feature: PlotFeature) -> bool;
+
     fn wait(&mut self);
 }
 
 pub struct PlotGenerator<B: PlottingBackend> {
     pub backend: B,
+    pub options: PlotOptions,
 }
 impl<B: PlottingBackend> PlotGenerator<B> {
+    /// Returns the number of samples to show in the plot captions, if that has been enabled.
+    fn caption_sample_count(&self, measurements: &MeasurementData<'_>) -> Option<usize> {
+        if self.options.show_sample_count {
+            Some(measurements.data.x().len())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the target time (in nanoseconds) of the given benchmark or summary, if one is set
+    /// either for it or for its group.
+    fn target_for(&self, id: &BenchmarkId) -> Option<f64> {
+        let targets = &self.options.targets;
+        targets
+            .get(id.as_title())
+            .or_else(|| targets.get(&id.group_id))
+            .copied()
+    }
+
+    /// Returns the formatted throughput of the typical estimate to show in the plot captions, if
+    /// that has been enabled and the benchmark has a throughput.
+    fn caption_throughput(
+        &self,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter<'_>,
+    ) -> Option<String> {
+        if !self.options.show_throughput {
+            return None;
+        }
+        measurements.throughput.as_ref().map(|throughput| {
+            let typical = measurements.absolute_estimates.typical().point_estimate;
+            formatter
+                .format_throughput(throughput, typical)
+                .trim()
+                .to_owned()
+        })
+    }
+
     fn abs_distribution(
         &mut self,
-        id: &BenchmarkId,
-        context: &ReportContext,
+        ctx: PlotContext<'_>,
+        measurements: &MeasurementData<'_>,
         formatter: &ValueFormatter,
         statistic: Statistic,
         distribution: &Distribution<f64>,
         estimate: &Estimate,
-        size: Option<Size>,
     ) {
         let ci = &estimate.confidence_interval;
         let typical = ci.upper_bound;
@@ -399,12 +623,29 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let unit = formatter.scale_values(typical, &mut ci_values);
         let (lb, ub, point) = (ci_values[0], ci_values[1], ci_values[2]);
 
-        let start = lb - (ub - lb) / 9.;
-        let end = ub + (ub - lb) / 9.;
         let mut scaled_xs: Vec<f64> = distribution.iter().cloned().collect();
         let _ = formatter.scale_values(typical, &mut scaled_xs);
         let scaled_xs_sample = Sample::new(&scaled_xs);
-        let (kde_xs, ys) = kde::sweep(scaled_xs_sample, KDE_POINTS, Some((start, end)));
+        let (kde_xs, ys) = kde::sweep_confidence_interval(
+            self.options.kde_estimator(),
+            scaled_xs_sample,
+            self.options.kde_points,
+            lb,
+            ub,
+        );
+
+        // The shaded band is the confidence interval unless a percentile range has been set.
+        let (band_lb, band_ub, bootstrap_label) = match self.options.band_percentiles {
+            Some([lower, upper]) => {
+                let percentiles = scaled_xs_sample.percentiles();
+                (
+                    percentiles.at(lower),
+                    percentiles.at(upper),
+                    format!("{}-{} percentile range", lower, upper),
+                )
+            }
+            None => (lb, ub, "Confidence interval".to_owned()),
+        };
 
         // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
         let n_point = kde_xs
@@ -418,63 +659,98 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let start = kde_xs
             .iter()
             .enumerate()
-            .find(|&(_, &x)| x >= lb)
+            .find(|&(_, &x)| x >= band_lb)
             .unwrap()
             .0;
         let end = kde_xs
             .iter()
             .enumerate()
             .rev()
-            .find(|&(_, &x)| x <= ub)
+            .find(|&(_, &x)| x <= band_ub)
             .unwrap()
             .0;
         let len = end - start;
 
-        let distribution_curve = LineCurve {
-            xs: &*kde_xs,
-            ys: &*ys,
-        };
-        let bootstrap_area = FilledCurve {
-            xs: &kde_xs[start..end],
-            ys_1: &ys[start..end],
-            ys_2: &vec![0.0; len],
-        };
-        let estimate = Line {
-            start: Point { x: point, y: 0.0 },
-            end: Point {
-                x: point,
-                y: y_point,
+        let distribution = BootstrapDistribution {
+            curve: LineCurve {
+                xs: &kde_xs,
+                ys: &ys,
+            },
+            interval: FilledCurve {
+                xs: &kde_xs[start..end],
+                ys_1: &ys[start..end],
+                ys_2: &vec![0.0; len],
+            },
+            interval_label: &bootstrap_label,
+            point_estimate: Line {
+                start: Point { x: point, y: 0.0 },
+                end: Point {
+                    x: point,
+                    y: y_point,
+                },
             },
+            point_estimate_label: "Point estimate",
         };
 
-        self.backend.abs_distribution(
-            id,
-            statistic,
-            size,
-            context.report_path(id, &format!("{}.svg", statistic)),
-            &unit,
-            distribution_curve,
-            bootstrap_area,
-            estimate,
-        );
+        // The noise band is drawn around the baseline's estimate, if there is one and the band has
+        // been enabled. Clamp the band to the plotted range, and leave it out entirely if the
+        // baseline is so far away that none of it would be visible.
+        let (x_min, x_max) = (kde_xs[0], kde_xs[kde_xs.len() - 1]);
+        let noise_band = measurements
+            .comparison
+            .as_ref()
+            .filter(|_| self.options.abs_noise_band)
+            .and_then(|comparison| {
+                let base = comparison.base_estimates.get(statistic)?.point_estimate;
+                let noise = comparison.noise_threshold;
+                let mut band = [base * (1.0 - noise), base * (1.0 + noise)];
+                let _ = formatter.scale_values(typical, &mut band);
+                if band[1] < x_min || band[0] > x_max {
+                    None
+                } else {
+                    Some(Rectangle {
+                        left: band[0].max(x_min),
+                        right: band[1].min(x_max),
+                        top: 1.0,
+                        bottom: 0.0,
+                    })
+                }
+            });
+
+        let throughput = self.caption_throughput(measurements, formatter);
+        let spec = PlotSpec {
+            sample_count: self.caption_sample_count(measurements),
+            throughput: throughput.as_deref(),
+            ..PlotSpec::new(
+                &ctx,
+                ctx.context
+                    .report_path(ctx.id, &format!("{}.svg", statistic)),
+                &unit,
+            )
+        };
+        self.backend
+            .abs_distribution(&spec, statistic, distribution, noise_band);
     }
 
     fn rel_distribution(
         &mut self,
-        id: &BenchmarkId,
-        context: &ReportContext,
+        ctx: PlotContext<'_>,
         statistic: Statistic,
-        distribution: &Distribution<f64>,
-        estimate: &Estimate,
-        noise_threshold: f64,
-        size: Option<Size>,
+        comparison: &ComparisonData,
     ) {
+        let distribution = comparison.relative_distributions.get(statistic);
+        let estimate = comparison.relative_estimates.get(statistic);
+        let noise_threshold = comparison.noise_threshold;
         let ci = &estimate.confidence_interval;
         let (lb, ub) = (ci.lower_bound, ci.upper_bound);
 
-        let start = lb - (ub - lb) / 9.;
-        let end = ub + (ub - lb) / 9.;
-        let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)));
+        let (xs, ys) = kde::sweep_confidence_interval(
+            self.options.kde_estimator().linear(),
+            distribution,
+            self.options.kde_points,
+            lb,
+            ub,
+        );
         let xs_ = Sample::new(&xs);
 
         // interpolate between two points of the KDE sweep to find the Y position at the point estimate.
@@ -519,34 +795,42 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             )
         };
 
-        let distribution_curve = LineCurve { xs: &*xs, ys: &*ys };
-        let confidence_interval = FilledCurve {
-            xs: &xs[start..end],
-            ys_1: &ys[start..end],
-            ys_2: &vec![0.0; len],
-        };
-        let estimate = Line {
-            start: Point { x: point, y: 0.0 },
-            end: Point {
-                x: point,
-                y: y_point,
-            },
-        };
         let noise_threshold = Rectangle {
             left: fc_start,
             right: fc_end,
             top: 1.0,
             bottom: 0.0,
         };
+        let point_estimate_label = if format::shows_speedup(point, self.options.speedup_threshold) {
+            format!("Point estimate ({})", format::speedup(point))
+        } else {
+            "Point estimate".to_owned()
+        };
+        let distribution = BootstrapDistribution {
+            curve: LineCurve { xs: &xs, ys: &ys },
+            interval: FilledCurve {
+                xs: &xs[start..end],
+                ys_1: &ys[start..end],
+                ys_2: &vec![0.0; len],
+            },
+            interval_label: "Confidence interval",
+            point_estimate: Line {
+                start: Point { x: point, y: 0.0 },
+                end: Point {
+                    x: point,
+                    y: y_point,
+                },
+            },
+            point_estimate_label: &point_estimate_label,
+        };
 
+        let path = ctx
+            .context
+            .report_path(ctx.id, &format!("change/{}.svg", statistic));
         self.backend.rel_distribution(
-            id,
+            &PlotSpec::new(&ctx, path, "%"),
             statistic,
-            size,
-            context.report_path(id, &format!("change/{}.svg", statistic)),
-            distribution_curve,
-            confidence_interval,
-            estimate,
+            distribution,
             noise_threshold,
         );
     }
@@ -565,20 +849,32 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let unit = formatter.scale_values(max_avg_time, &mut scaled_y);
         let scaled_y = Sample::new(&scaled_y);
 
-        let xs: Vec<f64> = (1..=scaled_y.len()).map(|i| i as f64).collect();
+        let timestamps = measurements
+            .timestamps
+            .filter(|_| self.options.sample_timestamps);
+        let xs = sample_xs(scaled_y.len(), timestamps);
+        let x_label = if timestamps.is_some() {
+            ELAPSED_TIME_LABEL
+        } else {
+            "Sample"
+        };
+        let median = self.options.rolling_median.map(|window| {
+            let median = rolling_median(scaled_y, window);
+            downsample(&xs, &median, self.options.max_plot_points)
+        });
+        let (xs, ys) = downsample(&xs, scaled_y, self.options.max_plot_points);
 
-        let points = Points {
-            xs: &xs,
-            ys: scaled_y,
+        let points = Points { xs: &xs, ys: &ys };
+        let spec = PlotSpec {
+            is_thumbnail,
+            ..PlotSpec::new(&ctx, file_path, &unit)
         };
         self.backend.iteration_times(
-            ctx.id,
-            ctx.size,
-            file_path,
-            &unit,
-            is_thumbnail,
+            &spec,
+            x_label,
             points,
             None,
+            median.as_ref().map(|(xs, ys)| LineCurve { xs, ys }),
         );
     }
 
@@ -604,25 +900,49 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let scaled_current_y = Sample::new(scaled_current_y);
         let scaled_base_y = Sample::new(scaled_base_y);
 
-        let current_xs: Vec<f64> = (1..=scaled_current_y.len()).map(|i| i as f64).collect();
-        let base_xs: Vec<f64> = (1..=scaled_base_y.len()).map(|i| i as f64).collect();
+        // Both runs are plotted against their timestamps if they both have them, each relative to
+        // its own first sample so that they overlap.
+        let (current_timestamps, base_timestamps) = match (
+            self.options.sample_timestamps,
+            measurements.timestamps,
+            &comparison.base_timestamps,
+        ) {
+            (true, Some(current), Some(base)) => (Some(current), Some(&base[..])),
+            _ => (None, None),
+        };
+        let current_xs = sample_xs(scaled_current_y.len(), current_timestamps);
+        let base_xs = sample_xs(scaled_base_y.len(), base_timestamps);
+        let x_label = if current_timestamps.is_some() {
+            ELAPSED_TIME_LABEL
+        } else {
+            "Sample"
+        };
+        let max_points = self.options.max_plot_points;
+        let median = self.options.rolling_median.map(|window| {
+            let median = rolling_median(scaled_current_y, window);
+            downsample(&current_xs, &median, max_points)
+        });
+        let (current_xs, current_ys) = downsample(&current_xs, scaled_current_y, max_points);
+        let (base_xs, base_ys) = downsample(&base_xs, scaled_base_y, max_points);
 
         let current_points = Points {
             xs: &current_xs,
-            ys: scaled_current_y,
+            ys: &current_ys,
         };
         let base_points = Points {
             xs: &base_xs,
-            ys: scaled_base_y,
+            ys: &base_ys,
         };
-        self.backend.iteration_times(
-            ctx.id,
-            ctx.size,
-            file_path,
-            &unit,
+        let spec = PlotSpec {
             is_thumbnail,
+            ..PlotSpec::new(&ctx, file_path, &unit)
+        };
+        self.backend.iteration_times(
+            &spec,
+            x_label,
             current_points,
             Some(base_points),
+            median.as_ref().map(|(xs, ys)| LineCurve { xs, ys }),
         );
     }
 
@@ -646,9 +966,25 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let scaled_y = Sample::new(&scaled_y);
 
         let point_estimate = Slope::fit(&measurements.data).0;
-        let mut scaled_points = [point_estimate * max_iters, lb * max_iters, ub * max_iters];
-        let _ = formatter.scale_values(typical, &mut scaled_points);
-        let [point, lb, ub] = scaled_points;
+        let (y_scale, xs) = if self.options.regression_log_y {
+            // A line through the origin can't be drawn on a logarithmic axis, so start at the
+            // smallest iteration count instead. The line also becomes a curve, so it needs more
+            // than two points.
+            const POINTS: usize = 50;
+            let min_iters = data.x().min();
+            let step = (max_iters - min_iters) / (POINTS - 1) as f64;
+            let xs: Vec<f64> = (0..POINTS).map(|i| min_iters + step * i as f64).collect();
+            (AxisScale::Logarithmic, xs)
+        } else {
+            (AxisScale::Linear, vec![0.0, max_iters])
+        };
+        let mut scaled_ys: Vec<f64> = [point_estimate, lb, ub]
+            .iter()
+            .flat_map(|slope| xs.iter().map(move |x| slope * x))
+            .collect();
+        let _ = formatter.scale_values(typical, &mut scaled_ys);
+        let (point_ys, bound_ys) = scaled_ys.split_at(xs.len());
+        let (lb_ys, ub_ys) = bound_ys.split_at(xs.len());
 
         let exponent = (max_iters.log10() / 3.).floor() as i32 * 3;
         let x_scale = 10f64.powi(-exponent);
@@ -659,34 +995,41 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             format!("Iterations (x 10^{})", exponent)
         };
 
+        // The regression line and its confidence interval are fitted to the whole sample; only
+        // the drawn points are downsampled.
+        let (sample_xs, sample_ys) = downsample(data.x(), scaled_y, self.options.max_plot_points);
         let sample = Points {
-            xs: data.x(),
-            ys: scaled_y,
+            xs: &sample_xs,
+            ys: &sample_ys,
         };
-        let regression = Line {
-            start: Point { x: 0.0, y: 0.0 },
-            end: Point {
-                x: max_iters,
-                y: point,
-            },
+        let regression = LineCurve {
+            xs: &xs,
+            ys: point_ys,
         };
         let confidence_interval = FilledCurve {
-            xs: &[0.0, max_iters],
-            ys_1: &[0.0, lb],
-            ys_2: &[0.0, ub],
+            xs: &xs,
+            ys_1: lb_ys,
+            ys_2: ub_ys,
         };
 
-        self.backend.regression(
-            ctx.id,
-            ctx.size,
-            file_path,
+        let x_axis = IterationsAxis {
+            label: &x_label,
+            scale: x_scale,
+            max: max_iters,
+        };
+
+        let throughput = self.caption_throughput(measurements, formatter);
+        let spec = PlotSpec {
             is_thumbnail,
-            &x_label,
-            x_scale,
-            &unit,
+            throughput: throughput.as_deref(),
+            ..PlotSpec::new(&ctx, file_path, &unit)
+        };
+        self.backend.regression(
+            &spec,
+            x_axis,
+            y_scale,
             sample,
-            regression,
-            confidence_interval,
+            (regression, confidence_interval),
         )
     }
 
@@ -773,18 +1116,21 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             ys_2: &[0.0, base_ub],
         };
 
-        self.backend.regression_comparison(
-            ctx.id,
-            ctx.size,
-            file_path,
+        let x_axis = IterationsAxis {
+            label: &x_label,
+            scale: x_scale,
+            max: max_iters,
+        };
+
+        let spec = PlotSpec {
             is_thumbnail,
-            &x_label,
-            x_scale,
-            &unit,
-            current_regression,
-            current_confidence_interval,
-            base_regression,
-            base_confidence_interval,
+            ..PlotSpec::new(&ctx, file_path, &unit)
+        };
+        self.backend.regression_comparison(
+            &spec,
+            x_axis,
+            (current_regression, current_confidence_interval),
+            (base_regression, base_confidence_interval),
         )
     }
 
@@ -817,7 +1163,12 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             format!("Iterations (x 10^{})", exponent)
         };
 
-        let (xs, ys) = kde::sweep(scaled_avg_times, KDE_POINTS, None);
+        let (xs, ys) = kde::sweep(
+            self.options.kde_estimator(),
+            scaled_avg_times,
+            self.options.kde_points,
+            None,
+        );
         let (lost, lomt, himt, hist) = avg_times.fences();
         let mut fences = [lost, lomt, himt, hist];
         let _ = formatter.scale_values(typical, &mut fences);
@@ -884,16 +1235,22 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             ys: &severe_ys,
         };
 
+        let y_axis = IterationsAxis {
+            label: &y_label,
+            scale: y_scale,
+            max: max_iters,
+        };
+
+        let throughput = self.caption_throughput(measurements, formatter);
+        let spec = PlotSpec {
+            sample_count: self.caption_sample_count(measurements),
+            throughput: throughput.as_deref(),
+            ..PlotSpec::new(&ctx, file_path, &unit)
+        };
         self.backend.pdf_full(
-            ctx.id,
-            ctx.size,
-            file_path,
-            &unit,
-            &y_label,
-            y_scale,
-            max_iters,
-            pdf,
-            mean,
+            &spec,
+            y_axis,
+            (mean, pdf),
             (low_severe, low_mild, high_mild, high_severe),
             (not_outlier_points, mild_points, severe_points),
         );
@@ -913,7 +1270,13 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let scaled_avg_times = Sample::new(&scaled_avg_times);
         let mean = scaled_avg_times.mean();
 
-        let (xs, ys, mean_y) = kde::sweep_and_estimate(scaled_avg_times, KDE_POINTS, None, mean);
+        let (xs, ys, mean_y) = kde::sweep_and_estimate(
+            self.options.kde_estimator(),
+            scaled_avg_times,
+            self.options.kde_points,
+            None,
+            mean,
+        );
 
         let mean = Line {
             start: Point { x: mean, y: 0.0 },
@@ -926,7 +1289,7 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         };
 
         self.backend
-            .pdf_thumbnail(ctx.size, file_path, &unit, mean, pdf);
+            .pdf_thumbnail(&PlotSpec::new(&ctx, file_path, &unit), mean, pdf);
     }
 
     fn pdf_comparison_plot(
@@ -939,7 +1302,14 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         is_thumbnail: bool,
     ) {
         let base_avg_times = Sample::new(&comparison.base_avg_times);
-        let typical = base_avg_times.max().max(measurements.avg_times.max());
+        let typical = measurements
+            .overlay_baselines
+            .iter()
+            .flat_map(|overlay| overlay.avg_times.iter().copied())
+            .fold(
+                base_avg_times.max().max(measurements.avg_times.max()),
+                f64::max,
+            );
         let mut scaled_base_avg_times: Vec<f64> = comparison.base_avg_times.clone();
         let unit = formatter.scale_values(typical, &mut scaled_base_avg_times);
         let scaled_base_avg_times = Sample::new(&scaled_base_avg_times);
@@ -954,10 +1324,20 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         let base_mean = scaled_base_avg_times.mean();
         let new_mean = scaled_new_avg_times.mean();
 
-        let (base_xs, base_ys, base_y_mean) =
-            kde::sweep_and_estimate(scaled_base_avg_times, KDE_POINTS, None, base_mean);
-        let (xs, ys, y_mean) =
-            kde::sweep_and_estimate(scaled_new_avg_times, KDE_POINTS, None, new_mean);
+        let (base_xs, base_ys, base_y_mean) = kde::sweep_and_estimate(
+            self.options.kde_estimator(),
+            scaled_base_avg_times,
+            self.options.kde_points,
+            None,
+            base_mean,
+        );
+        let (xs, ys, y_mean) = kde::sweep_and_estimate(
+            self.options.kde_estimator(),
+            scaled_new_avg_times,
+            self.options.kde_points,
+            None,
+            new_mean,
+        );
 
         let base_mean = Line {
             start: Point {
@@ -991,16 +1371,39 @@ impl<B: PlottingBackend> PlotGenerator<B> {
             ys_2: &vec![0.0; base_ys.len()],
         };
 
-        self.backend.pdf_comparison(
-            ctx.id,
-            ctx.size,
-            file_path,
+        // A distribution needs at least two samples, so smaller baselines are left out.
+        let overlay_pdfs: Vec<(&str, Vec<f64>, Vec<f64>)> = measurements
+            .overlay_baselines
+            .iter()
+            .filter(|overlay| overlay.avg_times.len() >= 2)
+            .map(|overlay| {
+                let mut scaled_avg_times = overlay.avg_times.clone();
+                let _ = formatter.scale_values(typical, &mut scaled_avg_times);
+                let scaled_avg_times = Sample::new(&scaled_avg_times);
+                let (xs, ys, _) = kde::sweep_and_estimate(
+                    self.options.kde_estimator(),
+                    scaled_avg_times,
+                    self.options.kde_points,
+                    None,
+                    scaled_avg_times.mean(),
+                );
+                (&*overlay.name, xs.into_vec(), ys.into_vec())
+            })
+            .collect();
+        let overlays: Vec<(&str, LineCurve)> = overlay_pdfs
+            .iter()
+            .map(|(name, xs, ys)| (*name, LineCurve { xs, ys }))
+            .collect();
+
+        let spec = PlotSpec {
             is_thumbnail,
-            &unit,
-            current_mean,
-            current_pdf,
-            base_mean,
-            base_pdf,
+            ..PlotSpec::new(&ctx, file_path, &unit)
+        };
+        self.backend.pdf_comparison(
+            &spec,
+            (current_mean, current_pdf),
+            (base_mean, base_pdf),
+            &overlays,
         );
     }
 
@@ -1010,8 +1413,17 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         comparison: &ComparisonData,
         file_path: PathBuf,
     ) {
+        // Samples with zero variance leave no finite t statistics to estimate the density of.
+        if comparison.t_distribution.len() < 2 {
+            return;
+        }
         let t = comparison.t_value;
-        let (xs, ys) = kde::sweep(&comparison.t_distribution, KDE_POINTS, None);
+        let (xs, ys) = kde::sweep(
+            self.options.kde_estimator().linear(),
+            &comparison.t_distribution,
+            self.options.kde_points,
+            None,
+        );
 
         let t = VerticalLine { x: t };
         let t_distribution = FilledCurve {
@@ -1021,39 +1433,50 @@ impl<B: PlottingBackend> PlotGenerator<B> {
         };
 
         self.backend
-            .t_test(ctx.id, ctx.size, file_path, t, t_distribution)
+            .t_test(&PlotSpec::new(&ctx, file_path, ""), t, t_distribution)
     }
 
-    fn history_plot(
-        &mut self,
-        ctx: PlotContext<'_>,
-        size: Size,
-        upper_bound: &[f64],
-        point_estimate: &[f64],
-        lower_bound: &[f64],
-        ids: &[String],
-        file_path: PathBuf,
-        unit: &str,
-    ) {
-        let xs: Vec<_> = (0..point_estimate.len()).map(|i| i as f64).collect();
+    fn history_plot(&mut self, ctx: PlotContext<'_>, history: &History<'_>, file_path: PathBuf) {
+        let point_estimates = history.point_estimates;
+        let xs: Vec<_> = (0..point_estimates.len()).map(|i| i as f64).collect();
+        // The runs which significantly changed from the previous run, if they're to be marked.
+        let marked = |direction: ChangeDirection| -> (Vec<f64>, Vec<f64>) {
+            if !self.options.history_changes {
+                return (vec![], vec![]);
+            }
+            history
+                .changes
+                .iter()
+                .zip(xs.iter().zip(point_estimates))
+                .filter(|(change, _)| **change == Some(direction))
+                .map(|(_, (&x, &y))| (x, y))
+                .unzip()
+        };
+        let (improved_xs, improved_ys) = marked(ChangeDirection::Improved);
+        let (regressed_xs, regressed_ys) = marked(ChangeDirection::Regressed);
         let point_estimate = LineCurve {
             xs: &xs,
-            ys: point_estimate,
+            ys: point_estimates,
         };
         let confidence_interval = FilledCurve {
             xs: &xs,
-            ys_1: upper_bound,
-            ys_2: lower_bound,
+            ys_1: history.upper_bounds,
+            ys_2: history.lower_bounds,
+        };
+        let improved = Points {
+            xs: &improved_xs,
+            ys: &improved_ys,
+        };
+        let regressed = Points {
+            xs: &regressed_xs,
+            ys: &regressed_ys,
         };
 
         self.backend.history_plot(
-            ctx.id,
-            size,
-            file_path,
-            point_estimate,
-            confidence_interval,
-            ids,
-            unit,
+            &PlotSpec::new(&ctx, file_path, history.unit),
+            (point_estimate, confidence_interval),
+            (improved, regressed),
+            history.ids,
         );
     }
 }
@@ -1250,6 +1673,7 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
     ) {
         REPORT_STATS
             .iter()
+            .filter(|stat| is_selected(ctx.context.statistics.as_deref(), **stat))
             .filter_map(|stat| {
                 measurements.distributions.get(*stat).and_then(|dist| {
                     measurements
@@ -1260,29 +1684,20 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
             })
             .for_each(|(statistic, distribution, estimate)| {
                 self.abs_distribution(
-                    ctx.id,
-                    ctx.context,
+                    ctx,
+                    measurements,
                     formatter,
                     statistic,
                     distribution,
                     estimate,
-                    ctx.size,
                 )
             })
     }
 
     fn rel_distributions(&mut self, ctx: PlotContext<'_>, comparison: &ComparisonData) {
-        crate::plot::CHANGE_STATS.iter().for_each(|&statistic| {
-            self.rel_distribution(
-                ctx.id,
-                ctx.context,
-                statistic,
-                comparison.relative_distributions.get(statistic),
-                comparison.relative_estimates.get(statistic),
-                comparison.noise_threshold,
-                ctx.size,
-            )
-        });
+        crate::plot::CHANGE_STATS
+            .iter()
+            .for_each(|&statistic| self.rel_distribution(ctx, statistic, comparison));
     }
 
     fn line_comparison(
@@ -1295,7 +1710,7 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
         let max = all_curves
             .iter()
             .map(|(_, bench)| bench.latest_stats.estimates.typical().point_estimate)
-            .fold(::std::f64::NAN, f64::max);
+            .fold(f64::NAN, f64::max);
 
         let mut dummy = [1.0];
         let unit = formatter.scale_values(max, &mut dummy);
@@ -1335,13 +1750,40 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
             .map(|(name, xs, ys)| (*name, LineCurve { xs: &*xs, ys: &*ys }))
             .collect();
 
+        // Highlight the benchmarks whose typical time exceeds their target.
+        let (exceeded_xs, mut exceeded_ys): (Vec<_>, Vec<_>) = all_curves
+            .iter()
+            .filter_map(|(id, bench)| {
+                let target = self.target_for(id)?;
+                let typical = bench.latest_stats.estimates.typical().point_estimate;
+                if typical > target {
+                    Some((id.as_number().unwrap(), typical))
+                } else {
+                    None
+                }
+            })
+            .unzip();
+        formatter.scale_values(max, &mut exceeded_ys);
+        let target_time = self.target_for(ctx.id).map(|target| {
+            let mut target = [target];
+            formatter.scale_values(max, &mut target);
+            target[0]
+        });
+
+        let targets = LineTargets {
+            time: target_time,
+            exceeded: Points {
+                xs: &exceeded_xs,
+                ys: &exceeded_ys,
+            },
+        };
+
         self.backend.line_comparison(
-            ctx.line_comparison_path(),
-            ctx.id.as_title(),
-            &unit,
+            &PlotSpec::new(&ctx, ctx.line_comparison_path(), &unit),
             value_type,
             ctx.context.plot_config.summary_scale,
             &lines,
+            targets,
         );
     }
 
@@ -1351,13 +1793,16 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
         formatter: &ValueFormatter,
         all_curves: &[(&BenchmarkId, &Benchmark)],
     ) {
+        let kde_points = self.options.kde_points;
+        let estimator = self.options.kde_estimator();
         let mut kdes = all_curves
             .iter()
             .rev()
             .map(|(id, sample)| {
                 let (x, mut y) = kde::sweep(
+                    estimator,
                     Sample::new(&sample.latest_stats.avg_values),
-                    KDE_POINTS,
+                    kde_points,
                     None,
                 );
                 let y_max = Sample::new(&y).max();
@@ -1390,17 +1835,171 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
             formatter.scale_values(max, xs);
         });
 
+        let estimates = if self.options.violin_estimates {
+            let estimates = all_curves
+                .iter()
+                .rev()
+                .map(|(_, sample)| {
+                    let estimates = &sample.latest_stats.estimates;
+                    let mean_ci = &estimates.mean.confidence_interval;
+                    let mut values = [
+                        estimates.median.point_estimate,
+                        mean_ci.lower_bound,
+                        mean_ci.upper_bound,
+                    ];
+                    formatter.scale_values(max, &mut values);
+                    ViolinEstimates {
+                        median: values[0],
+                        mean_lower_bound: values[1],
+                        mean_upper_bound: values[2],
+                    }
+                })
+                .collect::<Vec<_>>();
+            Some(estimates)
+        } else {
+            None
+        };
+
         let lines = kdes
             .iter()
             .map(|(name, xs, ys)| (*name, LineCurve { xs: &*xs, ys: &*ys }))
             .collect::<Vec<_>>();
 
+        let targets = all_curves
+            .iter()
+            .rev()
+            .map(|(id, sample)| {
+                let target = self.target_for(id)?;
+                let mut value = [target];
+                formatter.scale_values(max, &mut value);
+                Some(Target {
+                    value: value[0],
+                    exceeded: sample.latest_stats.estimates.typical().point_estimate > target,
+                })
+            })
+            .collect::<Vec<_>>();
+
         self.backend.violin(
-            ctx.violin_path(),
-            ctx.id.as_title(),
-            &unit,
+            &PlotSpec::new(&ctx, ctx.violin_path(), &unit),
             ctx.context.plot_config.summary_scale,
             &lines,
+            estimates.as_deref(),
+            &targets,
+        )
+    }
+
+    fn matrix(
+        &mut self,
+        ctx: PlotContext<'_>,
+        formatter: &ValueFormatter,
+        all_curves: &[(&BenchmarkId, &Benchmark)],
+    ) -> bool {
+        // Benchmarks that aren't parameterized over two (finite) dimensions are skipped, as are
+        // those with the same parameters as an earlier benchmark (eg. `4x2` and `4/2`), since they'd
+        // be drawn over the same cell.
+        let mut cells: Vec<(f64, f64, f64)> = vec![];
+        for (id, bench) in all_curves {
+            if let Some((x, y)) = id.as_parameter_pair() {
+                if cells.iter().any(|&(x2, y2, _)| x2 == x && y2 == y) {
+                    warn!(
+                        "Benchmark {} has the same parameters as another benchmark, so it is left \
+                        out of the heat map",
+                        id
+                    );
+                    continue;
+                }
+                cells.push((x, y, bench.latest_stats.estimates.mean.point_estimate));
+            }
+        }
+
+        // The parameters are all finite, so they can be compared.
+        let sorted_unique = |mut values: Vec<f64>| {
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            values.dedup();
+            values
+        };
+        let xs = sorted_unique(cells.iter().map(|&(x, _, _)| x).collect());
+        let ys = sorted_unique(cells.iter().map(|&(_, y, _)| y).collect());
+        if xs.len() < 2 || ys.len() < 2 {
+            return false;
+        }
+
+        let mut means: Vec<f64> = cells.iter().map(|&(_, _, mean)| mean).collect();
+        let max = means.iter().cloned().fold(f64::NAN, f64::max);
+        let unit = formatter.scale_values(max, &mut means);
+
+        let mut values = vec![vec![None; xs.len()]; ys.len()];
+        for (&(x, y, _), mean) in cells.iter().zip(means) {
+            let column = xs.iter().position(|&v| v == x);
+            let row = ys.iter().position(|&v| v == y);
+            if let (Some(column), Some(row)) = (column, row) {
+                values[row][column] = Some(mean);
+            }
+        }
+        let x_labels: Vec<String> = xs.iter().map(|x| x.to_string()).collect();
+        let y_labels: Vec<String> = ys.iter().map(|y| y.to_string()).collect();
+
+        self.backend.heatmap(
+            &PlotSpec::new(&ctx, ctx.matrix_path(), &unit),
+            HeatmapGrid {
+                x_labels: &x_labels,
+                y_labels: &y_labels,
+                values: &values,
+            },
+        )
+    }
+
+    fn latency_percentiles(
+        &mut self,
+        ctx: PlotContext<'_>,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter<'_>,
+    ) -> bool {
+        let percentiles = match &measurements.latency_percentiles {
+            Some(percentiles) => percentiles,
+            None => return false,
+        };
+        let mut latencies = percentiles.latencies.clone();
+        let typical = latencies.iter().cloned().fold(f64::NAN, f64::max);
+        let unit = formatter.scale_values(typical, &mut latencies);
+
+        let path = ctx.context.report_path(ctx.id, "latency.svg");
+        self.backend.latency_percentiles(
+            &PlotSpec::new(&ctx, path, &unit),
+            LineCurve {
+                xs: &percentiles.percentiles,
+                ys: &latencies,
+            },
+        )
+    }
+
+    fn cdf(
+        &mut self,
+        ctx: PlotContext<'_>,
+        measurements: &MeasurementData<'_>,
+        formatter: &ValueFormatter<'_>,
+    ) -> bool {
+        if !self.options.cdf {
+            return false;
+        }
+        let avg_times = &measurements.avg_times;
+        let typical = avg_times.max();
+        let mut scaled_avg_times: Vec<f64> = (avg_times as &Sample<f64>).iter().cloned().collect();
+        let unit = formatter.scale_values(typical, &mut scaled_avg_times);
+
+        let percentiles = Sample::new(&scaled_avg_times).percentiles();
+        let percentiles: Vec<(f64, f64)> = (self.options.cdf_percentiles.iter())
+            .map(|&percentile| (percentile, percentiles.at(percentile)))
+            .collect();
+
+        scaled_avg_times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let (xs, ys) = cdf_steps(&scaled_avg_times);
+
+        let path = ctx.context.report_path(ctx.id, "cdf.svg");
+        self.backend.cdf(
+            &PlotSpec::new(&ctx, path, &unit),
+            LineCurve { xs: &xs, ys: &ys },
+            &percentiles,
         )
     }
 
@@ -1412,28 +2011,113 @@ impl<B: PlottingBackend> Plotter for PlotGenerator<B> {
         )
     }
 
-    fn history(
+    fn history(&mut self, ctx: PlotContext<'_>, history: &History<'_>) {
+        self.history_plot(ctx, history, ctx.context.report_path(ctx.id, "history.svg"))
+    }
+
+    fn contact_sheet(
         &mut self,
-        ctx: PlotContext<'_>,
-        upper_bound: &[f64],
-        point_estimate: &[f64],
-        lower_bound: &[f64],
-        ids: &[String],
-        unit: &str,
-    ) {
-        self.history_plot(
-            ctx,
-            ctx.size.unwrap(),
-            upper_bound,
-            point_estimate,
-            lower_bound,
-            ids,
-            ctx.context.report_path(ctx.id, "history.svg"),
-            unit,
-        )
+        path: PathBuf,
+        formatter: &ValueFormatter,
+        all_benchmarks: &[(&BenchmarkId, &Benchmark)],
+    ) -> bool {
+        let kde_points = self.options.kde_points;
+        let estimator = self.options.kde_estimator();
+        // Each benchmark is scaled on its own, since they can differ by orders of magnitude.
+        let curves = all_benchmarks
+            .iter()
+            .filter(|(_, bench)| bench.latest_stats.avg_values.len() > 1)
+            .map(|(id, bench)| {
+                let avg_values = Sample::new(&bench.latest_stats.avg_values);
+                let (mut xs, ys) = kde::sweep(estimator, avg_values, kde_points, None);
+                let mean = avg_values.mean();
+                let unit = formatter.scale_values(mean, &mut xs);
+                let mut scaled_mean = [mean];
+                formatter.scale_values(mean, &mut scaled_mean);
+                (id.as_title().to_owned(), unit, scaled_mean[0], xs, ys)
+            })
+            .collect::<Vec<_>>();
+        if curves.is_empty() {
+            return false;
+        }
+
+        let cells = curves
+            .iter()
+            .map(|(title, unit, mean, xs, ys)| ContactSheetCell {
+                title: title.clone(),
+                unit: unit.clone(),
+                mean: *mean,
+                pdf: LineCurve { xs, ys },
+            })
+            .collect::<Vec<_>>();
+        self.backend.contact_sheet(path, &cells)
+    }
+
+    fn supports(&self, feature: PlotFeature) -> bool {
+        self.backend.supports(feature)
     }
 
     fn wait(&mut self) {
         self.backend.wait();
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cdf_steps() {
+        let (xs, ys) = cdf_steps(&[1.0, 2.0, 4.0, 8.0]);
+        assert_eq!(vec![1.0, 1.0, 2.0, 2.0, 4.0, 4.0, 8.0, 8.0], xs);
+        assert_eq!(vec![0.0, 0.25, 0.25, 0.5, 0.5, 0.75, 0.75, 1.0], ys);
+    }
+
+    #[test]
+    #[cfg(feature = "plotters_backend")]
+    fn test_contact_sheet_grid() {
+        assert_eq!((0, 0), contact_sheet_grid(0));
+        assert_eq!((1, 1), contact_sheet_grid(1));
+        assert_eq!((1, 2), contact_sheet_grid(2));
+        assert_eq!((2, 2), contact_sheet_grid(3));
+        assert_eq!((2, 2), contact_sheet_grid(4));
+        assert_eq!((2, 3), contact_sheet_grid(5));
+        assert_eq!((3, 3), contact_sheet_grid(9));
+        assert_eq!((3, 4), contact_sheet_grid(10));
+        assert_eq!((10, 10), contact_sheet_grid(100));
+    }
+
+    #[test]
+    fn test_sample_xs() {
+        assert_eq!(vec![1.0, 2.0, 3.0], sample_xs(3, None));
+        assert_eq!(
+            vec![0.0, 0.5, 2.0],
+            sample_xs(3, Some(&[100.0, 100.5, 102.0]))
+        );
+        // Timestamps which don't match the samples are ignored.
+        assert_eq!(vec![1.0, 2.0, 3.0], sample_xs(3, Some(&[100.0, 100.5])));
+    }
+
+    #[test]
+    fn test_rolling_median() {
+        let values = [1.0, 5.0, 2.0, 8.0, 3.0];
+        assert_eq!(vec![3.0, 2.0, 5.0, 3.0, 5.5], rolling_median(&values, 3));
+        assert_eq!(values.to_vec(), rolling_median(&values, 1));
+        assert_eq!(vec![3.0; 5], rolling_median(&values, 100));
+    }
+
+    #[test]
+    fn test_downsample() {
+        let xs: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let ys: Vec<f64> = xs.iter().map(|x| x * 2.0).collect();
+
+        let (all_xs, all_ys) = downsample(&xs, &ys, None);
+        assert_eq!(xs, all_xs);
+        assert_eq!(ys, all_ys);
+        assert_eq!(xs, downsample(&xs, &ys, Some(10)).0);
+
+        let (some_xs, some_ys) = downsample(&xs, &ys, Some(4));
+        assert_eq!(vec![1.0, 3.0, 6.0, 8.0], some_xs);
+        assert_eq!(vec![2.0, 6.0, 12.0, 16.0], some_ys);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::plot::plotters_backend::{PlotTarget, Style, DEFAULT_FONT, SIZE};
+use crate::plot::{LineCurve, PlotSpec};
+use plotters::prelude::*;
+
+pub fn cdf(
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    cdf: LineCurve,
+    percentiles: &[(f64, f64)],
+) {
+    let Style { colors, layout, .. } = style;
+    let x_min = cdf.xs.iter().cloned().fold(f64::INFINITY, f64::min);
+    let x_max = cdf.xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    // Leave a little room on either side so that the first and last steps are visible.
+    let padding = ((x_max - x_min) * 0.05).max(x_max.abs() * 1e-3);
+    let x_range = (x_min - padding)..(x_max + padding);
+
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
+    let stroke_width = layout.stroke_width(2, &root_area);
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(layout.margin())
+        .caption(
+            format!("{}: Cumulative distribution", spec.id.as_title()),
+            (DEFAULT_FONT, 20),
+        )
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
+        .build_cartesian_2d(x_range.clone(), 0.0..1.0)
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc(format!("Average Time ({})", spec.unit))
+        .y_desc("Fraction of samples")
+        .x_label_formatter(&|&x| layout.axis_label(x))
+        .y_label_formatter(&|&y: &f64| format!("{}%", (y * 100.0).round()))
+        .draw()
+        .unwrap();
+
+    for &(percentile, value) in percentiles {
+        let y = percentile / 100.0;
+        chart
+            .draw_series(LineSeries::new(
+                vec![(x_range.start, y), (x_range.end, y)],
+                colors
+                    .previous_sample
+                    .mix(0.5)
+                    .stroke_width(stroke_width / 2 + 1),
+            ))
+            .unwrap();
+        chart
+            .draw_series(std::iter::once(Text::new(
+                format!(
+                    "p{}: {} {}",
+                    percentile,
+                    layout.axis_label(value),
+                    spec.unit
+                ),
+                (x_range.start, y),
+                (DEFAULT_FONT, 12)
+                    .into_font()
+                    .color(&colors.previous_sample),
+            )))
+            .unwrap();
+    }
+
+    chart
+        .draw_series(LineSeries::new(
+            cdf.to_points(),
+            colors.current_sample.stroke_width(stroke_width),
+        ))
+        .unwrap();
+
+    layout.draw_footnote(&root_area);
+}
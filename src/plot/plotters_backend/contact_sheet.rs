@@ -0,0 +1,87 @@
+use crate::plot::plotters_backend::{PlotTarget, Style, DEFAULT_FONT};
+use crate::plot::{contact_sheet_grid, ContactSheetCell};
+use plotters::prelude::*;
+
+/// The size of each benchmark's cell in the contact sheet; the same as the thumbnails.
+const CELL_SIZE: (u32, u32) = (450, 300);
+/// The font size of the cell titles, which is larger than the thumbnails use so that they're
+/// legible when the whole sheet is scaled down to fit a screen.
+const TITLE_FONT_SIZE: u32 = 18;
+/// Titles longer than this are shortened so that they don't run into the neighbouring cells.
+const MAX_TITLE_CHARS: usize = 40;
+
+/// Shortens the title to at most `max_chars` characters. The start is dropped rather than the end,
+/// since benchmarks in the same group differ in the later parts of their IDs.
+fn fit_title(title: &str, max_chars: usize) -> String {
+    let count = title.chars().count();
+    if count <= max_chars {
+        return title.to_owned();
+    }
+    let tail: String = title.chars().skip(count + 1 - max_chars).collect();
+    format!("…{}", tail)
+}
+
+pub fn contact_sheet(style: &Style, target: &mut PlotTarget, cells: &[ContactSheetCell]) {
+    let Style { colors, layout, .. } = style;
+    let (rows, columns) = contact_sheet_grid(cells.len());
+    let size = (CELL_SIZE.0 * columns as u32, CELL_SIZE.1 * rows as u32);
+    let root_area = target.backend(size).into_drawing_area();
+
+    for (area, cell) in root_area.split_evenly((rows, columns)).iter().zip(cells) {
+        let x_range = plotters::data::fitting_range(cell.pdf.xs.iter());
+        let y_max = cell.pdf.ys.iter().cloned().fold(0.0, f64::max) * 1.1;
+
+        let mut chart = ChartBuilder::on(area)
+            .margin(layout.margin())
+            .caption(
+                fit_title(&cell.title, MAX_TITLE_CHARS),
+                (DEFAULT_FONT, TITLE_FONT_SIZE),
+            )
+            .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+            .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
+            .build_cartesian_2d(x_range, 0.0..y_max)
+            .unwrap();
+
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_desc(format!("Average Time ({})", cell.unit))
+            .x_label_formatter(&|&x| layout.axis_label(x))
+            .y_label_formatter(&|&y| layout.axis_label(y))
+            .x_labels(5)
+            .y_labels(3)
+            .draw()
+            .unwrap();
+
+        chart
+            .draw_series(AreaSeries::new(
+                cell.pdf.to_points(),
+                0.0,
+                colors.current_sample.mix(0.25).filled(),
+            ))
+            .unwrap();
+
+        chart
+            .draw_series(std::iter::once(PathElement::new(
+                vec![(cell.mean, 0.0), (cell.mean, y_max)],
+                colors
+                    .current_sample
+                    .filled()
+                    .stroke_width(layout.stroke_width(2, area)),
+            )))
+            .unwrap();
+    }
+    layout.draw_footnote(&root_area);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fit_title() {
+        assert_eq!("fib/20", fit_title("fib/20", 10));
+        assert_eq!("0123456789", fit_title("0123456789", 10));
+        assert_eq!("…23456789a", fit_title("0123456789a", 10));
+    }
+}
@@ -1,49 +1,64 @@
 use crate::estimate::Statistic;
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, SIZE};
-use crate::plot::{FilledCurve, Line, LineCurve, Rectangle as RectangleArea, Size};
-use crate::report::BenchmarkId;
+use crate::format::short;
+use crate::plot::plotters_backend::tooltips::Tooltips;
+use crate::plot::plotters_backend::{
+    caption_with_details, legend_position, PlotTarget, Style, DEFAULT_FONT, SIZE,
+};
+use crate::plot::{BootstrapDistribution, PlotSpec, Rectangle as RectangleArea};
 use crate::stats::univariate::Sample;
-use plotters::data::float::pretty_print_float;
 use plotters::prelude::*;
-use std::path::PathBuf;
+
+/// The opacity of the gradient bands at their bounds and at the point estimate.
+const GRADIENT_MIN_ALPHA: f64 = 0.05;
+const GRADIENT_MAX_ALPHA: f64 = 0.45;
+/// The number of nested shapes the gradient bands are drawn with when the fills are combined.
+const GRADIENT_STEPS: usize = 8;
 
 pub fn abs_distribution(
-    colors: &Colors,
-    id: &BenchmarkId,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     statistic: Statistic,
-    size: Option<Size>,
-    path: PathBuf,
-
-    x_unit: &str,
-    distribution_curve: LineCurve,
-    bootstrap_area: FilledCurve,
-    point_estimate: Line,
+    distribution: BootstrapDistribution,
+    noise_band: Option<RectangleArea>,
 ) {
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE).into()).into_drawing_area();
+    let Style { colors, layout, .. } = style;
+    let BootstrapDistribution {
+        curve: distribution_curve,
+        interval: bootstrap_area,
+        interval_label: bootstrap_label,
+        point_estimate,
+        point_estimate_label,
+    } = distribution;
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
+    let stroke_width = layout.stroke_width(3, &root_area);
 
     let x_range = plotters::data::fitting_range(distribution_curve.xs.iter());
     let mut y_range = plotters::data::fitting_range(distribution_curve.ys.iter());
 
-    y_range.end *= 1.1;
+    // Make sure the point estimate line fits as well as the curve.
+    y_range.end = y_range.end.max(point_estimate.end.y) * layout.y_headroom();
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
+        .margin(layout.margin())
         .caption(
-            format!("{}:{}", id.as_title(), statistic),
+            caption_with_details(format!("{}:{}", spec.id.as_title(), statistic), spec),
             (DEFAULT_FONT, 20),
         )
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
-        .build_cartesian_2d(x_range, y_range)
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
+        .build_cartesian_2d(x_range, y_range.clone())
         .unwrap();
 
     chart
         .configure_mesh()
         .disable_mesh()
-        .x_desc(format!("Average time ({})", x_unit))
+        .x_desc(format!("Average time ({})", spec.unit))
         .y_desc("Density (a.u.)")
-        .x_label_formatter(&|&v| pretty_print_float(v, true))
-        .y_label_formatter(&|&v| pretty_print_float(v, true))
+        .x_label_formatter(&|&v| layout.axis_label(v))
+        .y_label_formatter(&|&v| layout.axis_label(v))
         .draw()
         .unwrap();
 
@@ -56,64 +71,181 @@ pub fn abs_distribution(
         .label("Bootstrap distribution")
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &colors.current_sample));
 
-    chart
-        .draw_series(AreaSeries::new(
+    let band = if layout.gradient_bands && layout.combined_fills {
+        // Draw the band as nested shapes, each narrower around the point estimate than the last.
+        // The overlapping shapes build up the opacity toward the center, and since no two of them
+        // share an edge, there are no seams between them.
+        let center = point_estimate.start.x;
+        let points: Vec<(f64, f64)> = bootstrap_area.to_points().collect();
+        let lower = points.first().map_or(center, |&(x, _)| x);
+        let upper = points.last().map_or(center, |&(x, _)| x);
+        let step_alpha = 1.0
+            - ((1.0 - GRADIENT_MAX_ALPHA) / (1.0 - GRADIENT_MIN_ALPHA))
+                .powf(1.0 / (GRADIENT_STEPS - 1) as f64);
+        chart.draw_series((0..GRADIENT_STEPS).filter_map(|step| {
+            let fraction = 1.0 - step as f64 / GRADIENT_STEPS as f64;
+            let (start, end) = (
+                center - fraction * (center - lower),
+                center + fraction * (upper - center),
+            );
+            let section: Vec<(f64, f64)> = points
+                .iter()
+                .copied()
+                .filter(|&(x, _)| start <= x && x <= end)
+                .collect();
+            let (first, last) = (section.first()?.0, section.last()?.0);
+            let outline: Vec<(f64, f64)> = std::iter::once((first, 0.0))
+                .chain(section)
+                .chain(std::iter::once((last, 0.0)))
+                .collect();
+            let alpha = if step == 0 {
+                GRADIENT_MIN_ALPHA
+            } else {
+                step_alpha
+            };
+            Some(Polygon::new(
+                outline,
+                colors.current_sample.mix(alpha).filled(),
+            ))
+        }))
+    } else if layout.gradient_bands {
+        // Draw the band as thin slices, each more opaque the closer it is to the point estimate.
+        let center = point_estimate.start.x;
+        let points: Vec<(f64, f64)> = bootstrap_area.to_points().collect();
+        let lower = points.first().map_or(center, |&(x, _)| x);
+        let upper = points.last().map_or(center, |&(x, _)| x);
+        chart.draw_series(points.windows(2).map(|pair| {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            let middle = (x0 + x1) / 2.0;
+            let half_width = if middle < center {
+                center - lower
+            } else {
+                upper - center
+            };
+            let closeness = if half_width > 0.0 {
+                1.0 - ((middle - center).abs() / half_width).min(1.0)
+            } else {
+                1.0
+            };
+            Polygon::new(
+                vec![(x0, 0.0), (x0, y0), (x1, y1), (x1, 0.0)],
+                colors
+                    .current_sample
+                    .mix(GRADIENT_MIN_ALPHA + (GRADIENT_MAX_ALPHA - GRADIENT_MIN_ALPHA) * closeness)
+                    .filled(),
+            )
+        }))
+    } else {
+        chart.draw_series(AreaSeries::new(
             bootstrap_area.to_points(),
             0.0,
-            colors.current_sample.mix(0.25).filled().stroke_width(3),
+            colors
+                .current_sample
+                .mix(0.25)
+                .filled()
+                .stroke_width(stroke_width),
         ))
-        .unwrap()
-        .label("Confidence interval")
-        .legend(|(x, y)| {
-            Rectangle::new(
-                [(x, y - 5), (x + 20, y + 5)],
-                colors.current_sample.mix(0.25).filled(),
-            )
-        });
+    };
+    band.unwrap().label(bootstrap_label).legend(|(x, y)| {
+        Rectangle::new(
+            [(x, y - 5), (x + 20, y + 5)],
+            colors.current_sample.mix(0.25).filled(),
+        )
+    });
+
+    if let Some(noise_band) = noise_band {
+        chart
+            .draw_series(std::iter::once(Rectangle::new(
+                [
+                    (noise_band.left, y_range.start),
+                    (noise_band.right, y_range.end),
+                ],
+                colors.previous_sample.mix(0.1).filled(),
+            )))
+            .unwrap()
+            .label("Noise threshold")
+            .legend(|(x, y)| {
+                Rectangle::new(
+                    [(x, y - 5), (x + 20, y + 5)],
+                    colors.previous_sample.mix(0.25).filled(),
+                )
+            });
+    }
+
+    let mut tooltips = Tooltips::new(style.interactive_svg);
+    tooltips.add_line(
+        chart.backend_coord(&(point_estimate.start.x, point_estimate.start.y)),
+        chart.backend_coord(&(point_estimate.end.x, point_estimate.end.y)),
+        format!(
+            "{}: {} {}",
+            statistic,
+            short(point_estimate.start.x),
+            spec.unit
+        ),
+    );
 
     chart
         .draw_series(std::iter::once(PathElement::new(
             point_estimate.to_line_vec(),
-            colors.current_sample.filled().stroke_width(3),
+            colors.current_sample.filled().stroke_width(stroke_width),
         )))
         .unwrap()
-        .label("Point estimate")
+        .label(point_estimate_label)
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &colors.current_sample));
 
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.distributions,
+        SeriesLabelPosition::UpperRight,
+        distribution_curve.to_points(),
+    );
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperRight)
+        .position(position)
         .draw()
         .unwrap();
+    layout.draw_footnote(&root_area);
+    // The SVG isn't complete until everything drawing to it has been dropped.
+    drop(chart);
+    drop(root_area);
+    tooltips.embed(target);
 }
 
 pub fn rel_distribution(
-    colors: &Colors,
-    id: &BenchmarkId,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     statistic: Statistic,
-    size: Option<Size>,
-    path: PathBuf,
-
-    distribution_curve: LineCurve,
-    confidence_interval: FilledCurve,
-    point_estimate: Line,
+    distribution: BootstrapDistribution,
     noise_threshold: RectangleArea,
 ) {
+    let Style { colors, layout, .. } = style;
+    let BootstrapDistribution {
+        curve: distribution_curve,
+        interval: confidence_interval,
+        interval_label: confidence_interval_label,
+        point_estimate,
+        point_estimate_label,
+    } = distribution;
     let xs_ = Sample::new(distribution_curve.xs);
     let x_min = xs_.min();
     let x_max = xs_.max();
 
     let y_range = plotters::data::fitting_range(distribution_curve.ys);
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE).into()).into_drawing_area();
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
+    let stroke_width = layout.stroke_width(3, &root_area);
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
+        .margin(layout.margin())
         .caption(
-            format!("{}:{}", id.as_title(), statistic),
+            format!("{}:{}", spec.id.as_title(), statistic),
             (DEFAULT_FONT, 20),
         )
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_min..x_max, y_range.clone())
         .unwrap();
 
@@ -122,8 +254,8 @@ pub fn rel_distribution(
         .disable_mesh()
         .x_desc("Relative change (%)")
         .y_desc("Density (a.u.)")
-        .x_label_formatter(&|&v| pretty_print_float(v, true))
-        .y_label_formatter(&|&v| pretty_print_float(v, true))
+        .x_label_formatter(&|&v| layout.axis_label(v))
+        .y_label_formatter(&|&v| layout.axis_label(v))
         .draw()
         .unwrap();
 
@@ -140,10 +272,14 @@ pub fn rel_distribution(
         .draw_series(AreaSeries::new(
             confidence_interval.to_points(),
             0.0,
-            colors.current_sample.mix(0.25).filled().stroke_width(3),
+            colors
+                .current_sample
+                .mix(0.25)
+                .filled()
+                .stroke_width(stroke_width),
         ))
         .unwrap()
-        .label("Confidence interval")
+        .label(confidence_interval_label)
         .legend(|(x, y)| {
             Rectangle::new(
                 [(x, y - 5), (x + 20, y + 5)],
@@ -154,10 +290,10 @@ pub fn rel_distribution(
     chart
         .draw_series(std::iter::once(PathElement::new(
             point_estimate.to_line_vec(),
-            colors.current_sample.filled().stroke_width(3),
+            colors.current_sample.filled().stroke_width(stroke_width),
         )))
         .unwrap()
-        .label("Point estimate")
+        .label(point_estimate_label)
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &colors.current_sample));
 
     chart
@@ -176,9 +312,17 @@ pub fn rel_distribution(
                 colors.previous_sample.mix(0.25).filled(),
             )
         });
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.distributions,
+        SeriesLabelPosition::UpperRight,
+        distribution_curve.to_points(),
+    );
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperRight)
+        .position(position)
         .draw()
         .unwrap();
+    layout.draw_footnote(&root_area);
 }
@@ -0,0 +1,108 @@
+use crate::plot::plotters_backend::{PlotTarget, Style, DEFAULT_FONT, SIZE};
+use crate::plot::{HeatmapGrid, PlotSpec};
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+/// Returns the color for a value, going from blue for the fastest benchmark to red for the
+/// slowest.
+fn cell_color(value: f64, min: f64, max: f64) -> HSLColor {
+    let fraction = if max > min {
+        (value - min) / (max - min)
+    } else {
+        0.0
+    };
+    HSLColor(0.66 * (1.0 - fraction), 0.8, 0.5)
+}
+
+/// Returns the right (or top) edge of the cell at the given index.
+fn cell_end(index: u32, count: u32) -> SegmentValue<u32> {
+    if index + 1 < count {
+        SegmentValue::Exact(index + 1)
+    } else {
+        SegmentValue::Last
+    }
+}
+
+fn label_of(labels: &[String], value: &SegmentValue<u32>) -> String {
+    match value {
+        SegmentValue::CenterOf(index) => labels.get(*index as usize).cloned().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+pub fn heatmap(style: &Style, spec: &PlotSpec, target: &mut PlotTarget, grid: HeatmapGrid) {
+    let layout = &style.layout;
+    let x_count = grid.x_labels.len() as u32;
+    let y_count = grid.y_labels.len() as u32;
+
+    let (min, max) = grid
+        .values
+        .iter()
+        .flatten()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        });
+
+    let root_area = target.backend(SIZE.into()).into_drawing_area();
+
+    let x_label_formatter = |value: &SegmentValue<u32>| label_of(grid.x_labels, value);
+    let y_label_formatter = |value: &SegmentValue<u32>| label_of(grid.y_labels, value);
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(layout.margin())
+        .caption(
+            format!("{}: Mean ({})", spec.id.as_title(), spec.unit),
+            (DEFAULT_FONT, 20),
+        )
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
+        .build_cartesian_2d(
+            (0..x_count - 1).into_segmented(),
+            (0..y_count - 1).into_segmented(),
+        )
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_labels(x_count as usize)
+        .y_labels(y_count as usize)
+        .x_label_formatter(&x_label_formatter)
+        .y_label_formatter(&y_label_formatter)
+        .x_desc("First Parameter")
+        .y_desc("Second Parameter")
+        .draw()
+        .unwrap();
+
+    let cells = grid.values.iter().enumerate().flat_map(|(y, row)| {
+        row.iter()
+            .enumerate()
+            .filter_map(move |(x, value)| value.map(|v| (x as u32, y as u32, v)))
+    });
+
+    chart
+        .draw_series(cells.clone().map(|(x, y, value)| {
+            Rectangle::new(
+                [
+                    (SegmentValue::Exact(x), SegmentValue::Exact(y)),
+                    (cell_end(x, x_count), cell_end(y, y_count)),
+                ],
+                cell_color(value, min, max).filled(),
+            )
+        }))
+        .unwrap();
+
+    let text_style =
+        TextStyle::from((DEFAULT_FONT, 12).into_font()).pos(Pos::new(HPos::Center, VPos::Center));
+    chart
+        .draw_series(cells.map(|(x, y, value)| {
+            Text::new(
+                format!("{:.2}", value),
+                (SegmentValue::CenterOf(x), SegmentValue::CenterOf(y)),
+                text_style.clone(),
+            )
+        }))
+        .unwrap();
+    layout.draw_footnote(&root_area);
+}
@@ -1,20 +1,23 @@
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT};
-use crate::plot::{FilledCurve, LineCurve, Size};
-use plotters::data::float::pretty_print_float;
+use crate::plot::plotters_backend::{
+    legend_position, PlotTarget, Style, DEFAULT_FONT, POINT_SIZE, SIZE,
+};
+use crate::plot::{FilledCurve, LineCurve, PlotSpec, Points};
 use plotters::prelude::*;
-use std::path::PathBuf;
 
 pub fn history(
-    colors: &Colors,
-    title: &str,
-    size: Size,
-    path: PathBuf,
-    point_estimate: LineCurve,
-    confidence_interval: FilledCurve,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    point_estimate: (LineCurve, FilledCurve),
+    changes: (Points, Points),
     ids: &[String],
-    unit: &str,
 ) {
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let Style { colors, layout, .. } = style;
+    let (point_estimate, confidence_interval) = point_estimate;
+    let (improved, regressed) = changes;
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
 
     let x_range = plotters::data::fitting_range(point_estimate.xs.iter());
     let mut y_range = plotters::data::fitting_range(
@@ -28,20 +31,23 @@ pub fn history(
     y_range.start /= 1.1;
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
-        .caption(format!("{} History", title), (DEFAULT_FONT, 20))
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .caption(
+            format!("{} History", spec.id.as_title()),
+            (DEFAULT_FONT, 20),
+        )
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_range, y_range)
         .unwrap();
 
     chart
         .configure_mesh()
         .disable_mesh()
-        .y_desc(format!("Average time ({})", unit))
+        .y_desc(format!("Average time ({})", spec.unit))
         .x_desc("History")
         .x_label_formatter(&|&v| ids[v as usize].clone())
-        .y_label_formatter(&|&v| pretty_print_float(v, true))
+        .y_label_formatter(&|&v| layout.axis_label(v))
         .x_labels(ids.len())
         .draw()
         .unwrap();
@@ -84,9 +90,35 @@ pub fn history(
             )
         });
 
+    for (points, color, label) in [
+        (improved, colors.improved, "Improved"),
+        (regressed, colors.regressed, "Regressed"),
+    ] {
+        if points.xs.is_empty() {
+            continue;
+        }
+        chart
+            .draw_series(
+                points
+                    .to_points()
+                    .map(|point| Circle::new(point, POINT_SIZE + 1, color.filled())),
+            )
+            .unwrap()
+            .label(label)
+            .legend(move |(x, y)| Circle::new((x + 10, y), POINT_SIZE + 1, color.filled()));
+    }
+
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.history,
+        SeriesLabelPosition::UpperRight,
+        point_estimate.to_points(),
+    );
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperRight)
+        .position(position)
         .draw()
         .unwrap();
+    layout.draw_footnote(&root_area);
 }
@@ -1,23 +1,27 @@
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, POINT_SIZE, SIZE};
-use crate::plot::{Points, Size};
-use crate::report::BenchmarkId;
+use crate::format::short;
+use crate::plot::plotters_backend::tooltips::Tooltips;
+use crate::plot::plotters_backend::{legend_position, PlotTarget, Style, DEFAULT_FONT, SIZE};
+use crate::plot::{LineCurve, PlotSpec, Points};
 use crate::stats::univariate::Sample;
-use plotters::data::float::pretty_print_float;
 use plotters::prelude::*;
-use std::path::PathBuf;
 
 pub fn iteration_times(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
-    unit: &str,
-    is_thumbnail: bool,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    x_label: &str,
     current_times: Points,
     base_times: Option<Points>,
+    rolling_median: Option<LineCurve>,
 ) {
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let Style {
+        colors,
+        layout,
+        point_style,
+        ..
+    } = style;
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
 
     let mut cb = ChartBuilder::on(&root_area);
 
@@ -25,57 +29,107 @@ pub fn iteration_times(
         let max_x = Sample::new(current_times.xs)
             .max()
             .max(Sample::new(base.xs).max());
-        let x_range = (1.0)..(max_x);
+        let min_x = Sample::new(current_times.xs)
+            .min()
+            .min(Sample::new(base.xs).min());
+        let x_range = (min_x)..(max_x);
         let y_range = plotters::data::fitting_range(current_times.ys.iter().chain(base.ys.iter()));
         (x_range, y_range)
     } else {
         let max_x = Sample::new(current_times.xs).max();
-        let x_range = (1.0)..(max_x);
+        let x_range = Sample::new(current_times.xs).min()..(max_x);
         let y_range = plotters::data::fitting_range(current_times.ys.iter());
         (x_range, y_range)
     };
 
     let mut chart = cb
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_range, y_range)
         .unwrap();
 
     chart
         .configure_mesh()
-        .y_desc(format!("Average Iteration Time ({})", unit))
-        .x_label_formatter(&|x| pretty_print_float(*x, true))
+        .x_desc(x_label)
+        .y_desc(format!("Average Iteration Time ({})", spec.unit))
+        .x_label_formatter(&|x| layout.axis_label(*x))
         .light_line_style(&TRANSPARENT)
         .draw()
         .unwrap();
 
+    let mut tooltips = Tooltips::new(style.interactive_svg && !spec.is_thumbnail);
+    for (x, y) in current_times.to_points() {
+        tooltips.add_circle(
+            chart.backend_coord(&(x, y)),
+            format!("{} {}: {} {}", x_label, x, short(y), spec.unit),
+        );
+    }
+
     chart
         .draw_series(
             (current_times.to_points())
-                .map(|(x, y)| Circle::new((x, y), POINT_SIZE, colors.current_sample.filled())),
+                .map(|(x, y)| point_style.marker((x, y), &colors.current_sample)),
         )
         .unwrap()
         .label("Current")
-        .legend(|(x, y)| Circle::new((x + 10, y), POINT_SIZE, colors.current_sample.filled()));
+        .legend(|(x, y)| point_style.marker((x + 10, y), &colors.current_sample));
+
+    if let Some(base_times) = &base_times {
+        for (x, y) in base_times.to_points() {
+            tooltips.add_circle(
+                chart.backend_coord(&(x, y)),
+                format!(
+                    "Base {} {}: {} {}",
+                    x_label.to_lowercase(),
+                    x,
+                    short(y),
+                    spec.unit
+                ),
+            );
+        }
 
-    if let Some(base_times) = base_times {
         chart
             .draw_series(
                 (base_times.to_points())
-                    .map(|(x, y)| Circle::new((x, y), POINT_SIZE, colors.previous_sample.filled())),
+                    .map(|(x, y)| point_style.marker((x, y), &colors.previous_sample)),
             )
             .unwrap()
             .label("Base")
-            .legend(|(x, y)| Circle::new((x + 10, y), POINT_SIZE, colors.previous_sample.filled()));
+            .legend(|(x, y)| point_style.marker((x + 10, y), &colors.previous_sample));
+    }
+
+    if let Some(rolling_median) = &rolling_median {
+        let style = colors
+            .current_sample
+            .stroke_width(layout.stroke_width(2, &root_area));
+        chart
+            .draw_series(LineSeries::new(rolling_median.to_points(), style.clone()))
+            .unwrap()
+            .label("Rolling median")
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], style.clone()));
     }
 
-    if !is_thumbnail {
-        cb.caption(id.as_title(), (DEFAULT_FONT, 20));
+    if !spec.is_thumbnail {
+        cb.caption(spec.id.as_title(), (DEFAULT_FONT, 20));
+        let position = legend_position(
+            layout,
+            &chart,
+            layout.legend.iteration_times,
+            SeriesLabelPosition::UpperLeft,
+            current_times
+                .to_points()
+                .chain(base_times.iter().flat_map(Points::to_points)),
+        );
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(position)
             .draw()
             .unwrap();
+        layout.draw_footnote(&root_area);
     }
+    // The SVG isn't complete until everything drawing to it has been dropped.
+    drop(chart);
+    drop(root_area);
+    tooltips.embed(target);
 }
@@ -0,0 +1,88 @@
+use crate::plot::plotters_backend::{PlotTarget, Style, DEFAULT_FONT, POINT_SIZE, SIZE};
+use crate::plot::{LineCurve, PlotSpec};
+use plotters::prelude::*;
+
+// The x coordinate of each percentile is its number of nines, ie. -log10(1 - p), so that the tail
+// is spread out with p90, p99 and p99.9 evenly spaced.
+fn nines(percentile: f64) -> f64 {
+    -(1.0 - percentile / 100.0).log10()
+}
+
+fn percentile_label(nines: f64) -> String {
+    let percentile = 100.0 * (1.0 - 10f64.powf(-nines));
+    format!("p{}", (percentile * 10.0).round() / 10.0)
+}
+
+pub fn latency_percentiles(
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    latencies: LineCurve,
+) {
+    let Style { colors, layout, .. } = style;
+    let points: Vec<(f64, f64)> = latencies
+        .to_points()
+        .map(|(percentile, latency)| (nines(percentile), latency))
+        .collect();
+    let x_max = points.iter().map(|&(x, _)| x).fold(1.0, f64::max);
+    let y_max = points.iter().map(|&(_, y)| y).fold(0.0, f64::max);
+
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
+
+    let mut chart = ChartBuilder::on(&root_area)
+        .margin(layout.margin())
+        .caption(
+            format!("{}: Latency percentiles", spec.id.as_title()),
+            (DEFAULT_FONT, 20),
+        )
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
+        .build_cartesian_2d(0.0..x_max.ceil(), 0.0..y_max * layout.y_headroom())
+        .unwrap();
+
+    chart
+        .configure_mesh()
+        .disable_mesh()
+        .x_desc("Percentile")
+        .y_desc(format!("Latency ({})", spec.unit))
+        .x_labels(x_max.ceil() as usize + 1)
+        .x_label_formatter(&|&x| percentile_label(x))
+        .y_label_formatter(&|&y| layout.axis_label(y))
+        .draw()
+        .unwrap();
+
+    chart
+        .draw_series(
+            LineSeries::new(points.iter().copied(), colors.current_sample.filled())
+                .point_size(POINT_SIZE),
+        )
+        .unwrap();
+
+    // Label each point with its percentile, since the axis only marks the whole nines.
+    chart
+        .draw_series(points.iter().map(|&(x, y)| {
+            Text::new(
+                percentile_label(x),
+                (x, y),
+                (DEFAULT_FONT, 12).into_font().color(&colors.current_sample),
+            )
+        }))
+        .unwrap();
+
+    layout.draw_footnote(&root_area);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_percentile_label() {
+        assert_eq!("p50", percentile_label(nines(50.0)));
+        assert_eq!("p90", percentile_label(nines(90.0)));
+        assert_eq!("p99.9", percentile_label(nines(99.9)));
+        assert_eq!("p0", percentile_label(0.0));
+    }
+}
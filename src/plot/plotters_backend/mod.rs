@@ -1,20 +1,32 @@
+use crate::config::{LegendPosition, LegendPositions, PlotOptions};
 use crate::connection::AxisScale;
 use crate::estimate::Statistic;
 use crate::plot::{
-    FilledCurve, Line, LineCurve, PlottingBackend, Points, Rectangle as RectangleArea, Size,
-    VerticalLine,
+    BootstrapDistribution, ContactSheetCell, FilledCurve, HeatmapGrid, IterationsAxis, Line,
+    LineCurve, LineTargets, PlotFeature, PlotSpec, PlottingBackend, Points,
+    Rectangle as RectangleArea, Size, Target, VerticalLine, ViolinEstimates,
 };
-use crate::report::{BenchmarkId, ValueType};
+use crate::report::ValueType;
+use plotters::coord::{CoordTranslate, Shift};
+use plotters::data::float::pretty_print_float;
 use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use plotters::style::SizeDesc;
+use std::ops::Range;
 use std::path::PathBuf;
 
+mod cdf;
+mod contact_sheet;
 mod distributions;
+mod heatmap;
 mod history;
 mod iteration_times;
+mod latency;
 mod pdf;
 mod regression;
 mod summary;
 mod t_test;
+mod tooltips;
 
 static DEFAULT_FONT: FontFamily = FontFamily::SansSerif;
 static SIZE: Size = Size(960, 540);
@@ -27,6 +39,8 @@ pub struct Colors {
     pub mild_outlier: RGBColor,
     pub severe_outlier: RGBColor,
     pub comparison_colors: Vec<RGBColor>,
+    pub regressed: RGBColor,
+    pub improved: RGBColor,
 }
 impl From<crate::config::Color> for RGBColor {
     fn from(other: crate::config::Color) -> Self {
@@ -47,6 +61,14 @@ impl From<&crate::config::Colors> for Colors {
                 .copied()
                 .map(RGBColor::from)
                 .collect(),
+            regressed: other
+                .regressed
+                .unwrap_or(crate::config::REGRESSED_PLOT_COLOR)
+                .into(),
+            improved: other
+                .improved
+                .unwrap_or(crate::config::IMPROVED_PLOT_COLOR)
+                .into(),
         }
     }
 }
@@ -84,277 +106,734 @@ impl<'a> Points<'a> {
     }
 }
 
-pub struct PlottersBackend {
+/// Where a plot is drawn to. The plots are normally written to SVG files, but library consumers
+/// which don't want any files written can draw into an in-memory buffer instead and route the SVG
+/// wherever they like.
+// Only the tests draw into buffers within cargo-criterion itself.
+#[allow(dead_code)]
+pub enum PlotTarget {
+    File(PathBuf),
+    Buffer(String),
+}
+#[allow(dead_code)]
+impl PlotTarget {
+    /// An empty in-memory buffer to draw a plot into.
+    pub fn buffer() -> PlotTarget {
+        PlotTarget::Buffer(String::new())
+    }
+
+    /// The SVG drawn into this target, if it's an in-memory buffer.
+    pub fn into_svg(self) -> Option<Vec<u8>> {
+        match self {
+            PlotTarget::File(_) => None,
+            PlotTarget::Buffer(svg) => Some(svg.into_bytes()),
+        }
+    }
+
+    fn backend(&mut self, size: (u32, u32)) -> SVGBackend<'_> {
+        match self {
+            PlotTarget::File(path) => SVGBackend::new(path.as_path(), size),
+            PlotTarget::Buffer(svg) => SVGBackend::with_string(svg, size),
+        }
+    }
+}
+impl From<PathBuf> for PlotTarget {
+    fn from(other: PathBuf) -> Self {
+        PlotTarget::File(other)
+    }
+}
+
+/// The margin and label area sizes to use for all charts, as percentages of the chart size, the
+/// footnote (if any) to stamp in their corner and where to put their legends.
+#[derive(Clone)]
+pub struct Layout {
+    margin: f64,
+    label_area: f64,
+    y_headroom: f64,
+    footnote: Option<String>,
+    legend: LegendPositions,
+    axis_significant_figures: Option<usize>,
+    stroke_width: f64,
+    gradient_bands: bool,
+    combined_fills: bool,
+    right_to_left: bool,
+}
+impl From<&PlotOptions> for Layout {
+    fn from(other: &PlotOptions) -> Self {
+        Layout {
+            margin: other.margin,
+            label_area: other.label_area,
+            y_headroom: other.y_headroom,
+            footnote: timestamp_footnote(other),
+            legend: other.legend.clone(),
+            axis_significant_figures: other.axis_significant_figures,
+            stroke_width: other.stroke_width,
+            gradient_bands: other.gradient_bands,
+            combined_fills: other.combined_fills,
+            right_to_left: other.right_to_left,
+        }
+    }
+}
+
+/// Rounds the value to the given number of significant figures and formats it with exactly that
+/// many, so that every label on an axis has the same precision.
+fn significant_figures(value: f64, figures: usize) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return pretty_print_float(value, true);
+    }
+    let magnitude = |value: f64| value.abs().log10().floor() as i32;
+    let step = 10f64.powi(magnitude(value) + 1 - figures as i32);
+    let rounded = (value / step).round() * step;
+    // Rounding can carry into the next power of ten (eg. 9.96 to 10.0), which takes a digit.
+    let decimals = (figures as i32 - 1 - magnitude(rounded)).max(0) as usize;
+    format!("{:.*}", decimals, rounded)
+}
+
+/// The time of the run (and the git commit, if requested) to stamp on each plot.
+fn timestamp_footnote(options: &PlotOptions) -> Option<String> {
+    if !options.timestamp {
+        return None;
+    }
+    let mut footnote = chrono::Local::now()
+        .format("%Y-%m-%d %H:%M:%S %z")
+        .to_string();
+    if options.timestamp_git_hash {
+        match crate::since::head_revision() {
+            Ok(hash) => footnote = format!("{} ({})", footnote, hash),
+            Err(e) => warn!("Unable to add the git commit to the plots: {:?}", e),
+        }
+    }
+    Some(footnote)
+}
+impl Layout {
+    fn margin(&self) -> impl SizeDesc {
+        self.margin.percent()
+    }
+
+    fn side_label_area(&self) -> impl SizeDesc {
+        self.label_area.percent_width().min(self.min_label_area(60))
+    }
+
+    fn bottom_label_area(&self) -> impl SizeDesc {
+        self.label_area
+            .percent_height()
+            .min(self.min_label_area(40))
+    }
+
+    /// The label areas are kept from shrinking below the given size in pixels on small plots so
+    /// that the labels still fit. That's the size for the default setting, and it's scaled with
+    /// the label area setting so that other settings still take effect.
+    fn min_label_area(&self, pixels: i32) -> i32 {
+        let scale = self.label_area / PlotOptions::default().label_area;
+        (f64::from(pixels) * scale).round() as i32
+    }
+
+    /// The side of the chart to draw the y-axis on; the right for right-to-left layouts.
+    fn y_label_position(&self) -> LabelAreaPosition {
+        if self.right_to_left {
+            LabelAreaPosition::Right
+        } else {
+            LabelAreaPosition::Left
+        }
+    }
+
+    fn y_headroom(&self) -> f64 {
+        self.y_headroom
+    }
+
+    /// Scales the width of a line, given in pixels for a full-size plot, for the width of the plot
+    /// and the configured `stroke_width` factor. The result is at least one pixel.
+    fn stroke_width<DB: DrawingBackend>(
+        &self,
+        base: u32,
+        root_area: &DrawingArea<DB, Shift>,
+    ) -> u32 {
+        let (width, _) = root_area.dim_in_pixel();
+        let scale = width as f64 / SIZE.0 as f64 * self.stroke_width;
+        ((base as f64 * scale).round() as u32).max(1)
+    }
+
+    /// Formats a value for the axis labels, with the configured number of significant figures if
+    /// one is set.
+    fn axis_label(&self, value: f64) -> String {
+        match self.axis_significant_figures {
+            Some(figures) => significant_figures(value, figures),
+            None => pretty_print_float(value, true),
+        }
+    }
+
+    /// Draws the footnote in small grey text in the bottom-right corner (bottom-left for
+    /// right-to-left layouts), clear of the axis labels. Thumbnails are too small for it, so
+    /// callers skip it for those.
+    fn draw_footnote<DB: DrawingBackend>(&self, root_area: &DrawingArea<DB, Shift>) {
+        if let Some(footnote) = &self.footnote {
+            let (width, height) = root_area.dim_in_pixel();
+            let (h_pos, x) = if self.right_to_left {
+                (HPos::Left, 4)
+            } else {
+                (HPos::Right, width as i32 - 4)
+            };
+            let color = BLACK.mix(0.5);
+            let style = TextStyle::from((DEFAULT_FONT, 10).into_font())
+                .color(&color)
+                .pos(Pos::new(h_pos, VPos::Bottom));
+            root_area
+                .draw_text(footnote, &style, (x, height as i32 - 2))
+                .unwrap();
+        }
+    }
+}
+
+/// Returns where to draw the legend of a chart: the configured position, or the default for the
+/// type of plot if none was configured. If the configured position is `auto`, the legend goes in
+/// whichever corner of the plotting area the fewest of the data points fall in. Right-to-left
+/// layouts mirror the other positions, but not `auto`, since the data itself isn't mirrored.
+fn legend_position<DB: DrawingBackend, CT: CoordTranslate<From = (f64, f64)>>(
+    layout: &Layout,
+    chart: &ChartContext<'_, DB, CT>,
+    configured: Option<LegendPosition>,
+    default: SeriesLabelPosition,
+    data: impl IntoIterator<Item = (f64, f64)>,
+) -> SeriesLabelPosition {
+    let position = match configured {
+        None => default,
+        Some(LegendPosition::Auto) => {
+            let (x_pixels, y_pixels) = chart.plotting_area().get_pixel_range();
+            let pixels = data
+                .into_iter()
+                .filter(|&(x, y)| x.is_finite() && y.is_finite())
+                .map(|point| chart.as_coord_spec().translate(&point));
+            return emptiest_corner(pixels, x_pixels, y_pixels);
+        }
+        Some(LegendPosition::UpperLeft) => SeriesLabelPosition::UpperLeft,
+        Some(LegendPosition::UpperMiddle) => SeriesLabelPosition::UpperMiddle,
+        Some(LegendPosition::UpperRight) => SeriesLabelPosition::UpperRight,
+        Some(LegendPosition::MiddleLeft) => SeriesLabelPosition::MiddleLeft,
+        Some(LegendPosition::MiddleRight) => SeriesLabelPosition::MiddleRight,
+        Some(LegendPosition::LowerLeft) => SeriesLabelPosition::LowerLeft,
+        Some(LegendPosition::LowerMiddle) => SeriesLabelPosition::LowerMiddle,
+        Some(LegendPosition::LowerRight) => SeriesLabelPosition::LowerRight,
+    };
+    if layout.right_to_left {
+        mirror_legend_position(position)
+    } else {
+        position
+    }
+}
+
+/// Swaps the left and right sides of a legend position.
+fn mirror_legend_position(position: SeriesLabelPosition) -> SeriesLabelPosition {
+    match position {
+        SeriesLabelPosition::UpperLeft => SeriesLabelPosition::UpperRight,
+        SeriesLabelPosition::UpperRight => SeriesLabelPosition::UpperLeft,
+        SeriesLabelPosition::MiddleLeft => SeriesLabelPosition::MiddleRight,
+        SeriesLabelPosition::MiddleRight => SeriesLabelPosition::MiddleLeft,
+        SeriesLabelPosition::LowerLeft => SeriesLabelPosition::LowerRight,
+        SeriesLabelPosition::LowerRight => SeriesLabelPosition::LowerLeft,
+        other => other,
+    }
+}
+
+// Returns the corner of the plotting area containing the fewest of the given pixels, taking each
+// corner to be the outer third of the width and height. Ties go to the upper corners, then to the
+// right.
+fn emptiest_corner(
+    pixels: impl Iterator<Item = (i32, i32)>,
+    x_pixels: Range<i32>,
+    y_pixels: Range<i32>,
+) -> SeriesLabelPosition {
+    let width = (x_pixels.end - x_pixels.start).max(1);
+    let height = (y_pixels.end - y_pixels.start).max(1);
+
+    // Upper right, upper left, lower right, lower left.
+    let mut counts = [0usize; 4];
+    for (x, y) in pixels {
+        let left = (x - x_pixels.start) * 3 < width;
+        let right = (x_pixels.end - x) * 3 < width;
+        let upper = (y - y_pixels.start) * 3 < height;
+        let lower = (y_pixels.end - y) * 3 < height;
+        let corner = match (upper, lower, right, left) {
+            (true, _, true, _) => 0,
+            (true, _, _, true) => 1,
+            (_, true, true, _) => 2,
+            (_, true, _, true) => 3,
+            _ => continue,
+        };
+        counts[corner] += 1;
+    }
+
+    let (emptiest, _) = counts
+        .iter()
+        .enumerate()
+        .min_by_key(|&(_, count)| count)
+        .unwrap();
+    match emptiest {
+        0 => SeriesLabelPosition::UpperRight,
+        1 => SeriesLabelPosition::UpperLeft,
+        2 => SeriesLabelPosition::LowerRight,
+        _ => SeriesLabelPosition::LowerLeft,
+    }
+}
+
+/// The size and opacity of the markers in the scatter plots.
+#[derive(Clone, Copy)]
+pub struct PointStyle {
+    size: u32,
+    alpha: f64,
+}
+impl From<&PlotOptions> for PointStyle {
+    fn from(other: &PlotOptions) -> Self {
+        PointStyle {
+            size: other.point_size,
+            alpha: other.point_alpha,
+        }
+    }
+}
+impl PointStyle {
+    fn marker<Coord, C: Color>(&self, center: Coord, color: &C) -> Circle<Coord, u32> {
+        Circle::new(center, self.size, color.mix(self.alpha).filled())
+    }
+}
+
+/// The vertical spacing (in pixels) and thickness (as a fraction of the spacing) of the violins
+/// in the violin plots.
+#[derive(Clone, Copy)]
+pub struct ViolinStyle {
+    spacing: u32,
+    width: f64,
+}
+impl From<&PlotOptions> for ViolinStyle {
+    fn from(other: &PlotOptions) -> Self {
+        ViolinStyle {
+            spacing: other.violin_spacing.unwrap_or(18),
+            width: other.violin_width.unwrap_or(1.0),
+        }
+    }
+}
+
+/// Appends the throughput and the sample count (if any) to the given caption, eg.
+/// "my_bench (1.2 GiB/s, n=100)".
+fn caption_with_details(caption: String, spec: &PlotSpec) -> String {
+    let details: Vec<String> = spec
+        .throughput
+        .map(ToOwned::to_owned)
+        .into_iter()
+        .chain(spec.sample_count.map(|count| format!("n={}", count)))
+        .collect();
+    if details.is_empty() {
+        caption
+    } else {
+        format!("{} ({})", caption, details.join(", "))
+    }
+}
+
+/// How the plots are drawn, whatever is drawn on them.
+pub struct Style {
     colors: Colors,
+    layout: Layout,
+    point_style: PointStyle,
+    violin_style: ViolinStyle,
+    interactive_svg: bool,
+}
+impl Style {
+    pub fn new(colors: &crate::config::Colors, options: &PlotOptions) -> Self {
+        Style {
+            colors: colors.into(),
+            layout: options.into(),
+            point_style: options.into(),
+            violin_style: options.into(),
+            interactive_svg: options.interactive_svg,
+        }
+    }
+}
+
+pub struct PlottersBackend {
+    style: Style,
 }
 impl PlottersBackend {
-    pub fn new(colors: &crate::config::Colors) -> Self {
+    pub fn new(colors: &crate::config::Colors, options: &PlotOptions) -> Self {
         PlottersBackend {
-            colors: colors.into(),
+            style: Style::new(colors, options),
         }
     }
 }
 impl PlottingBackend for PlottersBackend {
     fn abs_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        x_unit: &str,
-        distribution_curve: LineCurve,
-        bootstrap_area: FilledCurve,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
+        noise_band: Option<RectangleArea>,
     ) {
         distributions::abs_distribution(
-            &self.colors,
-            id,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
             statistic,
-            size,
-            path,
-            x_unit,
-            distribution_curve,
-            bootstrap_area,
-            point_estimate,
+            distribution,
+            noise_band,
         )
     }
 
     fn rel_distribution(
         &mut self,
-        id: &BenchmarkId,
+        spec: &PlotSpec,
         statistic: Statistic,
-        size: Option<Size>,
-        path: PathBuf,
-
-        distribution_curve: LineCurve,
-        confidence_interval: FilledCurve,
-        point_estimate: Line,
+        distribution: BootstrapDistribution,
         noise_threshold: RectangleArea,
     ) {
         distributions::rel_distribution(
-            &self.colors,
-            id,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
             statistic,
-            size,
-            path,
-            distribution_curve,
-            confidence_interval,
-            point_estimate,
+            distribution,
             noise_threshold,
         )
     }
 
     fn iteration_times(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        unit: &str,
-        is_thumbnail: bool,
+        spec: &PlotSpec,
+        x_label: &str,
         current_times: Points,
         base_times: Option<Points>,
+        rolling_median: Option<LineCurve>,
     ) {
         iteration_times::iteration_times(
-            &self.colors,
-            id,
-            size,
-            path,
-            unit,
-            is_thumbnail,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            x_label,
             current_times,
             base_times,
+            rolling_median,
         )
     }
 
     fn regression(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        y_scale: AxisScale,
         sample: Points,
-        regression: Line,
-        confidence_interval: FilledCurve,
+        regression: (LineCurve, FilledCurve),
     ) {
         regression::regression(
-            &self.colors,
-            id,
-            size,
-            path,
-            is_thumbnail,
-            x_label,
-            x_scale,
-            unit,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            x_axis,
+            y_scale,
             sample,
             regression,
-            confidence_interval,
         );
     }
 
     fn regression_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-        x_label: &str,
-        x_scale: f64,
-        unit: &str,
-        current_regression: Line,
-        current_confidence_interval: FilledCurve,
-        base_regression: Line,
-        base_confidence_interval: FilledCurve,
+        spec: &PlotSpec,
+        x_axis: IterationsAxis,
+        current_regression: (Line, FilledCurve),
+        base_regression: (Line, FilledCurve),
     ) {
         regression::regression_comparison(
-            &self.colors,
-            id,
-            size,
-            path,
-            is_thumbnail,
-            x_label,
-            x_scale,
-            unit,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            x_axis,
             current_regression,
-            current_confidence_interval,
             base_regression,
-            base_confidence_interval,
         );
     }
 
     fn pdf_full(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        unit: &str,
-        y_label: &str,
-        y_scale: f64,
-        max_iters: f64,
-        pdf: FilledCurve,
-        mean: VerticalLine,
+        spec: &PlotSpec,
+        y_axis: IterationsAxis,
+        pdf: (VerticalLine, FilledCurve),
         fences: (VerticalLine, VerticalLine, VerticalLine, VerticalLine),
         points: (Points, Points, Points),
     ) {
         pdf::pdf_full(
-            &self.colors,
-            id,
-            size,
-            path,
-            unit,
-            y_label,
-            y_scale,
-            max_iters,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            y_axis,
             pdf,
-            mean,
             fences,
             points,
         );
     }
 
-    fn pdf_thumbnail(
-        &mut self,
-        size: Option<Size>,
-        path: PathBuf,
-        unit: &str,
-        mean: Line,
-        pdf: FilledCurve,
-    ) {
-        pdf::pdf_thumbnail(&self.colors, size, path, unit, mean, pdf);
+    fn pdf_thumbnail(&mut self, spec: &PlotSpec, mean: Line, pdf: FilledCurve) {
+        pdf::pdf_thumbnail(&self.style, spec, &mut spec.path.clone().into(), mean, pdf);
     }
 
     fn pdf_comparison(
         &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        is_thumbnail: bool,
-        unit: &str,
-        current_mean: Line,
-        current_pdf: FilledCurve,
-        base_mean: Line,
-        base_pdf: FilledCurve,
+        spec: &PlotSpec,
+        current_pdf: (Line, FilledCurve),
+        base_pdf: (Line, FilledCurve),
+        overlays: &[(&str, LineCurve)],
     ) {
         pdf::pdf_comparison(
-            &self.colors,
-            id,
-            size,
-            path,
-            is_thumbnail,
-            unit,
-            current_mean,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
             current_pdf,
-            base_mean,
             base_pdf,
+            overlays,
         );
     }
 
-    fn t_test(
-        &mut self,
-        id: &BenchmarkId,
-        size: Option<Size>,
-        path: PathBuf,
-        t: VerticalLine,
-        t_distribution: FilledCurve,
-    ) {
-        t_test::t_test(&self.colors, id, size, path, t, t_distribution);
+    fn t_test(&mut self, spec: &PlotSpec, t: VerticalLine, t_distribution: FilledCurve) {
+        t_test::t_test(
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            t,
+            t_distribution,
+        );
     }
 
     fn line_comparison(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         value_type: ValueType,
         axis_scale: AxisScale,
         lines: &[(Option<&String>, LineCurve)],
+        targets: LineTargets,
     ) {
         summary::line_comparison(
-            &self.colors,
-            path,
-            title,
-            unit,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
             value_type,
             axis_scale,
             lines,
+            targets,
         );
     }
 
     fn violin(
         &mut self,
-        path: PathBuf,
-        title: &str,
-        unit: &str,
+        spec: &PlotSpec,
         axis_scale: AxisScale,
         lines: &[(&str, LineCurve)],
+        estimates: Option<&[ViolinEstimates]>,
+        targets: &[Option<Target>],
     ) {
-        summary::violin(&self.colors, path, title, unit, axis_scale, lines);
+        summary::violin(
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            axis_scale,
+            lines,
+            estimates,
+            targets,
+        );
+    }
+
+    fn heatmap(&mut self, spec: &PlotSpec, grid: HeatmapGrid) -> bool {
+        heatmap::heatmap(&self.style, spec, &mut spec.path.clone().into(), grid);
+        true
+    }
+
+    fn latency_percentiles(&mut self, spec: &PlotSpec, latencies: LineCurve) -> bool {
+        latency::latency_percentiles(&self.style, spec, &mut spec.path.clone().into(), latencies);
+        true
+    }
+
+    fn cdf(&mut self, spec: &PlotSpec, cdf: LineCurve, percentiles: &[(f64, f64)]) -> bool {
+        cdf::cdf(
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
+            cdf,
+            percentiles,
+        );
+        true
     }
 
     fn history_plot(
         &mut self,
-        id: &BenchmarkId,
-        size: Size,
-        path: PathBuf,
-        point_estimate: LineCurve,
-        confidence_interval: FilledCurve,
+        spec: &PlotSpec,
+        point_estimate: (LineCurve, FilledCurve),
+        changes: (Points, Points),
         ids: &[String],
-        unit: &str,
     ) {
         history::history(
-            &self.colors,
-            id.as_title(),
-            size,
-            path,
+            &self.style,
+            spec,
+            &mut spec.path.clone().into(),
             point_estimate,
-            confidence_interval,
+            changes,
             ids,
-            unit,
         );
     }
 
+    fn contact_sheet(&mut self, path: PathBuf, cells: &[ContactSheetCell]) -> bool {
+        contact_sheet::contact_sheet(&self.style, &mut path.into(), cells);
+        true
+    }
+
+    fn supports(&self, _feature: PlotFeature) -> bool {
+        true
+    }
+
     fn wait(&mut self) {}
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::report::BenchmarkId;
+
+    fn spec(id: &BenchmarkId) -> PlotSpec<'_> {
+        PlotSpec {
+            id,
+            size: None,
+            path: PathBuf::new(),
+            is_thumbnail: false,
+            unit: "",
+            sample_count: None,
+            throughput: None,
+        }
+    }
+
+    #[test]
+    fn test_significant_figures() {
+        assert_eq!("1.23", significant_figures(1.2345678, 3));
+        assert_eq!("1.20", significant_figures(1.2, 3));
+        assert_eq!("0.00457", significant_figures(0.0045678, 3));
+        assert_eq!("1230", significant_figures(1234.5, 3));
+        assert_eq!("-12", significant_figures(-12.3, 2));
+        assert_eq!("10", significant_figures(9.96, 2));
+    }
+
+    #[test]
+    fn test_stroke_width() {
+        let layout = Layout::from(&PlotOptions::default());
+        let mut target = PlotTarget::buffer();
+        let root_area = target.backend((960, 540)).into_drawing_area();
+        assert_eq!(3, layout.stroke_width(3, &root_area));
+        drop(root_area);
+        let root_area = target.backend((320, 180)).into_drawing_area();
+        assert_eq!(1, layout.stroke_width(3, &root_area));
+        drop(root_area);
+
+        let layout = Layout {
+            stroke_width: 2.0,
+            ..Layout::from(&PlotOptions::default())
+        };
+        let root_area = target.backend((960, 540)).into_drawing_area();
+        assert_eq!(6, layout.stroke_width(3, &root_area));
+    }
+
+    #[test]
+    fn test_label_area() {
+        let layout = Layout::from(&PlotOptions::default());
+        assert_eq!(60, layout.side_label_area().in_pixels(&(960, 540)));
+        assert_eq!(60, layout.side_label_area().in_pixels(&(300, 200)));
+        assert_eq!(40, layout.bottom_label_area().in_pixels(&(300, 200)));
+
+        let layout = Layout {
+            label_area: 1.0,
+            ..Layout::from(&PlotOptions::default())
+        };
+        assert_eq!(12, layout.side_label_area().in_pixels(&(1200, 800)));
+        assert_eq!(8, layout.bottom_label_area().in_pixels(&(1200, 800)));
+    }
+
+    #[test]
+    fn test_draw_to_buffer() {
+        let style = Style::new(&crate::config::Colors::default(), &PlotOptions::default());
+        let id = BenchmarkId::new("my_group".to_owned(), None, None, None);
+        let xs = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let ys = [0.05, 0.25, 0.4, 0.25, 0.05];
+        let zeros = [0.0; 5];
+
+        let mut target = PlotTarget::buffer();
+        t_test::t_test(
+            &style,
+            &spec(&id),
+            &mut target,
+            VerticalLine { x: 0.5 },
+            FilledCurve {
+                xs: &xs,
+                ys_1: &ys,
+                ys_2: &zeros,
+            },
+        );
+
+        let svg = String::from_utf8(target.into_svg().unwrap()).unwrap();
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("my_group: Welch t test"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_draw_footnote() {
+        let style = Style {
+            layout: Layout {
+                footnote: Some("2021-03-04 05:06:07 +0000 (abc1234)".to_owned()),
+                ..Layout::from(&PlotOptions::default())
+            },
+            ..Style::new(&crate::config::Colors::default(), &PlotOptions::default())
+        };
+        let id = BenchmarkId::new("my_group".to_owned(), None, None, None);
+        let xs = [-2.0, -1.0, 0.0, 1.0, 2.0];
+        let ys = [0.05, 0.25, 0.4, 0.25, 0.05];
+        let zeros = [0.0; 5];
+
+        let mut target = PlotTarget::buffer();
+        t_test::t_test(
+            &style,
+            &spec(&id),
+            &mut target,
+            VerticalLine { x: 0.5 },
+            FilledCurve {
+                xs: &xs,
+                ys_1: &ys,
+                ys_2: &zeros,
+            },
+        );
+
+        let svg = String::from_utf8(target.into_svg().unwrap()).unwrap();
+        assert!(svg.contains("2021-03-04 05:06:07 +0000 (abc1234)"));
+    }
+
+    #[test]
+    fn test_mirror_legend_position() {
+        assert!(matches!(
+            mirror_legend_position(SeriesLabelPosition::UpperLeft),
+            SeriesLabelPosition::UpperRight
+        ));
+        assert!(matches!(
+            mirror_legend_position(SeriesLabelPosition::MiddleRight),
+            SeriesLabelPosition::MiddleLeft
+        ));
+        assert!(matches!(
+            mirror_legend_position(SeriesLabelPosition::LowerMiddle),
+            SeriesLabelPosition::LowerMiddle
+        ));
+    }
+
+    #[test]
+    fn test_emptiest_corner() {
+        let corner = |pixels: &[(i32, i32)]| emptiest_corner(pixels.iter().copied(), 0..90, 0..90);
+        // Nothing anywhere, so the first preference wins.
+        assert!(matches!(corner(&[]), SeriesLabelPosition::UpperRight));
+        // A curve rising to the right fills the upper right and lower left corners.
+        assert!(matches!(
+            corner(&[(5, 85), (45, 45), (85, 5)]),
+            SeriesLabelPosition::UpperLeft
+        ));
+        // Points in the middle of an edge aren't in any corner.
+        assert!(matches!(
+            corner(&[(45, 5), (85, 5), (5, 5), (85, 85)]),
+            SeriesLabelPosition::LowerLeft
+        ));
+    }
+}
@@ -1,41 +1,53 @@
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, POINT_SIZE, SIZE};
-use crate::plot::{FilledCurve, Line, Points, Size, VerticalLine};
-use crate::report::BenchmarkId;
+use crate::format::short;
+use crate::plot::plotters_backend::tooltips::Tooltips;
+use crate::plot::plotters_backend::{
+    caption_with_details, legend_position, PlotTarget, Style, DEFAULT_FONT, SIZE,
+};
+use crate::plot::{FilledCurve, IterationsAxis, Line, LineCurve, PlotSpec, Points, VerticalLine};
 use crate::stats::univariate::Sample;
-use plotters::data::float::pretty_print_float;
 use plotters::prelude::*;
 use plotters::style::RGBAColor;
-use std::path::PathBuf;
 
 pub fn pdf_full(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
-    unit: &str,
-    y_label: &str,
-    y_scale: f64,
-    max_iters: f64,
-    pdf: FilledCurve,
-    mean: VerticalLine,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    y_axis: IterationsAxis,
+    pdf: (VerticalLine, FilledCurve),
     fences: (VerticalLine, VerticalLine, VerticalLine, VerticalLine),
     points: (Points, Points, Points),
 ) {
+    let Style {
+        colors,
+        layout,
+        point_style,
+        ..
+    } = style;
+    let IterationsAxis {
+        label: y_label,
+        scale: y_scale,
+        max: max_iters,
+    } = y_axis;
+    let (mean, pdf) = pdf;
     let (low_severe, low_mild, high_mild, high_severe) = fences;
     let (not_outlier, mild, severe) = points;
     let xs_ = Sample::new(pdf.xs);
 
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
 
     let range = plotters::data::fitting_range(pdf.ys_1.iter());
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
-        .caption(id.as_title(), (DEFAULT_FONT, 20))
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Right, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .caption(
+            caption_with_details(spec.id.as_title().to_owned(), spec),
+            (DEFAULT_FONT, 20),
+        )
+        // The density is on a secondary y-axis, so this chart has one on each side either way.
+        .set_label_area_size(LabelAreaPosition::Left, layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Right, layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(xs_.min()..xs_.max(), 0.0..max_iters)
         .unwrap()
         .set_secondary_coord(xs_.min()..xs_.max(), 0.0..range.end);
@@ -44,17 +56,17 @@ pub fn pdf_full(
         .configure_mesh()
         .disable_mesh()
         .y_desc(y_label)
-        .x_desc(format!("Average Time ({})", unit))
-        .x_label_formatter(&|&x| pretty_print_float(x, true))
-        .y_label_formatter(&|&y| pretty_print_float(y * y_scale, true))
+        .x_desc(format!("Average Time ({})", spec.unit))
+        .x_label_formatter(&|&x| layout.axis_label(x))
+        .y_label_formatter(&|&y| layout.axis_label(y * y_scale))
         .draw()
         .unwrap();
 
     chart
         .configure_secondary_axes()
         .y_desc("Density (a.u.)")
-        .x_label_formatter(&|&x| pretty_print_float(x, true))
-        .y_label_formatter(&|&y| pretty_print_float(y, true))
+        .x_label_formatter(&|&x| layout.axis_label(x))
+        .y_label_formatter(&|&y| layout.axis_label(y))
         .draw()
         .unwrap();
 
@@ -91,14 +103,29 @@ pub fn pdf_full(
         ])
         .unwrap();
 
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.pdf,
+        SeriesLabelPosition::MiddleRight,
+        (not_outlier.to_points())
+            .chain(mild.to_points())
+            .chain(severe.to_points()),
+    );
+
+    let mut tooltips = Tooltips::new(style.interactive_svg);
     let mut draw_data_point_series = |points: Points, color: RGBAColor, name: &str| {
+        for (x, y) in points.to_points() {
+            tooltips.add_circle(
+                chart.backend_coord(&(x, y)),
+                format!("{} {}", short(x), spec.unit),
+            );
+        }
         chart
-            .draw_series(
-                (points.to_points()).map(|(x, y)| Circle::new((x, y), POINT_SIZE, color.filled())),
-            )
+            .draw_series((points.to_points()).map(|(x, y)| point_style.marker((x, y), &color)))
             .unwrap()
             .label(name)
-            .legend(move |(x, y)| Circle::new((x + 10, y), POINT_SIZE, color.filled()));
+            .legend(move |(x, y)| point_style.marker((x + 10, y), &color));
     };
 
     draw_data_point_series(
@@ -108,29 +135,38 @@ pub fn pdf_full(
     );
     draw_data_point_series(mild, colors.mild_outlier.to_rgba(), "Mild outliers");
     draw_data_point_series(severe, colors.severe_outlier.to_rgba(), "Severe outliers");
-    chart.configure_series_labels().draw().unwrap();
+    chart
+        .configure_series_labels()
+        .position(position)
+        .draw()
+        .unwrap();
+    layout.draw_footnote(&root_area);
+    // The SVG isn't complete until everything drawing to it has been dropped.
+    drop(chart);
+    drop(root_area);
+    tooltips.embed(target);
 }
 
 pub fn pdf_thumbnail(
-    colors: &Colors,
-    size: Option<Size>,
-    path: PathBuf,
-    unit: &str,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     mean: Line,
     pdf: FilledCurve,
 ) {
+    let Style { colors, layout, .. } = style;
     let xs_ = Sample::new(pdf.xs);
     let ys_ = Sample::new(pdf.ys_1);
 
     let y_limit = ys_.max() * 1.1;
 
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(xs_.min()..xs_.max(), 0.0..y_limit)
         .unwrap();
 
@@ -138,9 +174,9 @@ pub fn pdf_thumbnail(
         .configure_mesh()
         .disable_mesh()
         .y_desc("Density (a.u.)")
-        .x_desc(format!("Average Time ({})", unit))
-        .x_label_formatter(&|&x| pretty_print_float(x, true))
-        .y_label_formatter(&|&y| pretty_print_float(y, true))
+        .x_desc(format!("Average Time ({})", spec.unit))
+        .x_label_formatter(&|&x| layout.axis_label(x))
+        .y_label_formatter(&|&y| layout.axis_label(y))
         .x_labels(5)
         .draw()
         .unwrap();
@@ -156,40 +192,54 @@ pub fn pdf_thumbnail(
     chart
         .draw_series(std::iter::once(PathElement::new(
             mean.to_line_vec(),
-            colors.current_sample.filled().stroke_width(2),
+            colors
+                .current_sample
+                .filled()
+                .stroke_width(layout.stroke_width(2, &root_area)),
         )))
         .unwrap();
 }
 
 pub fn pdf_comparison(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
-    is_thumbnail: bool,
-    unit: &str,
-    current_mean: Line,
-    current_pdf: FilledCurve,
-    base_mean: Line,
-    base_pdf: FilledCurve,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    current_pdf: (Line, FilledCurve),
+    base_pdf: (Line, FilledCurve),
+    overlays: &[(&str, LineCurve)],
 ) {
-    let x_range = plotters::data::fitting_range(base_pdf.xs.iter().chain(current_pdf.xs.iter()));
-    let y_range =
-        plotters::data::fitting_range(base_pdf.ys_1.iter().chain(current_pdf.ys_1.iter()));
+    let Style { colors, layout, .. } = style;
+    let (current_mean, current_pdf) = current_pdf;
+    let (base_mean, base_pdf) = base_pdf;
+    let x_range = plotters::data::fitting_range(
+        base_pdf
+            .xs
+            .iter()
+            .chain(current_pdf.xs.iter())
+            .chain(overlays.iter().flat_map(|(_, pdf)| pdf.xs.iter())),
+    );
+    let y_range = plotters::data::fitting_range(
+        base_pdf
+            .ys_1
+            .iter()
+            .chain(current_pdf.ys_1.iter())
+            .chain(overlays.iter().flat_map(|(_, pdf)| pdf.ys.iter())),
+    );
 
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
+    let stroke_width = layout.stroke_width(2, &root_area);
 
     let mut cb = ChartBuilder::on(&root_area);
 
-    if !is_thumbnail {
-        cb.caption(id.as_title(), (DEFAULT_FONT, 20));
+    if !spec.is_thumbnail {
+        cb.caption(spec.id.as_title(), (DEFAULT_FONT, 20));
     }
 
     let mut chart = cb
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_range, y_range.clone())
         .unwrap();
 
@@ -197,9 +247,9 @@ pub fn pdf_comparison(
         .configure_mesh()
         .disable_mesh()
         .y_desc("Density (a.u.)")
-        .x_desc(format!("Average Time ({})", unit))
-        .x_label_formatter(&|&x| pretty_print_float(x, true))
-        .y_label_formatter(&|&y| pretty_print_float(y, true))
+        .x_desc(format!("Average Time ({})", spec.unit))
+        .x_label_formatter(&|&x| layout.axis_label(x))
+        .y_label_formatter(&|&y| layout.axis_label(y))
         .x_labels(5)
         .draw()
         .unwrap();
@@ -237,7 +287,7 @@ pub fn pdf_comparison(
     chart
         .draw_series(std::iter::once(PathElement::new(
             base_mean.to_line_vec(),
-            colors.previous_sample.filled().stroke_width(2),
+            colors.previous_sample.filled().stroke_width(stroke_width),
         )))
         .unwrap()
         .label("Base Mean")
@@ -246,13 +296,37 @@ pub fn pdf_comparison(
     chart
         .draw_series(std::iter::once(PathElement::new(
             current_mean.to_line_vec(),
-            colors.current_sample.filled().stroke_width(2),
+            colors.current_sample.filled().stroke_width(stroke_width),
         )))
         .unwrap()
         .label("New Mean")
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &colors.current_sample));
 
-    if !is_thumbnail {
-        chart.configure_series_labels().draw().unwrap();
+    for (i, (name, pdf)) in overlays.iter().enumerate() {
+        let color = colors.comparison_colors[i % colors.comparison_colors.len()];
+        chart
+            .draw_series(LineSeries::new(
+                pdf.to_points(),
+                color.stroke_width(stroke_width),
+            ))
+            .unwrap()
+            .label(*name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    if !spec.is_thumbnail {
+        let position = legend_position(
+            layout,
+            &chart,
+            layout.legend.pdf,
+            SeriesLabelPosition::MiddleRight,
+            base_pdf.to_points().chain(current_pdf.to_points()),
+        );
+        chart
+            .configure_series_labels()
+            .position(position)
+            .draw()
+            .unwrap();
+        layout.draw_footnote(&root_area);
     }
 }
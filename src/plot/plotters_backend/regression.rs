@@ -1,135 +1,209 @@
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, POINT_SIZE, SIZE};
-use crate::plot::{FilledCurve, Line, Points, Size};
-use crate::report::BenchmarkId;
-use plotters::data::float::pretty_print_float;
+use crate::connection::AxisScale;
+use crate::format::short;
+use crate::plot::plotters_backend::tooltips::Tooltips;
+use crate::plot::plotters_backend::{
+    caption_with_details, legend_position, PlotTarget, Style, DEFAULT_FONT, SIZE,
+};
+use crate::plot::{FilledCurve, IterationsAxis, Line, LineCurve, PlotSpec, Points};
+use plotters::coord::ranged1d::{AsRangedCoord, ValueFormatter as PlottersValueFormatter};
+use plotters::coord::Shift;
 use plotters::prelude::*;
-use std::path::PathBuf;
 
 pub fn regression(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
-    is_thumbnail: bool,
-    x_label: &str,
-    x_scale: f64,
-    unit: &str,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    x_axis: IterationsAxis,
+    y_scale: AxisScale,
     sample: Points,
-    regression: Line,
-    confidence_interval: FilledCurve,
+    regression: (LineCurve, FilledCurve),
 ) {
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
 
-    let mut cb = ChartBuilder::on(&root_area);
-    if !is_thumbnail {
-        cb.caption(id.as_title(), (DEFAULT_FONT, 20));
+    let y_range = plotters::data::fitting_range(sample.ys.iter());
+    let tooltips = match y_scale {
+        AxisScale::Linear => {
+            draw_regression_figure(style, spec, &root_area, x_axis, y_range, sample, regression)
+        }
+        AxisScale::Logarithmic => draw_regression_figure(
+            style,
+            spec,
+            &root_area,
+            x_axis,
+            y_range.log_scale(),
+            sample,
+            regression,
+        ),
+    };
+    if !spec.is_thumbnail {
+        style.layout.draw_footnote(&root_area);
+    }
+    // The SVG isn't complete until everything drawing to it has been dropped.
+    drop(root_area);
+    tooltips.embed(target);
+}
+
+/// Draws the regression chart with the given y range, which may be logarithmic, and returns the
+/// tooltips to embed once the SVG is complete.
+fn draw_regression_figure<YR: AsRangedCoord<Value = f64>>(
+    style: &Style,
+    spec: &PlotSpec,
+    root_area: &DrawingArea<SVGBackend, Shift>,
+    x_axis: IterationsAxis,
+    y_range: YR,
+    sample: Points,
+    regression: (LineCurve, FilledCurve),
+) -> Tooltips
+where
+    YR::CoordDescType: PlottersValueFormatter<f64>,
+{
+    let Style {
+        colors,
+        layout,
+        point_style,
+        ..
+    } = style;
+    let IterationsAxis {
+        label: x_label,
+        scale: x_scale,
+        ..
+    } = x_axis;
+    let (regression, confidence_interval) = regression;
+    let unit = spec.unit;
+
+    let mut cb = ChartBuilder::on(root_area);
+    if !spec.is_thumbnail {
+        cb.caption(
+            caption_with_details(spec.id.as_title().to_owned(), spec),
+            (DEFAULT_FONT, 20),
+        );
     }
 
     let x_range = plotters::data::fitting_range(sample.xs.iter());
-    let y_range = plotters::data::fitting_range(sample.ys.iter());
 
-    let mut chart = cb
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
-        .build_cartesian_2d(x_range, y_range)
-        .unwrap();
+    cb.margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area());
+
+    let mut tooltips = Tooltips::new(style.interactive_svg && !spec.is_thumbnail);
+    let stroke_width = layout.stroke_width(2, root_area);
+    let mut chart = cb.build_cartesian_2d(x_range, y_range).unwrap();
 
     chart
         .configure_mesh()
         .x_desc(x_label)
         .y_desc(format!("Total sample time ({})", unit))
-        .x_label_formatter(&|x| pretty_print_float(x * x_scale, true))
+        .x_label_formatter(&|x| layout.axis_label(x * x_scale))
+        .y_label_formatter(&|y| layout.axis_label(*y))
         .light_line_style(&TRANSPARENT)
         .draw()
         .unwrap();
 
+    for (x, y) in sample.to_points() {
+        tooltips.add_circle(
+            chart.backend_coord(&(x, y)),
+            format!("{} iterations: {} {}", x * x_scale, short(y), unit),
+        );
+    }
+
     chart
         .draw_series(
-            (sample.to_points())
-                .map(|(x, y)| Circle::new((x, y), POINT_SIZE, colors.current_sample.filled())),
+            (sample.to_points()).map(|(x, y)| point_style.marker((x, y), &colors.current_sample)),
         )
         .unwrap()
         .label("Sample")
-        .legend(|(x, y)| Circle::new((x + 10, y), POINT_SIZE, colors.current_sample.filled()));
+        .legend(move |(x, y)| point_style.marker((x + 10, y), &colors.current_sample));
 
     chart
         .draw_series(std::iter::once(PathElement::new(
-            regression.to_line_vec(),
+            regression.to_points().collect::<Vec<_>>(),
             &colors.current_sample,
         )))
         .unwrap()
         .label("Linear regression")
-        .legend(|(x, y)| {
+        .legend(move |(x, y)| {
             PathElement::new(
                 vec![(x, y), (x + 20, y)],
-                colors.current_sample.filled().stroke_width(2),
+                colors.current_sample.filled().stroke_width(stroke_width),
             )
         });
 
+    // The upper bound of the confidence interval from left to right, then the lower bound back.
+    let upper_bound =
+        (confidence_interval.xs.iter().copied()).zip(confidence_interval.ys_2.iter().copied());
+    let lower_bound =
+        (confidence_interval.xs.iter().copied()).zip(confidence_interval.ys_1.iter().copied());
     chart
         .draw_series(std::iter::once(Polygon::new(
-            vec![
-                (confidence_interval.xs[0], confidence_interval.ys_2[0]),
-                (confidence_interval.xs[1], confidence_interval.ys_1[1]),
-                (confidence_interval.xs[1], confidence_interval.ys_2[1]),
-            ],
+            upper_bound.chain(lower_bound.rev()).collect::<Vec<_>>(),
             colors.current_sample.mix(0.25).filled(),
         )))
         .unwrap()
         .label("Confidence interval")
-        .legend(|(x, y)| {
+        .legend(move |(x, y)| {
             Rectangle::new(
                 [(x, y - 5), (x + 20, y + 5)],
                 colors.current_sample.mix(0.25).filled(),
             )
         });
 
-    if !is_thumbnail {
+    if !spec.is_thumbnail {
+        let position = legend_position(
+            layout,
+            &chart,
+            layout.legend.regression,
+            SeriesLabelPosition::UpperLeft,
+            sample.to_points(),
+        );
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(position)
             .draw()
             .unwrap();
     }
+
+    tooltips
 }
 
 pub fn regression_comparison(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
-    is_thumbnail: bool,
-    x_label: &str,
-    x_scale: f64,
-    unit: &str,
-    current_regression: Line,
-    current_confidence_interval: FilledCurve,
-    base_regression: Line,
-    base_confidence_interval: FilledCurve,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
+    x_axis: IterationsAxis,
+    current_regression: (Line, FilledCurve),
+    base_regression: (Line, FilledCurve),
 ) {
+    let Style { colors, layout, .. } = style;
+    let IterationsAxis {
+        label: x_label,
+        scale: x_scale,
+        ..
+    } = x_axis;
+    let (current_regression, current_confidence_interval) = current_regression;
+    let (base_regression, base_confidence_interval) = base_regression;
     let y_max = current_regression.end.y.max(base_regression.end.y);
-    let size = size.unwrap_or(SIZE);
-    let root_area = SVGBackend::new(&path, size.into()).into_drawing_area();
+    let size = spec.size.unwrap_or(SIZE);
+    let root_area = target.backend(size.into()).into_drawing_area();
+    let stroke_width = layout.stroke_width(2, &root_area);
 
     let mut cb = ChartBuilder::on(&root_area);
-    if !is_thumbnail {
-        cb.caption(id.as_title(), (DEFAULT_FONT, 20));
+    if !spec.is_thumbnail {
+        cb.caption(spec.id.as_title(), (DEFAULT_FONT, 20));
     }
 
     let mut chart = cb
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(0.0..current_regression.end.x, 0.0..y_max)
         .unwrap();
 
     chart
         .configure_mesh()
         .x_desc(x_label)
-        .y_desc(format!("Total sample time ({})", unit))
-        .x_label_formatter(&|x| pretty_print_float(x * x_scale, true))
+        .y_desc(format!("Total sample time ({})", spec.unit))
+        .x_label_formatter(&|x| layout.axis_label(x * x_scale))
         .light_line_style(&TRANSPARENT)
         .draw()
         .unwrap();
@@ -161,7 +235,7 @@ pub fn regression_comparison(
         .legend(|(x, y)| {
             PathElement::new(
                 vec![(x, y), (x + 20, y)],
-                colors.previous_sample.filled().stroke_width(2),
+                colors.previous_sample.filled().stroke_width(stroke_width),
             )
         });
 
@@ -192,15 +266,26 @@ pub fn regression_comparison(
         .legend(|(x, y)| {
             PathElement::new(
                 vec![(x, y), (x + 20, y)],
-                colors.current_sample.filled().stroke_width(2),
+                colors.current_sample.filled().stroke_width(stroke_width),
             )
         });
 
-    if !is_thumbnail {
+    if !spec.is_thumbnail {
+        let position = legend_position(
+            layout,
+            &chart,
+            layout.legend.regression,
+            SeriesLabelPosition::UpperLeft,
+            base_regression
+                .to_line_vec()
+                .into_iter()
+                .chain(current_regression.to_line_vec()),
+        );
         chart
             .configure_series_labels()
-            .position(SeriesLabelPosition::UpperLeft)
+            .position(position)
             .draw()
             .unwrap();
+        layout.draw_footnote(&root_area);
     }
 }
@@ -1,60 +1,84 @@
 use crate::connection::AxisScale;
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, POINT_SIZE, SIZE};
-use crate::plot::LineCurve;
+use crate::plot::plotters_backend::{
+    legend_position, PlotTarget, Style, DEFAULT_FONT, POINT_SIZE, SIZE,
+};
+use crate::plot::{LineCurve, LineTargets, PlotSpec, Points, Target, ViolinEstimates};
 use crate::report::ValueType;
 use plotters::coord::{
     ranged1d::{AsRangedCoord, ValueFormatter as PlottersValueFormatter},
     Shift,
 };
 use plotters::prelude::*;
-use std::path::PathBuf;
 
 pub fn line_comparison(
-    colors: &Colors,
-    path: PathBuf,
-    title: &str,
-    unit: &str,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     value_type: ValueType,
     axis_scale: AxisScale,
     lines: &[(Option<&String>, LineCurve)],
+    targets: LineTargets,
 ) {
+    let LineTargets {
+        time: target_time,
+        exceeded,
+    } = targets;
     let x_range =
         plotters::data::fitting_range(lines.iter().flat_map(|(_, curve)| curve.xs.iter()));
-    let y_range =
+    let mut y_range =
         plotters::data::fitting_range(lines.iter().flat_map(|(_, curve)| curve.ys.iter()));
-    let root_area = SVGBackend::new(&path, SIZE.into())
+    // Make sure the target line is within the plot.
+    if let Some(target_time) = target_time {
+        y_range.start = y_range.start.min(target_time);
+        y_range.end = y_range.end.max(target_time);
+    }
+    // The target line spans the whole x axis.
+    let target_line = target_time
+        .map(|target_time| vec![(x_range.start, target_time), (x_range.end, target_time)]);
+    let root_area = target
+        .backend(SIZE.into())
         .into_drawing_area()
-        .titled(&format!("{}: Comparison", title), (DEFAULT_FONT, 20))
+        .titled(
+            &format!("{}: Comparison", spec.id.as_title()),
+            (DEFAULT_FONT, 20),
+        )
         .unwrap();
 
     match axis_scale {
         AxisScale::Linear => draw_line_comparison_figure(
-            colors, root_area, unit, x_range, y_range, value_type, lines,
+            style,
+            spec,
+            root_area,
+            (x_range, y_range),
+            value_type,
+            lines,
+            (target_line, exceeded),
         ),
         AxisScale::Logarithmic => draw_line_comparison_figure(
-            colors,
+            style,
+            spec,
             root_area,
-            unit,
-            x_range.log_scale(),
-            y_range.log_scale(),
+            (x_range.log_scale(), y_range.log_scale()),
             value_type,
             lines,
+            (target_line, exceeded),
         ),
     }
 }
 
 fn draw_line_comparison_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value = f64>>(
-    colors: &Colors,
+    style: &Style,
+    spec: &PlotSpec,
     root_area: DrawingArea<SVGBackend, Shift>,
-    y_unit: &str,
-    x_range: XR,
-    y_range: YR,
+    (x_range, y_range): (XR, YR),
     value_type: ValueType,
     data: &[(Option<&String>, LineCurve)],
+    (target_line, exceeded): (Option<Vec<(f64, f64)>>, Points),
 ) where
     XR::CoordDescType: PlottersValueFormatter<f64>,
     YR::CoordDescType: PlottersValueFormatter<f64>,
 {
+    let Style { colors, layout, .. } = style;
     let input_suffix = match value_type {
         ValueType::Bytes => " Size (Bytes)",
         ValueType::Elements => " Size (Elements)",
@@ -62,9 +86,9 @@ fn draw_line_comparison_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord
     };
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_range, y_range)
         .unwrap();
 
@@ -72,7 +96,7 @@ fn draw_line_comparison_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord
         .configure_mesh()
         .disable_mesh()
         .x_desc(format!("Input{}", input_suffix))
-        .y_desc(format!("Average time ({})", y_unit))
+        .y_desc(format!("Average time ({})", spec.unit))
         .draw()
         .unwrap();
 
@@ -97,64 +121,139 @@ fn draw_line_comparison_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord
         }
     }
 
+    if let Some(target_line) = target_line {
+        chart
+            .draw_series(std::iter::once(PathElement::new(
+                target_line,
+                BLACK.stroke_width(layout.stroke_width(1, &root_area)),
+            )))
+            .unwrap()
+            .label("Target")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK));
+    }
+
+    if !exceeded.xs.is_empty() {
+        let stroke_width = layout.stroke_width(2, &root_area);
+        chart
+            .draw_series(exceeded.to_points().map(|point| {
+                Circle::new(
+                    point,
+                    POINT_SIZE + 3,
+                    colors.severe_outlier.stroke_width(stroke_width),
+                )
+            }))
+            .unwrap()
+            .label("Above target")
+            .legend(move |(x, y)| {
+                Circle::new(
+                    (x + 10, y),
+                    POINT_SIZE + 3,
+                    colors.severe_outlier.stroke_width(stroke_width),
+                )
+            });
+    }
+
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.summary,
+        SeriesLabelPosition::UpperLeft,
+        data.iter().flat_map(|(_, curve)| curve.to_points()),
+    );
     chart
         .configure_series_labels()
-        .position(SeriesLabelPosition::UpperLeft)
+        .position(position)
         .draw()
         .unwrap();
+    layout.draw_footnote(&root_area);
 }
 
 pub fn violin(
-    colors: &Colors,
-    path: PathBuf,
-    title: &str,
-    unit: &str,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     axis_scale: AxisScale,
     lines: &[(&str, LineCurve)],
+    estimates: Option<&[ViolinEstimates]>,
+    targets: &[Option<Target>],
 ) {
     let mut x_range =
         plotters::data::fitting_range(lines.iter().flat_map(|(_, curve)| curve.xs.iter()));
     x_range.start = 0.0;
-    let y_range = -0.5..lines.len() as f64 - 0.5;
+    // Make sure the target markers are within the plot.
+    for target in targets.iter().flatten() {
+        x_range.end = x_range.end.max(target.value);
+    }
 
-    let size = (960, 150 + (18 * lines.len() as u32));
+    let size = (960, 150 + (style.violin_style.spacing * lines.len() as u32));
 
-    let root_area = SVGBackend::new(&path, size)
+    let root_area = target
+        .backend(size)
         .into_drawing_area()
-        .titled(&format!("{}: Violin plot", title), (DEFAULT_FONT, 20))
+        .titled(
+            &format!("{}: Violin plot", spec.id.as_title()),
+            (DEFAULT_FONT, 20),
+        )
         .unwrap();
 
     match axis_scale {
-        AxisScale::Linear => draw_violin_figure(colors, root_area, unit, x_range, y_range, lines),
-        AxisScale::Logarithmic => {
-            draw_violin_figure(colors, root_area, unit, x_range.log_scale(), y_range, lines)
+        AxisScale::Linear => {
+            draw_violin_figure(style, spec, root_area, x_range, lines, estimates, targets)
         }
+        AxisScale::Logarithmic => draw_violin_figure(
+            style,
+            spec,
+            root_area,
+            x_range.log_scale(),
+            lines,
+            estimates,
+            targets,
+        ),
     }
 }
 
-fn draw_violin_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value = f64>>(
-    colors: &Colors,
+fn draw_violin_figure<XR: AsRangedCoord<Value = f64>>(
+    style: &Style,
+    spec: &PlotSpec,
     root_area: DrawingArea<SVGBackend, Shift>,
-    unit: &str,
     x_range: XR,
-    y_range: YR,
     data: &[(&str, LineCurve)],
+    estimates: Option<&[ViolinEstimates]>,
+    targets: &[Option<Target>],
 ) where
     XR::CoordDescType: PlottersValueFormatter<f64>,
-    YR::CoordDescType: PlottersValueFormatter<f64>,
 {
+    let Style {
+        colors,
+        layout,
+        violin_style: style,
+        ..
+    } = style;
+    let y_range = -0.5..data.len() as f64 - 0.5;
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
-        .set_label_area_size(LabelAreaPosition::Left, (10).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_width().min(40))
+        .margin(layout.margin())
+        .set_label_area_size(
+            layout.y_label_position(),
+            (layout.label_area * 2.0)
+                .percent_width()
+                .min(layout.min_label_area(60)),
+        )
+        .set_label_area_size(
+            LabelAreaPosition::Bottom,
+            layout
+                .label_area
+                .percent_width()
+                .min(layout.min_label_area(40)),
+        )
         .build_cartesian_2d(x_range, y_range)
         .unwrap();
+    let stroke_width = layout.stroke_width(2, &root_area);
 
     chart
         .configure_mesh()
         .disable_mesh()
         .y_desc("Input")
-        .x_desc(format!("Average time ({})", unit))
+        .x_desc(format!("Average time ({})", spec.unit))
         .y_label_style((DEFAULT_FONT, 10))
         .y_label_formatter(&|v: &f64| data[v.round() as usize].0.to_string())
         .y_labels(data.len())
@@ -166,7 +265,9 @@ fn draw_violin_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value =
 
         chart
             .draw_series(AreaSeries::new(
-                curve.to_points().map(|(x, y)| (x, base + y / 2.0)),
+                curve
+                    .to_points()
+                    .map(|(x, y)| (x, base + y * style.width / 2.0)),
                 base,
                 &colors.current_sample,
             ))
@@ -174,10 +275,53 @@ fn draw_violin_figure<XR: AsRangedCoord<Value = f64>, YR: AsRangedCoord<Value =
 
         chart
             .draw_series(AreaSeries::new(
-                curve.to_points().map(|(x, y)| (x, base - y / 2.0)),
+                curve
+                    .to_points()
+                    .map(|(x, y)| (x, base - y * style.width / 2.0)),
                 base,
                 &colors.current_sample,
             ))
             .unwrap();
+
+        if let Some(estimates) = estimates {
+            // Draw a bracket spanning the confidence interval of the mean, and a dot at the median.
+            let estimate = &estimates[i];
+            let (lb, ub) = (estimate.mean_lower_bound, estimate.mean_upper_bound);
+            let bracket = vec![
+                vec![(lb, base - 0.1), (lb, base + 0.1)],
+                vec![(lb, base), (ub, base)],
+                vec![(ub, base - 0.1), (ub, base + 0.1)],
+            ];
+            chart
+                .draw_series(
+                    bracket
+                        .into_iter()
+                        .map(|points| PathElement::new(points, BLACK.stroke_width(stroke_width))),
+                )
+                .unwrap();
+            chart
+                .draw_series(std::iter::once(Circle::new(
+                    (estimate.median, base),
+                    POINT_SIZE,
+                    BLACK.filled(),
+                )))
+                .unwrap();
+        }
+
+        if let Some(target) = &targets[i] {
+            // Mark the target across the violin, in red if the benchmark is slower than it.
+            let color = if target.exceeded {
+                colors.severe_outlier
+            } else {
+                BLACK
+            };
+            chart
+                .draw_series(std::iter::once(PathElement::new(
+                    vec![(target.value, base - 0.4), (target.value, base + 0.4)],
+                    color.stroke_width(stroke_width),
+                )))
+                .unwrap();
+        }
     }
+    layout.draw_footnote(&root_area);
 }
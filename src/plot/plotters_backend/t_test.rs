@@ -1,32 +1,32 @@
-use crate::plot::plotters_backend::{Colors, DEFAULT_FONT, SIZE};
-use crate::plot::{FilledCurve, Size, VerticalLine};
-use crate::report::BenchmarkId;
+use crate::plot::plotters_backend::{legend_position, PlotTarget, Style, DEFAULT_FONT, SIZE};
+use crate::plot::{FilledCurve, PlotSpec, VerticalLine};
 use plotters::prelude::*;
-use std::path::PathBuf;
 
 pub fn t_test(
-    colors: &Colors,
-    id: &BenchmarkId,
-    size: Option<Size>,
-    path: PathBuf,
+    style: &Style,
+    spec: &PlotSpec,
+    target: &mut PlotTarget,
     t: VerticalLine,
     t_distribution: FilledCurve,
 ) {
+    let Style { colors, layout, .. } = style;
     let x_range = plotters::data::fitting_range(t_distribution.xs.iter());
     let mut y_range = plotters::data::fitting_range(t_distribution.ys_1.iter());
     y_range.start = 0.0;
     y_range.end *= 1.1;
 
-    let root_area = SVGBackend::new(&path, size.unwrap_or(SIZE).into()).into_drawing_area();
+    let root_area = target
+        .backend(spec.size.unwrap_or(SIZE).into())
+        .into_drawing_area();
 
     let mut chart = ChartBuilder::on(&root_area)
-        .margin((5).percent())
+        .margin(layout.margin())
         .caption(
-            format!("{}: Welch t test", id.as_title()),
+            format!("{}: Welch t test", spec.id.as_title()),
             (DEFAULT_FONT, 20),
         )
-        .set_label_area_size(LabelAreaPosition::Left, (5).percent_width().min(60))
-        .set_label_area_size(LabelAreaPosition::Bottom, (5).percent_height().min(40))
+        .set_label_area_size(layout.y_label_position(), layout.side_label_area())
+        .set_label_area_size(LabelAreaPosition::Bottom, layout.bottom_label_area())
         .build_cartesian_2d(x_range, y_range.clone())
         .unwrap();
 
@@ -56,11 +56,26 @@ pub fn t_test(
     chart
         .draw_series(std::iter::once(PathElement::new(
             t.to_line_vec(y_range.end),
-            colors.current_sample.filled().stroke_width(2),
+            colors
+                .current_sample
+                .filled()
+                .stroke_width(layout.stroke_width(2, &root_area)),
         )))
         .unwrap()
         .label("t statistic")
         .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &colors.current_sample));
 
-    chart.configure_series_labels().draw().unwrap();
+    let position = legend_position(
+        layout,
+        &chart,
+        layout.legend.t_test,
+        SeriesLabelPosition::MiddleRight,
+        t_distribution.to_points(),
+    );
+    chart
+        .configure_series_labels()
+        .position(position)
+        .draw()
+        .unwrap();
+    layout.draw_footnote(&root_area);
 }
@@ -0,0 +1,169 @@
+use crate::plot::plotters_backend::PlotTarget;
+
+type BackendCoord = (i32, i32);
+
+/// An SVG element which can be given a tooltip, identified by its backend coordinates.
+#[derive(PartialEq)]
+enum Element {
+    Circle(BackendCoord),
+    Line(BackendCoord, BackendCoord),
+}
+
+/// Tooltips to embed into an SVG file written by plotters (see `--interactive-svg`).
+///
+/// Plotters can't attach a `<title>` to the elements it draws, so instead we record the tooltips
+/// along with the backend coordinates of the elements they belong to and insert them into the SVG
+/// after plotters has written it. This doesn't change the rendered geometry.
+///
+/// The tooltips must be added in the same order as their elements are drawn. They're matched to
+/// the elements in that order, so that points drawn at the same pixel each keep their own tooltip.
+pub struct Tooltips {
+    enabled: bool,
+    tooltips: Vec<(Element, String)>,
+}
+impl Tooltips {
+    pub fn new(enabled: bool) -> Tooltips {
+        Tooltips {
+            enabled,
+            tooltips: Vec::new(),
+        }
+    }
+
+    /// Add a tooltip to the circle centered at the given backend coordinate.
+    pub fn add_circle(&mut self, center: BackendCoord, text: String) {
+        if self.enabled {
+            self.tooltips.push((Element::Circle(center), text));
+        }
+    }
+
+    /// Add a tooltip to the straight line between the given backend coordinates.
+    pub fn add_line(&mut self, start: BackendCoord, end: BackendCoord, text: String) {
+        if self.enabled {
+            self.tooltips.push((Element::Line(start, end), text));
+        }
+    }
+
+    /// Embed the tooltips into the SVG which has been drawn to the given target.
+    pub fn embed(&self, target: &mut PlotTarget) {
+        if !self.enabled || self.tooltips.is_empty() {
+            return;
+        }
+        match target {
+            PlotTarget::File(path) => {
+                let result = std::fs::read_to_string(&path)
+                    .and_then(|svg| std::fs::write(&path, self.embed_into(&svg)));
+                if let Err(e) = result {
+                    error!("Failed to add tooltips to plot {:?}: {}", path, e);
+                }
+            }
+            PlotTarget::Buffer(svg) => *svg = self.embed_into(svg),
+        }
+    }
+
+    fn embed_into(&self, svg: &str) -> String {
+        let mut output = String::with_capacity(svg.len());
+        let mut pending = self.tooltips.iter().peekable();
+        for line in svg.lines() {
+            // Other elements may be drawn in between, so skip any which aren't the next one with a
+            // tooltip.
+            let tooltip = match (parse_element(line), pending.peek()) {
+                (Some((tag, element)), Some((next, text))) if element == *next => {
+                    pending.next();
+                    Some((tag, text))
+                }
+                _ => None,
+            };
+            match tooltip {
+                Some((tag, text)) => {
+                    // Turn the self-closing element into one containing a title element.
+                    output.push_str(&line[..line.len() - 2]);
+                    output.push_str("><title>");
+                    output.push_str(&escape(text));
+                    output.push_str("</title></");
+                    output.push_str(tag);
+                    output.push('>');
+                }
+                None => output.push_str(line),
+            }
+            output.push('\n');
+        }
+        output
+    }
+}
+
+/// Parse a self-closing SVG element which can be given a tooltip, returning its tag too.
+fn parse_element(element: &str) -> Option<(&'static str, Element)> {
+    let element = element.trim();
+    if !element.ends_with("/>") {
+        return None;
+    }
+    if element.starts_with("<circle ") {
+        let cx = attribute(element, "cx")?.parse().ok()?;
+        let cy = attribute(element, "cy")?.parse().ok()?;
+        Some(("circle", Element::Circle((cx, cy))))
+    } else if element.starts_with("<polyline ") {
+        let points = attribute(element, "points")?
+            .split_whitespace()
+            .map(parse_point)
+            .collect::<Option<Vec<BackendCoord>>>()?;
+        match points.as_slice() {
+            [start, end] => Some(("polyline", Element::Line(*start, *end))),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Find the value of the given attribute in an SVG element.
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = format!(" {}=\"", name);
+    let start = element.find(&prefix)? + prefix.len();
+    let len = element[start..].find('"')?;
+    Some(&element[start..start + len])
+}
+
+fn parse_point(point: &str) -> Option<BackendCoord> {
+    let mut parts = point.split(',');
+    let x = parts.next()?.parse().ok()?;
+    let y = parts.next()?.parse().ok()?;
+    Some((x, y))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_embed_into() {
+        let svg = r##"<svg width="100" height="100" xmlns="http://www.w3.org/2000/svg">
+<circle cx="10" cy="20" r="3" opacity="0.5" fill="#1F78B4" stroke="none"/>
+<circle cx="10" cy="20" r="3" opacity="0.5" fill="#1F78B4" stroke="none"/>
+<circle cx="50" cy="50" r="3" opacity="0.5" fill="#1F78B4" stroke="none"/>
+<polyline fill="none" opacity="1" stroke="#1F78B4" stroke-width="1" points="30,0 30,100 "/>
+<circle cx="10" cy="20" r="3" opacity="0.5" fill="#1F78B4" stroke="none"/>
+</svg>
+"##;
+        let mut tooltips = Tooltips::new(true);
+        tooltips.add_circle((10, 20), "first".to_owned());
+        tooltips.add_circle((10, 20), "second <2>".to_owned());
+        tooltips.add_line((30, 0), (30, 100), "line".to_owned());
+
+        let output = tooltips.embed_into(svg);
+        let lines: Vec<&str> = output.lines().collect();
+        // Points drawn at the same pixel each keep their own tooltip.
+        assert!(lines[1].ends_with("stroke=\"none\"><title>first</title></circle>"));
+        assert!(lines[2].ends_with("stroke=\"none\"><title>second &lt;2&gt;</title></circle>"));
+        // Elements without tooltips are left alone.
+        assert!(lines[3].ends_with("/>"));
+        assert!(lines[4].ends_with("100 \"><title>line</title></polyline>"));
+        assert!(lines[5].ends_with("/>"));
+        assert_eq!(svg.lines().count(), lines.len());
+    }
+}
@@ -0,0 +1,148 @@
+//! Support for failing the run when a benchmark regresses (see `--fail-on-regression`) or changes
+//! in either direction (see `--fail-on-change`).
+
+use crate::report::{
+    compare_to_threshold, BenchmarkId, ComparisonData, ComparisonResult, MeasurementData, Report,
+    ReportContext,
+};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{anyhow, Result};
+use std::cell::RefCell;
+
+/// Returns the direction of the change if the comparison shows a statistically significant change
+/// beyond the noise threshold. This uses the same rules as the command-line report.
+pub fn significant_change(comparison: &ComparisonData) -> Option<ComparisonResult> {
+    if comparison.p_value >= comparison.significance_threshold {
+        return None;
+    }
+    match compare_to_threshold(
+        &comparison.relative_estimates.mean,
+        comparison.noise_threshold,
+    ) {
+        ComparisonResult::NonSignificant => None,
+        result => Some(result),
+    }
+}
+
+/// Returns true if the benchmark title matches any of the given glob patterns, meaning that the
+/// benchmark should not be considered when deciding whether the run failed.
+pub fn is_ungated(patterns: &[String], title: &str) -> bool {
+    patterns.iter().any(|pattern| glob_match(pattern, title))
+}
+
+/// Simple glob matching, where `*` matches any sequence of characters and `?` matches any single
+/// character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last '*' seen in the pattern, and the text position it's currently matched to.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last '*' absorb one more character and try again.
+            backtrack = Some((star_p, star_t + 1));
+            p = star_p + 1;
+            t = star_t + 1;
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Report which records every benchmark that regressed (or, if `fail_on_improvement` is set,
+/// improved) during this run, so that cargo-criterion can exit with an error once all benchmarks
+/// have been executed.
+pub struct RegressionGate {
+    ungated_benchmarks: Vec<String>,
+    fail_on_improvement: bool,
+    // The title of each failed benchmark and the line listing it in the error.
+    failed: RefCell<Vec<(String, String)>>,
+}
+impl RegressionGate {
+    pub fn new(ungated_benchmarks: Vec<String>, fail_on_improvement: bool) -> RegressionGate {
+        RegressionGate {
+            ungated_benchmarks,
+            fail_on_improvement,
+            failed: RefCell::new(vec![]),
+        }
+    }
+
+    /// Returns an error listing the failed benchmarks, if there were any.
+    pub fn check(&self) -> Result<()> {
+        let failed = self.failed.borrow();
+        let failed: Vec<&str> = failed.iter().map(|(_, failure)| failure.as_str()).collect();
+        if failed.is_empty() {
+            Ok(())
+        } else if self.fail_on_improvement {
+            Err(anyhow!(
+                "Performance has changed for the following benchmarks:\n{}",
+                failed.join("\n")
+            ))
+        } else {
+            Err(anyhow!(
+                "Performance has regressed for the following benchmarks:\n{}",
+                failed.join("\n")
+            ))
+        }
+    }
+}
+impl Report for RegressionGate {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        let title = id.as_title();
+        if is_ungated(&self.ungated_benchmarks, title) {
+            return;
+        }
+        let mut failed = self.failed.borrow_mut();
+        // A benchmark measured again (see `--stabilize`) is judged on its latest measurement.
+        failed.retain(|(failed_title, _)| failed_title != title);
+
+        let change = measurements
+            .comparison
+            .as_ref()
+            .and_then(significant_change);
+        let failure = match change {
+            Some(ComparisonResult::Regressed) if self.fail_on_improvement => {
+                format!("{} (regressed)", title)
+            }
+            Some(ComparisonResult::Regressed) => title.to_owned(),
+            Some(ComparisonResult::Improved) if self.fail_on_improvement => {
+                format!("{} (improved)", title)
+            }
+            _ => return,
+        };
+        failed.push((title.to_owned(), failure));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("fib/20", "fib/20"));
+        assert!(!glob_match("fib/20", "fib/200"));
+        assert!(glob_match("fib/*", "fib/200"));
+        assert!(glob_match("*/20", "fib/20"));
+        assert!(glob_match("f?b*", "fib/20"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*a*b", "xaxxab"));
+        assert!(!glob_match("*a*b", "xaxxa"));
+        assert!(!glob_match("fib/?", "fib/20"));
+    }
+}
@@ -1,5 +1,10 @@
+use crate::analysis::BenchmarkConfig;
+use crate::config::{Color, Colors, RoundingMode, SelectedStatistic, TimeUnit};
 use crate::connection::{PlotConfiguration, Throughput};
-use crate::estimate::{ChangeDistributions, ChangeEstimates, Distributions, Estimate, Estimates};
+use crate::estimate::{
+    ChangeDistributions, ChangeEstimates, Distributions, Estimate, Estimates, LatencyPercentiles,
+    Statistic,
+};
 use crate::format;
 use crate::model::{BenchmarkGroup, Model, SavedStatistics};
 use crate::stats::bivariate::regression::Slope;
@@ -10,7 +15,7 @@ use crate::stats::Distribution;
 use crate::value_formatter::ValueFormatter;
 use std::cell::Cell;
 use std::cmp;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::io::stderr;
 use std::io::Write;
@@ -30,7 +35,18 @@ pub struct ComparisonData {
     pub base_iter_counts: Vec<f64>,
     pub base_sample_times: Vec<f64>,
     pub base_avg_times: Vec<f64>,
+    // The wall-clock time at which each sample of the baseline was taken, if it recorded them.
+    pub base_timestamps: Option<Vec<f64>>,
     pub base_estimates: Estimates,
+    pub statistic_changes: Vec<StatisticChange>,
+}
+
+/// The relative change in a single statistic compared to the baseline, and whether that change
+/// exceeds the noise threshold.
+pub struct StatisticChange {
+    pub statistic: Statistic,
+    pub estimate: Estimate,
+    pub result: ComparisonResult,
 }
 
 pub struct MeasurementData<'a> {
@@ -40,6 +56,21 @@ pub struct MeasurementData<'a> {
     pub distributions: Distributions,
     pub comparison: Option<ComparisonData>,
     pub throughput: Option<Throughput>,
+    // Does the KDE of the average times have more than one significant peak?
+    pub multimodal: bool,
+    // Did every sample take the same time per iteration, leaving no distribution to estimate?
+    pub zero_variance: bool,
+    // The percentiles of the per-element latencies, if the benchmark reported them.
+    pub latency_percentiles: Option<LatencyPercentiles>,
+    // The estimated overhead of the measurement loop per iteration, if the benchmark reported it.
+    pub overhead: Option<f64>,
+    // The wall-clock time at which each sample was taken, in seconds since the Unix epoch, if the
+    // benchmark recorded them.
+    pub timestamps: Option<&'a [f64]>,
+    // The settings of the analysis, which are stored so that it can be repeated later.
+    pub config: BenchmarkConfig,
+    // The named baselines which are drawn over the comparison plots (see --overlay-baselines).
+    pub overlay_baselines: Vec<OverlayBaseline>,
 }
 impl<'a> MeasurementData<'a> {
     pub fn iter_counts(&self) -> &Sample<f64> {
@@ -51,6 +82,12 @@ impl<'a> MeasurementData<'a> {
     }
 }
 
+/// The latest measurements of a benchmark in one of the named baselines.
+pub struct OverlayBaseline {
+    pub name: String,
+    pub avg_times: Vec<f64>,
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum ValueType {
     Bytes,
@@ -144,6 +181,21 @@ impl BenchmarkId {
         }
     }
 
+    /// Returns this ID with the toolchain appended to the function ID, so that the results of each
+    /// toolchain are reported as separate series of the same benchmark group.
+    pub fn with_toolchain(self, toolchain: &str) -> BenchmarkId {
+        let function_id = match self.function_id {
+            Some(function_id) => format!("{} ({})", function_id, toolchain),
+            None => toolchain.to_owned(),
+        };
+        BenchmarkId::new(
+            self.group_id,
+            Some(function_id),
+            self.value_str,
+            self.throughput,
+        )
+    }
+
     pub fn as_title(&self) -> &str {
         &self.title
     }
@@ -162,6 +214,20 @@ impl BenchmarkId {
         }
     }
 
+    /// Parses the value string as a pair of numeric parameters such as `1024x4`, `1024/4` or
+    /// `1024,4`. Returns None if the benchmark isn't parameterized over exactly two dimensions, or
+    /// if either parameter isn't finite, since those can't be placed on an axis.
+    pub fn as_parameter_pair(&self) -> Option<(f64, f64)> {
+        let value_str = self.value_str.as_ref()?;
+        let mut parts = value_str.split(&['x', '/', ','][..]);
+        let first = parts.next()?.trim().parse::<f64>().ok()?;
+        let second = parts.next()?.trim().parse::<f64>().ok()?;
+        if parts.next().is_some() || !first.is_finite() || !second.is_finite() {
+            return None;
+        }
+        Some((first, second))
+    }
+
     pub fn value_type(&self) -> Option<ValueType> {
         match self.throughput {
             Some(Throughput::Bytes(_)) => Some(ValueType::Bytes),
@@ -237,6 +303,9 @@ impl fmt::Debug for BenchmarkId {
 pub struct ReportContext {
     pub output_directory: PathBuf,
     pub plot_config: PlotConfiguration,
+    pub time_unit: Option<TimeUnit>,
+    pub rounding: Option<RoundingMode>,
+    pub statistics: Option<Vec<SelectedStatistic>>,
 }
 impl ReportContext {
     pub fn report_path<P: AsRef<Path> + ?Sized>(&self, id: &BenchmarkId, file_name: &P) -> PathBuf {
@@ -270,6 +339,8 @@ pub trait Report {
         _formatter: &ValueFormatter,
     ) {
     }
+    /// Called instead of `measurement_complete` if the benchmark failed, eg. because it panicked.
+    fn benchmark_failed(&self, _id: &BenchmarkId, _context: &ReportContext, _error: &str) {}
     fn summarize(
         &self,
         _context: &ReportContext,
@@ -342,6 +413,12 @@ impl<'a> Report for Reports<'a> {
         }
     }
 
+    fn benchmark_failed(&self, id: &BenchmarkId, context: &ReportContext, error: &str) {
+        for report in &self.reports {
+            report.benchmark_failed(id, context, error);
+        }
+    }
+
     fn summarize(
         &self,
         context: &ReportContext,
@@ -379,26 +456,62 @@ impl<'a> Report for Reports<'a> {
     }
 }
 
+// Prints a line of the command-line report to stderr (see `CliReport::print_line`).
+macro_rules! cli_eprintln {
+    ($report:expr) => {
+        $report.print_line(String::new())
+    };
+    ($report:expr, $($arg:tt)*) => {
+        $report.print_line(format!($($arg)*))
+    };
+}
+
 pub struct CliReport {
     pub enable_text_overwrite: bool,
     pub enable_text_coloring: bool,
     pub verbose: bool,
+    pub compact: bool,
     pub show_differences: bool,
+    pub ungated_benchmarks: Vec<String>,
+    pub show_geometric_mean: bool,
+    pub weights: HashMap<String, f64>,
+    pub show_total_time: bool,
+    pub speedup_threshold: Option<f64>,
+    pub regressed_color: Option<Color>,
+    pub improved_color: Option<Color>,
+    pub no_change_color: Option<Color>,
 
     last_line_len: Cell<usize>,
 }
 impl CliReport {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         enable_text_overwrite: bool,
         enable_text_coloring: bool,
         show_differences: bool,
         verbose: bool,
+        compact: bool,
+        ungated_benchmarks: Vec<String>,
+        show_geometric_mean: bool,
+        weights: HashMap<String, f64>,
+        show_total_time: bool,
+        speedup_threshold: Option<f64>,
+        colors: &Colors,
     ) -> CliReport {
         CliReport {
             enable_text_overwrite,
             enable_text_coloring,
             show_differences,
             verbose,
+            compact,
+            ungated_benchmarks,
+            show_geometric_mean,
+            weights,
+            show_total_time,
+            speedup_threshold,
+            regressed_color: colors.regressed,
+            improved_color: colors.improved,
+            no_change_color: colors.no_change,
 
             last_line_len: Cell::new(0),
         }
@@ -417,6 +530,7 @@ impl CliReport {
     //Passing a String is the common case here.
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::needless_pass_by_value))]
     fn print_overwritable(&self, s: String) {
+        let s = self.plain(s);
         if self.enable_text_overwrite {
             self.last_line_len.set(s.len());
             eprint!("{}", s);
@@ -426,6 +540,37 @@ impl CliReport {
         }
     }
 
+    // Prints a line to stderr. Every line of the report is printed by this or by
+    // print_overwritable, so that plain output is guaranteed.
+    fn print_line(&self, s: String) {
+        eprintln!("{}", self.plain(s));
+    }
+
+    // Without text coloring, the output mustn't contain any escape sequences, including any
+    // which are part of the benchmark IDs.
+    fn plain(&self, s: String) -> String {
+        if self.enable_text_coloring {
+            s
+        } else {
+            crate::ansi::strip(&s).into_owned()
+        }
+    }
+
+    // Prints a progress message for a running benchmark. In compact mode these only appear as a
+    // status line which is overwritten, so they're left out entirely if that isn't possible.
+    fn print_status(&self, s: String) {
+        if self.compact && !self.enable_text_overwrite {
+            return;
+        }
+        self.text_overwrite();
+        self.print_overwritable(s);
+    }
+
+    // Formats a change in time, or one of its bounds, as a percentage or a speedup factor.
+    fn format_change(&self, change: f64, point_estimate: f64) -> String {
+        format::change_or_speedup(change, point_estimate, self.speedup_threshold)
+    }
+
     fn green(&self, s: String) -> String {
         if self.enable_text_coloring {
             format!("\x1B[32m{}\x1B[39m", s)
@@ -442,14 +587,31 @@ impl CliReport {
         }
     }
 
-    fn red(&self, s: String) -> String {
-        if self.enable_text_coloring {
-            format!("\x1B[31m{}\x1B[39m", s)
-        } else {
-            s
+    /// Color the text with the configured color if there is one, or with the given ANSI color
+    /// code otherwise.
+    fn highlight(&self, color: Option<Color>, default_code: Option<u8>, s: String) -> String {
+        if !self.enable_text_coloring {
+            return s;
+        }
+        match (color, default_code) {
+            (Some(Color { r, g, b }), _) => format!("\x1B[38;2;{};{};{}m{}\x1B[39m", r, g, b, s),
+            (None, Some(code)) => format!("\x1B[{}m{}\x1B[39m", code, s),
+            (None, None) => s,
         }
     }
 
+    fn improved(&self, s: String) -> String {
+        self.highlight(self.improved_color, Some(32), s)
+    }
+
+    fn regressed(&self, s: String) -> String {
+        self.highlight(self.regressed_color, Some(31), s)
+    }
+
+    fn no_change(&self, s: String) -> String {
+        self.highlight(self.no_change_color, None, s)
+    }
+
     fn bold(&self, s: String) -> String {
         if self.enable_text_coloring {
             format!("\x1B[1m{}\x1B[22m", s)
@@ -477,7 +639,8 @@ impl CliReport {
 
         let percent = |n: usize| 100. * n as f64 / sample_size as f64;
 
-        eprintln!(
+        cli_eprintln!(
+            self,
             "{}",
             self.yellow(format!(
                 "Found {} outliers among {} measurements ({:.2}%)",
@@ -489,7 +652,7 @@ impl CliReport {
 
         let print = |n, label| {
             if n != 0 {
-                eprintln!("  {} ({:.2}%) {}", n, percent(n), label);
+                cli_eprintln!(self, "  {} ({:.2}%) {}", n, percent(n), label);
             }
         };
 
@@ -498,15 +661,142 @@ impl CliReport {
         print(him, "high mild");
         print(his, "high severe");
     }
+
+    /// Prints the number of benchmarks which were run, skipped by the filter and failed, with the
+    /// names of the failed benchmarks (and of the others too, in verbose mode).
+    fn run_summary(&self, model: &Model) {
+        let summary = model.run_summary();
+        let reused = if summary.reused.is_empty() {
+            String::new()
+        } else {
+            format!(", {} reused from an earlier run", summary.reused.len())
+        };
+        cli_eprintln!(
+            self,
+            "Benchmarks: {} run{}, {} skipped by the filter, {} failed",
+            summary.run.len(),
+            reused,
+            summary.skipped.len(),
+            summary.failed.len()
+        );
+        let list = |label: &str, titles: &[String]| {
+            if !titles.is_empty() {
+                cli_eprintln!(self, "  {}: {}", label, titles.join(", "));
+            }
+        };
+        if self.verbose {
+            list("Run", &summary.run);
+            list("Reused", &summary.reused);
+            list("Skipped", &summary.skipped);
+        }
+        list("Failed", &summary.failed);
+
+        if summary.run.is_empty() && summary.failed.is_empty() && summary.reused.is_empty() {
+            cli_eprintln!(
+                self,
+                "{}",
+                self.yellow(
+                    "No benchmarks were run. Check that the filter matches the benchmark names."
+                        .to_owned()
+                )
+            );
+        }
+    }
+
+    // Prints the result of a benchmark on a single line, for `--progress compact`.
+    fn compact_line(
+        &self,
+        id: &BenchmarkId,
+        meas: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        let typical_estimate = meas.absolute_estimates.typical();
+        let mut line = format!(
+            "{} time: {} [{} {}]",
+            self.green(format!("{:<23}", id.as_title())),
+            self.bold(formatter.format_value(typical_estimate.point_estimate)),
+            self.faint(formatter.format_value(typical_estimate.confidence_interval.lower_bound)),
+            self.faint(formatter.format_value(typical_estimate.confidence_interval.upper_bound)),
+        );
+        if let Some(ref throughput) = meas.throughput {
+            line += &format!(
+                "  thrpt: {}",
+                formatter.format_throughput(throughput, typical_estimate.point_estimate)
+            );
+        }
+        if self.show_differences {
+            if let Some(ref comp) = meas.comparison {
+                let mean_est = &comp.relative_estimates.mean;
+                let change = self.format_change(mean_est.point_estimate, mean_est.point_estimate);
+                let change = if comp.p_value >= comp.significance_threshold {
+                    self.no_change(change)
+                } else {
+                    match compare_to_threshold(mean_est, comp.noise_threshold) {
+                        ComparisonResult::Improved => {
+                            format!("{} (improved)", self.improved(self.bold(change)))
+                        }
+                        ComparisonResult::Regressed => {
+                            format!("{} (regressed)", self.regressed(self.bold(change)))
+                        }
+                        ComparisonResult::NonSignificant => self.no_change(change),
+                    }
+                };
+                line += &format!("  change: {}", change);
+            }
+        }
+        cli_eprintln!(self, "{}", line);
+    }
+
+    /// Prints one line for each of the statistics selected with `--statistics`.
+    fn selected_statistics(
+        &self,
+        statistics: &[SelectedStatistic],
+        meas: &MeasurementData<'_>,
+        formatter: &ValueFormatter,
+    ) {
+        for statistic in statistics {
+            match *statistic {
+                SelectedStatistic::Estimate(statistic) => {
+                    if let Some(estimate) = meas.absolute_estimates.get(statistic) {
+                        cli_eprintln!(
+                            self,
+                            "{:<7}[{} {} {}]",
+                            statistic,
+                            self.faint(
+                                formatter.format_value(estimate.confidence_interval.lower_bound)
+                            ),
+                            self.bold(formatter.format_value(estimate.point_estimate)),
+                            self.faint(
+                                formatter.format_value(estimate.confidence_interval.upper_bound)
+                            ),
+                        );
+                    }
+                }
+                SelectedStatistic::Percentile(percentile) => {
+                    let latency = meas.latency_percentiles.as_ref().and_then(|latencies| {
+                        let index = latencies
+                            .percentiles
+                            .iter()
+                            .position(|&p| p == percentile)?;
+                        Some(latencies.latencies[index])
+                    });
+                    let value = match latency {
+                        Some(latency) => self.bold(formatter.format_value(latency)),
+                        None => self.faint("(no latencies recorded)".to_owned()),
+                    };
+                    cli_eprintln!(self, "{:<7}{}", format!("p{}", percentile), value);
+                }
+            }
+        }
+    }
 }
 impl Report for CliReport {
     fn benchmark_start(&self, id: &BenchmarkId, _: &ReportContext) {
-        self.print_overwritable(format!("Benchmarking {}", id));
+        self.print_status(format!("Benchmarking {}", id));
     }
 
     fn warmup(&self, id: &BenchmarkId, _: &ReportContext, warmup_ns: f64) {
-        self.text_overwrite();
-        self.print_overwritable(format!(
+        self.print_status(format!(
             "Benchmarking {}: Warming up for {}",
             id,
             format::time(warmup_ns)
@@ -514,8 +804,7 @@ impl Report for CliReport {
     }
 
     fn analysis(&self, id: &BenchmarkId, _: &ReportContext) {
-        self.text_overwrite();
-        self.print_overwritable(format!("Benchmarking {}: Analyzing", id));
+        self.print_status(format!("Benchmarking {}: Analyzing", id));
     }
 
     fn measurement_start(
@@ -526,14 +815,13 @@ impl Report for CliReport {
         estimate_ns: f64,
         iter_count: u64,
     ) {
-        self.text_overwrite();
         let iter_string = if self.verbose {
             format!("{} iterations", iter_count)
         } else {
             format::iter_count(iter_count)
         };
 
-        self.print_overwritable(format!(
+        self.print_status(format!(
             "Benchmarking {}: Collecting {} samples in estimated {} ({})",
             id,
             sample_count,
@@ -545,24 +833,30 @@ impl Report for CliReport {
     fn measurement_complete(
         &self,
         id: &BenchmarkId,
-        _: &ReportContext,
+        context: &ReportContext,
         meas: &MeasurementData<'_>,
         formatter: &ValueFormatter,
     ) {
         self.text_overwrite();
 
+        if self.compact {
+            self.compact_line(id, meas, formatter);
+            return;
+        }
+
         let typical_estimate = meas.absolute_estimates.typical();
 
         {
             let mut id = id.as_title().to_owned();
 
             if id.len() > 23 {
-                eprintln!("{}", self.green(id.clone()));
+                cli_eprintln!(self, "{}", self.green(id.clone()));
                 id.clear();
             }
             let id_len = id.len();
 
-            eprintln!(
+            cli_eprintln!(
+                self,
                 "{}{}time:   [{} {} {}]",
                 self.green(id),
                 " ".repeat(24 - id_len),
@@ -576,8 +870,18 @@ impl Report for CliReport {
             );
         }
 
+        if meas.zero_variance {
+            cli_eprintln!(
+                self,
+                "{}every sample took exactly {} per iteration",
+                " ".repeat(24),
+                formatter.format_value(meas.absolute_estimates.mean.point_estimate)
+            );
+        }
+
         if let Some(ref throughput) = meas.throughput {
-            eprintln!(
+            cli_eprintln!(
+                self,
                 "{}thrpt:  [{} {} {}]",
                 " ".repeat(24),
                 self.faint(formatter.format_throughput(
@@ -597,83 +901,101 @@ impl Report for CliReport {
                 let different_mean = comp.p_value < comp.significance_threshold;
                 let mean_est = &comp.relative_estimates.mean;
                 let point_estimate = mean_est.point_estimate;
-                let mut point_estimate_str = format::change(point_estimate, true);
-                // The change in throughput is related to the change in timing. Reducing the timing by
-                // 50% increases the througput by 100%.
-                let to_thrpt_estimate = |ratio: f64| 1.0 / (1.0 + ratio) - 1.0;
-                let mut thrpt_point_estimate_str =
-                    format::change(to_thrpt_estimate(point_estimate), true);
+                let mut point_estimate_str = self.format_change(point_estimate, point_estimate);
+                let thrpt_est = mean_est.to_throughput_change();
+                let mut thrpt_point_estimate_str = format::change(thrpt_est.point_estimate, true);
                 let explanation_str: String;
 
                 if !different_mean {
+                    point_estimate_str = self.no_change(point_estimate_str);
+                    thrpt_point_estimate_str = self.no_change(thrpt_point_estimate_str);
                     explanation_str = "No change in performance detected.".to_owned();
                 } else {
                     let comparison = compare_to_threshold(mean_est, comp.noise_threshold);
                     match comparison {
                         ComparisonResult::Improved => {
-                            point_estimate_str = self.green(self.bold(point_estimate_str));
+                            point_estimate_str = self.improved(self.bold(point_estimate_str));
                             thrpt_point_estimate_str =
-                                self.green(self.bold(thrpt_point_estimate_str));
-                            explanation_str =
-                                format!("Performance has {}.", self.green("improved".to_owned()));
+                                self.improved(self.bold(thrpt_point_estimate_str));
+                            explanation_str = format!(
+                                "Performance has {}.",
+                                self.improved("improved".to_owned())
+                            );
                         }
                         ComparisonResult::Regressed => {
-                            point_estimate_str = self.red(self.bold(point_estimate_str));
+                            point_estimate_str = self.regressed(self.bold(point_estimate_str));
                             thrpt_point_estimate_str =
-                                self.red(self.bold(thrpt_point_estimate_str));
-                            explanation_str =
-                                format!("Performance has {}.", self.red("regressed".to_owned()));
+                                self.regressed(self.bold(thrpt_point_estimate_str));
+                            explanation_str = if crate::regression_gate::is_ungated(
+                                &self.ungated_benchmarks,
+                                id.as_title(),
+                            ) {
+                                format!(
+                                    "Performance has {} (not gated).",
+                                    self.regressed("regressed".to_owned())
+                                )
+                            } else {
+                                format!(
+                                    "Performance has {}.",
+                                    self.regressed("regressed".to_owned())
+                                )
+                            };
                         }
                         ComparisonResult::NonSignificant => {
+                            point_estimate_str = self.no_change(point_estimate_str);
+                            thrpt_point_estimate_str = self.no_change(thrpt_point_estimate_str);
                             explanation_str = "Change within noise threshold.".to_owned();
                         }
                     }
                 }
 
                 if meas.throughput.is_some() {
-                    eprintln!("{}change:", " ".repeat(17));
+                    cli_eprintln!(self, "{}change:", " ".repeat(17));
 
-                    eprintln!(
+                    cli_eprintln!(
+                        self,
                         "{}time:   [{} {} {}] (p = {:.2} {} {:.2})",
                         " ".repeat(24),
-                        self.faint(format::change(
+                        self.faint(self.format_change(
                             mean_est.confidence_interval.lower_bound,
-                            true
+                            point_estimate
                         )),
                         point_estimate_str,
-                        self.faint(format::change(
+                        self.faint(self.format_change(
                             mean_est.confidence_interval.upper_bound,
-                            true
+                            point_estimate
                         )),
                         comp.p_value,
                         if different_mean { "<" } else { ">" },
                         comp.significance_threshold
                     );
-                    eprintln!(
+                    cli_eprintln!(
+                        self,
                         "{}thrpt:  [{} {} {}]",
                         " ".repeat(24),
                         self.faint(format::change(
-                            to_thrpt_estimate(mean_est.confidence_interval.upper_bound),
+                            thrpt_est.confidence_interval.lower_bound,
                             true
                         )),
                         thrpt_point_estimate_str,
                         self.faint(format::change(
-                            to_thrpt_estimate(mean_est.confidence_interval.lower_bound),
+                            thrpt_est.confidence_interval.upper_bound,
                             true
                         )),
                     );
                 } else {
-                    eprintln!(
+                    cli_eprintln!(
+                        self,
                         "{}change: [{} {} {}] (p = {:.2} {} {:.2})",
                         " ".repeat(24),
-                        self.faint(format::change(
+                        self.faint(self.format_change(
                             mean_est.confidence_interval.lower_bound,
-                            true
+                            point_estimate
                         )),
                         point_estimate_str,
-                        self.faint(format::change(
+                        self.faint(self.format_change(
                             mean_est.confidence_interval.upper_bound,
-                            true
+                            point_estimate
                         )),
                         comp.p_value,
                         if different_mean { "<" } else { ">" },
@@ -681,10 +1003,18 @@ impl Report for CliReport {
                     );
                 }
 
-                eprintln!("{}{}", " ".repeat(24), explanation_str);
+                cli_eprintln!(self, "{}{}", " ".repeat(24), explanation_str);
             }
         }
 
+        if let Some(statistics) = &context.statistics {
+            if self.verbose {
+                self.outliers(&meas.avg_times);
+            }
+            self.selected_statistics(statistics, meas, formatter);
+            return;
+        }
+
         if self.verbose {
             self.outliers(&meas.avg_times);
 
@@ -698,7 +1028,8 @@ impl Report for CliReport {
 
             let data = &meas.data;
             if let Some(slope_estimate) = meas.absolute_estimates.slope.as_ref() {
-                eprintln!(
+                cli_eprintln!(
+                    self,
                     "{:<7}{} {:<15}[{:0.7} {:0.7}]",
                     "slope",
                     format_short_estimate(slope_estimate),
@@ -707,25 +1038,107 @@ impl Report for CliReport {
                     Slope(slope_estimate.confidence_interval.upper_bound).r_squared(data),
                 );
             }
-            eprintln!(
+            cli_eprintln!(
+                self,
                 "{:<7}{} {:<15}{}",
                 "mean",
                 format_short_estimate(&meas.absolute_estimates.mean),
                 "std. dev.",
                 format_short_estimate(&meas.absolute_estimates.std_dev),
             );
-            eprintln!(
+            cli_eprintln!(
+                self,
                 "{:<7}{} {:<15}{}",
                 "median",
                 format_short_estimate(&meas.absolute_estimates.median),
                 "med. abs. dev.",
                 format_short_estimate(&meas.absolute_estimates.median_abs_dev),
             );
+            if let Some(overhead) = meas.overhead {
+                cli_eprintln!(self, "overhead {}", formatter.format_value(overhead));
+            }
+        }
+    }
+
+    fn summarize(
+        &self,
+        _context: &ReportContext,
+        group_id: &str,
+        benchmark_group: &BenchmarkGroup,
+        formatter: &ValueFormatter,
+    ) {
+        if !self.show_geometric_mean {
+            return;
+        }
+        if let Some(geometric_mean) =
+            crate::analysis::group_geometric_mean(benchmark_group, &self.weights)
+        {
+            let change = match geometric_mean.change {
+                Some(change) => format!(" (change: {})", self.format_change(change, change)),
+                None => String::new(),
+            };
+            cli_eprintln!(
+                self,
+                "{}: {}: {}{}",
+                self.green(group_id.to_owned()),
+                if geometric_mean.weighted {
+                    "weighted geometric mean"
+                } else {
+                    "geometric mean"
+                },
+                self.bold(formatter.format_value(geometric_mean.mean)),
+                change
+            );
+        }
+    }
+
+    fn final_summary(&self, context: &ReportContext, model: &Model) {
+        self.run_summary(model);
+        if !self.show_total_time {
+            return;
+        }
+        if let Some(total) = crate::analysis::suite_total(model) {
+            let formatter = ValueFormatter::wall_time(context.time_unit, context.rounding);
+            let change = match total.change {
+                Some(change) => format!(" (change: {})", self.format_change(change, change)),
+                None => String::new(),
+            };
+            cli_eprintln!(
+                self,
+                "Total time: {}{}",
+                self.bold(formatter.format_value(total.total)),
+                change
+            );
+        }
+    }
+
+    fn benchmark_failed(&self, id: &BenchmarkId, _: &ReportContext, error: &str) {
+        self.text_overwrite();
+        if self.compact {
+            cli_eprintln!(
+                self,
+                "{}  {}: {}",
+                self.green(id.as_title().to_owned()),
+                self.regressed("Failed".to_owned()),
+                error.lines().next().unwrap_or("")
+            );
+            return;
+        }
+        cli_eprintln!(self, "{}", self.green(id.as_title().to_owned()));
+        cli_eprintln!(
+            self,
+            "                        {}",
+            self.regressed("Failed".to_owned())
+        );
+        for line in error.lines() {
+            cli_eprintln!(self, "                        {}", line);
         }
     }
 
     fn group_separator(&self) {
-        eprintln!();
+        if !self.compact {
+            cli_eprintln!(self);
+        }
     }
 }
 
@@ -739,7 +1152,7 @@ impl Report for BencherReport {
         _estimate_ns: f64,
         _iter_count: u64,
     ) {
-        eprint!("test {} ... ", id);
+        eprint!("test {} ... ", crate::ansi::strip(id.as_title()));
     }
 
     fn measurement_complete(
@@ -792,6 +1205,40 @@ pub fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult
 mod test {
     use super::*;
 
+    #[test]
+    fn test_plain_cli_output() {
+        let colors = Colors {
+            regressed: Some(Color { r: 255, g: 0, b: 0 }),
+            ..Colors::default()
+        };
+        let report = CliReport::new(
+            false,
+            false,
+            true,
+            false,
+            false,
+            vec![],
+            false,
+            HashMap::new(),
+            false,
+            None,
+            &colors,
+        );
+        let id = "fib/\x1b[31m20\x1b[0m".to_owned();
+        let lines = vec![
+            report.green(id.clone()),
+            report.yellow(id.clone()),
+            report.improved(id.clone()),
+            report.regressed(report.bold(id.clone())),
+            report.no_change(report.faint(id.clone())),
+        ];
+        for line in lines {
+            let line = report.plain(line);
+            assert!(!line.contains('\x1b'), "Found an escape in {:?}", line);
+            assert_eq!("fib/20", line);
+        }
+    }
+
     #[test]
     fn test_make_filename_safe_replaces_characters() {
         let input = "?/\\*\"";
@@ -852,4 +1299,39 @@ mod test {
         new_id.ensure_directory_name_unique(&directories);
         assert_ne!(existing_id.as_directory_name(), new_id.as_directory_name());
     }
+    #[test]
+    fn test_benchmark_id_as_parameter_pair() {
+        let pair = |value: &str| {
+            BenchmarkId::new("group".to_owned(), None, Some(value.to_owned()), None)
+                .as_parameter_pair()
+        };
+        assert_eq!(Some((1024.0, 4.0)), pair("1024x4"));
+        assert_eq!(Some((1024.0, 4.0)), pair("1024/4"));
+        assert_eq!(Some((0.5, 2.0)), pair("0.5, 2"));
+        assert_eq!(None, pair("1024"));
+        assert_eq!(None, pair("1x2x3"));
+        assert_eq!(None, pair("sizexthreads"));
+        assert_eq!(None, pair("NaN/4"));
+        assert_eq!(None, pair("1,inf"));
+        assert_eq!(
+            None,
+            BenchmarkId::new("group".to_owned(), None, None, None).as_parameter_pair()
+        );
+    }
+
+    #[test]
+    fn test_benchmark_id_with_toolchain() {
+        let id = BenchmarkId::new(
+            "group".to_owned(),
+            Some("function".to_owned()),
+            Some("10".to_owned()),
+            None,
+        )
+        .with_toolchain("nightly");
+        assert_eq!("group/function (nightly)/10", id.as_title());
+        assert_eq!(Some(10.0), id.as_number());
+
+        let id = BenchmarkId::new("group".to_owned(), None, None, None).with_toolchain("stable");
+        assert_eq!("group/stable", id.as_title());
+    }
 }
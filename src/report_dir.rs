@@ -0,0 +1,240 @@
+//! The directory that the reports are written into.
+//!
+//! By default, the reports are written directly into `<criterion_home>/reports` as the benchmarks
+//! run. With `--atomic-reports`, they are written into a staging copy of that directory instead,
+//! which only replaces the real one once the run has completed, so that an interrupted run never
+//! leaves a half-written report behind.
+//!
+//! The reports can also be exported as a static site with `--export-site`.
+
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+pub struct ReportDirectory {
+    /// The directory the reports end up in.
+    target: PathBuf,
+    /// The directory the reports are written to during the run, if it isn't the target.
+    staging: Option<PathBuf>,
+}
+impl ReportDirectory {
+    /// Reports are written straight into the given directory.
+    pub fn direct(target: PathBuf) -> ReportDirectory {
+        ReportDirectory {
+            target,
+            staging: None,
+        }
+    }
+
+    /// Reports are written into a staging copy of the given directory, which replaces it when
+    /// `commit` is called. The copy starts out with the existing reports so that the reports of
+    /// benchmarks which aren't run this time are kept.
+    pub fn staged(target: PathBuf) -> Result<ReportDirectory> {
+        let staging = sibling(&target, "staging");
+        // Left over from an interrupted run.
+        if staging.exists() {
+            fs::remove_dir_all(&staging)
+                .with_context(|| format!("Unable to remove directory {:?}", staging))?;
+        }
+        if target.is_dir() {
+            copy_dir(&target, &staging)?;
+        } else {
+            fs::create_dir_all(&staging)
+                .with_context(|| format!("Unable to create directory {:?}", staging))?;
+        }
+
+        Ok(ReportDirectory {
+            target,
+            staging: Some(staging),
+        })
+    }
+
+    /// The directory that the reports should be written to.
+    pub fn path(&self) -> &Path {
+        self.staging.as_ref().unwrap_or(&self.target)
+    }
+
+    /// Move the staged reports into place, replacing the previous ones.
+    pub fn commit(self) -> Result<()> {
+        let staging = match self.staging {
+            Some(staging) => staging,
+            None => return Ok(()),
+        };
+
+        if let Err(e) = swap(&staging, &self.target) {
+            // Renaming fails if the target is on a different filesystem (eg. a mount point), so
+            // fall back to replacing it one file at a time.
+            debug!(
+                "Unable to rename {:?} to {:?} ({}), copying it instead",
+                staging, self.target, e
+            );
+            copy_dir(&staging, &self.target)?;
+            fs::remove_dir_all(&staging)
+                .with_context(|| format!("Unable to remove directory {:?}", staging))?;
+        }
+        Ok(())
+    }
+}
+
+/// Copies the reports into a self-contained directory which can be published as a static site (see
+/// `--export-site`). The reports go into `reports/` as they are, next to the flamegraphs of the
+/// profiled benchmarks so that the relative links between them still work, and an `index.html` at
+/// the top redirects to the report index.
+pub fn export_site(criterion_home: &Path, site: &Path) -> Result<()> {
+    let reports = criterion_home.join("reports");
+    if !reports.is_dir() {
+        bail!("There are no reports in {:?} to export", reports);
+    }
+
+    // Replace the reports of any previous export so that it doesn't keep deleted benchmarks.
+    let site_reports = site.join("reports");
+    if site_reports.exists() {
+        fs::remove_dir_all(&site_reports)
+            .with_context(|| format!("Unable to remove directory {:?}", site_reports))?;
+    }
+    copy_dir(&reports, &site_reports)?;
+
+    let flamegraphs = WalkDir::new(criterion_home)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != reports && entry.path() != site)
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_name() == "flamegraph.svg"
+                && entry.path().parent().and_then(Path::file_name) == Some("profile".as_ref())
+        });
+    for flamegraph in flamegraphs {
+        let relative = flamegraph.path().strip_prefix(criterion_home).unwrap();
+        let destination = site.join(relative);
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Unable to create directory {:?}", parent))?;
+        }
+        fs::copy(flamegraph.path(), &destination).with_context(|| {
+            format!(
+                "Unable to copy {:?} to {:?}",
+                flamegraph.path(),
+                destination
+            )
+        })?;
+    }
+
+    let write = |name: &str, contents: &str| {
+        let path = site.join(name);
+        fs::write(&path, contents).with_context(|| format!("Unable to write {:?}", path))
+    };
+    write("index.html", SITE_INDEX)?;
+    // Stops GitHub Pages from running the files through Jekyll, which would hide some of them.
+    write(".nojekyll", "")?;
+    Ok(())
+}
+
+const SITE_INDEX: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta http-equiv="Content-Type" content="text/html; charset=utf-8">
+    <meta http-equiv="refresh" content="0; url=reports/index.html">
+    <title>Criterion.rs Benchmark Reports</title>
+</head>
+<body>
+    <a href="reports/index.html">Benchmark reports</a>
+</body>
+</html>
+"#;
+
+/// A path next to the given one, eg. `reports.staging` for `reports`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Replace the target directory with the staging directory. If this fails, the target is left
+/// as it was.
+fn swap(staging: &Path, target: &Path) -> std::io::Result<()> {
+    let old = sibling(target, "old");
+    if old.exists() {
+        fs::remove_dir_all(&old)?;
+    }
+    let had_target = target.exists();
+    if had_target {
+        fs::rename(target, &old)?;
+    }
+    if let Err(e) = fs::rename(staging, target) {
+        if had_target {
+            fs::rename(&old, target)?;
+        }
+        return Err(e);
+    }
+    if had_target {
+        if let Err(e) = fs::remove_dir_all(&old) {
+            warn!("Unable to remove the previous reports in {:?}: {}", old, e);
+        }
+    }
+    Ok(())
+}
+
+/// Recursively copy the contents of one directory into another. Each file is copied to a
+/// temporary name next to its destination and then renamed over it, so that no file in the
+/// destination is ever partially written.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for entry in WalkDir::new(from) {
+        let entry = entry.with_context(|| format!("Unable to read directory {:?}", from))?;
+        let relative = entry.path().strip_prefix(from).unwrap();
+        let destination = to.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&destination)
+                .with_context(|| format!("Unable to create directory {:?}", destination))?;
+        } else {
+            let temporary = sibling(&destination, "tmp");
+            fs::copy(entry.path(), &temporary)
+                .and_then(|_| fs::rename(&temporary, &destination))
+                .with_context(|| {
+                    format!("Unable to copy {:?} to {:?}", entry.path(), destination)
+                })?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sibling() {
+        assert_eq!(
+            sibling(Path::new("target/criterion/reports"), "staging"),
+            Path::new("target/criterion/reports.staging")
+        );
+        assert_eq!(
+            sibling(Path::new("reports/index.html"), "tmp"),
+            Path::new("reports/index.html.tmp")
+        );
+    }
+
+    #[test]
+    fn test_export_site() {
+        let home =
+            std::env::temp_dir().join(format!("cargo-criterion-site-{}", std::process::id()));
+        let site = home.join("site");
+        assert!(export_site(&home, &site).is_err());
+
+        let report = home.join("reports").join("fib").join("20");
+        let profile = home.join("fib").join("20").join("profile");
+        fs::create_dir_all(&report).unwrap();
+        fs::create_dir_all(&profile).unwrap();
+        fs::write(report.join("index.html"), "<html/>").unwrap();
+        fs::write(profile.join("flamegraph.svg"), "<svg/>").unwrap();
+
+        let result = export_site(&home, &site);
+        let exported = |path: &str| site.join(path).is_file();
+        let all_exported = exported("index.html")
+            && exported("reports/fib/20/index.html")
+            && exported("fib/20/profile/flamegraph.svg");
+        fs::remove_dir_all(&home).unwrap();
+        result.unwrap();
+        assert!(all_exported);
+    }
+}
@@ -0,0 +1,147 @@
+//! Support for running cargo-criterion as a headless server which runs the benchmarks on request
+//! (see `--serve`), so that tools like IDEs don't have to start a new process for every run.
+//!
+//! Clients send one JSON request per line and receive the usual JSON messages (as printed by
+//! `--message-format=json`) for each run, followed by a `run-complete` message.
+
+use crate::config::FullConfig;
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+enum Request {
+    /// Compile the benchmarks if necessary and run those which match the filter.
+    Run { filter: Option<String> },
+    /// Stop the server.
+    Shutdown,
+}
+
+/// Listen on the given address and serve run requests until a client asks the server to shut
+/// down. Clients are served one at a time.
+pub fn serve(configuration: &FullConfig, address: &str) -> Result<()> {
+    let addresses = loopback_addresses(address)?;
+    let listener = TcpListener::bind(&addresses[..])
+        .with_context(|| format!("Unable to listen for requests on {}", address))?;
+    let local_address = listener
+        .local_addr()
+        .context("Unable to get local address of socket")?;
+    eprintln!("Listening for benchmark requests on {}", local_address);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Failed to accept a connection: {}", e);
+                continue;
+            }
+        };
+        match serve_client(configuration, stream) {
+            Ok(true) => return Ok(()),
+            Ok(false) => {}
+            Err(e) => warn!("Lost the connection to a client: {:?}", e),
+        }
+    }
+    Ok(())
+}
+
+// Resolves the address to listen on, checking that it's a loopback address before anything is
+// bound to it. Anyone who can connect can run the benchmarks, so only local clients are accepted.
+fn loopback_addresses(address: &str) -> Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = address
+        .to_socket_addrs()
+        .with_context(|| format!("Unable to resolve the address {}", address))?
+        .collect();
+    if addresses.is_empty() {
+        bail!("The address {} didn't resolve to anything", address);
+    }
+    if let Some(remote) = addresses.iter().find(|address| !address.ip().is_loopback()) {
+        bail!(
+            "--serve must listen on a loopback address (eg. localhost:7878), not {}",
+            remote
+        );
+    }
+    Ok(addresses)
+}
+
+// Handles the requests of one client until it disconnects. Returns true if it asked the server to
+// shut down.
+fn serve_client(configuration: &FullConfig, stream: TcpStream) -> Result<bool> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream.try_clone()?;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Request>(&line) {
+            Ok(Request::Run { filter }) => {
+                let result = run(configuration, filter.as_deref(), &stream);
+                let response = match result {
+                    Ok(()) => json!({ "reason": "run-complete", "success": true }),
+                    Err(e) => json!({
+                        "reason": "run-complete",
+                        "success": false,
+                        "error": format!("{:#}", e),
+                    }),
+                };
+                writeln!(writer, "{}", response)?;
+            }
+            Ok(Request::Shutdown) => {
+                writeln!(writer, "{}", json!({ "reason": "shutdown" }))?;
+                return Ok(true);
+            }
+            Err(e) => {
+                let response = json!({ "reason": "invalid-request", "error": e.to_string() });
+                writeln!(writer, "{}", response)?;
+            }
+        }
+    }
+    Ok(false)
+}
+
+// Runs the benchmarks which match the filter (or else the command-line filter) just as a normal
+// run would, except that the JSON messages are sent to the client.
+fn run(configuration: &FullConfig, filter: Option<&str>, stream: &TcpStream) -> Result<()> {
+    let filter = filter
+        .map(OsStr::new)
+        .or(configuration.bench_filter.as_deref());
+    crate::run_benchmarks(configuration, filter, Some(Box::new(stream.try_clone()?)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        match serde_json::from_str(r#"{"command": "run", "filter": "fib"}"#).unwrap() {
+            Request::Run { filter } => assert_eq!(Some("fib".to_owned()), filter),
+            Request::Shutdown => panic!("Expected a run request"),
+        }
+        match serde_json::from_str(r#"{"command": "run"}"#).unwrap() {
+            Request::Run { filter } => assert_eq!(None, filter),
+            Request::Shutdown => panic!("Expected a run request"),
+        }
+        assert!(matches!(
+            serde_json::from_str(r#"{"command": "shutdown"}"#).unwrap(),
+            Request::Shutdown
+        ));
+        assert!(serde_json::from_str::<Request>(r#"{"command": "explode"}"#).is_err());
+    }
+
+    #[test]
+    fn test_loopback_addresses() {
+        assert_eq!(
+            vec!["127.0.0.1:7878".parse::<SocketAddr>().unwrap()],
+            loopback_addresses("127.0.0.1:7878").unwrap()
+        );
+        assert!(loopback_addresses("[::1]:7878").is_ok());
+        assert!(loopback_addresses("0.0.0.0:7878").is_err());
+        assert!(loopback_addresses("192.168.1.2:7878").is_err());
+        assert!(loopback_addresses("not an address").is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Support for `--shuffle`, which runs the benchmark targets in a random order so that thermal and
+//! cache effects don't always favor the same benchmarks, and `--order-by-duration`, which runs them
+//! in order of how long they took in the last run.
+
+use crate::config::DurationOrder;
+use std::cmp::Ordering;
+
+/// A small seedable pseudo-random number generator (SplitMix64). The shuffle doesn't need to be
+/// cryptographically random, only reproducible from the seed.
+struct SplitMix64(u64);
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`. The modulo bias is negligible for the small bounds used
+    /// here.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// Returns a seed for when the user hasn't chosen one.
+pub fn random_seed() -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Sort the items by their durations in the given order. Items without a duration go last, in
+/// their original order.
+pub fn order_by_duration<T>(
+    items: &mut [T],
+    order: DurationOrder,
+    duration: impl Fn(&T) -> Option<f64>,
+) {
+    items.sort_by(|a, b| match (duration(a), duration(b)) {
+        (Some(a), Some(b)) => match order {
+            DurationOrder::Longest => b.partial_cmp(&a),
+            DurationOrder::Shortest => a.partial_cmp(&b),
+        }
+        .unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
+
+/// Shuffle the items into an order determined by the seed (Fisher-Yates).
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i + 1);
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_shuffle() {
+        let original: Vec<u32> = (0..20).collect();
+
+        let mut first = original.clone();
+        shuffle(&mut first, 42);
+        let mut second = original.clone();
+        shuffle(&mut second, 42);
+        // The same seed always gives the same order.
+        assert_eq!(first, second);
+        assert_ne!(first, original);
+
+        // Every item is still there exactly once.
+        first.sort_unstable();
+        assert_eq!(first, original);
+
+        let mut empty: Vec<u32> = vec![];
+        shuffle(&mut empty, 42);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_order_by_duration() {
+        let durations = [
+            ("a", None),
+            ("b", Some(2.0)),
+            ("c", Some(3.0)),
+            ("d", None),
+            ("e", Some(1.0)),
+        ];
+        let names = |items: &[(&str, Option<f64>)]| -> Vec<String> {
+            items.iter().map(|(name, _)| name.to_string()).collect()
+        };
+
+        let mut longest = durations;
+        order_by_duration(&mut longest, DurationOrder::Longest, |item| item.1);
+        assert_eq!(vec!["c", "b", "e", "a", "d"], names(&longest));
+
+        let mut shortest = durations;
+        order_by_duration(&mut shortest, DurationOrder::Shortest, |item| item.1);
+        assert_eq!(vec!["e", "b", "c", "a", "d"], names(&shortest));
+    }
+}
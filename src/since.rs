@@ -0,0 +1,148 @@
+//! Support for `--since`, which only runs the benchmark targets whose source files have changed
+//! since a given git revision.
+
+use crate::bench_target::BenchTarget;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Run git with the given arguments in the current directory and return the lines it prints.
+fn git(args: &[&str]) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .with_context(|| format!("Unable to run git {}", args.join(" ")))?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(ToOwned::to_owned)
+        .collect())
+}
+
+/// Returns the abbreviated hash of the commit checked out in the current directory.
+#[cfg(feature = "plotters_backend")]
+pub fn head_revision() -> Result<String> {
+    match git(&["rev-parse", "--short", "HEAD"])?.pop() {
+        Some(hash) => Ok(hash),
+        None => bail!("Unable to find the current git commit"),
+    }
+}
+
+/// Returns the absolute paths of the files which differ between the given revision and the
+/// working tree, including uncommitted and untracked files.
+pub fn changed_files(revision: &str) -> Result<Vec<PathBuf>> {
+    let root = match git(&["rev-parse", "--show-toplevel"])?.pop() {
+        Some(root) => PathBuf::from(root),
+        None => bail!("Unable to find the root of the git repository"),
+    };
+    let mut changed = git(&["diff", "--name-only", revision, "--"])
+        .with_context(|| format!("Unable to compare the working tree to {:?}", revision))?;
+    changed.extend(git(&[
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+    ])?);
+
+    Ok(changed.into_iter().map(|path| root.join(path)).collect())
+}
+
+/// Returns the paths whose changes should cause the given target to be run. These are either set
+/// in the `benchmark_paths` table of criterion.toml (relative to the current directory), or
+/// default to the directory of the package the target belongs to.
+fn source_paths(
+    target: &BenchTarget,
+    benchmark_paths: &HashMap<String, Vec<PathBuf>>,
+) -> Vec<PathBuf> {
+    let paths = match benchmark_paths.get(&target.name) {
+        Some(paths) => paths.clone(),
+        None => target
+            .manifest_path
+            .as_ref()
+            .and_then(|manifest| manifest.parent())
+            .map(|package| vec![package.to_owned()])
+            .unwrap_or_default(),
+    };
+    paths
+        .into_iter()
+        .map(|path| std::fs::canonicalize(&path).unwrap_or(path))
+        .collect()
+}
+
+fn is_affected(changed: &[PathBuf], sources: &[PathBuf]) -> bool {
+    changed
+        .iter()
+        .any(|file| sources.iter().any(|source| file.starts_with(source)))
+}
+
+/// Keep only the targets which have at least one changed file among their source paths. Targets
+/// whose source paths aren't known are skipped.
+pub fn select_targets(
+    targets: Vec<BenchTarget>,
+    changed: &[PathBuf],
+    benchmark_paths: &HashMap<String, Vec<PathBuf>>,
+) -> Vec<BenchTarget> {
+    let (affected, unaffected) = partition_targets(targets, changed, benchmark_paths);
+    for target in &unaffected {
+        info!(
+            "Skipping {}: none of {:?} have changed",
+            target.name,
+            source_paths(target, benchmark_paths)
+        );
+    }
+    affected
+}
+
+/// Splits the targets into those which have at least one changed file among their source paths
+/// and those which don't. Targets whose source paths aren't known are treated as unchanged.
+pub fn partition_targets(
+    targets: Vec<BenchTarget>,
+    changed: &[PathBuf],
+    benchmark_paths: &HashMap<String, Vec<PathBuf>>,
+) -> (Vec<BenchTarget>, Vec<BenchTarget>) {
+    targets
+        .into_iter()
+        .partition(|target| is_affected(changed, &source_paths(target, benchmark_paths)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_affected() {
+        let changed = vec![
+            PathBuf::from("/repo/crates/parser/src/lib.rs"),
+            PathBuf::from("/repo/README.md"),
+        ];
+        assert!(is_affected(
+            &changed,
+            &[PathBuf::from("/repo/crates/parser")]
+        ));
+        assert!(is_affected(
+            &changed,
+            &[
+                PathBuf::from("/repo/crates/lexer"),
+                PathBuf::from("/repo/README.md")
+            ]
+        ));
+        assert!(!is_affected(
+            &changed,
+            &[PathBuf::from("/repo/crates/lexer")]
+        ));
+        // Path components are compared, not prefixes of the string.
+        assert!(!is_affected(
+            &changed,
+            &[PathBuf::from("/repo/crates/pars")]
+        ));
+        assert!(!is_affected(&changed, &[]));
+    }
+}
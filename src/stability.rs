@@ -0,0 +1,105 @@
+//! Support for re-measuring benchmarks until their measurements are stable (see `--stabilize`).
+
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use crate::variance_gate::relative_std_dev;
+use linked_hash_map::LinkedHashMap;
+use std::cell::RefCell;
+
+/// Builds a benchmark filter which matches exactly the given benchmark titles. Criterion.rs treats
+/// the filter as a regular expression, so the titles are escaped and anchored.
+pub fn exact_filter(titles: &[String]) -> String {
+    let escaped: Vec<String> = titles
+        .iter()
+        .map(|title| {
+            let mut escaped = String::with_capacity(title.len());
+            for c in title.chars() {
+                if "\\.+*?()|[]{}^$#&-~".contains(c) {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            escaped
+        })
+        .collect();
+    format!("^({})$", escaped.join("|"))
+}
+
+/// Report which records the relative standard deviation of the latest measurement of every
+/// benchmark, so that those above the target can be measured again.
+pub struct StabilityCheck {
+    target_rsd: f64,
+    // The latest relative standard deviation of each benchmark, by title.
+    latest: RefCell<LinkedHashMap<String, f64>>,
+}
+impl StabilityCheck {
+    pub fn new(target_rsd: f64) -> StabilityCheck {
+        StabilityCheck {
+            target_rsd,
+            latest: RefCell::new(LinkedHashMap::new()),
+        }
+    }
+
+    /// Returns the titles of the benchmarks whose latest measurement is above the target.
+    pub fn unstable(&self) -> Vec<String> {
+        self.latest
+            .borrow()
+            .iter()
+            .filter(|(_, rsd)| **rsd > self.target_rsd)
+            .map(|(title, _)| title.clone())
+            .collect()
+    }
+
+    /// Warns about the benchmarks which didn't stabilize within the allowed number of attempts.
+    pub fn report(&self, attempts: usize) {
+        let latest = self.latest.borrow();
+        let unstable: Vec<String> = latest
+            .iter()
+            .filter(|(_, rsd)| **rsd > self.target_rsd)
+            .map(|(title, rsd)| {
+                format!(
+                    "{} (RSD {})",
+                    title,
+                    crate::format::change(*rsd, false).trim()
+                )
+            })
+            .collect();
+        if !unstable.is_empty() {
+            warn!(
+                "The following benchmarks didn't stabilize within {} attempts:\n{}",
+                attempts,
+                unstable.join("\n")
+            );
+        }
+    }
+}
+impl Report for StabilityCheck {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        let rsd = relative_std_dev(measurements);
+        // A zero mean gives NaN or infinity, which more samples won't fix.
+        if rsd.is_finite() {
+            self.latest
+                .borrow_mut()
+                .insert(id.as_title().to_owned(), rsd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exact_filter() {
+        assert_eq!(
+            "^(fib/20|sort \\(n=1\\.5k\\))$",
+            exact_filter(&["fib/20".to_owned(), "sort (n=1.5k)".to_owned()])
+        );
+    }
+}
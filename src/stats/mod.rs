@@ -9,6 +9,7 @@
 mod test;
 
 pub mod bivariate;
+pub mod reservoir;
 pub mod tuple;
 pub mod univariate;
 
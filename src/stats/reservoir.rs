@@ -0,0 +1,48 @@
+//! Reservoir sampling, for bounding the size of very large samples before they're analyzed
+
+use crate::stats::rand_util::new_rng;
+
+/// Chooses `count` of the indices `0..len` uniformly at random, returning them in ascending order
+/// so that the chosen values keep their original order. Returns every index if there are no more
+/// than `count`.
+pub fn reservoir_indices(len: usize, count: usize) -> Vec<usize> {
+    if len <= count {
+        return (0..len).collect();
+    }
+
+    let mut rng = new_rng();
+    let mut reservoir: Vec<usize> = (0..count).collect();
+    for i in count..len {
+        let j = rng.rand_range(0u64..(i as u64 + 1)) as usize;
+        if j < count {
+            reservoir[j] = i;
+        }
+    }
+    reservoir.sort_unstable();
+    reservoir
+}
+
+/// Returns the values at the given indices.
+pub fn select<A: Copy>(values: &[A], indices: &[usize]) -> Vec<A> {
+    indices.iter().map(|&i| values[i]).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reservoir_indices() {
+        assert_eq!(vec![0, 1, 2], reservoir_indices(3, 10));
+
+        let indices = reservoir_indices(10_000, 100);
+        assert_eq!(100, indices.len());
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(indices.iter().all(|&i| i < 10_000));
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(vec![10.0, 30.0], select(&[10.0, 20.0, 30.0], &[0, 2]));
+    }
+}
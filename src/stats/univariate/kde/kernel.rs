@@ -26,6 +26,26 @@ where
     }
 }
 
+/// Epanechnikov kernel, scaled to unit variance like the Gaussian kernel so that the same
+/// bandwidth suits both
+#[derive(Clone, Copy)]
+pub struct Epanechnikov;
+
+impl<A> Kernel<A> for Epanechnikov
+where
+    A: Float,
+{
+    fn evaluate(&self, x: A) -> A {
+        let five = A::cast(5);
+        let u = x.powi(2) / five;
+        if u < A::cast(1) {
+            A::cast(0.75) * (A::cast(1) - u) / five.sqrt()
+        } else {
+            A::cast(0)
+        }
+    }
+}
+
 #[cfg(test)]
 macro_rules! test {
     ($ty:ident) => {
@@ -71,6 +91,25 @@ macro_rules! test {
                     }
                 }
             }
+
+            mod epanechnikov {
+                use approx::relative_eq;
+                use quickcheck::quickcheck;
+
+                use crate::stats::univariate::kde::kernel::{Epanechnikov, Kernel};
+
+                quickcheck! {
+                    fn symmetric(x: $ty) -> bool {
+                        relative_eq!(Epanechnikov.evaluate(-x), Epanechnikov.evaluate(x))
+                    }
+                }
+
+                quickcheck! {
+                    fn non_negative(x: $ty) -> bool {
+                        Epanechnikov.evaluate(x) >= 0.
+                    }
+                }
+            }
         }
     };
 }
@@ -78,7 +78,19 @@ impl Bandwidth {
                 let n = A::cast(sample.len());
                 let sigma = sample.std_dev(None);
 
-                sigma * (factor / n).powf(exponent)
+                if sigma > A::cast(0) {
+                    sigma * (factor / n).powf(exponent)
+                } else {
+                    // Every value is the same, so there's no spread to estimate the bandwidth
+                    // from. Use a small fraction of the value rather than zero, which the
+                    // estimates would divide by.
+                    let scale = sample.mean().abs();
+                    if scale > A::cast(0) {
+                        scale * A::cast(1e-3)
+                    } else {
+                        A::cast(1)
+                    }
+                }
             }
         }
     }
@@ -129,6 +141,18 @@ macro_rules! test {
                     }
                 }
             }
+
+            #[test]
+            fn constant_sample() {
+                let values: [$ty; 4] = [5., 5., 5., 5.];
+                let kde = Kde::new(Sample::new(&values), Gaussian, Bandwidth::Silverman);
+                assert!(kde.bandwidth() > 0.);
+                assert!(kde.estimate(5.).is_finite());
+
+                let zeros: [$ty; 2] = [0., 0.];
+                let kde = Kde::new(Sample::new(&zeros), Gaussian, Bandwidth::Silverman);
+                assert!(kde.estimate(0.).is_finite());
+            }
         }
     };
 }
@@ -1,51 +1,111 @@
+use crate::config::{RoundingMode, TimeUnit};
 use crate::connection::{Connection, IncomingMessage, OutgoingMessage, Throughput};
+use crate::format;
 use std::cell::RefCell;
 
 pub struct ValueFormatter<'a> {
-    connection: RefCell<&'a mut Connection>,
+    // The benchmark which formats its own values. If there is no benchmark (eg. when the samples
+    // were read from stdin), the values are assumed to be wall-clock times in nanoseconds.
+    connection: Option<RefCell<&'a mut Connection>>,
+    // If set, values are formatted as times in this unit instead of asking the benchmark to scale
+    // them.
+    time_unit: Option<TimeUnit>,
+    // If set, values are scaled by the benchmark but rounded and formatted here, so that the
+    // rounding is the same for every benchmark.
+    rounding: Option<RoundingMode>,
 }
 impl<'a> ValueFormatter<'a> {
-    pub fn new(conn: &mut Connection) -> ValueFormatter {
+    pub fn new(
+        conn: &mut Connection,
+        time_unit: Option<TimeUnit>,
+        rounding: Option<RoundingMode>,
+    ) -> ValueFormatter {
         ValueFormatter {
-            connection: RefCell::new(conn),
+            connection: Some(RefCell::new(conn)),
+            time_unit,
+            rounding,
+        }
+    }
+
+    /// Create a formatter for wall-clock times in nanoseconds which doesn't need a benchmark to
+    /// talk to.
+    pub fn wall_time(
+        time_unit: Option<TimeUnit>,
+        rounding: Option<RoundingMode>,
+    ) -> ValueFormatter<'static> {
+        ValueFormatter {
+            connection: None,
+            time_unit,
+            rounding,
         }
     }
 }
 impl<'a> ValueFormatter<'a> {
     pub fn format_value(&self, value: f64) -> String {
-        self.connection
+        if let Some(rounding) = self.rounding {
+            let mut values = [value];
+            let unit = match self.time_unit {
+                Some(time_unit) => {
+                    let (nanos_per_unit, unit) = format::time_unit_scale(time_unit);
+                    values[0] /= nanos_per_unit;
+                    unit.to_owned()
+                }
+                None => self.scale_values(value, &mut values),
+            };
+            return format!("{:>6} {}", format::short_rounded(values[0], rounding), unit);
+        }
+        if let Some(unit) = self.time_unit {
+            return format::time_in_unit(value, unit);
+        }
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            None => return format::time(value),
+        };
+        connection
             .borrow_mut()
             .send(&OutgoingMessage::FormatValue { value })
             .unwrap();
-        match self.connection.borrow_mut().recv().unwrap().unwrap() {
+        match connection.borrow_mut().recv().unwrap().unwrap() {
             IncomingMessage::FormattedValue { value } => value,
             other => panic!("Unexpected message {:?}", other),
         }
     }
 
     pub fn format_throughput(&self, throughput: &Throughput, value: f64) -> String {
-        self.connection
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            None => {
+                let mut values = [value];
+                let unit = scale_wall_time_throughputs(value, throughput, &mut values);
+                return format!("{:>6} {}", format::short(values[0]), unit);
+            }
+        };
+        connection
             .borrow_mut()
             .send(&OutgoingMessage::FormatThroughput {
                 value,
                 throughput: throughput.clone(),
             })
             .unwrap();
-        match self.connection.borrow_mut().recv().unwrap().unwrap() {
+        match connection.borrow_mut().recv().unwrap().unwrap() {
             IncomingMessage::FormattedValue { value } => value,
             other => panic!("Unexpected message {:?}", other),
         }
     }
 
     pub fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> String {
-        self.connection
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            None => return scale_wall_times(typical_value, values),
+        };
+        connection
             .borrow_mut()
             .send(&OutgoingMessage::ScaleValues {
                 typical_value,
                 values,
             })
             .unwrap();
-        match self.connection.borrow_mut().recv().unwrap().unwrap() {
+        match connection.borrow_mut().recv().unwrap().unwrap() {
             IncomingMessage::ScaledValues {
                 scaled_values,
                 unit,
@@ -65,7 +125,11 @@ impl<'a> ValueFormatter<'a> {
         throughput: &Throughput,
         values: &mut [f64],
     ) -> String {
-        self.connection
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            None => return scale_wall_time_throughputs(typical_value, throughput, values),
+        };
+        connection
             .borrow_mut()
             .send(&OutgoingMessage::ScaleThroughputs {
                 typical_value,
@@ -73,7 +137,7 @@ impl<'a> ValueFormatter<'a> {
                 throughput: throughput.clone(),
             })
             .unwrap();
-        match self.connection.borrow_mut().recv().unwrap().unwrap() {
+        match connection.borrow_mut().recv().unwrap().unwrap() {
             IncomingMessage::ScaledValues {
                 scaled_values,
                 unit,
@@ -86,11 +150,16 @@ impl<'a> ValueFormatter<'a> {
     }
 
     pub fn scale_for_machines(&self, values: &mut [f64]) -> String {
-        self.connection
+        let connection = match &self.connection {
+            Some(connection) => connection,
+            // Wall-clock times are already in nanoseconds.
+            None => return "ns".to_owned(),
+        };
+        connection
             .borrow_mut()
             .send(&OutgoingMessage::ScaleForMachines { values })
             .unwrap();
-        match self.connection.borrow_mut().recv().unwrap().unwrap() {
+        match connection.borrow_mut().recv().unwrap().unwrap() {
             IncomingMessage::ScaledValues {
                 scaled_values,
                 unit,
@@ -104,9 +173,68 @@ impl<'a> ValueFormatter<'a> {
 }
 impl<'a> Drop for ValueFormatter<'a> {
     fn drop(&mut self) {
-        let _ = self
-            .connection
-            .borrow_mut()
-            .send(&OutgoingMessage::Continue);
+        if let Some(connection) = &self.connection {
+            let _ = connection.borrow_mut().send(&OutgoingMessage::Continue);
+        }
+    }
+}
+
+// These mirror the wall-time formatting of Criterion.rs, for use when there is no benchmark to ask.
+
+fn scale_wall_times(typical_value: f64, values: &mut [f64]) -> String {
+    let (factor, unit) = if typical_value < 1.0 {
+        (1e3, "ps")
+    } else if typical_value < 1e3 {
+        (1.0, "ns")
+    } else if typical_value < 1e6 {
+        (1e-3, "µs")
+    } else if typical_value < 1e9 {
+        (1e-6, "ms")
+    } else {
+        (1e-9, "s")
+    };
+
+    for value in values {
+        *value *= factor;
+    }
+    unit.to_owned()
+}
+
+fn scale_wall_time_throughputs(
+    typical_value: f64,
+    throughput: &Throughput,
+    values: &mut [f64],
+) -> String {
+    let (count, units) = match *throughput {
+        Throughput::Bytes(bytes) => (
+            bytes,
+            [
+                (1.0, "  B/s"),
+                (1024.0, "KiB/s"),
+                (1024.0 * 1024.0, "MiB/s"),
+                (1024.0 * 1024.0 * 1024.0, "GiB/s"),
+            ],
+        ),
+        Throughput::Elements(elements) => (
+            elements,
+            [
+                (1.0, " elem/s"),
+                (1e3, "Kelem/s"),
+                (1e6, "Melem/s"),
+                (1e9, "Gelem/s"),
+            ],
+        ),
+    };
+    let count = count as f64;
+    let per_second = count * (1e9 / typical_value);
+    let (denominator, unit) = units
+        .iter()
+        .rev()
+        .find(|(denominator, _)| per_second >= *denominator)
+        .unwrap_or(&units[0]);
+
+    for value in values {
+        *value = count * (1e9 / *value) / denominator;
     }
+    (*unit).to_owned()
 }
@@ -0,0 +1,78 @@
+//! Support for flagging benchmarks whose measurements are too noisy to be useful (see `--max-rsd`
+//! and `--fail-on-high-variance`).
+
+use crate::report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use crate::value_formatter::ValueFormatter;
+use anyhow::{anyhow, Result};
+use std::cell::RefCell;
+
+/// Returns the relative standard deviation of the measurements, ie. the standard deviation of the
+/// sample times over their mean.
+pub(crate) fn relative_std_dev(measurements: &MeasurementData<'_>) -> f64 {
+    let estimates = &measurements.absolute_estimates;
+    estimates.std_dev.point_estimate / estimates.mean.point_estimate
+}
+
+fn percentage(fraction: f64) -> String {
+    crate::format::change(fraction, false).trim().to_owned()
+}
+
+/// Report which records every benchmark whose relative standard deviation exceeds the threshold,
+/// so that they can be listed (or fail the run) once all benchmarks have been executed.
+pub struct VarianceGate {
+    max_rsd: f64,
+    fail: bool,
+    flagged: RefCell<Vec<(String, f64)>>,
+}
+impl VarianceGate {
+    pub fn new(max_rsd: f64, fail: bool) -> VarianceGate {
+        VarianceGate {
+            max_rsd,
+            fail,
+            flagged: RefCell::new(vec![]),
+        }
+    }
+
+    /// Lists the flagged benchmarks, if there were any. This is an error if `fail` is set and a
+    /// warning otherwise.
+    pub fn check(&self) -> Result<()> {
+        let flagged = self.flagged.borrow();
+        if flagged.is_empty() {
+            return Ok(());
+        }
+
+        let list: Vec<String> = flagged
+            .iter()
+            .map(|(title, rsd)| format!("{} (RSD {})", title, percentage(*rsd)))
+            .collect();
+        let message = format!(
+            "The following benchmarks have a relative standard deviation above {}:\n{}",
+            percentage(self.max_rsd),
+            list.join("\n")
+        );
+        if self.fail {
+            Err(anyhow!(message))
+        } else {
+            warn!("{}", message);
+            Ok(())
+        }
+    }
+}
+impl Report for VarianceGate {
+    fn measurement_complete(
+        &self,
+        id: &BenchmarkId,
+        _: &ReportContext,
+        measurements: &MeasurementData<'_>,
+        _: &ValueFormatter,
+    ) {
+        let rsd = relative_std_dev(measurements);
+        let mut flagged = self.flagged.borrow_mut();
+        // A benchmark measured again (see `--stabilize`) is judged on its latest measurement.
+        flagged.retain(|(title, _)| title != id.as_title());
+        // A zero mean gives NaN or infinity, which isn't meaningful either way.
+        if rsd.is_finite() && rsd > self.max_rsd {
+            flagged.push((id.as_title().to_owned(), rsd));
+        }
+    }
+}